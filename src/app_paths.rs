@@ -0,0 +1,49 @@
+/* --> Summary of Contents
+
+	pub fn config_dir() -> PathBuf
+		Per-user directory that holds DaedriVictus settings and
+		local databases. Created on first use.
+
+	pub fn config_file(&str) -> PathBuf
+		Path of a named file inside config_dir()
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use std::{env, fs, path::PathBuf};
+
+/* <-- Imports */
+/* --> Const */
+
+const APP_FOLDER: &str = "DaedriVictus";
+
+/* <-- Const */
+/* --> Functions */
+
+pub fn config_dir() -> PathBuf {
+    // %APPDATA% on windows, $XDG_CONFIG_HOME or ~/.config elsewhere, working directory as a last resort
+    let base: PathBuf = match env::var_os("APPDATA") {
+        Some(appdata) => PathBuf::from(appdata),
+        None => match env::var_os("XDG_CONFIG_HOME") {
+            Some(xdg) => PathBuf::from(xdg),
+            None => match env::var_os("HOME") {
+                Some(home) => PathBuf::from(home).join(".config"),
+                None => PathBuf::from("."),
+            },
+        },
+    };
+
+    let dir: PathBuf = base.join(APP_FOLDER);
+    if !dir.exists() {
+        if let Err(e) = fs::create_dir_all(&dir) {
+            println!("Unable to create config directory {}: {}", dir.display(), e);
+        }
+    }
+    dir
+}
+
+pub fn config_file(name: &str) -> PathBuf {
+    config_dir().join(name)
+}
+
+/* <-- Functions */
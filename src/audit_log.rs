@@ -0,0 +1,1067 @@
+/* --> Summary of Contents
+
+	Audit trail of the data changed through the tool (ISO 17025)
+
+	Every UPDATE, DELETE and INSERT run from the editor, the CLI or the
+	REPL is logged in audit_log.db in the config directory: who, when,
+	which connection and table, the primary key of the row and the old
+	and new value of every column that changed. Triggers keep the log
+	append only.
+
+	pub fn capture(&ConnectionBase, &str, &str) -> Vec<Capture>
+		Before the statement runs: the rows its WHERE matches (or the row
+		count of the table for an INSERT) and the primary key columns
+
+	pub fn changes(&ConnectionBase, &str, &str, &str, Vec<Capture>) -> Vec<AuditEntry>
+		After it ran: the captured rows read again by key and compared.
+		A statement that failed changed nothing and leaves no entry.
+		Statements that cannot be captured (joins, MERGE, more than
+		MAX_CAPTURED_ROWS rows, no key) are logged with a note instead.
+
+	pub fn run_audited(&Session, &str, QueryType) -> Result<RecordSet, String>
+		A request on the CLI or REPL session (cli.rs) with the capture
+		around it, the OS user as analyst
+
+	pub fn modification(&str) -> Option<Modification>
+		What one statement changes: action, table and WHERE clause
+
+	pub fn table_at(&str, &[Token], usize) -> Option<(String, usize)>
+	pub fn count(&ConnectionBase, &str, &str, Option<&str>) -> Result<i64, String>
+		A possibly qualified table name among the tokens, and the rows of
+		a table a WHERE clause matches (statement_guard.rs asks with them)
+
+	pub fn analysts(&ConnectionBase, &str) -> Vec<String>
+	pub fn ask_analyst(&[String], &str) -> Option<String>
+	pub fn resolve_analyst(&mut Option<String>, &ConnectionBase, &str) -> String
+		The analyst list of the users table, when the connection has one,
+		and the modal dialog asking who is at the keyboard, once a session
+
+	pub fn show_audit_log()
+		Window with the entries, filtered by analyst, table, key, text and
+		date range, and an Export button
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    button::Button,
+    dialog,
+    enums::{Align, CallbackTrigger},
+    frame::Frame,
+    group::Flex,
+    input::Input,
+    menu::Choice,
+    prelude::{GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    window::Window,
+};
+use fltk_table::SmartTable;
+use std::{collections::HashMap, env, fs, path::Path};
+
+use crate::app_paths;
+use crate::cli::{self, Session};
+use crate::credentials;
+use crate::date_columns;
+use crate::learning_fltk::{center, escape_menu_label, fill_table};
+use crate::record_export::{self, ExportFormat};
+use crate::schema_diff::{quote, quote_literal};
+use crate::session_tab::{grid_opts, odbc_source};
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, Record, RecordSet, SqlData, SqlType};
+use crate::sql_format::Dialect;
+use crate::sql_lexer::{self, Token, TokenKind};
+use crate::sqlite3_interface::{SqliteFloat, SqliteInteger, SqliteString, Type};
+
+/* <-- Imports */
+/* --> Const */
+
+const AUDIT_FILE: &str = "audit_log.db";
+const MAX_CAPTURED_ROWS: i64 = 5000;
+const KEY_CHUNK: usize = 200; // rows read back per statement
+const MAX_SHOWN: i64 = 5000;
+const NAME_COLUMNS: [&str; 6] = ["analyst", "analyst_name", "user_name", "username", "full_name", "name"];
+
+const AUDIT_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS audit_entries (
+        id INTEGER PRIMARY KEY,
+        logged TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%S', 'now', 'localtime')),
+        analyst TEXT NOT NULL,
+        os_user TEXT NOT NULL,
+        connection TEXT NOT NULL,
+        table_name TEXT NOT NULL,
+        action TEXT NOT NULL,
+        row_key TEXT NOT NULL DEFAULT '',
+        column_name TEXT NOT NULL DEFAULT '',
+        old_value TEXT,
+        new_value TEXT,
+        note TEXT NOT NULL DEFAULT '',
+        statement TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS audit_entries_logged ON audit_entries (logged);
+    CREATE TRIGGER IF NOT EXISTS audit_entries_no_update BEFORE UPDATE ON audit_entries
+    BEGIN SELECT RAISE(ABORT, 'audit entries cannot be changed'); END;
+    CREATE TRIGGER IF NOT EXISTS audit_entries_no_delete BEFORE DELETE ON audit_entries
+    BEGIN SELECT RAISE(ABORT, 'audit entries cannot be deleted'); END;";
+
+const ENTRY_COLUMNS: [&str; 12] = [
+    "logged", "analyst", "os_user", "connection", "table_name", "action", "row_key", "column_name", "old_value", "new_value", "note", "statement",
+];
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    Insert,
+    Update,
+    Delete,
+    Other, // MERGE, TRUNCATE, REPLACE, INSERT OR REPLACE: logged, not captured
+}
+
+// what capture() found before the statement ran
+#[derive(Clone)]
+enum Before {
+    Rows(RecordSet),
+    Count(i64),
+    TooMany(i64),
+    NotCaptured(String),
+}
+
+#[derive(Clone, Copy)]
+enum DialogMessage {
+    Ok,
+    Cancel,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct Modification {
+    pub action: Action,
+    pub table: Option<String>, // as written, None when the target is a join
+    pub where_clause: Option<String>,
+    pub statement: String,
+    pub columns: Vec<String>,              // INSERT column list
+    pub rows: Vec<Vec<Option<String>>>,   // INSERT ... VALUES, when every value is a literal
+}
+
+#[derive(Clone)]
+pub struct Capture {
+    pub modification: Modification,
+    keys: Vec<String>,
+    before: Before,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AuditEntry {
+    pub logged: String, // set by the database
+    pub analyst: String,
+    pub os_user: String,
+    pub connection: String,
+    pub table_name: String,
+    pub action: String,
+    pub row_key: String, // "index=12", the primary key of the row
+    pub column_name: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub note: String,
+    pub statement: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AuditFilter {
+    pub analyst: String,
+    pub table: String,
+    pub row_key: String,
+    pub text: String, // column, values or statement
+    pub from: String, // YYYY-MM-DD, inclusive
+    pub to: String,
+}
+
+pub struct AuditLog {
+    db: sqlite::Connection,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Insert => "INSERT",
+            Action::Update => "UPDATE",
+            Action::Delete => "DELETE",
+            Action::Other => "OTHER",
+        }
+    }
+}
+
+impl Modification {
+    // without quotes or schema, as the log and the key lookup want it
+    pub fn table_name(&self) -> String {
+        self.table
+            .as_deref()
+            .and_then(|t| t.rsplit('.').next())
+            .map(|t| t.trim_matches(['"', '[', ']', '`']).to_string())
+            .unwrap_or_default()
+    }
+}
+
+impl AuditLog {
+    pub fn open_default() -> Result<Self, sqlite::Error> {
+        Self::open(&app_paths::config_file(AUDIT_FILE))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, sqlite::Error> {
+        let db: sqlite::Connection = sqlite::open(path)?;
+        db.execute(AUDIT_SCHEMA)?;
+        Ok(AuditLog { db })
+    }
+
+    pub fn record(&self, entries: &[AuditEntry]) -> Result<(), sqlite::Error> {
+        self.db.execute("BEGIN TRANSACTION;")?;
+        {
+            let mut stmt = self.db.prepare(
+                "insert into audit_entries (analyst, os_user, connection, table_name, action, row_key, column_name, old_value, new_value, note, statement)
+                 values (:analyst, :os_user, :connection, :table, :action, :key, :column, :old, :new, :note, :statement);",
+            )?;
+            for entry in entries {
+                stmt.bind((":analyst", entry.analyst.as_str()))?;
+                stmt.bind((":os_user", entry.os_user.as_str()))?;
+                stmt.bind((":connection", entry.connection.as_str()))?;
+                stmt.bind((":table", entry.table_name.as_str()))?;
+                stmt.bind((":action", entry.action.as_str()))?;
+                stmt.bind((":key", entry.row_key.as_str()))?;
+                stmt.bind((":column", entry.column_name.as_str()))?;
+                stmt.bind((":old", entry.old_value.as_deref()))?;
+                stmt.bind((":new", entry.new_value.as_deref()))?;
+                stmt.bind((":note", entry.note.as_str()))?;
+                stmt.bind((":statement", entry.statement.as_str()))?;
+                stmt.next()?;
+                stmt.reset()?;
+            }
+        }
+        self.db.execute("COMMIT;")
+    }
+
+    // newest first, every filter is a case insensitive "contains" except the dates
+    pub fn entries(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>, sqlite::Error> {
+        let mut stmt = self.db.prepare(format!(
+            "select {} from audit_entries
+             where (:analyst = '' or analyst like '%' || :analyst || '%' or os_user like '%' || :analyst || '%')
+               and (:table = '' or table_name like '%' || :table || '%')
+               and (:key = '' or row_key like '%' || :key || '%')
+               and (:text = '' or column_name like '%' || :text || '%' or old_value like '%' || :text || '%'
+                    or new_value like '%' || :text || '%' or statement like '%' || :text || '%')
+               and (:from = '' or logged >= :from)
+               and (:to = '' or logged < date(:to, '+1 day'))
+             order by id desc limit {};",
+            ENTRY_COLUMNS.join(", "),
+            MAX_SHOWN
+        ))?;
+        stmt.bind((":analyst", filter.analyst.trim()))?;
+        stmt.bind((":table", filter.table.trim()))?;
+        stmt.bind((":key", filter.row_key.trim()))?;
+        stmt.bind((":text", filter.text.trim()))?;
+        stmt.bind((":from", filter.from.trim()))?;
+        stmt.bind((":to", filter.to.trim()))?;
+        let mut entries: Vec<AuditEntry> = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            entries.push(AuditEntry {
+                logged: stmt.read::<String, _>("logged")?,
+                analyst: stmt.read::<String, _>("analyst")?,
+                os_user: stmt.read::<String, _>("os_user")?,
+                connection: stmt.read::<String, _>("connection")?,
+                table_name: stmt.read::<String, _>("table_name")?,
+                action: stmt.read::<String, _>("action")?,
+                row_key: stmt.read::<String, _>("row_key")?,
+                column_name: stmt.read::<String, _>("column_name")?,
+                old_value: stmt.read::<Option<String>, _>("old_value")?,
+                new_value: stmt.read::<Option<String>, _>("new_value")?,
+                note: stmt.read::<String, _>("note")?,
+                statement: stmt.read::<String, _>("statement")?,
+            });
+        }
+        Ok(entries)
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn os_user() -> String {
+    env::var("USERNAME").or_else(|_| env::var("USER")).unwrap_or_else(|_| String::from("unknown"))
+}
+
+// the full path of a SQLite file, the data source of an ODBC connection, never its login
+pub fn connection_name(connection_type: &ConnectionBase, connection: &str) -> String {
+    match connection_type {
+        ConnectionBase::Sqlite => format!("{} (SQLite)", connection),
+        ConnectionBase::Odbc => format!("{} (ODBC)", odbc_source(connection).unwrap_or_else(|| String::from("connection string"))),
+    }
+}
+
+// a table name as written: name, "name", [schema].[name], ...
+pub fn table_at(sql: &str, tokens: &[Token], start: usize) -> Option<(String, usize)> {
+    let mut index: usize = start;
+    loop {
+        let token: &Token = tokens.get(index)?;
+        if !matches!(token.kind, TokenKind::Word | TokenKind::QuotedIdent) {
+            return None;
+        }
+        match tokens.get(index + 1) {
+            Some(dot) if dot.is_punct(sql, ".") => index += 2,
+            _ => return Some((sql[tokens[start].start..token.end].to_string(), index + 1)),
+        }
+    }
+}
+
+// T-SQL TOP (n) after UPDATE / DELETE
+fn skip_top(sql: &str, tokens: &[Token], mut index: usize) -> usize {
+    if tokens.get(index).is_some_and(|t| t.is_word(sql, "TOP")) {
+        index += 1;
+        if tokens.get(index).is_some_and(|t| t.is_punct(sql, "(")) {
+            while tokens.get(index).is_some_and(|t| !t.is_punct(sql, ")")) {
+                index += 1;
+            }
+        }
+        index += 1;
+    }
+    index
+}
+
+// index of the first depth 0 token that is one of `words`, from `start`
+fn find_word(sql: &str, tokens: &[Token], start: usize, words: &[&str]) -> Option<usize> {
+    let mut depth: i32 = 0;
+    for (index, token) in tokens.iter().enumerate().skip(start) {
+        if token.is_punct(sql, "(") {
+            depth += 1;
+        } else if token.is_punct(sql, ")") {
+            depth -= 1;
+        } else if depth == 0 && words.iter().any(|w| token.is_word(sql, w)) {
+            return Some(index);
+        }
+    }
+    None
+}
+
+// the WHERE clause up to RETURNING / ORDER BY / LIMIT / OPTION
+fn where_clause(sql: &str, tokens: &[Token], start: usize) -> Option<String> {
+    let where_at: usize = find_word(sql, tokens, start, &["WHERE"])?;
+    let first: &Token = tokens.get(where_at + 1)?;
+    let end: usize = match find_word(sql, tokens, where_at + 1, &["RETURNING", "ORDER", "LIMIT", "OPTION"]) {
+        Some(index) => tokens[index].start,
+        None => tokens.iter().rev().find(|t| !t.is_punct(sql, ";")).map_or(sql.len(), |t| t.end),
+    };
+    Some(sql[first.start..end].trim().to_string())
+}
+
+// one literal of a VALUES list, None for NULL
+fn literal_at(sql: &str, tokens: &[Token], index: usize) -> Option<(Option<String>, usize)> {
+    let token: &Token = tokens.get(index)?;
+    match token.kind {
+        // N'text' in T-SQL is one token with its prefix, X'blob' is not text
+        TokenKind::String if !token.text(sql).starts_with(['X', 'x']) => {
+            let text: &str = token.text(sql).trim_start_matches(['N', 'n']);
+            let inner: &str = text.strip_prefix('\'')?.strip_suffix('\'')?;
+            Some((Some(inner.replace("''", "'")), index + 1))
+        }
+        TokenKind::Number => Some((Some(token.text(sql).to_string()), index + 1)),
+        TokenKind::Word if token.is_word(sql, "NULL") => Some((None, index + 1)),
+        TokenKind::Operator if token.text(sql) == "-" && tokens.get(index + 1).is_some_and(|t| t.kind == TokenKind::Number) => {
+            Some((Some(format!("-{}", tokens[index + 1].text(sql))), index + 2))
+        }
+        _ => None,
+    }
+}
+
+// ( a, b ) ( c, d ) ... of an INSERT, empty when anything is not a literal
+fn values_rows(sql: &str, tokens: &[Token], mut index: usize) -> Vec<Vec<Option<String>>> {
+    let mut rows: Vec<Vec<Option<String>>> = Vec::new();
+    while tokens.get(index).is_some_and(|t| t.is_punct(sql, "(")) {
+        let mut row: Vec<Option<String>> = Vec::new();
+        index += 1;
+        loop {
+            let (value, next) = match literal_at(sql, tokens, index) {
+                Some(literal) => literal,
+                None => return Vec::new(),
+            };
+            row.push(value);
+            index = next;
+            match tokens.get(index) {
+                Some(t) if t.is_punct(sql, ",") => index += 1,
+                Some(t) if t.is_punct(sql, ")") => break,
+                _ => return Vec::new(),
+            }
+        }
+        rows.push(row);
+        index += 1;
+        if tokens.get(index).is_some_and(|t| t.is_punct(sql, ",")) {
+            index += 1;
+        }
+    }
+    rows
+}
+
+pub fn modification(statement: &str) -> Option<Modification> {
+    let sql: &str = statement;
+    let tokens: Vec<Token> = sql_lexer::significant(&sql_lexer::tokenize(sql));
+    let first: &Token = tokens.first()?;
+    let action: Action = match first.text(sql).to_ascii_uppercase().as_str() {
+        "UPDATE" => Action::Update,
+        "DELETE" => Action::Delete,
+        "INSERT" if tokens.get(1).is_some_and(|t| t.is_word(sql, "OR")) => Action::Other,
+        "INSERT" => Action::Insert,
+        "MERGE" | "TRUNCATE" | "REPLACE" => Action::Other,
+        _ => return None,
+    };
+    let mut found: Modification = Modification {
+        action,
+        table: None,
+        where_clause: None,
+        statement: statement.trim().to_string(),
+        columns: Vec::new(),
+        rows: Vec::new(),
+    };
+    match action {
+        Action::Update => {
+            // UPDATE OR IGNORE in SQLite, UPDATE TOP (n) in T-SQL
+            let mut index: usize = skip_top(sql, &tokens, 1);
+            if tokens.get(index).is_some_and(|t| t.is_word(sql, "OR")) {
+                index += 2;
+            }
+            let (table, next) = table_at(sql, &tokens, index)?;
+            let set_at: usize = find_word(sql, &tokens, next, &["SET"])?;
+            // UPDATE ... FROM joins other tables, the WHERE is not about one table
+            if find_word(sql, &tokens, set_at, &["FROM"]).is_none() && next == set_at {
+                found.table = Some(table);
+            }
+            found.where_clause = where_clause(sql, &tokens, set_at);
+        }
+        Action::Delete => {
+            let mut index: usize = skip_top(sql, &tokens, 1);
+            if tokens.get(index).is_some_and(|t| t.is_word(sql, "FROM")) {
+                index += 1;
+            }
+            let (table, next) = table_at(sql, &tokens, index)?;
+            // DELETE t FROM t JOIN ..., a FROM or JOIN of its own before the WHERE
+            let where_at: Option<usize> = find_word(sql, &tokens, next, &["WHERE"]);
+            let joined: bool = find_word(sql, &tokens, next, &["FROM", "JOIN"]).is_some_and(|i| where_at.is_none_or(|w| i < w));
+            if !joined {
+                found.table = Some(table);
+            }
+            found.where_clause = where_clause(sql, &tokens, next);
+        }
+        Action::Insert => {
+            let index: usize = if tokens.get(1).is_some_and(|t| t.is_word(sql, "INTO")) { 2 } else { 1 };
+            let (table, mut next) = table_at(sql, &tokens, index)?;
+            found.table = Some(table);
+            if tokens.get(next).is_some_and(|t| t.is_punct(sql, "(")) {
+                next += 1;
+                while let Some(token) = tokens.get(next) {
+                    next += 1;
+                    if token.is_punct(sql, ")") {
+                        break;
+                    }
+                    if !token.is_punct(sql, ",") {
+                        found.columns.push(token.text(sql).trim_matches(['"', '[', ']', '`']).to_string());
+                    }
+                }
+            }
+            if tokens.get(next).is_some_and(|t| t.is_word(sql, "VALUES")) {
+                found.rows = values_rows(sql, &tokens, next + 1);
+            }
+        }
+        Action::Other => {}
+    }
+    Some(found)
+}
+
+pub fn count(connection_type: &ConnectionBase, connection: &str, table: &str, where_clause: Option<&str>) -> Result<i64, String> {
+    count_with(&|sql| cli::run_request(connection_type, connection, QueryType::UserDefined(sql)), table, where_clause)
+}
+
+// the capture's queries go through query, a new connection each or the session's one
+fn count_with(query: &dyn Fn(String) -> Result<RecordSet, String>, table: &str, where_clause: Option<&str>) -> Result<i64, String> {
+    let filter: String = where_clause.map_or(String::new(), |w| format!(" WHERE {}", w));
+    let sql: String = format!("SELECT COUNT(*) AS row_count FROM {}{}", table, filter);
+    let record_set: RecordSet = query(sql)?;
+    record_set
+        .records
+        .first()
+        .and_then(|r| r.text("row_count"))
+        .and_then(|n| n.trim().parse::<i64>().ok())
+        .ok_or_else(|| String::from("The row count came back empty"))
+}
+
+// primary key columns, named as the captured rows name them; "index" or "id" when the table has none
+fn key_columns(connection_type: &ConnectionBase, query: &dyn Fn(String) -> Result<RecordSet, String>, table: &str, rows: &RecordSet) -> Vec<String> {
+    let literal: String = quote_literal(table);
+    let keys: Vec<String> = match connection_type {
+        ConnectionBase::Sqlite => {
+            let sql: String = format!("PRAGMA table_info({})", quote(table, Dialect::Sqlite));
+            let mut columns: Vec<(i64, String)> = query(sql)
+                .map(|rs| {
+                    rs.records
+                        .iter()
+                        .filter_map(|r| Some((r.text("pk")?.trim().parse::<i64>().ok()?, r.text("name")?)))
+                        .filter(|(pk, _)| *pk > 0)
+                        .collect()
+                })
+                .unwrap_or_default();
+            columns.sort();
+            columns.into_iter().map(|(_, name)| name).collect()
+        }
+        ConnectionBase::Odbc => {
+            let sql: String = format!(
+                "SELECT k.COLUMN_NAME FROM INFORMATION_SCHEMA.TABLE_CONSTRAINTS tc \
+                 JOIN INFORMATION_SCHEMA.KEY_COLUMN_USAGE k ON k.CONSTRAINT_NAME = tc.CONSTRAINT_NAME AND k.TABLE_NAME = tc.TABLE_NAME \
+                 WHERE tc.CONSTRAINT_TYPE = 'PRIMARY KEY' AND k.TABLE_NAME = {} ORDER BY k.ORDINAL_POSITION",
+                literal
+            );
+            query(sql)
+                .map(|rs| rs.records.iter().filter_map(|r| r.text("COLUMN_NAME")).collect())
+                .unwrap_or_default()
+        }
+    };
+    let named = |wanted: &str| rows.column_order.iter().find(|c| c.eq_ignore_ascii_case(wanted.trim())).cloned();
+    let keys: Vec<String> = keys.iter().filter_map(|k| named(k)).collect();
+    if !keys.is_empty() {
+        return keys;
+    }
+    named("index").or_else(|| named("id")).into_iter().collect()
+}
+
+fn capture_one(connection_type: &ConnectionBase, query: &dyn Fn(String) -> Result<RecordSet, String>, modification: Modification) -> Capture {
+    let mut keys: Vec<String> = Vec::new();
+    let before: Before = match (&modification.action, &modification.table) {
+        (Action::Other, _) => Before::NotCaptured(String::from("this kind of statement is not captured")),
+        (_, None) => Before::NotCaptured(String::from("the statement joins tables, values not captured")),
+        (Action::Insert, Some(table)) => match count_with(query, table, None) {
+            Ok(rows) => Before::Count(rows),
+            Err(e) => Before::NotCaptured(format!("row count not read: {}", credentials::redact(&e))),
+        },
+        (_, Some(table)) => {
+            let filter: Option<&str> = modification.where_clause.as_deref();
+            match count_with(query, table, filter) {
+                Ok(rows) if rows > MAX_CAPTURED_ROWS => Before::TooMany(rows),
+                Ok(_) => {
+                    let sql: String = format!("SELECT * FROM {}{}", table, filter.map_or(String::new(), |w| format!(" WHERE {}", w)));
+                    match query(sql) {
+                        Ok(rows) => {
+                            keys = key_columns(connection_type, query, &modification.table_name(), &rows);
+                            Before::Rows(rows)
+                        }
+                        Err(e) => Before::NotCaptured(format!("old values not read: {}", credentials::redact(&e))),
+                    }
+                }
+                Err(e) => Before::NotCaptured(format!("old values not read: {}", credentials::redact(&e))),
+            }
+        }
+    };
+    Capture { modification, keys, before }
+}
+
+pub fn capture(connection_type: &ConnectionBase, connection: &str, sql: &str) -> Vec<Capture> {
+    capture_with(connection_type, &|sql| cli::run_request(connection_type, connection, QueryType::UserDefined(sql)), sql)
+}
+
+fn capture_with(connection_type: &ConnectionBase, query: &dyn Fn(String) -> Result<RecordSet, String>, sql: &str) -> Vec<Capture> {
+    let ranges: Vec<(usize, usize)> = sql_lexer::statement_ranges(sql);
+    // SQLite runs the first statement of the text, ODBC sends the whole batch
+    let ranges: &[(usize, usize)] = match connection_type {
+        ConnectionBase::Sqlite => &ranges[..ranges.len().min(1)],
+        ConnectionBase::Odbc => &ranges,
+    };
+    ranges
+        .iter()
+        .filter_map(|(start, end)| modification(&sql[*start..*end]))
+        .map(|m| capture_one(connection_type, query, m))
+        .collect()
+}
+
+fn key_literal(record: &Record, column: &str, column_info: &HashMap<String, SqlType>) -> String {
+    match (record.columns.get(column).and_then(|v| v.as_ref()), column_info.get(column)) {
+        (Some(SqlData::Sqlite(SqliteInteger(i))), _) => i.to_string(),
+        (Some(SqlData::Sqlite(SqliteFloat(f))), _) => f.to_string(),
+        (Some(SqlData::Sqlite(SqliteString(text))), _) => quote_literal(text),
+        (Some(SqlData::Odbc(text)), Some(data_type)) if data_type.is_numeric() && text.trim().parse::<f64>().is_ok() => text.trim().to_string(),
+        _ => match record.text(column) {
+            Some(text) => quote_literal(&text),
+            None => String::from("NULL"),
+        },
+    }
+}
+
+fn row_key(record: &Record, keys: &[String]) -> String {
+    keys.iter()
+        .map(|k| format!("{}={}", k, record.text(k).unwrap_or_else(|| String::from("NULL"))))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// "col=value, col=value" for a row kept whole in one entry
+fn row_text(record: &Record, columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|c| format!("{}={}", c, record.text(c).unwrap_or_else(|| String::from("NULL"))))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// the captured rows as they are now, by key
+fn rows_after(connection_type: &ConnectionBase, query: &dyn Fn(String) -> Result<RecordSet, String>, table: &str, keys: &[String], before: &RecordSet) -> Result<HashMap<String, Record>, String> {
+    let dialect: Dialect = Dialect::of(connection_type);
+    let mut after: HashMap<String, Record> = HashMap::new();
+    for chunk in before.records.chunks(KEY_CHUNK) {
+        let matches: Vec<String> = chunk
+            .iter()
+            .map(|record| {
+                let parts: Vec<String> = keys
+                    .iter()
+                    .map(|k| format!("{} = {}", quote(k, dialect), key_literal(record, k, &before.column_info)))
+                    .collect();
+                format!("({})", parts.join(" AND "))
+            })
+            .collect();
+        let sql: String = format!("SELECT * FROM {} WHERE {}", table, matches.join(" OR "));
+        let record_set: RecordSet = query(sql)?;
+        for record in record_set.records {
+            after.insert(row_key(&record, keys), record);
+        }
+    }
+    Ok(after)
+}
+
+pub fn changes(connection_type: &ConnectionBase, connection: &str, connection_label: &str, analyst: &str, captures: Vec<Capture>) -> Vec<AuditEntry> {
+    changes_with(connection_type, &|sql| cli::run_request(connection_type, connection, QueryType::UserDefined(sql)), connection_label, analyst, captures)
+}
+
+fn changes_with(
+    connection_type: &ConnectionBase,
+    query: &dyn Fn(String) -> Result<RecordSet, String>,
+    connection_label: &str,
+    analyst: &str,
+    captures: Vec<Capture>,
+) -> Vec<AuditEntry> {
+    let os_user: String = os_user();
+    let mut entries: Vec<AuditEntry> = Vec::new();
+    for capture in captures {
+        let modification: &Modification = &capture.modification;
+        let entry = |row_key: String, column_name: &str, old_value: Option<String>, new_value: Option<String>, note: String| AuditEntry {
+            analyst: analyst.to_string(),
+            os_user: os_user.clone(),
+            connection: connection_label.to_string(),
+            table_name: modification.table_name(),
+            action: modification.action.name().to_string(),
+            row_key,
+            column_name: column_name.to_string(),
+            old_value,
+            new_value,
+            note,
+            statement: modification.statement.clone(),
+            ..Default::default()
+        };
+        let table: &str = modification.table.as_deref().unwrap_or_default();
+        match &capture.before {
+            Before::NotCaptured(why) => entries.push(entry(String::new(), "", None, None, why.clone())),
+            Before::TooMany(rows) => entries.push(entry(
+                String::new(),
+                "",
+                None,
+                None,
+                format!("{} rows matched, more than {} are not captured", rows, MAX_CAPTURED_ROWS),
+            )),
+            Before::Count(rows) => {
+                let added: i64 = match count_with(query, table, None) {
+                    Ok(now) => now - rows,
+                    Err(_) => 0,
+                };
+                if added <= 0 {
+                    continue; // the insert failed
+                }
+                if modification.rows.is_empty() || modification.columns.is_empty() {
+                    entries.push(entry(String::new(), "", None, None, format!("{} rows added, values not captured", added)));
+                    continue;
+                }
+                for row in &modification.rows {
+                    let key: String = modification
+                        .columns
+                        .iter()
+                        .zip(row)
+                        .filter(|(c, _)| c.eq_ignore_ascii_case("index") || c.eq_ignore_ascii_case("id"))
+                        .map(|(c, v)| format!("{}={}", c, v.as_deref().unwrap_or("NULL")))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    for (column, value) in modification.columns.iter().zip(row) {
+                        entries.push(entry(key.clone(), column, None, value.clone(), String::new()));
+                    }
+                }
+            }
+            Before::Rows(before) if capture.keys.is_empty() => {
+                for record in &before.records {
+                    entries.push(entry(
+                        String::new(),
+                        "",
+                        Some(row_text(record, &before.column_order)),
+                        None,
+                        String::from("the table has no primary key, new values not captured"),
+                    ));
+                }
+            }
+            Before::Rows(before) => {
+                let after: HashMap<String, Record> = match rows_after(connection_type, query, table, &capture.keys, before) {
+                    Ok(after) => after,
+                    Err(e) => {
+                        entries.push(entry(String::new(), "", None, None, format!("new values not read: {}", credentials::redact(&e))));
+                        continue;
+                    }
+                };
+                for record in &before.records {
+                    let key: String = row_key(record, &capture.keys);
+                    match (after.get(&key), modification.action) {
+                        (Some(now), _) => {
+                            for column in &before.column_order {
+                                let (old, new) = (record.text(column), now.text(column));
+                                if old != new {
+                                    entries.push(entry(key.clone(), column, old, new, String::new()));
+                                }
+                            }
+                        }
+                        (None, Action::Delete) => {
+                            for column in &before.column_order {
+                                entries.push(entry(key.clone(), column, record.text(column), None, String::new()));
+                            }
+                        }
+                        (None, _) => entries.push(entry(
+                            key,
+                            "",
+                            Some(row_text(record, &before.column_order)),
+                            None,
+                            String::from("the row is no longer found by its key"),
+                        )),
+                    }
+                }
+            }
+        }
+    }
+    entries
+}
+
+pub fn write(entries: &[AuditEntry]) {
+    if entries.is_empty() {
+        return;
+    }
+    if let Err(e) = AuditLog::open_default().and_then(|log| log.record(entries)) {
+        println!("Unable to write the audit log: {}", e);
+    }
+}
+
+// old and new values are read on the session's connection, inside a transaction the script opened
+pub fn run_audited(session: &Session, analyst: &str, request: QueryType) -> Result<RecordSet, String> {
+    let query = |sql: String| session.run(QueryType::UserDefined(sql));
+    let captures: Vec<Capture> = match &request {
+        QueryType::UserDefined(sql) => capture_with(&session.connection_type, &query, sql),
+        QueryType::SqlFunction(_) => Vec::new(),
+    };
+    let result: Result<RecordSet, String> = session.run(request);
+    if !captures.is_empty() {
+        let label: String = connection_name(&session.connection_type, &session.connection);
+        write(&changes_with(&session.connection_type, &query, &label, analyst, captures));
+    }
+    result
+}
+
+// names from the users table, empty when the connection does not have one
+pub fn analysts(connection_type: &ConnectionBase, connection: &str) -> Vec<String> {
+    let sql: String = format!("SELECT * FROM {}", quote("users", Dialect::of(connection_type)));
+    let record_set: RecordSet = match cli::run_request(connection_type, connection, QueryType::UserDefined(sql)) {
+        Ok(record_set) => record_set,
+        Err(_) => return Vec::new(),
+    };
+    let column: Option<String> = NAME_COLUMNS
+        .iter()
+        .find_map(|wanted| record_set.column_order.iter().find(|c| c.eq_ignore_ascii_case(wanted)))
+        .or_else(|| record_set.column_order.iter().find(|c| !c.eq_ignore_ascii_case("index") && !c.eq_ignore_ascii_case("id")))
+        .cloned();
+    let mut names: Vec<String> = match column {
+        Some(column) => record_set.records.iter().filter_map(|r| r.text(&column)).map(|n| n.trim().to_string()).filter(|n| !n.is_empty()).collect(),
+        None => Vec::new(),
+    };
+    names.sort_by_key(|n| n.to_lowercase());
+    names.dedup();
+    names
+}
+
+// the analyst list with the OS user last, the one matching the OS user selected
+pub fn ask_analyst(names: &[String], os_user: &str) -> Option<String> {
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+    let mut win: Window = Window::default()
+        .with_size(420, 130)
+        .with_label("Audit Trail")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut prompt: Frame = Frame::new(10, 10, 400, 25, "Who is changing the data? The audit log records the name.");
+    prompt.set_align(Align::Inside | Align::Left);
+    let mut analyst: Choice = Choice::new(80, 45, 330, 25, "Analyst");
+    let mut choices: Vec<String> = names.to_vec();
+    choices.push(os_user.to_string());
+    for name in &choices {
+        analyst.add_choice(&escape_menu_label(&name.replace('|', " ")));
+    }
+    let preferred: usize = choices
+        .iter()
+        .position(|n| n.to_lowercase().contains(&os_user.to_lowercase()) || os_user.to_lowercase().contains(&n.to_lowercase()))
+        .unwrap_or(choices.len() - 1);
+    analyst.set_value(preferred as i32);
+
+    let mut ok_butn: Button = Button::new(250, 90, 75, 28, "&OK");
+    ok_butn.emit(sndr, DialogMessage::Ok);
+    let mut cancel_butn: Button = Button::new(335, 90, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+    win.end();
+    win.show();
+
+    let mut chosen: Option<String> = None;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::Ok) => {
+                chosen = usize::try_from(analyst.value()).ok().and_then(|i| choices.get(i)).cloned();
+                win.hide();
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            None => {}
+        }
+    }
+    chosen
+}
+
+// asks the first time only, the OS user stands in when nobody is picked
+pub fn resolve_analyst(analyst: &mut Option<String>, connection_type: &ConnectionBase, connection: &str) -> String {
+    if analyst.is_none() {
+        let names: Vec<String> = analysts(connection_type, connection);
+        *analyst = Some(ask_analyst(&names, &os_user()).unwrap_or_else(os_user));
+    }
+    analyst.clone().unwrap_or_default()
+}
+
+fn report(entries: &[AuditEntry]) -> RecordSet {
+    let mut record_set: RecordSet = RecordSet::default();
+    for column in ENTRY_COLUMNS {
+        record_set.column_order.push(column.to_string());
+        record_set.column_info.insert(column.to_string(), SqlType::Sqlite(Type::String));
+    }
+    for entry in entries {
+        let values: [Option<&str>; 12] = [
+            Some(&entry.logged),
+            Some(&entry.analyst),
+            Some(&entry.os_user),
+            Some(&entry.connection),
+            Some(&entry.table_name),
+            Some(&entry.action),
+            Some(&entry.row_key),
+            Some(&entry.column_name),
+            entry.old_value.as_deref(),
+            entry.new_value.as_deref(),
+            Some(&entry.note),
+            Some(&entry.statement),
+        ];
+        let mut record: Record = Record::default();
+        for (column, value) in ENTRY_COLUMNS.iter().zip(values) {
+            record.columns.insert(column.to_string(), value.map(|v| SqlData::Sqlite(SqliteString(v.to_string()))));
+        }
+        record_set.records.push(record);
+    }
+    record_set
+}
+
+// dates in the filter may be typed as the data shows them, the log compares YYYY-MM-DD
+fn filter_date(text: &str) -> Result<String, String> {
+    if text.trim().is_empty() {
+        return Ok(String::new());
+    }
+    date_columns::parse_any(text.trim())
+        .map(date_columns::iso)
+        .ok_or_else(|| format!("{} is not a date", text.trim()))
+}
+
+pub fn show_audit_log() {
+    let mut win: Window = Window::default()
+        .with_size(1100, 620)
+        .with_label("Audit Log")
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let mut filter_row: Flex = Flex::default().row();
+    let mut inputs: Vec<Input> = Vec::new();
+    for (label, width) in [("Analyst", 55), ("Table", 45), ("Key", 35), ("Text", 40), ("From", 45), ("To", 30)] {
+        let mut caption: Frame = Frame::default().with_label(label);
+        caption.set_align(Align::Inside | Align::Right);
+        filter_row.fixed(&caption, width);
+        let mut input: Input = Input::default();
+        input.set_trigger(CallbackTrigger::EnterKeyAlways);
+        inputs.push(input);
+    }
+    inputs[4].set_tooltip("First day, e.g. 2024-01-31 or 1/31/2024");
+    inputs[5].set_tooltip("Last day");
+    let mut apply_butn: Button = Button::default().with_label("&Apply");
+    filter_row.fixed(&apply_butn, 70);
+    filter_row.end();
+    layout.fixed(&filter_row, 28);
+
+    let mut grid: SmartTable = SmartTable::default().with_opts(grid_opts(0, 0));
+    let mut buttons: Flex = Flex::default().row();
+    let mut summary: Frame = Frame::default();
+    summary.set_align(Align::Inside | Align::Left);
+    let mut export_butn: Button = Button::default().with_label("&Export...");
+    buttons.fixed(&export_butn, 90);
+    buttons.end();
+    layout.fixed(&buttons, 30);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    let read_filter = {
+        let inputs: Vec<Input> = inputs.clone();
+        move || -> Result<AuditFilter, String> {
+            Ok(AuditFilter {
+                analyst: inputs[0].value(),
+                table: inputs[1].value(),
+                row_key: inputs[2].value(),
+                text: inputs[3].value(),
+                from: filter_date(&inputs[4].value())?,
+                to: filter_date(&inputs[5].value())?,
+            })
+        }
+    };
+    let load = {
+        let read_filter = read_filter.clone();
+        move || -> Result<Vec<AuditEntry>, String> {
+            let filter: AuditFilter = read_filter()?;
+            AuditLog::open_default().and_then(|log| log.entries(&filter)).map_err(|e| e.to_string())
+        }
+    };
+    let mut refresh = {
+        let load = load.clone();
+        move || match load() {
+            Ok(entries) => {
+                let shown: RecordSet = report(&entries);
+                fill_table(&shown, &mut grid, shown.records.clone());
+                grid.redraw();
+                let limit: &str = if entries.len() as i64 == MAX_SHOWN { " (the newest, narrow the filter to see older ones)" } else { "" };
+                summary.set_label(&format!("{} entries{}", entries.len(), limit));
+            }
+            Err(e) => summary.set_label(&e.replace('&', "&&").replace('@', "@@")),
+        }
+    };
+    refresh();
+
+    for input in inputs.iter_mut() {
+        let mut refresh = refresh.clone();
+        input.set_callback(move |_| refresh());
+    }
+    apply_butn.set_callback(move |_| refresh());
+
+    export_butn.set_callback(move |_| {
+        let entries: Vec<AuditEntry> = match load() {
+            Ok(entries) => entries,
+            Err(e) => {
+                let (x, y): (i32, i32) = center();
+                dialog::alert(x - 200, y - 100, &e);
+                return;
+            }
+        };
+        let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+        chooser.set_title("Export the audit log");
+        chooser.set_filter("CSV\t*.csv\nTSV\t*.tsv\nJSON\t*.json\nText table\t*.txt");
+        chooser.set_option(dialog::FileDialogOptions::SaveAsConfirm);
+        chooser.show();
+        let path: String = chooser.filename().to_string_lossy().into_owned();
+        if path.is_empty() {
+            return;
+        }
+        let format: ExportFormat = ExportFormat::from_path(&path)
+            .or_else(|| path.ends_with(".txt").then_some(ExportFormat::Table))
+            .unwrap_or(ExportFormat::Csv);
+        if let Err(e) = fs::write(&path, record_export::render(&report(&entries), format)) {
+            println!("Unable to write {}: {}", path, e);
+        }
+    });
+    win.show();
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|v| v.map(str::to_string)).collect()
+    }
+
+    #[test]
+    fn update_and_delete_name_the_table_and_where() {
+        let found: Modification = modification("UPDATE dbo.tools SET due = '2025-01-01' WHERE id = 4 ORDER BY id").unwrap();
+        assert_eq!(found.action, Action::Update);
+        assert_eq!(found.table.as_deref(), Some("dbo.tools"));
+        assert_eq!(found.where_clause.as_deref(), Some("id = 4"));
+
+        let found: Modification = modification("update top (5) [tools] set a = 1").unwrap();
+        assert_eq!(found.table.as_deref(), Some("[tools]"));
+        assert_eq!(found.where_clause, None);
+
+        let found: Modification = modification("DELETE FROM tools WHERE name = 'a;b';").unwrap();
+        assert_eq!(found.action, Action::Delete);
+        assert_eq!(found.table.as_deref(), Some("tools"));
+        assert_eq!(found.where_clause.as_deref(), Some("name = 'a;b'"));
+        assert_eq!(found.statement, "DELETE FROM tools WHERE name = 'a;b';");
+    }
+
+    #[test]
+    fn joined_targets_have_no_table() {
+        let found: Modification = modification("UPDATE t SET a = u.a FROM t JOIN u ON u.id = t.id WHERE t.id = 1").unwrap();
+        assert_eq!(found.table, None);
+        let found: Modification = modification("DELETE t FROM t JOIN u ON u.id = t.id WHERE u.gone = 1").unwrap();
+        assert_eq!(found.table, None);
+        // a subquery in the WHERE is not a join
+        let found: Modification = modification("DELETE FROM t WHERE id IN (SELECT id FROM u)").unwrap();
+        assert_eq!(found.table.as_deref(), Some("t"));
+    }
+
+    #[test]
+    fn insert_values_are_read_when_all_are_literals() {
+        let found: Modification =
+            modification("INSERT INTO tools (id, \"name\", [cost]) VALUES (1, 'it''s', -2.5), (2, N'b', NULL)").unwrap();
+        assert_eq!(found.action, Action::Insert);
+        assert_eq!(found.table.as_deref(), Some("tools"));
+        assert_eq!(found.columns, vec!["id", "name", "cost"]);
+        assert_eq!(found.rows, vec![texts(&[Some("1"), Some("it's"), Some("-2.5")]), texts(&[Some("2"), Some("b"), None])]);
+
+        let found: Modification = modification("insert into tools (id, due) values (3, date('now'))").unwrap();
+        assert!(found.rows.is_empty());
+        let found: Modification = modification("insert into tools (id, name) values (4, '").unwrap();
+        assert!(found.rows.is_empty());
+        let found: Modification = modification("INSERT INTO tools SELECT * FROM old_tools").unwrap();
+        assert!(found.columns.is_empty() && found.rows.is_empty());
+    }
+
+    #[test]
+    fn other_changes_are_logged_without_a_table() {
+        for sql in ["INSERT OR REPLACE INTO t VALUES (1)", "TRUNCATE TABLE t", "MERGE t USING u ON 1 = 1"] {
+            let found: Modification = modification(sql).unwrap();
+            assert_eq!(found.action, Action::Other, "{}", sql);
+            assert_eq!(found.table, None);
+        }
+    }
+
+    #[test]
+    fn queries_are_not_modifications() {
+        for sql in ["SELECT * FROM t", "-- UPDATE t SET a = 1", "", "PRAGMA table_info(t)"] {
+            assert!(modification(sql).is_none(), "{}", sql);
+        }
+    }
+}
+
+/* <-- Tests */
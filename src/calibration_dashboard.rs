@@ -0,0 +1,378 @@
+/* --> Summary of Contents
+
+	Calibration due dashboard over a measuring_instruments table
+
+	pub fn load(&ConnectionBase, &str, &str, &DateSettings) -> Result<Vec<Instrument>, String>
+		Every instrument with its due bucket. Department, Tool Owner and
+		Service Status are codes; cd_department, cd_tool_owners and
+		cd_status give them names when the connection has them. Due dates
+		are read as date_columns.rs reads them, '1/1/1900' means none.
+
+	pub fn bucket(Option<i64>, i64, &str) -> DueBucket
+		Overdue, due in 30 / 60 / 90 days or current, from the due day and
+		today (days since 1970-01-01). Instruments out of service and
+		those without a due date are kept apart.
+
+	pub fn record_sql(&ConnectionBase, &str, &str) -> String
+		The SELECT for one instrument, by its "index"
+
+	pub fn show_dashboard(&str, &ConnectionBase, &str, Vec<Instrument>, usize, Sender<Message>)
+		Department -> Tool Owner -> instrument tree, colored by bucket.
+		Double-clicking an instrument opens its record in the tab the
+		dashboard was started from.
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, Sender},
+    enums::{Align, Color, FrameType},
+    frame::Frame,
+    group::Flex,
+    menu::Choice,
+    prelude::{GroupExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    tree::{Tree, TreeItem, TreeItemReselectMode, TreeReason, TreeSelect},
+    window::Window,
+};
+use std::collections::HashMap;
+
+use crate::cli;
+use crate::date_columns::{self, DateColumns, DateFormat, DateSettings};
+use crate::learning_fltk::Message;
+use crate::library_panel::escape_tree_label;
+use crate::schema_diff::{quote, quote_literal, quote_table};
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, Record, RecordSet};
+use crate::sql_format::Dialect;
+
+/* <-- Imports */
+/* --> Const */
+
+const UNSET_CODE: &str = "999"; // the column default, the lookup tables call it "Not Defined"
+const DUE_COLUMN: &str = "Calib Due Date";
+
+const BUCKETS: [DueBucket; 7] = [
+    DueBucket::Overdue,
+    DueBucket::Due30,
+    DueBucket::Due60,
+    DueBucket::Due90,
+    DueBucket::Current,
+    DueBucket::NoDueDate,
+    DueBucket::OutOfService,
+];
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DueBucket {
+    Overdue,
+    Due30,
+    Due60,
+    Due90,
+    Current,
+    NoDueDate,
+    OutOfService,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct Instrument {
+    pub key: String, // the "index" column
+    pub equipment_id: String,
+    pub model: String,
+    pub manufacturer: String,
+    pub due_text: String,
+    pub due: Option<i64>, // days since 1970-01-01
+    pub department: String,
+    pub tool_owner: String,
+    pub status: String,
+    pub bucket: DueBucket,
+}
+
+impl DueBucket {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DueBucket::Overdue => "Overdue",
+            DueBucket::Due30 => "Due in 30 days",
+            DueBucket::Due60 => "Due in 60 days",
+            DueBucket::Due90 => "Due in 90 days",
+            DueBucket::Current => "Current",
+            DueBucket::NoDueDate => "No due date",
+            DueBucket::OutOfService => "Out of service",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            DueBucket::Overdue => Color::from_rgb(255, 190, 190),
+            DueBucket::Due30 => Color::from_rgb(255, 215, 170),
+            DueBucket::Due60 => Color::from_rgb(255, 240, 170),
+            DueBucket::Due90 => Color::from_rgb(240, 245, 195),
+            DueBucket::Current => Color::from_rgb(200, 235, 200),
+            DueBucket::NoDueDate | DueBucket::OutOfService => Color::from_rgb(225, 225, 225),
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn bucket(due: Option<i64>, today: i64, status: &str) -> DueBucket {
+    if status.eq_ignore_ascii_case("out of service") {
+        return DueBucket::OutOfService;
+    }
+    match due.map(|d| d - today) {
+        None => DueBucket::NoDueDate,
+        Some(days) if days < 0 => DueBucket::Overdue,
+        Some(days) if days <= 30 => DueBucket::Due30,
+        Some(days) if days <= 60 => DueBucket::Due60,
+        Some(days) if days <= 90 => DueBucket::Due90,
+        Some(_) => DueBucket::Current,
+    }
+}
+
+fn text(record: &Record, column: &str) -> String {
+    record.text(column).unwrap_or_default().trim().to_string()
+}
+
+// code -> name from a lookup table, empty when the connection does not have it
+fn lookup(connection_type: &ConnectionBase, connection: &str, table: &str, name_column: &str) -> HashMap<String, String> {
+    let dialect: Dialect = Dialect::of(connection_type);
+    let sql: String = format!(
+        "SELECT {}, {} FROM {}",
+        quote("index", dialect),
+        quote(name_column, dialect),
+        quote(table, dialect)
+    );
+    match cli::run_request(connection_type, connection, QueryType::UserDefined(sql)) {
+        Ok(record_set) => record_set.records.iter().map(|r| (text(r, "index"), text(r, name_column))).collect(),
+        Err(_) => HashMap::new(),
+    }
+}
+
+fn named(codes: &HashMap<String, String>, code: &str) -> String {
+    match (code, codes.get(code)) {
+        ("" | UNSET_CODE, None) => String::from("Not Defined"),
+        (_, Some(name)) if !name.is_empty() => name.clone(),
+        (code, _) => code.to_string(),
+    }
+}
+
+pub fn record_sql(connection_type: &ConnectionBase, table: &str, key: &str) -> String {
+    let dialect: Dialect = Dialect::of(connection_type);
+    let value: String = match key.parse::<i64>() {
+        Ok(number) => number.to_string(),
+        Err(_) => quote_literal(key),
+    };
+    format!("SELECT * FROM {} WHERE {} = {};", quote_table(table, dialect), quote("index", dialect), value)
+}
+
+pub fn load(connection_type: &ConnectionBase, connection: &str, table: &str, settings: &DateSettings) -> Result<Vec<Instrument>, String> {
+    let sql: String = format!("SELECT * FROM {}", quote_table(table, Dialect::of(connection_type)));
+    let record_set: RecordSet = cli::run_request(connection_type, connection, QueryType::UserDefined(sql))?;
+    let missing: Vec<&str> = ["index", "Equipment ID", DUE_COLUMN, "Department", "Tool Owner"]
+        .into_iter()
+        .filter(|c| !record_set.column_order.iter().any(|o| o == c))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("{} has no column {}", table, missing.join(", ")));
+    }
+
+    let departments: HashMap<String, String> = lookup(connection_type, connection, "cd_department", "department");
+    let owners: HashMap<String, String> = lookup(connection_type, connection, "cd_tool_owners", "name");
+    let statuses: HashMap<String, String> = lookup(connection_type, connection, "cd_status", "status");
+    let mut dates: DateColumns = record_set.date_columns(settings);
+    // too few readable dates for detection, the sample database's format is the best guess
+    dates.formats.entry(String::from(DUE_COLUMN)).or_insert(DateFormat::MonthDayYear);
+    let now: i64 = date_columns::today();
+    Ok(record_set
+        .records
+        .iter()
+        .map(|record| {
+            let due_text: String = text(record, DUE_COLUMN);
+            let due: Option<i64> = dates.date(record, DUE_COLUMN);
+            let status: String = named(&statuses, &text(record, "Service Status"));
+            Instrument {
+                key: text(record, "index"),
+                equipment_id: text(record, "Equipment ID"),
+                model: text(record, "Model"),
+                manufacturer: text(record, "Manufacturer"),
+                due_text,
+                due,
+                department: named(&departments, &text(record, "Department")),
+                tool_owner: named(&owners, &text(record, "Tool Owner")),
+                bucket: bucket(due, now, &status),
+                status,
+            }
+        })
+        .collect())
+}
+
+fn instrument_label(instrument: &Instrument, today: i64) -> String {
+    let when: String = match instrument.due.map(|d| d - today) {
+        Some(days) if days < 0 => format!("due {}, {} days overdue", instrument.due_text, -days),
+        Some(days) => format!("due {}, in {} days", instrument.due_text, days),
+        None if instrument.due_text.is_empty() => String::from("no due date"),
+        None => format!("due {}", instrument.due_text),
+    };
+    let mut label: String = format!("{}  |  {}  |  {}", instrument.equipment_id, instrument.bucket.label(), when);
+    let model: String = format!("{} {}", instrument.manufacturer, instrument.model).trim().to_string();
+    if !model.is_empty() {
+        label.push_str(&format!("  |  {}", model));
+    }
+    label
+}
+
+fn group_label(name: &str, instruments: &[&Instrument]) -> String {
+    let overdue: usize = instruments.iter().filter(|i| i.bucket == DueBucket::Overdue).count();
+    let soon: usize = instruments
+        .iter()
+        .filter(|i| matches!(i.bucket, DueBucket::Due30 | DueBucket::Due60 | DueBucket::Due90))
+        .count();
+    format!("{} ({} overdue, {} due within 90 days, {} total)", name, overdue, soon, instruments.len())
+}
+
+// rebuilds the tree for the buckets shown, returns tree path -> instrument key
+fn fill_tree(tree: &mut Tree, instruments: &[Instrument], shown: &[DueBucket]) -> HashMap<String, String> {
+    let now: i64 = date_columns::today();
+    let mut index: HashMap<String, String> = HashMap::new();
+    tree.clear();
+
+    let mut selected: Vec<&Instrument> = instruments.iter().filter(|i| shown.contains(&i.bucket)).collect();
+    // most urgent first inside each owner, instruments without a date last
+    selected.sort_by(|a, b| {
+        (a.department.as_str(), a.tool_owner.as_str(), a.due.is_none(), a.due)
+            .cmp(&(b.department.as_str(), b.tool_owner.as_str(), b.due.is_none(), b.due))
+    });
+    let mut departments: Vec<&str> = selected.iter().map(|i| i.department.as_str()).collect();
+    departments.dedup();
+    for department in departments {
+        let in_department: Vec<&Instrument> = selected.iter().copied().filter(|i| i.department == department).collect();
+        let department_path: String = escape_tree_label(&group_label(department, &in_department));
+        if let Some(mut item) = tree.add(&department_path) {
+            style_group(&mut item, &in_department);
+        }
+        let mut owners: Vec<&str> = in_department.iter().map(|i| i.tool_owner.as_str()).collect();
+        owners.dedup();
+        for owner in owners {
+            let in_owner: Vec<&Instrument> = in_department.iter().copied().filter(|i| i.tool_owner == owner).collect();
+            let owner_path: String = format!("{}/{}", department_path, escape_tree_label(&group_label(owner, &in_owner)));
+            if let Some(mut item) = tree.add(&owner_path) {
+                style_group(&mut item, &in_owner);
+            }
+            for instrument in in_owner {
+                let path: String = format!("{}/{}", owner_path, escape_tree_label(&instrument_label(instrument, now)));
+                if let Some(mut item) = tree.add(&path) {
+                    item.set_label_bgcolor(instrument.bucket.color());
+                    if matches!(instrument.bucket, DueBucket::NoDueDate | DueBucket::OutOfService) {
+                        item.set_label_fgcolor(Color::Dark3);
+                    }
+                }
+                index.insert(path, instrument.key.clone());
+            }
+        }
+    }
+    tree.redraw();
+    index
+}
+
+fn style_group(item: &mut TreeItem, instruments: &[&Instrument]) {
+    if instruments.iter().any(|i| i.bucket == DueBucket::Overdue) {
+        item.set_label_fgcolor(Color::DarkRed);
+    }
+    // owners with nothing pressing stay folded
+    if !instruments.iter().any(|i| matches!(i.bucket, DueBucket::Overdue | DueBucket::Due30)) {
+        item.close();
+    }
+}
+
+fn shown_buckets(filter: i32) -> Vec<DueBucket> {
+    match filter {
+        0 => vec![DueBucket::Overdue, DueBucket::Due30, DueBucket::Due60, DueBucket::Due90],
+        1 => vec![DueBucket::Overdue],
+        2 => vec![DueBucket::Overdue, DueBucket::Due30],
+        _ => BUCKETS.to_vec(),
+    }
+}
+
+pub fn show_dashboard(
+    label: &str,
+    connection_type: &ConnectionBase,
+    table: &str,
+    instruments: Vec<Instrument>,
+    tab: usize,
+    sndr: Sender<Message>,
+) {
+    let mut win: Window = Window::default()
+        .with_size(900, 620)
+        .with_label(&format!("Calibration Due: {} ({})", table, label))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    // one colored count per bucket
+    let counts: Flex = Flex::default().row();
+    for due_bucket in BUCKETS {
+        let count: usize = instruments.iter().filter(|i| i.bucket == due_bucket).count();
+        let mut frame: Frame = Frame::default().with_label(&format!("{}\n{}", due_bucket.label(), count));
+        frame.set_frame(FrameType::FlatBox);
+        frame.set_color(due_bucket.color());
+    }
+    counts.end();
+    layout.fixed(&counts, 45);
+
+    let mut filter_row: Flex = Flex::default().row();
+    let mut filter_label: Frame = Frame::default().with_label("Show");
+    filter_label.set_align(Align::Inside | Align::Right);
+    filter_row.fixed(&filter_label, 50);
+    let mut filter: Choice = Choice::default();
+    filter.add_choice("Overdue and due within 90 days|Overdue|Overdue and due within 30 days|All instruments");
+    filter.set_value(0);
+    filter_row.fixed(&filter, 280);
+    Frame::default();
+    filter_row.end();
+    layout.fixed(&filter_row, 25);
+
+    let mut tree: Tree = Tree::default();
+    tree.set_show_root(false);
+    tree.set_select_mode(TreeSelect::Single);
+    // the second click of a double-click lands on an item that is already selected
+    tree.set_item_reselect_mode(TreeItemReselectMode::Always);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    // double-click: the record in the tab the dashboard belongs to
+    let record_sql = {
+        let (table, connection_type): (String, ConnectionBase) = (table.to_string(), connection_type.clone());
+        move |key: &str| record_sql(&connection_type, &table, key)
+    };
+    let set_tree_callback = {
+        let sndr: Sender<Message> = sndr.clone();
+        move |tree: &mut Tree, index: HashMap<String, String>| {
+            let sndr: Sender<Message> = sndr.clone();
+            let record_sql = record_sql.clone();
+            tree.set_callback(move |tree| {
+                if !matches!(tree.callback_reason(), TreeReason::Selected | TreeReason::Reselected) || !app::event_clicks() {
+                    return;
+                }
+                let path: Option<String> = tree.first_selected_item().and_then(|item| tree.item_pathname(&item).ok());
+                if let Some(key) = path.and_then(|p| index.get(&p)) {
+                    sndr.send(Message::ShowRecord(tab, record_sql(key)));
+                }
+            });
+        }
+    };
+    let index: HashMap<String, String> = fill_tree(&mut tree, &instruments, &shown_buckets(0));
+    set_tree_callback(&mut tree, index);
+
+    filter.set_callback(move |choice| {
+        let index: HashMap<String, String> = fill_tree(&mut tree, &instruments, &shown_buckets(choice.value()));
+        set_tree_callback(&mut tree, index);
+    });
+    win.show();
+}
+
+/* <-- Functions */
@@ -0,0 +1,695 @@
+/* --> Summary of Contents
+
+	Process capability of measured dimensions against the specs table
+
+	specs holds lower_tolerance / nominal / upper_tolerance per prod_code
+	and dimension. The tolerances are offsets from nominal (-0.006, 0.704,
+	0.006); a row whose lower tolerance is above zero or whose upper one is
+	below zero already holds the limits themselves.
+
+	pub fn load_specs(&ConnectionBase, &str, &[String]) -> Result<Vec<Spec>, String>
+		The specs of the given products
+
+	pub fn samples(&RecordSet, &CapabilityRequest, &[Spec]) -> BTreeMap<(String, String), Vec<f64>>
+		Measurements by product and dimension. The result set is either
+		long (a dimension column and a value column) or wide (one column
+		per dimension, named as in specs). NULL and text are skipped.
+
+	pub fn analyze(BTreeMap<(String, String), Vec<f64>>, &[Spec]) -> Vec<Capability>
+		n, mean, sample standard deviation, Cp, Cpk, percent out of
+		tolerance and a histogram for every dimension
+
+	pub fn report(&[Capability]) -> RecordSet
+		The table of the report window as a result set for record_export
+
+	pub fn ask_request(&RecordSet, &[String], usize) -> Option<CapabilityRequest>
+		Modal dialog: where the product, dimension and value are in the
+		result and which tab's connection holds the specs
+
+	pub fn show_report(&str, Vec<Capability>)
+		Window with a row per dimension, Cpk colored, the histogram of the
+		selected row with its limits, and an Export button
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    button::Button,
+    dialog,
+    draw,
+    enums::{Align, Color, Font, FrameType},
+    frame::Frame,
+    group::Flex,
+    input::Input,
+    menu::Choice,
+    prelude::{GroupExt, InputExt, MenuExt, TableExt, WidgetBase, WidgetExt, WindowExt},
+    table::{TableContext, TableRow, TableRowSelectFlag, TableRowSelectMode},
+    window::Window,
+};
+use std::collections::BTreeMap;
+
+use crate::cli;
+use crate::learning_fltk::{center, escape_menu_label};
+use crate::record_export::{self, ExportFormat};
+use crate::schema_diff::{quote, quote_literal};
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, Record, RecordSet, SqlData, SqlType};
+use crate::sql_format::Dialect;
+use crate::sqlite3_interface::{SqliteFloat, SqliteInteger, SqliteNull, SqliteString, Type};
+
+/* <-- Imports */
+/* --> Const */
+
+const HEADERS: [&str; 13] = [
+    "Product", "Dimension", "n", "Mean", "Std Dev", "Min", "Max", "LSL", "Nominal", "USL", "Cp", "Cpk", "% Out",
+];
+const MIN_BINS: usize = 5;
+const MAX_BINS: usize = 20;
+const CAPABLE: f64 = 1.33; // the usual customer requirement for Cpk
+const WIDE: &str = "(one column per dimension)";
+const NO_COLUMN: &str = "(all rows are one product)";
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Layout {
+    Long { dimension: String, value: String },
+    Wide,
+}
+
+#[derive(Clone, Copy)]
+enum DialogMessage {
+    Analyze,
+    Cancel,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct Spec {
+    pub prod_code: String,
+    pub dimension: String,
+    pub lower_tolerance: f64,
+    pub nominal: f64,
+    pub upper_tolerance: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct CapabilityRequest {
+    pub product_column: Option<String>, // None: every row is `product`
+    pub product: String,
+    pub layout: Layout,
+    pub specs_tab: usize, // index into the tab list given to ask_request
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Histogram {
+    pub start: f64,
+    pub width: f64,
+    pub counts: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Capability {
+    pub prod_code: String,
+    pub dimension: String,
+    pub spec: Option<Spec>,
+    pub n: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub cp: Option<f64>,
+    pub cpk: Option<f64>,
+    pub out_of_tolerance: Option<f64>, // percent
+    pub histogram: Histogram,
+}
+
+impl Spec {
+    fn absolute(&self) -> bool {
+        self.lower_tolerance > 0.0 || self.upper_tolerance < 0.0
+    }
+
+    pub fn lsl(&self) -> f64 {
+        if self.absolute() {
+            self.lower_tolerance
+        } else {
+            self.nominal + self.lower_tolerance
+        }
+    }
+
+    pub fn usl(&self) -> f64 {
+        if self.absolute() {
+            self.upper_tolerance
+        } else {
+            self.nominal + self.upper_tolerance
+        }
+    }
+}
+
+impl Histogram {
+    pub fn bin_of(&self, value: f64) -> usize {
+        (((value - self.start) / self.width).floor().max(0.0) as usize).min(self.counts.len() - 1)
+    }
+}
+
+impl Capability {
+    // Cpk under 1 makes scrap, under 1.33 is marginal
+    fn color(&self) -> Color {
+        match self.cpk {
+            Some(cpk) if cpk < 1.0 => Color::from_rgb(255, 190, 190),
+            Some(cpk) if cpk < CAPABLE => Color::from_rgb(255, 236, 160),
+            Some(_) => Color::from_rgb(200, 235, 200),
+            None => Color::from_rgb(225, 225, 225),
+        }
+    }
+
+    fn cells(&self) -> Vec<String> {
+        let number = |value: f64| format!("{:.4}", value);
+        let optional = |value: Option<f64>, decimals: usize| value.map_or(String::new(), |v| format!("{:.*}", decimals, v));
+        vec![
+            self.prod_code.clone(),
+            self.dimension.clone(),
+            self.n.to_string(),
+            number(self.mean),
+            number(self.std_dev),
+            number(self.min),
+            number(self.max),
+            optional(self.spec.as_ref().map(Spec::lsl), 4),
+            optional(self.spec.as_ref().map(|s| s.nominal), 4),
+            optional(self.spec.as_ref().map(Spec::usl), 4),
+            optional(self.cp, 2),
+            optional(self.cpk, 2),
+            optional(self.out_of_tolerance, 1),
+        ]
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn number(record: &Record, column: &str) -> Option<f64> {
+    record.text(column)?.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn same(left: &str, right: &str) -> bool {
+    left.trim().eq_ignore_ascii_case(right.trim())
+}
+
+pub fn load_specs(connection_type: &ConnectionBase, connection: &str, products: &[String]) -> Result<Vec<Spec>, String> {
+    if products.is_empty() {
+        return Ok(Vec::new());
+    }
+    let dialect: Dialect = Dialect::of(connection_type);
+    let codes: Vec<String> = products.iter().map(|p| quote_literal(p)).collect();
+    let sql: String = format!(
+        "SELECT prod_code, dimension, lower_tolerance, nominal, upper_tolerance FROM {} WHERE prod_code IN ({})",
+        quote("specs", dialect),
+        codes.join(", ")
+    );
+    let record_set: RecordSet = cli::run_request(connection_type, connection, QueryType::UserDefined(sql))?;
+    Ok(record_set
+        .records
+        .iter()
+        .filter_map(|record| {
+            Some(Spec {
+                prod_code: record.text("prod_code")?.trim().to_string(),
+                dimension: record.text("dimension")?.trim().to_string(),
+                lower_tolerance: number(record, "lower_tolerance")?,
+                nominal: number(record, "nominal")?,
+                upper_tolerance: number(record, "upper_tolerance")?,
+            })
+        })
+        .collect())
+}
+
+// product codes of the result, in the order they first appear
+pub fn products(record_set: &RecordSet, request: &CapabilityRequest) -> Vec<String> {
+    let mut products: Vec<String> = Vec::new();
+    match &request.product_column {
+        Some(column) => {
+            for record in &record_set.records {
+                if let Some(code) = record.text(column).map(|c| c.trim().to_string()).filter(|c| !c.is_empty()) {
+                    if !products.contains(&code) {
+                        products.push(code);
+                    }
+                }
+            }
+        }
+        None => products.push(request.product.trim().to_string()),
+    }
+    products
+}
+
+pub fn samples(record_set: &RecordSet, request: &CapabilityRequest, specs: &[Spec]) -> BTreeMap<(String, String), Vec<f64>> {
+    let mut samples: BTreeMap<(String, String), Vec<f64>> = BTreeMap::new();
+    for record in &record_set.records {
+        let product: String = match &request.product_column {
+            Some(column) => match record.text(column) {
+                Some(code) if !code.trim().is_empty() => code.trim().to_string(),
+                _ => continue,
+            },
+            None => request.product.trim().to_string(),
+        };
+        match &request.layout {
+            Layout::Long { dimension, value } => {
+                if let (Some(name), Some(measured)) = (record.text(dimension), number(record, value)) {
+                    samples.entry((product, name.trim().to_string())).or_default().push(measured);
+                }
+            }
+            // the columns named like a dimension of this product's specs
+            Layout::Wide => {
+                for spec in specs.iter().filter(|s| same(&s.prod_code, &product)) {
+                    let column: Option<&String> = record_set.column_order.iter().find(|c| same(c, &spec.dimension));
+                    if let Some(measured) = column.and_then(|c| number(record, c)) {
+                        samples.entry((product.clone(), spec.dimension.clone())).or_default().push(measured);
+                    }
+                }
+            }
+        }
+    }
+    samples
+}
+
+// bins over the data and the limits together, so the limit lines fall inside the chart
+fn histogram(values: &[f64], spec: Option<&Spec>) -> Histogram {
+    let bins: usize = ((values.len() as f64).sqrt().ceil() as usize).clamp(MIN_BINS, MAX_BINS);
+    let mut low: f64 = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let mut high: f64 = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if let Some(spec) = spec {
+        low = low.min(spec.lsl());
+        high = high.max(spec.usl());
+    }
+    let width: f64 = if high > low { (high - low) / bins as f64 } else { 1.0 };
+    let start: f64 = if high > low { low } else { low - width * bins as f64 / 2.0 };
+    let mut histogram: Histogram = Histogram { start, width, counts: vec![0; bins] };
+    for value in values {
+        let bin: usize = histogram.bin_of(*value);
+        histogram.counts[bin] += 1;
+    }
+    histogram
+}
+
+pub fn analyze(samples: BTreeMap<(String, String), Vec<f64>>, specs: &[Spec]) -> Vec<Capability> {
+    samples
+        .into_iter()
+        .filter(|(_, values)| !values.is_empty())
+        .map(|((prod_code, dimension), values)| {
+            let spec: Option<Spec> = specs
+                .iter()
+                .find(|s| same(&s.prod_code, &prod_code) && same(&s.dimension, &dimension))
+                .cloned();
+            let n: usize = values.len();
+            let mean: f64 = values.iter().sum::<f64>() / n as f64;
+            // sample standard deviation, one value has none
+            let std_dev: f64 = match n {
+                1 => 0.0,
+                _ => (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1) as f64).sqrt(),
+            };
+            let (cp, cpk, out_of_tolerance): (Option<f64>, Option<f64>, Option<f64>) = match &spec {
+                Some(spec) => {
+                    let (lsl, usl): (f64, f64) = (spec.lsl(), spec.usl());
+                    let outside: usize = values.iter().filter(|v| **v < lsl || **v > usl).count();
+                    let spread: Option<f64> = (std_dev > 0.0).then_some(std_dev);
+                    (
+                        spread.map(|s| (usl - lsl) / (6.0 * s)),
+                        spread.map(|s| (usl - mean).min(mean - lsl) / (3.0 * s)),
+                        Some(outside as f64 * 100.0 / n as f64),
+                    )
+                }
+                None => (None, None, None),
+            };
+            Capability {
+                histogram: histogram(&values, spec.as_ref()),
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                prod_code,
+                dimension,
+                spec,
+                n,
+                mean,
+                std_dev,
+                cp,
+                cpk,
+                out_of_tolerance,
+            }
+        })
+        .collect()
+}
+
+// numbers stay numbers for the JSON export, the histogram goes along as "start width: counts"
+pub fn report(capabilities: &[Capability]) -> RecordSet {
+    let mut record_set: RecordSet = RecordSet::default();
+    for (index, header) in HEADERS.iter().chain(["Histogram"].iter()).enumerate() {
+        let data_type: Type = if (2..HEADERS.len()).contains(&index) { Type::Float } else { Type::String };
+        record_set.column_order.push(header.to_string());
+        record_set.column_info.insert(header.to_string(), SqlType::Sqlite(data_type));
+    }
+    for capability in capabilities {
+        let mut record: Record = Record::default();
+        for (header, cell) in HEADERS.iter().zip(capability.cells()) {
+            let value: SqlData = match (header, cell.parse::<f64>()) {
+                (&"Product" | &"Dimension", _) => SqlData::Sqlite(SqliteString(cell)),
+                (&"n", _) => SqlData::Sqlite(SqliteInteger(capability.n as i64)),
+                (_, Ok(number)) => SqlData::Sqlite(SqliteFloat(number)),
+                (_, Err(_)) => SqlData::Sqlite(SqliteNull),
+            };
+            record.columns.insert(header.to_string(), Some(value));
+        }
+        let counts: Vec<String> = capability.histogram.counts.iter().map(|c| c.to_string()).collect();
+        let histogram: String = format!("{:.4} {:.4}: {}", capability.histogram.start, capability.histogram.width, counts.join(" "));
+        record.columns.insert(String::from("Histogram"), Some(SqlData::Sqlite(SqliteString(histogram))));
+        record_set.records.push(record);
+    }
+    record_set
+}
+
+fn find_column(columns: &[String], names: &[&str]) -> Option<usize> {
+    names.iter().find_map(|name| columns.iter().position(|c| same(c, name)))
+}
+
+pub fn ask_request(record_set: &RecordSet, tabs: &[String], active: usize) -> Option<CapabilityRequest> {
+    let columns: &[String] = &record_set.column_order;
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+    let mut win: Window = Window::default()
+        .with_size(520, 235)
+        .with_label("Capability Analysis")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut product_column: Choice = Choice::new(130, 10, 380, 25, "Product column");
+    let mut product: Input = Input::new(130, 40, 380, 25, "Product code");
+    let mut dimension_column: Choice = Choice::new(130, 75, 380, 25, "Dimension column");
+    let mut value_column: Choice = Choice::new(130, 105, 380, 25, "Value column");
+    let mut specs_tab: Choice = Choice::new(130, 140, 380, 25, "Specs from");
+    product.set_tooltip("Used when the result has no product column");
+    specs_tab.set_tooltip("The tab connected to the database with the specs table");
+
+    product_column.add_choice(NO_COLUMN);
+    dimension_column.add_choice(WIDE);
+    for column in columns {
+        let label: String = escape_menu_label(&column.replace('|', " "));
+        product_column.add_choice(&label);
+        dimension_column.add_choice(&label);
+        value_column.add_choice(&label);
+    }
+    for tab in tabs {
+        specs_tab.add_choice(&escape_menu_label(&tab.replace('|', " ")));
+    }
+    // the names the sample database uses
+    product_column.set_value(find_column(columns, &["prod_code", "mrp_prod_code", "sfol_prod_code", "product"]).map_or(0, |i| i as i32 + 1));
+    dimension_column.set_value(find_column(columns, &["dimension", "feature", "characteristic"]).map_or(0, |i| i as i32 + 1));
+    value_column.set_value(
+        find_column(columns, &["value", "measurement", "measured", "result", "reading"])
+            .or_else(|| (!columns.is_empty()).then(|| columns.len() - 1))
+            .map_or(-1, |i| i as i32),
+    );
+    specs_tab.set_value(active as i32);
+
+    let mut analyze_butn: Button = Button::new(350, 200, 75, 28, "&Analyze");
+    analyze_butn.emit(sndr, DialogMessage::Analyze);
+    let mut cancel_butn: Button = Button::new(435, 200, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+    win.end();
+    win.show();
+
+    let column_at = |choice: &Choice, offset: i32| usize::try_from(choice.value() - offset).ok().and_then(|i| columns.get(i)).cloned();
+    let mut request: Option<CapabilityRequest> = None;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::Analyze) => {
+                let layout: Layout = match (column_at(&dimension_column, 1), column_at(&value_column, 0)) {
+                    (Some(dimension), Some(value)) => Layout::Long { dimension, value },
+                    (Some(_), None) => {
+                        alert("Choose the column that holds the measured values.");
+                        continue;
+                    }
+                    (None, _) => Layout::Wide,
+                };
+                let product_column: Option<String> = column_at(&product_column, 1);
+                if product_column.is_none() && product.value().trim().is_empty() {
+                    alert("Choose the product column or enter the product code.");
+                    continue;
+                }
+                request = Some(CapabilityRequest {
+                    product_column,
+                    product: product.value(),
+                    layout,
+                    specs_tab: usize::try_from(specs_tab.value()).unwrap_or(active),
+                });
+                win.hide();
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            None => {}
+        }
+    }
+    request
+}
+
+fn alert(message: &str) {
+    let (x, y): (i32, i32) = center();
+    dialog::alert(x - 200, y - 100, message);
+}
+
+fn draw_histogram(capability: &Capability, x: i32, y: i32, w: i32, h: i32) {
+    draw::draw_rect_fill(x, y, w, h, Color::White);
+    let histogram: &Histogram = &capability.histogram;
+    let (left, bottom, width, height): (i32, i32, i32, i32) = (x + 40, y + h - 25, w - 60, h - 45);
+    let tallest: usize = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    let bar_width: f64 = width as f64 / histogram.counts.len() as f64;
+    let to_x = |value: f64| left + ((value - histogram.start) / (histogram.width * histogram.counts.len() as f64) * width as f64) as i32;
+
+    draw::set_font(Font::Helvetica, 11);
+    for (bin, count) in histogram.counts.iter().enumerate() {
+        let bar_height: i32 = (*count as f64 / tallest as f64 * height as f64) as i32;
+        let bar_x: i32 = left + (bin as f64 * bar_width) as i32;
+        draw::draw_rect_fill(bar_x, bottom - bar_height, bar_width.ceil() as i32, bar_height, Color::from_rgb(120, 160, 210));
+        draw::set_draw_color(Color::Dark3);
+        draw::draw_rect(bar_x, bottom - bar_height, bar_width.ceil() as i32, bar_height);
+    }
+    draw::set_draw_color(Color::Black);
+    draw::draw_line(left, bottom, left + width, bottom);
+    draw::draw_text2(&tallest.to_string(), x, bottom - height, 35, 14, Align::Right);
+    draw::draw_text2("0", x, bottom - 14, 35, 14, Align::Right);
+    let end: f64 = histogram.start + histogram.width * histogram.counts.len() as f64;
+    draw::draw_text2(&format!("{:.4}", histogram.start), left - 30, bottom + 4, 60, 14, Align::Center);
+    draw::draw_text2(&format!("{:.4}", end), left + width - 30, bottom + 4, 60, 14, Align::Center);
+
+    // the limits in red, nominal and mean dashed
+    let marker = |value: f64, color: Color, style: draw::LineStyle, label: &str| {
+        let marker_x: i32 = to_x(value);
+        draw::set_draw_color(color);
+        draw::set_line_style(style, 2);
+        draw::draw_line(marker_x, bottom, marker_x, bottom - height - 5);
+        draw::set_line_style(draw::LineStyle::Solid, 0);
+        draw::draw_text2(label, marker_x - 30, y + 2, 60, 12, Align::Center);
+    };
+    if let Some(spec) = &capability.spec {
+        marker(spec.lsl(), Color::Red, draw::LineStyle::Solid, "LSL");
+        marker(spec.usl(), Color::Red, draw::LineStyle::Solid, "USL");
+        marker(spec.nominal, Color::DarkGreen, draw::LineStyle::Dash, "Nominal");
+    }
+    marker(capability.mean, Color::Blue, draw::LineStyle::Dot, "Mean");
+}
+
+pub fn show_report(label: &str, capabilities: Vec<Capability>) {
+    let mut win: Window = Window::default()
+        .with_size(900, 620)
+        .with_label(&format!("Capability: {}", label))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let without_spec: usize = capabilities.iter().filter(|c| c.spec.is_none()).count();
+    let below: usize = capabilities.iter().filter(|c| c.cpk.is_some_and(|cpk| cpk < CAPABLE)).count();
+    let mut summary: Frame = Frame::default().with_label(&format!(
+        "{} dimensions, {} with Cpk below {}, {} without a spec",
+        capabilities.len(),
+        below,
+        CAPABLE,
+        without_spec
+    ));
+    summary.set_align(Align::Inside | Align::Left);
+    layout.fixed(&summary, 25);
+
+    let mut table: TableRow = TableRow::default();
+    let mut chart: Frame = Frame::default();
+    chart.set_frame(FrameType::DownBox);
+    layout.fixed(&chart, 220);
+    let mut buttons: Flex = Flex::default().row();
+    Frame::default();
+    let mut export_butn: Button = Button::default().with_label("&Export...");
+    buttons.fixed(&export_butn, 90);
+    buttons.end();
+    layout.fixed(&buttons, 30);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    let rows: Vec<Vec<String>> = capabilities.iter().map(Capability::cells).collect();
+    let colors: Vec<Color> = capabilities.iter().map(Capability::color).collect();
+    table.set_rows(rows.len() as i32);
+    table.set_cols(HEADERS.len() as i32);
+    table.set_col_header(true);
+    table.set_col_resize(true);
+    table.set_row_height_all(22);
+    table.set_type(TableRowSelectMode::Single);
+    for (index, header) in HEADERS.iter().enumerate() {
+        let widest: usize = rows.iter().map(|cells| cells[index].chars().count()).fold(header.chars().count(), usize::max);
+        table.set_col_width(index as i32, (widest as i32 * 8 + 16).clamp(50, 200));
+    }
+    table.end();
+    table.draw_cell(move |table, context, row, col, x, y, w, h| match context {
+        TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+        TableContext::ColHeader => {
+            draw::draw_box(FrameType::ThinUpBox, x, y, w, h, Color::FrameDefault);
+            draw::set_draw_color(Color::Black);
+            draw::draw_text2(HEADERS[col as usize], x + 4, y, w - 8, h, Align::Left);
+        }
+        TableContext::Cell => {
+            // Cp, Cpk and % Out carry the color, the selected row is shaded
+            let background: Color = match (table.row_selected(row), col >= 10) {
+                (true, _) => Color::from_rgb(210, 225, 245),
+                (false, true) => colors[row as usize],
+                (false, false) => Color::White,
+            };
+            let align: Align = if col >= 2 { Align::Right } else { Align::Left };
+            draw::push_clip(x, y, w, h);
+            draw::draw_rect_fill(x, y, w, h, background);
+            draw::set_draw_color(Color::Black);
+            draw::draw_text2(&rows[row as usize][col as usize], x + 4, y, w - 8, h, align);
+            draw::set_draw_color(Color::Light2);
+            draw::draw_rect(x, y, w, h);
+            draw::pop_clip();
+        }
+        _ => {}
+    });
+    if !capabilities.is_empty() {
+        let _ = table.select_row(0, TableRowSelectFlag::Select);
+    }
+
+    let report_set: RecordSet = report(&capabilities);
+    chart.draw({
+        let mut table: TableRow = table.clone();
+        move |frame| {
+            let selected: Option<&Capability> = (0..table.rows()).find(|r| table.row_selected(*r)).and_then(|r| capabilities.get(r as usize));
+            match selected {
+                Some(capability) => draw_histogram(capability, frame.x() + 2, frame.y() + 2, frame.w() - 4, frame.h() - 4),
+                None => {
+                    draw::set_draw_color(Color::Dark3);
+                    draw::draw_text2("Select a dimension", frame.x(), frame.y(), frame.w(), frame.h(), Align::Center);
+                }
+            }
+        }
+    });
+    table.set_callback({
+        let mut chart: Frame = chart.clone();
+        move |_| chart.redraw()
+    });
+
+    export_butn.set_callback(move |_| {
+        let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+        chooser.set_title("Export the capability report");
+        chooser.set_filter("CSV\t*.csv\nTSV\t*.tsv\nJSON\t*.json\nText table\t*.txt");
+        chooser.set_option(dialog::FileDialogOptions::SaveAsConfirm);
+        chooser.show();
+        let path: String = chooser.filename().to_string_lossy().into_owned();
+        if path.is_empty() {
+            return;
+        }
+        let format: ExportFormat = ExportFormat::from_path(&path)
+            .or_else(|| path.ends_with(".txt").then_some(ExportFormat::Table))
+            .unwrap_or(ExportFormat::Csv);
+        match std::fs::write(&path, record_export::render(&report_set, format)) {
+            Ok(_) => summary.set_label(&format!("Report written to {}", path)),
+            Err(e) => println!("Unable to write {}: {}", path, e),
+        }
+    });
+    win.show();
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(lower_tolerance: f64, nominal: f64, upper_tolerance: f64) -> Spec {
+        Spec {
+            prod_code: String::from("CI020"),
+            dimension: String::from("OD"),
+            lower_tolerance,
+            nominal,
+            upper_tolerance,
+        }
+    }
+
+    fn analyze_one(values: &[f64], specs: &[Spec]) -> Capability {
+        let samples: BTreeMap<(String, String), Vec<f64>> =
+            BTreeMap::from([((String::from("ci020"), String::from("od")), values.to_vec())]);
+        analyze(samples, specs).remove(0)
+    }
+
+    fn close(left: Option<f64>, right: f64) -> bool {
+        left.is_some_and(|l| (l - right).abs() < 1e-9)
+    }
+
+    #[test]
+    fn limits_from_offsets_or_absolute() {
+        let offsets: Spec = spec(-0.006, 0.704, 0.006);
+        assert!(close(Some(offsets.lsl()), 0.698) && close(Some(offsets.usl()), 0.710));
+        let absolute: Spec = spec(0.698, 0.704, 0.710);
+        assert!(close(Some(absolute.lsl()), 0.698) && close(Some(absolute.usl()), 0.710));
+    }
+
+    #[test]
+    fn cp_and_cpk() {
+        let capability: Capability = analyze_one(&[0.700, 0.704, 0.708], &[spec(-0.006, 0.704, 0.006)]);
+        assert_eq!(capability.n, 3);
+        assert!(close(Some(capability.mean), 0.704) && close(Some(capability.std_dev), 0.004));
+        assert!(close(capability.cp, 0.5) && close(capability.cpk, 0.5));
+        assert!(close(capability.out_of_tolerance, 0.0));
+
+        // off center, Cpk follows the nearer limit
+        let capability: Capability = analyze_one(&[0.703, 0.707, 0.711], &[spec(-0.006, 0.704, 0.006)]);
+        assert!(close(capability.cp, 0.5) && close(capability.cpk, 0.25));
+        assert!(close(capability.out_of_tolerance, 100.0 / 3.0));
+    }
+
+    #[test]
+    fn no_cp_without_spread() {
+        // one value has no sample standard deviation
+        let capability: Capability = analyze_one(&[0.712], &[spec(-0.006, 0.704, 0.006)]);
+        assert_eq!((capability.std_dev, capability.cp, capability.cpk), (0.0, None, None));
+        assert!(close(capability.out_of_tolerance, 100.0));
+        assert_eq!(capability.histogram.counts.iter().sum::<usize>(), 1);
+
+        let capability: Capability = analyze_one(&[0.5; 4], &[spec(-0.25, 0.5, 0.25)]);
+        assert_eq!((capability.std_dev, capability.cp, capability.cpk), (0.0, None, None));
+        assert!(close(capability.out_of_tolerance, 0.0));
+    }
+
+    #[test]
+    fn no_spec_no_indices() {
+        let capability: Capability = analyze_one(&[1.0, 2.0], &[]);
+        assert_eq!((capability.cp, capability.cpk, capability.out_of_tolerance), (None, None, None));
+        assert!(analyze(BTreeMap::from([((String::from("x"), String::from("y")), Vec::new())]), &[]).is_empty());
+    }
+
+    #[test]
+    fn histogram_covers_data_and_limits() {
+        let values: Vec<f64> = (0..30).map(|i| 0.700 + i as f64 * 0.0003).collect();
+        let limits: Spec = spec(-0.006, 0.704, 0.006);
+        let histogram: Histogram = histogram(&values, Some(&limits));
+        assert_eq!(histogram.counts.len(), 6);
+        assert_eq!(histogram.counts.iter().sum::<usize>(), values.len());
+        assert!(close(Some(histogram.start), limits.lsl()));
+        assert!(histogram.start + histogram.width * 6.0 >= limits.usl() - 1e-12);
+    }
+}
+
+/* <-- Tests */
@@ -0,0 +1,506 @@
+/* --> Summary of Contents
+
+	Headless mode: run SQL against a connection and write the result,
+	no display needed (nightly reports from cron / the task scheduler)
+
+	pub fn run(&[String]) -> i32
+		Parse the arguments, run the statements, write the last result
+		set. Returns the process exit code: 0 ok, 1 the connection or a
+		statement failed, 2 bad arguments.
+
+	pub fn connect(&Target, bool) -> Result<(ConnectionBase, String), String>
+	pub fn run_request(&ConnectionBase, &str, QueryType) -> Result<RecordSet, String>
+	pub fn run_read_only(&ConnectionBase, &str, QueryType) -> Result<RecordSet, String>
+	pub fn stream_rows(&ConnectionBase, &str, bool, &str, usize, &mut dyn FnMut(RecordSet) -> bool) -> Result<(), String>
+		The rows of a query batch by batch, for loads too big to hold
+	pub fn target_safety(&Target) -> Safety
+	pub struct Session
+		One connection for a whole script or REPL session, so BEGIN and
+		COMMIT, temp tables and T-SQL variables carry between statements.
+		Shared with the REPL (repl.rs), which asks on the terminal for the
+		logins the environment does not hold
+
+	Logins never go on the command line, where other users can see them:
+		DAEDRIVICTUS_USER / DAEDRIVICTUS_PASSWORD  prompt profiles and --odbc
+		DAEDRIVICTUS_PASSPHRASE                    unlocks stored credentials
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{self, Read, Write},
+};
+
+use crate::audit_log;
+use crate::connection_profiles::{ConnectionProfile, ProfileStore};
+use crate::credentials::{self, CredentialMode, CredentialStore, Credentials};
+use crate::date_columns::{DateColumns, DateSettings};
+use crate::line_editor::{self, LineEditor, ReadResult};
+use crate::profile_manager;
+use crate::query_library::{self, QueryLibrary, SavedQuery};
+use crate::record_export::{self, ExportFormat};
+use crate::repl;
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, RecordSet};
+use crate::sql_lexer;
+use crate::statement_guard::{self, Safety};
+
+/* <-- Imports */
+/* --> Const */
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+
+const ENV_USER: &str = "DAEDRIVICTUS_USER";
+const ENV_PASSWORD: &str = "DAEDRIVICTUS_PASSWORD";
+const ENV_PASSPHRASE: &str = "DAEDRIVICTUS_PASSPHRASE";
+
+const USAGE: &str = "\
+Usage: DaedriVictus [connection] [sql] [options]
+       DaedriVictus --repl [connection]
+
+Connection (one of):
+  --profile NAME        saved connection from the Connection Manager
+  --sqlite FILE         SQLite database file
+  --odbc CONNSTR        ODBC connection string, e.g. \"DSN=Production;\"
+
+SQL (one of):
+  --sql TEXT            statements to run, separated by ;
+  --file PATH           read the statements from a file, - for stdin
+  --saved FOLDER/NAME   query from the query library
+
+Options:
+  --param NAME=VALUE    value for a :NAME parameter, repeatable
+  --format FORMAT       csv, tsv, json or table, defaults to the --output
+                        extension or csv
+  --output PATH         write the result to a file instead of stdout
+  --iso-dates           write text date columns (1/1/2024, Excel serials)
+                        as YYYY-MM-DD, see Tools/Date Columns
+  --repl                interactive shell, .help lists its commands,
+                        --format sets its starting .mode
+  --help                this text
+
+The script runs on one connection, statement by statement on SQLite and
+batch by batch (split on GO lines) over ODBC. A failed statement stops it,
+a transaction the script began and did not commit is rolled back.
+Only the result set of the last statement that returns rows is written.
+Exit codes: 0 ok, 1 the connection or a statement failed, 2 bad arguments.";
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Debug, PartialEq)]
+pub enum Target {
+    Profile(String),
+    Sqlite(String),
+    Odbc(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SqlSource {
+    Text(String),
+    File(String),
+    Saved(String),
+}
+
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+enum SessionHandle {
+    Sqlite(sqlite::Connection),
+    Odbc(odbc::Connection<'static, odbc::odbc_safe::AutocommitOn>),
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CliOptions {
+    pub target: Option<Target>,
+    pub source: Option<SqlSource>,
+    pub params: Vec<(String, String)>,
+    pub format: Option<ExportFormat>,
+    pub output: Option<String>,
+    pub help: bool,
+    pub repl: bool,
+    pub iso_dates: bool,
+}
+
+// dropping a session closes its connection, which rolls back a transaction left open
+pub struct Session {
+    pub connection_type: ConnectionBase,
+    pub connection: String,
+    handle: SessionHandle,
+}
+
+impl Session {
+    // a read-only profile opens the database read-only, as run_read_only does
+    pub fn open(connection_type: &ConnectionBase, connection: &str, read_only: bool) -> Result<Session, String> {
+        let handle: SessionHandle = match connection_type {
+            ConnectionBase::Sqlite => crate::sqlite3_interface::open_session(connection, read_only)
+                .map(SessionHandle::Sqlite)
+                .map_err(|e| e.to_string())?,
+            ConnectionBase::Odbc => crate::odbc_interface::open_session(connection, read_only)
+                .map(SessionHandle::Odbc)
+                .map_err(|e| e.to_string())?,
+        };
+        Ok(Session { connection_type: connection_type.clone(), connection: connection.to_string(), handle })
+    }
+
+    pub fn run(&self, request: QueryType) -> Result<RecordSet, String> {
+        match &self.handle {
+            SessionHandle::Sqlite(db_handle) => crate::sqlite3_interface::run_request(db_handle, request).map_err(|e| e.to_string()),
+            SessionHandle::Odbc(conn) => crate::odbc_interface::session_request(conn, request).map_err(|e| e.to_string()),
+        }
+    }
+
+    // SQLite prepares one statement at a time, a T-SQL batch goes whole so its variables live through it
+    pub fn pieces(&self, sql: &str) -> Vec<(usize, usize)> {
+        match self.connection_type {
+            ConnectionBase::Sqlite => sql_lexer::statement_ranges(sql),
+            ConnectionBase::Odbc => sql_lexer::batch_ranges(sql),
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn run(args: &[String]) -> i32 {
+    let result = parse_args(args).map_err(CliError::Usage).and_then(|options| {
+        if options.help {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        if options.repl {
+            return repl::run(options.target.as_ref(), options.format).map_err(CliError::Failed);
+        }
+        execute(&options)
+    });
+    match result {
+        Ok(_) => EXIT_OK,
+        Err(CliError::Usage(message)) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            EXIT_USAGE
+        }
+        Err(CliError::Failed(message)) => {
+            eprintln!("{}", credentials::redact(&message));
+            EXIT_FAILED
+        }
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<CliOptions, String> {
+    let mut options: CliOptions = CliOptions::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // --name=value and --name value are both accepted
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        if name == "--help" || name == "-h" {
+            options.help = true;
+            continue;
+        }
+        if name == "--repl" {
+            options.repl = true;
+            continue;
+        }
+        if name == "--iso-dates" {
+            options.iso_dates = true;
+            continue;
+        }
+        let mut value = || -> Result<String, String> {
+            match inline.clone().or_else(|| args.next().cloned()) {
+                Some(value) => Ok(value),
+                None => Err(format!("{} needs a value", name)),
+            }
+        };
+        match name {
+            "--profile" => set_once(&mut options.target, Target::Profile(value()?), "connection")?,
+            "--sqlite" => set_once(&mut options.target, Target::Sqlite(value()?), "connection")?,
+            "--odbc" => set_once(&mut options.target, Target::Odbc(value()?), "connection")?,
+            "--sql" => set_once(&mut options.source, SqlSource::Text(value()?), "SQL source")?,
+            "--file" => set_once(&mut options.source, SqlSource::File(value()?), "SQL source")?,
+            "--saved" => set_once(&mut options.source, SqlSource::Saved(value()?), "SQL source")?,
+            "--param" => {
+                let pair: String = value()?;
+                match pair.split_once('=') {
+                    Some((key, value)) if !key.trim().is_empty() => options
+                        .params
+                        .push((key.trim().trim_start_matches(':').to_string(), value.to_string())),
+                    _ => return Err(format!("--param expects NAME=VALUE, got {}", pair)),
+                }
+            }
+            "--format" => {
+                let format: String = value()?;
+                options.format = Some(ExportFormat::from_name(&format).ok_or(format!("Unknown format {}", format))?);
+            }
+            "--output" => options.output = Some(value()?),
+            _ => return Err(format!("Unknown argument {}", arg)),
+        }
+    }
+    if options.help {
+        return Ok(options);
+    }
+    if options.repl {
+        // the shell connects later with .connect when no connection is given
+        if options.source.is_some() || options.output.is_some() {
+            return Err(String::from("--repl takes only a connection, use .output and .mode inside the shell"));
+        }
+        return Ok(options);
+    }
+    if options.target.is_none() {
+        return Err(String::from("No connection given, use --profile, --sqlite or --odbc"));
+    }
+    if options.source.is_none() {
+        return Err(String::from("No SQL given, use --sql, --file or --saved"));
+    }
+    Ok(options)
+}
+
+fn set_once<T>(slot: &mut Option<T>, value: T, what: &str) -> Result<(), String> {
+    if slot.is_some() {
+        return Err(format!("Only one {} can be given", what));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+fn execute(options: &CliOptions) -> Result<(), CliError> {
+    let (connection_type, connection) = resolve_connection(options.target.as_ref().unwrap(), false)?;
+    let sql: String = load_sql(options.source.as_ref().unwrap(), &options.params)?;
+    // nobody is there to confirm, a production DELETE without WHERE is refused
+    let safety: Safety = target_safety(options.target.as_ref().unwrap());
+    statement_guard::check(safety, &sql, |_| false).map_err(CliError::Failed)?;
+
+    // every statement runs on the one connection, the last one that returns columns is the report.
+    // on a failure the session is dropped with the error, rolling back what the script left open
+    let session: Session = Session::open(&connection_type, &connection, safety.read_only).map_err(CliError::Failed)?;
+    let mut report: RecordSet = RecordSet::default();
+    let statements: Vec<(usize, usize)> = session.pieces(&sql);
+    for (index, (start, end)) in statements.iter().enumerate() {
+        let request: QueryType = QueryType::UserDefined(sql[*start..*end].to_string());
+        let result: Result<RecordSet, String> = if safety.read_only {
+            session.run(request)
+        } else {
+            audit_log::run_audited(&session, &audit_log::os_user(), request)
+        };
+        let record_set: RecordSet = result
+            .map_err(|e| CliError::Failed(format!("Statement {} of {} failed: {}", index + 1, statements.len(), e)))?;
+        if !record_set.column_order.is_empty() {
+            report = record_set;
+        }
+    }
+
+    let format: ExportFormat = options
+        .format
+        .or_else(|| options.output.as_deref().and_then(ExportFormat::from_path))
+        .unwrap_or(ExportFormat::Csv);
+    if options.iso_dates {
+        let dates: DateColumns = report.date_columns(&DateSettings::load());
+        report = report.with_iso_dates(&dates);
+    }
+    let text: String = record_export::render(&report, format);
+    match &options.output {
+        Some(path) => {
+            fs::write(path, text).map_err(|e| CliError::Failed(format!("Unable to write {}: {}", path, e)))?;
+            eprintln!("{} rows written to {}", report.records.len(), path);
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(text.as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(|e| CliError::Failed(format!("Unable to write the result: {}", e)))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run_request(connection_type: &ConnectionBase, connection: &str, request: QueryType) -> Result<RecordSet, String> {
+    match connection_type {
+        ConnectionBase::Sqlite => crate::sqlite3_interface::query_interface(connection.to_string(), request)
+            .map_err(|e| e.to_string()),
+        ConnectionBase::Odbc => crate::odbc_interface::entry_point(connection.to_string(), request)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+// a read-only profile opens the database read-only as well
+pub fn run_read_only(connection_type: &ConnectionBase, connection: &str, request: QueryType) -> Result<RecordSet, String> {
+    match connection_type {
+        ConnectionBase::Sqlite => crate::sqlite3_interface::read_only_query(connection.to_string(), request)
+            .map_err(|e| e.to_string()),
+        ConnectionBase::Odbc => crate::odbc_interface::read_only_entry_point(connection.to_string(), request)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+pub fn stream_rows(
+    connection_type: &ConnectionBase,
+    connection: &str,
+    read_only: bool,
+    sql: &str,
+    batch_size: usize,
+    each: &mut dyn FnMut(RecordSet) -> bool,
+) -> Result<(), String> {
+    match connection_type {
+        ConnectionBase::Sqlite => crate::sqlite3_interface::stream_rows(connection, read_only, sql, batch_size, each)
+            .map_err(|e| e.to_string()),
+        ConnectionBase::Odbc => crate::odbc_interface::stream_rows(connection, read_only, sql, batch_size, each)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+// only saved profiles carry the read-only and production flags
+pub fn target_safety(target: &Target) -> Safety {
+    match target {
+        Target::Profile(name) => ProfileStore::load().get(name).map(|p| p.safety()).unwrap_or_default(),
+        Target::Sqlite(_) | Target::Odbc(_) => Safety::default(),
+    }
+}
+
+fn env_login() -> Option<Credentials> {
+    let password: String = env::var(ENV_PASSWORD).ok()?;
+    Some(Credentials {
+        user: env::var(ENV_USER).unwrap_or_default(),
+        password,
+    })
+}
+
+// asked on the terminal, for the REPL
+fn terminal_login(name: &str) -> Option<Credentials> {
+    println!("{} asks for a login", name);
+    let user: String = match LineEditor::new(None).read_line("User: ") {
+        Ok(ReadResult::Line(user)) => user.trim().to_string(),
+        _ => return None,
+    };
+    let password: String = line_editor::read_password("Password: ").ok().flatten()?;
+    Some(Credentials { user, password })
+}
+
+pub fn connect(target: &Target, interactive: bool) -> Result<(ConnectionBase, String), String> {
+    resolve_connection(target, interactive).map_err(|e| match e {
+        CliError::Usage(message) | CliError::Failed(message) => message,
+    })
+}
+
+fn resolve_connection(target: &Target, interactive: bool) -> Result<(ConnectionBase, String), CliError> {
+    match target {
+        Target::Sqlite(path) => {
+            if !std::path::Path::new(path).exists() {
+                // sqlite would quietly create an empty database
+                return Err(CliError::Failed(format!("{} does not exist", path)));
+            }
+            Ok((ConnectionBase::Sqlite, path.clone()))
+        }
+        Target::Odbc(conn_str) => Ok((
+            ConnectionBase::Odbc,
+            match env_login() {
+                Some(login) => credentials::apply_credentials(conn_str, &login),
+                None => conn_str.clone(),
+            },
+        )),
+        Target::Profile(name) => {
+            let store: ProfileStore = ProfileStore::load();
+            let profile: &ConnectionProfile = store
+                .get(name)
+                .ok_or_else(|| CliError::Usage(format!("No saved connection named {}", name)))?;
+            let login: Option<Credentials> = match profile.login {
+                CredentialMode::None => None,
+                CredentialMode::Prompt => Some(
+                    env_login()
+                        .or_else(|| if interactive { terminal_login(name) } else { None })
+                        .ok_or_else(|| {
+                            CliError::Failed(format!("{} asks for a login, set {} and {}", name, ENV_USER, ENV_PASSWORD))
+                        })?,
+                ),
+                CredentialMode::Stored => {
+                    let asked = || if interactive { line_editor::read_password("Master passphrase: ").ok().flatten() } else { None };
+                    let passphrase: String = env::var(ENV_PASSPHRASE).ok().or_else(asked).ok_or_else(|| {
+                        CliError::Failed(format!("{} uses stored credentials, set {} to unlock them", name, ENV_PASSPHRASE))
+                    })?;
+                    let unlocked: CredentialStore = CredentialStore::unlock(&passphrase).map_err(CliError::Failed)?;
+                    Some(
+                        unlocked
+                            .get(name)
+                            .cloned()
+                            .ok_or_else(|| CliError::Failed(format!("No stored credentials for {}", name)))?,
+                    )
+                }
+            };
+            Ok((profile.connection_base(), profile_manager::connection_string_for(profile, login.as_ref())))
+        }
+    }
+}
+
+fn load_sql(source: &SqlSource, params: &[(String, String)]) -> Result<String, CliError> {
+    let mut values: HashMap<String, String> = HashMap::new();
+    let sql: String = match source {
+        SqlSource::Text(sql) => sql.clone(),
+        SqlSource::File(path) if path == "-" => {
+            let mut sql: String = String::new();
+            io::stdin()
+                .read_to_string(&mut sql)
+                .map_err(|e| CliError::Failed(format!("Unable to read stdin: {}", e)))?;
+            sql
+        }
+        SqlSource::File(path) => {
+            fs::read_to_string(path).map_err(|e| CliError::Failed(format!("Unable to read {}: {}", path, e)))?
+        }
+        SqlSource::Saved(path) => {
+            let query: SavedQuery = find_saved(path)?;
+            // the saved defaults, overridden by --param below
+            values.extend(query.params.iter().filter(|(_, v)| !v.is_empty()).cloned());
+            query.sql
+        }
+    };
+    values.extend(params.iter().cloned());
+
+    let missing: Vec<String> = query_library::detect_parameters(&sql)
+        .into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(CliError::Usage(format!("No value for :{}, pass --param {}=...", missing.join(", :"), missing[0])));
+    }
+    Ok(query_library::substitute_parameters(&sql, &values))
+}
+
+// "Calibration/Due this month", or just the name when it is unique
+fn find_saved(path: &str) -> Result<SavedQuery, CliError> {
+    let library: QueryLibrary = QueryLibrary::open_default()
+        .map_err(|e| CliError::Failed(format!("Query library unavailable: {}", e.message.unwrap_or_default())))?;
+    let queries: Vec<SavedQuery> = library
+        .queries()
+        .map_err(|e| CliError::Failed(format!("Unable to read the query library: {}", e.message.unwrap_or_default())))?;
+    let wanted: &str = path.trim().trim_matches('/');
+    let full_path = |q: &SavedQuery| {
+        if q.folder_path.is_empty() {
+            q.name.clone()
+        } else {
+            format!("{}/{}", q.folder_path, q.name)
+        }
+    };
+    if let Some(query) = queries.iter().find(|q| full_path(q) == wanted) {
+        return Ok(query.clone());
+    }
+    let by_name: Vec<&SavedQuery> = queries.iter().filter(|q| q.name == wanted).collect();
+    match by_name.as_slice() {
+        [query] => Ok((*query).clone()),
+        [] => Err(CliError::Usage(format!("No saved query {}", wanted))),
+        _ => Err(CliError::Usage(format!(
+            "{} is in more than one folder, use one of: {}",
+            wanted,
+            by_name.iter().map(|q| full_path(q)).collect::<Vec<String>>().join(", ")
+        ))),
+    }
+}
+
+/* <-- Functions */
@@ -0,0 +1,296 @@
+/* --> Summary of Contents
+
+	ConnectionProfile
+		A named connection: a SQLite file, an ODBC DSN or a full
+		driver based ODBC connection string. A read-only profile is
+		opened read-only and refuses writes, a production profile asks
+		before a DELETE without WHERE (statement_guard.rs).
+
+	ProfileStore
+		All saved profiles plus the most recently used list, persisted
+		to connections.ini in the config directory.
+
+	pub fn test_profile(&ConnectionProfile, String) -> Result<(), String>
+		Open the connection once and report whether it worked.
+		Errors are redacted, they may echo the connection string.
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use crate::app_paths;
+use crate::credentials::{self, CredentialMode};
+use crate::ini_file::{IniDocument, IniSection};
+use crate::sql_aux_funcs::ConnectionBase;
+use crate::statement_guard::Safety;
+use std::path::{Path, PathBuf};
+
+/* <-- Imports */
+/* --> Const */
+
+const PROFILE_FILE: &str = "connections.ini";
+const PROFILE_PREFIX: &str = "profile:";
+const RECENT_SECTION: &str = "recent";
+const MAX_RECENT: usize = 8;
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, PartialEq)]
+pub enum ProfileTarget {
+    SqliteFile(String),   // path to the .db file
+    Dsn(String),          // name of a configured ODBC data source
+    DriverString(String), // full ODBC connection string, Driver={...};Server=...
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone)]
+pub struct ConnectionProfile {
+    pub name: String,
+    pub target: ProfileTarget,
+    pub login: CredentialMode,
+    pub user: String, // default user name offered at the login prompt, never a password
+    pub read_only: bool,
+    pub production: bool,
+}
+
+pub struct ProfileStore {
+    pub profiles: Vec<ConnectionProfile>,
+    pub recent: Vec<String>, // profile names, most recent first
+    path: PathBuf,
+}
+
+impl ProfileTarget {
+    pub fn kind_label(&self) -> &'static str {
+        match self {
+            ProfileTarget::SqliteFile(_) => "SQLite file",
+            ProfileTarget::Dsn(_) => "ODBC DSN",
+            ProfileTarget::DriverString(_) => "ODBC connection string",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            ProfileTarget::SqliteFile(v) | ProfileTarget::Dsn(v) | ProfileTarget::DriverString(v) => v,
+        }
+    }
+
+    // inverse of the "type" key written to the profile file
+    fn from_parts(kind: &str, value: &str) -> Option<Self> {
+        match kind {
+            "sqlite" => Some(ProfileTarget::SqliteFile(value.to_string())),
+            "dsn" => Some(ProfileTarget::Dsn(value.to_string())),
+            "driver" => Some(ProfileTarget::DriverString(value.to_string())),
+            _ => None,
+        }
+    }
+
+    fn kind_key(&self) -> &'static str {
+        match self {
+            ProfileTarget::SqliteFile(_) => "sqlite",
+            ProfileTarget::Dsn(_) => "dsn",
+            ProfileTarget::DriverString(_) => "driver",
+        }
+    }
+}
+
+impl ConnectionProfile {
+    pub fn connection_base(&self) -> ConnectionBase {
+        match self.target {
+            ProfileTarget::SqliteFile(_) => ConnectionBase::Sqlite,
+            ProfileTarget::Dsn(_) | ProfileTarget::DriverString(_) => ConnectionBase::Odbc,
+        }
+    }
+
+    pub fn safety(&self) -> Safety {
+        Safety {
+            read_only: self.read_only,
+            production: self.production,
+        }
+    }
+
+    // the string handed to sqlite::open or SQLDriverConnect
+    pub fn connection_string(&self) -> String {
+        match &self.target {
+            ProfileTarget::SqliteFile(path) => path.clone(),
+            ProfileTarget::Dsn(dsn) => format!("DSN={};", dsn),
+            ProfileTarget::DriverString(conn_str) => conn_str.clone(),
+        }
+    }
+
+    fn from_section(name: &str, section: &IniSection) -> Option<Self> {
+        let target: ProfileTarget =
+            ProfileTarget::from_parts(section.get("type")?, section.get("target").unwrap_or(""))?;
+        let mut profile: ConnectionProfile = ConnectionProfile {
+            name: name.to_string(),
+            target,
+            login: CredentialMode::from_key(section.get("login").unwrap_or("none")),
+            user: section.get("user").unwrap_or("").to_string(),
+            read_only: section.get_bool("read_only"),
+            production: section.get_bool("production"),
+        };
+        // a hand edited file may still carry UID=/PWD=, drop them so the next save cleans the file
+        if profile.strip_credentials() {
+            println!("Removed credentials from the saved connection string of profile {}", name);
+        }
+        Some(profile)
+    }
+
+    fn write_section(&self, section: &mut IniSection) {
+        let (target, _) = credentials::split_credentials(self.target.value());
+        let target: &str = match self.target {
+            ProfileTarget::SqliteFile(_) => self.target.value(),
+            _ => &target,
+        };
+        section
+            .set("type", self.target.kind_key())
+            .set("target", target)
+            .set("login", self.login.key())
+            .set("user", &self.user)
+            .set("read_only", if self.read_only { "yes" } else { "no" })
+            .set("production", if self.production { "yes" } else { "no" });
+    }
+
+    // move UID/PWD out of a driver connection string. the user name is kept on the profile,
+    // the password is discarded and the profile falls back to prompting for it
+    pub fn strip_credentials(&mut self) -> bool {
+        let conn_str: String = match &self.target {
+            ProfileTarget::DriverString(conn_str) => conn_str.clone(),
+            _ => return false,
+        };
+        match credentials::split_credentials(&conn_str) {
+            (stripped, Some(creds)) => {
+                self.target = ProfileTarget::DriverString(stripped);
+                if self.user.is_empty() {
+                    self.user = creds.user;
+                }
+                if self.login == CredentialMode::None {
+                    self.login = CredentialMode::Prompt;
+                }
+                true
+            }
+            (_, None) => false,
+        }
+    }
+}
+
+impl ProfileStore {
+    pub fn load() -> Self {
+        Self::load_from(app_paths::config_file(PROFILE_FILE))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let doc: IniDocument = match IniDocument::load(&path) {
+            Ok(doc) => doc,
+            Err(e) => {
+                println!("Unable to read {}: {}", path.display(), e);
+                IniDocument::default()
+            }
+        };
+
+        let profiles: Vec<ConnectionProfile> = doc
+            .sections_with_prefix(PROFILE_PREFIX)
+            .filter_map(|(name, section)| ConnectionProfile::from_section(name, section))
+            .collect();
+
+        let recent: Vec<String> = match doc.section(RECENT_SECTION) {
+            Some(section) => section
+                .get_all("name")
+                .filter(|name| profiles.iter().any(|p| p.name == *name))
+                .map(String::from)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        ProfileStore {
+            profiles,
+            recent,
+            path,
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut doc: IniDocument = IniDocument::default();
+
+        for profile in &self.profiles {
+            let section: &mut IniSection = doc.push_section(&format!("{}{}", PROFILE_PREFIX, profile.name));
+            profile.write_section(section);
+        }
+
+        let recent: &mut IniSection = doc.push_section(RECENT_SECTION);
+        for name in &self.recent {
+            recent.append("name", name);
+        }
+
+        doc.save(&self.path)
+            .map_err(|e| format!("Unable to save {}: {}", self.path.display(), e))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConnectionProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+
+    // insert or replace by name. `previous_name` lets the manager rename a profile in place
+    pub fn upsert(&mut self, previous_name: Option<&str>, profile: ConnectionProfile) {
+        let lookup: &str = previous_name.unwrap_or(&profile.name);
+        if let Some(old_name) = previous_name {
+            for name in self.recent.iter_mut() {
+                if name == old_name {
+                    *name = profile.name.clone();
+                }
+            }
+        }
+        match self.profiles.iter().position(|p| p.name == lookup) {
+            Some(index) => self.profiles[index] = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.profiles.retain(|p| p.name != name);
+        self.recent.retain(|n| n != name);
+    }
+
+    pub fn touch_recent(&mut self, name: &str) {
+        self.recent.retain(|n| n != name);
+        self.recent.insert(0, name.to_string());
+        self.recent.truncate(MAX_RECENT);
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn validate_profile(profile: &ConnectionProfile) -> Result<(), String> {
+    if profile.name.trim().is_empty() {
+        return Err(String::from("A profile needs a name."));
+    }
+    if profile.target.value().trim().is_empty() {
+        return Err(format!("Enter a {} for the profile.", profile.target.kind_label()));
+    }
+    Ok(())
+}
+
+// `conn_str` is the profile's connection string with any login already applied
+pub fn test_profile(profile: &ConnectionProfile, conn_str: String) -> Result<(), String> {
+    validate_profile(profile)?;
+    match profile.connection_base() {
+        ConnectionBase::Sqlite => {
+            // sqlite::open creates missing files, which would turn a typo into an empty database
+            if !Path::new(profile.target.value()).exists() {
+                return Err(format!("File not found: {}", profile.target.value()));
+            }
+            crate::sqlite3_interface::test_connection(conn_str)
+                .map_err(|e| e.message.unwrap_or_else(|| String::from("Unknown SQLite error")))
+        }
+        ConnectionBase::Odbc => crate::odbc_interface::test_connection(conn_str)
+            .map_err(|e| credentials::redact(&e.to_string())),
+    }
+}
+
+/* <-- Functions */
@@ -0,0 +1,706 @@
+/* --> Summary of Contents
+
+	X̄-R and I-MR control charts of a numeric result column
+
+	pub fn build(&RecordSet, &ChartRequest, Option<&Spec>) -> Result<ControlChart, String>
+		With a subgroup column the rows are grouped in the order the
+		subgroups first appear and an X̄-R chart is computed (subgroups of
+		one size, 2 to 10 values, for the A2 / D3 / D4 of that size);
+		without one every row is a point of an I-MR chart. When the result has a
+		prod_code or dimension column only the rows of the chosen product
+		and dimension are charted.
+
+	pub fn western_electric(&[f64], f64, f64) -> Vec<Violation>
+		Rule 1: a point beyond 3 sigma; 2: two of three beyond 2 sigma on
+		one side; 3: four of five beyond 1 sigma on one side; 4: eight in
+		a row on one side of the center line. The X̄ / I chart is checked
+		against all four, the range chart against rule 1.
+
+	pub fn ask_request(&RecordSet, &[String], usize) -> Option<ChartRequest>
+		Modal dialog: value and subgroup column, product and dimension for
+		the spec limits and the tab whose connection has specs
+
+	pub fn show_chart(&str, ControlChart)
+		Window with both charts, the violations listed below them and
+		PNG / SVG export of the drawing
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    button::Button,
+    dialog,
+    draw::{self, LineStyle},
+    enums::{Align, Color, ColorDepth, Font, FrameType},
+    frame::Frame,
+    group::Flex,
+    input::Input,
+    menu::Choice,
+    prelude::{DisplayExt, GroupExt, ImageExt, InputExt, MenuExt, SurfaceDevice, WidgetBase, WidgetExt, WindowExt},
+    surface::{ImageSurface, SvgFileSurface},
+    text::{TextBuffer, TextDisplay},
+    window::Window,
+};
+use std::fs;
+
+use crate::capability::Spec;
+use crate::learning_fltk::{center, escape_menu_label};
+use crate::sql_aux_funcs::{Record, RecordSet};
+
+/* <-- Imports */
+/* --> Const */
+
+// control chart constants by subgroup size, index 0 is a subgroup of 2
+const A2: [f64; 9] = [1.880, 1.023, 0.729, 0.577, 0.483, 0.419, 0.373, 0.337, 0.308];
+const D3: [f64; 9] = [0.0, 0.0, 0.0, 0.0, 0.0, 0.076, 0.136, 0.184, 0.223];
+const D4: [f64; 9] = [3.267, 2.574, 2.282, 2.114, 2.004, 1.924, 1.864, 1.816, 1.777];
+const E2: f64 = 2.66; // I chart limits from the average moving range
+const MAX_SUBGROUP: usize = 10;
+const INDIVIDUALS: &str = "(none, individuals: I-MR)";
+
+const VIOLATION_COLOR: Color = Color::from_rgb(220, 30, 30);
+const LIMIT_COLOR: Color = Color::from_rgb(200, 60, 60);
+const SPEC_COLOR: Color = Color::from_rgb(40, 90, 200);
+const CENTER_COLOR: Color = Color::from_rgb(30, 140, 60);
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChartKind {
+    XbarR,
+    IndividualsMR,
+}
+
+#[derive(Clone, Copy)]
+enum DialogMessage {
+    Draw,
+    Cancel,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct ChartRequest {
+    pub value_column: String,
+    pub subgroup_column: Option<String>, // None: I-MR
+    pub product: String,
+    pub dimension: String,
+    pub specs_tab: usize, // index into the tab list given to ask_request
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    pub point: usize,
+    pub rule: u8,
+}
+
+#[derive(Clone, Debug)]
+pub struct Series {
+    pub title: String,
+    pub points: Vec<f64>,
+    pub center: f64,
+    pub upper: f64,
+    pub lower: f64,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ControlChart {
+    pub kind: ChartKind,
+    pub labels: Vec<String>, // subgroup names or row numbers, one per point
+    pub location: Series,    // X̄ or I
+    pub dispersion: Series,  // R or MR, the first MR point belongs to the second row
+    pub spec: Option<(f64, f64)>,
+    pub subgroup_size: usize,
+}
+
+impl Violation {
+    pub fn describe(&self) -> &'static str {
+        match self.rule {
+            1 => "beyond 3 sigma",
+            2 => "2 of 3 beyond 2 sigma on one side",
+            3 => "4 of 5 beyond 1 sigma on one side",
+            _ => "8 in a row on one side of the center line",
+        }
+    }
+}
+
+impl ControlChart {
+    pub fn title(&self) -> &'static str {
+        match self.kind {
+            ChartKind::XbarR => "X̄-R",
+            ChartKind::IndividualsMR => "I-MR",
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let count: usize = self.location.violations.len() + self.dispersion.violations.len();
+        match self.kind {
+            ChartKind::XbarR => format!("{} subgroups of {}, {} rule violations", self.labels.len(), self.subgroup_size, count),
+            ChartKind::IndividualsMR => format!("{} individual values, {} rule violations", self.labels.len(), count),
+        }
+    }
+
+    pub fn violation_lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        for (series, offset) in [(&self.location, 0), (&self.dispersion, self.labels.len() - self.dispersion.points.len())] {
+            for violation in &series.violations {
+                lines.push(format!(
+                    "{} {}: {:.4} rule {}, {}",
+                    series.title,
+                    self.labels[violation.point + offset],
+                    series.points[violation.point],
+                    violation.rule,
+                    violation.describe()
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn number(record: &Record, column: &str) -> Option<f64> {
+    record.text(column)?.trim().parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+fn find_column<'a>(columns: &'a [String], names: &[&str]) -> Option<&'a String> {
+    names.iter().find_map(|name| columns.iter().find(|c| c.eq_ignore_ascii_case(name)))
+}
+
+// rows of another product or dimension in a long result are left out
+fn wanted(record: &Record, column: Option<&String>, value: &str) -> bool {
+    match column {
+        Some(column) if !value.trim().is_empty() => {
+            record.text(column).is_some_and(|v| v.trim().eq_ignore_ascii_case(value.trim()))
+        }
+        _ => true,
+    }
+}
+
+pub fn western_electric(points: &[f64], center: f64, sigma: f64) -> Vec<Violation> {
+    if sigma <= 0.0 {
+        return Vec::new();
+    }
+    let zone = |value: f64| (value - center) / sigma;
+    let mut violations: Vec<Violation> = Vec::new();
+    for (index, value) in points.iter().enumerate() {
+        let side: f64 = zone(*value).signum();
+        // count the points of the window beyond `limit` on the side of this point
+        let beyond = |length: usize, limit: f64| {
+            let window: &[f64] = &points[(index + 1).saturating_sub(length)..=index];
+            (window.len() == length).then(|| window.iter().filter(|v| zone(**v) * side > limit).count())
+        };
+        let rule: Option<u8> = if zone(*value).abs() > 3.0 {
+            Some(1)
+        } else if zone(*value).abs() > 2.0 && beyond(3, 2.0).is_some_and(|count| count >= 2) {
+            Some(2)
+        } else if zone(*value).abs() > 1.0 && beyond(5, 1.0).is_some_and(|count| count >= 4) {
+            Some(3)
+        } else if zone(*value) != 0.0 && beyond(8, 0.0) == Some(8) {
+            Some(4)
+        } else {
+            None
+        };
+        if let Some(rule) = rule {
+            violations.push(Violation { point: index, rule });
+        }
+    }
+    violations
+}
+
+// the A2 / D3 / D4 of one size hold for every subgroup only when they are all that size
+fn subgroup_size(subgroups: &[(String, Vec<f64>)]) -> Result<usize, String> {
+    let smallest: usize = subgroups.iter().map(|(_, values)| values.len()).min().unwrap_or(0);
+    let largest: usize = subgroups.iter().map(|(_, values)| values.len()).max().unwrap_or(0);
+    if smallest != largest {
+        let short: Vec<&str> = subgroups.iter().filter(|(_, v)| v.len() < largest).map(|(name, _)| name.as_str()).collect();
+        return Err(format!(
+            "Subgroups hold {} to {} values, X̄-R needs subgroups of one size. Short: {}. Leave out their rows or choose another subgroup column.",
+            smallest,
+            largest,
+            short.join(", ")
+        ));
+    }
+    if largest > MAX_SUBGROUP {
+        return Err(format!("Subgroups hold {} values, X̄-R works with 2 to {}. Choose another subgroup column.", largest, MAX_SUBGROUP));
+    }
+    Ok(largest)
+}
+
+fn beyond_limits(points: &[f64], upper: f64, lower: f64) -> Vec<Violation> {
+    points
+        .iter()
+        .enumerate()
+        .filter(|(_, v)| **v > upper || **v < lower)
+        .map(|(point, _)| Violation { point, rule: 1 })
+        .collect()
+}
+
+pub fn build(record_set: &RecordSet, request: &ChartRequest, spec: Option<&Spec>) -> Result<ControlChart, String> {
+    let product_column: Option<&String> = find_column(&record_set.column_order, &["prod_code", "mrp_prod_code", "sfol_prod_code"]);
+    let dimension_column: Option<&String> = find_column(&record_set.column_order, &["dimension"]);
+    let rows: Vec<&Record> = record_set
+        .records
+        .iter()
+        .filter(|r| wanted(r, product_column, &request.product) && wanted(r, dimension_column, &request.dimension))
+        .collect();
+    let spec: Option<(f64, f64)> = spec.map(|s| (s.lsl(), s.usl()));
+
+    match &request.subgroup_column {
+        Some(subgroup_column) => {
+            // subgroups in the order they first appear
+            let mut subgroups: Vec<(String, Vec<f64>)> = Vec::new();
+            for record in rows {
+                let (name, value) = match (record.text(subgroup_column), number(record, &request.value_column)) {
+                    (Some(name), Some(value)) => (name.trim().to_string(), value),
+                    _ => continue,
+                };
+                match subgroups.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, values)) => values.push(value),
+                    None => subgroups.push((name, vec![value])),
+                }
+            }
+            subgroups.retain(|(_, values)| values.len() >= 2);
+            if subgroups.len() < 2 {
+                return Err(String::from("An X̄-R chart needs at least two subgroups with two or more values each."));
+            }
+            let size: usize = subgroup_size(&subgroups)?;
+            let means: Vec<f64> = subgroups.iter().map(|(_, v)| v.iter().sum::<f64>() / v.len() as f64).collect();
+            let ranges: Vec<f64> = subgroups
+                .iter()
+                .map(|(_, v)| v.iter().copied().fold(f64::NEG_INFINITY, f64::max) - v.iter().copied().fold(f64::INFINITY, f64::min))
+                .collect();
+            let grand_mean: f64 = means.iter().sum::<f64>() / means.len() as f64;
+            let mean_range: f64 = ranges.iter().sum::<f64>() / ranges.len() as f64;
+            let (a2, d3, d4): (f64, f64, f64) = (A2[size - 2], D3[size - 2], D4[size - 2]);
+            let (upper, lower): (f64, f64) = (grand_mean + a2 * mean_range, grand_mean - a2 * mean_range);
+            let (range_upper, range_lower): (f64, f64) = (d4 * mean_range, d3 * mean_range);
+            Ok(ControlChart {
+                kind: ChartKind::XbarR,
+                labels: subgroups.iter().map(|(name, _)| name.clone()).collect(),
+                location: Series {
+                    title: String::from("X̄"),
+                    violations: western_electric(&means, grand_mean, (upper - grand_mean) / 3.0),
+                    points: means,
+                    center: grand_mean,
+                    upper,
+                    lower,
+                },
+                dispersion: Series {
+                    title: String::from("R"),
+                    violations: beyond_limits(&ranges, range_upper, range_lower),
+                    points: ranges,
+                    center: mean_range,
+                    upper: range_upper,
+                    lower: range_lower,
+                },
+                spec,
+                subgroup_size: size,
+            })
+        }
+        None => {
+            let values: Vec<f64> = rows.iter().filter_map(|r| number(r, &request.value_column)).collect();
+            if values.len() < 2 {
+                return Err(String::from("An I-MR chart needs at least two values."));
+            }
+            let moving_ranges: Vec<f64> = values.windows(2).map(|pair| (pair[1] - pair[0]).abs()).collect();
+            let mean: f64 = values.iter().sum::<f64>() / values.len() as f64;
+            let mean_range: f64 = moving_ranges.iter().sum::<f64>() / moving_ranges.len() as f64;
+            let (upper, lower): (f64, f64) = (mean + E2 * mean_range, mean - E2 * mean_range);
+            let range_upper: f64 = D4[0] * mean_range;
+            Ok(ControlChart {
+                kind: ChartKind::IndividualsMR,
+                labels: (1..=values.len()).map(|row| row.to_string()).collect(),
+                location: Series {
+                    title: String::from("I"),
+                    violations: western_electric(&values, mean, (upper - mean) / 3.0),
+                    points: values,
+                    center: mean,
+                    upper,
+                    lower,
+                },
+                dispersion: Series {
+                    title: String::from("MR"),
+                    violations: beyond_limits(&moving_ranges, range_upper, 0.0),
+                    points: moving_ranges,
+                    center: mean_range,
+                    upper: range_upper,
+                    lower: 0.0,
+                },
+                spec,
+                subgroup_size: 1,
+            })
+        }
+    }
+}
+
+// a horizontal limit line with its value at the right edge
+fn limit_line(y: i32, left: i32, right: i32, color: Color, style: LineStyle, label: &str) {
+    draw::set_draw_color(color);
+    draw::set_line_style(style, 1);
+    draw::draw_line(left, y, right, y);
+    draw::set_line_style(LineStyle::Solid, 0);
+    draw::draw_text2(label, right + 4, y - 7, 90, 14, Align::Left);
+}
+
+// one panel: the points of a series with its limits; `offset` shifts MR points under the rows they belong to
+fn draw_series(series: &Series, spec: Option<(f64, f64)>, labels: &[String], offset: usize, area: (i32, i32, i32, i32)) {
+    let (x, y, w, h): (i32, i32, i32, i32) = area;
+    let (left, top, width, height): (i32, i32, i32, i32) = (x + 60, y + 20, w - 160, h - 40);
+    let mut values: Vec<f64> = series.points.clone();
+    values.extend([series.upper, series.lower, series.center]);
+    if let Some((lsl, usl)) = spec {
+        values.extend([lsl, usl]);
+    }
+    let low: f64 = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let high: f64 = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let pad: f64 = if high > low { (high - low) * 0.05 } else { 1.0 };
+    let (low, high): (f64, f64) = (low - pad, high + pad);
+    let to_y = |value: f64| top + height - ((value - low) / (high - low) * height as f64) as i32;
+    let step: f64 = width as f64 / labels.len().max(2).saturating_sub(1) as f64;
+    let to_x = |point: usize| left + ((point + offset) as f64 * step) as i32;
+
+    draw::set_font(Font::Helvetica, 11);
+    draw::set_draw_color(Color::Black);
+    draw::draw_text2(&series.title, x + 4, y + 2, 200, 14, Align::Left);
+    draw::set_draw_color(Color::Light2);
+    draw::draw_rect(left, top, width, height);
+    draw::set_draw_color(Color::Dark3);
+    draw::draw_text2(&format!("{:.4}", high), x, top - 7, 55, 14, Align::Right);
+    draw::draw_text2(&format!("{:.4}", low), x, top + height - 7, 55, 14, Align::Right);
+
+    limit_line(to_y(series.center), left, left + width, CENTER_COLOR, LineStyle::Solid, &format!("CL {:.4}", series.center));
+    limit_line(to_y(series.upper), left, left + width, LIMIT_COLOR, LineStyle::Dash, &format!("UCL {:.4}", series.upper));
+    limit_line(to_y(series.lower), left, left + width, LIMIT_COLOR, LineStyle::Dash, &format!("LCL {:.4}", series.lower));
+    if let Some((lsl, usl)) = spec {
+        limit_line(to_y(usl), left, left + width, SPEC_COLOR, LineStyle::Solid, &format!("USL {:.4}", usl));
+        limit_line(to_y(lsl), left, left + width, SPEC_COLOR, LineStyle::Solid, &format!("LSL {:.4}", lsl));
+    }
+
+    draw::set_draw_color(Color::from_rgb(60, 60, 60));
+    for point in 1..series.points.len() {
+        draw::draw_line(to_x(point - 1), to_y(series.points[point - 1]), to_x(point), to_y(series.points[point]));
+    }
+    for (point, value) in series.points.iter().enumerate() {
+        match series.violations.iter().find(|v| v.point == point) {
+            Some(violation) => {
+                draw::set_draw_color(VIOLATION_COLOR);
+                draw::draw_pie(to_x(point) - 4, to_y(*value) - 4, 9, 9, 0.0, 360.0);
+                draw::draw_text2(&violation.rule.to_string(), to_x(point) - 10, to_y(*value) - 20, 20, 12, Align::Center);
+            }
+            None => {
+                draw::set_draw_color(Color::from_rgb(60, 60, 60));
+                draw::draw_pie(to_x(point) - 2, to_y(*value) - 2, 5, 5, 0.0, 360.0);
+            }
+        }
+    }
+
+    // about ten labels along the bottom
+    draw::set_draw_color(Color::Dark3);
+    let every: usize = (labels.len() / 10).max(1);
+    for point in (0..series.points.len()).filter(|p| (p + offset).is_multiple_of(every)) {
+        draw::draw_text2(&labels[point + offset], to_x(point) - 40, top + height + 3, 80, 14, Align::Center | Align::Clip);
+    }
+}
+
+fn draw_chart(chart: &ControlChart, x: i32, y: i32, w: i32, h: i32) {
+    draw::draw_rect_fill(x, y, w, h, Color::White);
+    let location_height: i32 = h * 3 / 5;
+    draw_series(&chart.location, chart.spec, &chart.labels, 0, (x, y, w, location_height));
+    let offset: usize = chart.labels.len() - chart.dispersion.points.len();
+    draw_series(&chart.dispersion, None, &chart.labels, offset, (x, y + location_height, w, h - location_height));
+}
+
+// stored (uncompressed) deflate blocks: a chart is small and this needs no image crate
+fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc: u32 = 0xFFFF_FFFF;
+        for byte in bytes {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+            }
+        }
+        !crc
+    }
+    fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+        png.extend((data.len() as u32).to_be_bytes());
+        let start: usize = png.len();
+        png.extend(kind);
+        png.extend(data);
+        let crc: u32 = crc32(&png[start..]);
+        png.extend(crc.to_be_bytes());
+    }
+
+    // every scanline starts with filter type 0
+    let row: usize = width as usize * 3;
+    let mut raw: Vec<u8> = Vec::with_capacity((row + 1) * height as usize);
+    for line in rgb.chunks(row).take(height as usize) {
+        raw.push(0);
+        raw.extend(line);
+    }
+    let mut zlib: Vec<u8> = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = raw.chunks(65535).collect();
+    for (index, block) in blocks.iter().enumerate() {
+        zlib.push(u8::from(index + 1 == blocks.len()));
+        zlib.extend((block.len() as u16).to_le_bytes());
+        zlib.extend((!(block.len() as u16)).to_le_bytes());
+        zlib.extend(*block);
+    }
+    let (mut a, mut b): (u32, u32) = (1, 0);
+    for byte in &raw {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    zlib.extend(((b << 16) | a).to_be_bytes());
+
+    let mut header: Vec<u8> = Vec::new();
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    header.extend([8, 2, 0, 0, 0]); // 8 bit RGB
+    let mut png: Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"IDAT", &zlib);
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn export_png(frame: &Frame, path: &str) -> Result<(), String> {
+    let surface: ImageSurface = ImageSurface::new(frame.w(), frame.h(), false);
+    ImageSurface::push_current(&surface);
+    surface.draw(frame, 0, 0);
+    let image = surface.image();
+    ImageSurface::pop_current();
+    let image = image
+        .ok_or_else(|| String::from("The chart could not be drawn"))?
+        .convert(ColorDepth::Rgb8)
+        .map_err(|e| format!("{:?}", e))?;
+    let png: Vec<u8> = encode_png(image.data_w() as u32, image.data_h() as u32, &image.to_rgb_data());
+    fs::write(path, png).map_err(|e| format!("Unable to write {}: {}", path, e))
+}
+
+fn export_svg(frame: &Frame, path: &str) -> Result<(), String> {
+    // the surface panics on a file it cannot create, find out first
+    fs::write(path, "").map_err(|e| format!("Unable to write {}: {}", path, e))?;
+    let surface: SvgFileSurface = SvgFileSurface::new(frame.w(), frame.h(), path);
+    SvgFileSurface::push_current(&surface);
+    surface.draw(frame, 0, 0);
+    SvgFileSurface::pop_current();
+    // the file is completed when the surface is dropped
+    drop(surface);
+    Ok(())
+}
+
+fn alert(message: &str) {
+    let (x, y): (i32, i32) = center();
+    dialog::alert(x - 200, y - 100, message);
+}
+
+pub fn ask_request(record_set: &RecordSet, tabs: &[String], active: usize) -> Option<ChartRequest> {
+    let columns: &[String] = &record_set.column_order;
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+    let mut win: Window = Window::default()
+        .with_size(520, 235)
+        .with_label("Control Chart")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut value_column: Choice = Choice::new(130, 10, 380, 25, "Value column");
+    let mut subgroup_column: Choice = Choice::new(130, 40, 380, 25, "Subgroup column");
+    let mut product: Input = Input::new(130, 75, 380, 25, "Product code");
+    let mut dimension: Input = Input::new(130, 105, 380, 25, "Dimension");
+    let mut specs_tab: Choice = Choice::new(130, 140, 380, 25, "Specs from");
+    product.set_tooltip("With the dimension, the row of specs whose limits are drawn; empty for none");
+    subgroup_column.set_tooltip("Rows with the same value form a subgroup, e.g. work_order_id or a sample time");
+
+    subgroup_column.add_choice(INDIVIDUALS);
+    for column in columns {
+        let label: String = escape_menu_label(&column.replace('|', " "));
+        value_column.add_choice(&label);
+        subgroup_column.add_choice(&label);
+    }
+    for tab in tabs {
+        specs_tab.add_choice(&escape_menu_label(&tab.replace('|', " ")));
+    }
+    // the measurement is usually the last numeric column
+    let numeric: Option<usize> = columns
+        .iter()
+        .rposition(|c| record_set.records.first().is_some_and(|r| number(r, c).is_some()) && !c.eq_ignore_ascii_case("index"));
+    value_column.set_value(numeric.map_or(0, |i| i as i32));
+    let first_text = |names: &[&str]| {
+        find_column(columns, names).and_then(|c| record_set.records.first().and_then(|r| r.text(c)))
+    };
+    product.set_value(&first_text(&["prod_code", "mrp_prod_code", "sfol_prod_code"]).unwrap_or_default());
+    dimension.set_value(&first_text(&["dimension"]).or_else(|| numeric.map(|i| columns[i].clone())).unwrap_or_default());
+    specs_tab.set_value(active as i32);
+
+    let mut draw_butn: Button = Button::new(350, 200, 75, 28, "&Draw");
+    draw_butn.emit(sndr, DialogMessage::Draw);
+    let mut cancel_butn: Button = Button::new(435, 200, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+    win.end();
+    win.show();
+
+    let mut request: Option<ChartRequest> = None;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::Draw) => {
+                let value: Option<String> = usize::try_from(value_column.value()).ok().and_then(|i| columns.get(i)).cloned();
+                let value_column: String = match value {
+                    Some(value) => value,
+                    None => {
+                        alert("Choose the column with the measured values.");
+                        continue;
+                    }
+                };
+                request = Some(ChartRequest {
+                    value_column,
+                    subgroup_column: usize::try_from(subgroup_column.value() - 1).ok().and_then(|i| columns.get(i)).cloned(),
+                    product: product.value().trim().to_string(),
+                    dimension: dimension.value().trim().to_string(),
+                    specs_tab: usize::try_from(specs_tab.value()).unwrap_or(active),
+                });
+                win.hide();
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            None => {}
+        }
+    }
+    request
+}
+
+pub fn show_chart(label: &str, chart: ControlChart) {
+    let mut win: Window = Window::default()
+        .with_size(980, 700)
+        .with_label(&format!("{} chart: {}", chart.title(), label))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let mut summary: Frame = Frame::default().with_label(&chart.summary());
+    summary.set_align(Align::Inside | Align::Left);
+    layout.fixed(&summary, 25);
+    let mut canvas: Frame = Frame::default();
+    canvas.set_frame(FrameType::DownBox);
+    let mut violations: TextDisplay = TextDisplay::default();
+    let mut buffer: TextBuffer = TextBuffer::default();
+    buffer.set_text(&chart.violation_lines().join("\n"));
+    violations.set_buffer(buffer);
+    layout.fixed(&violations, 100);
+
+    let mut buttons: Flex = Flex::default().row();
+    Frame::default();
+    let mut png_butn: Button = Button::default().with_label("Export &PNG...");
+    let mut svg_butn: Button = Button::default().with_label("Export &SVG...");
+    buttons.fixed(&png_butn, 110);
+    buttons.fixed(&svg_butn, 110);
+    buttons.end();
+    layout.fixed(&buttons, 30);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    canvas.draw(move |frame| draw_chart(&chart, frame.x(), frame.y(), frame.w(), frame.h()));
+
+    for (button, extension) in [(&mut png_butn, "png"), (&mut svg_butn, "svg")] {
+        let canvas: Frame = canvas.clone();
+        let mut summary: Frame = summary.clone();
+        button.set_callback(move |_| {
+            let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+            chooser.set_title("Export the chart");
+            chooser.set_filter(&format!("{}\t*.{}", extension.to_uppercase(), extension));
+            chooser.set_option(dialog::FileDialogOptions::SaveAsConfirm);
+            chooser.show();
+            let mut path: String = chooser.filename().to_string_lossy().into_owned();
+            if path.is_empty() {
+                return;
+            }
+            if !path.to_lowercase().ends_with(&format!(".{}", extension)) {
+                path.push_str(&format!(".{}", extension));
+            }
+            let written: Result<(), String> = match extension {
+                "png" => export_png(&canvas, &path),
+                _ => export_svg(&canvas, &path),
+            };
+            match written {
+                Ok(_) => summary.set_label(&format!("Chart written to {}", path)),
+                Err(e) => alert(&e),
+            }
+        });
+    }
+    win.show();
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(points: &[f64]) -> Vec<(usize, u8)> {
+        western_electric(points, 0.0, 1.0).iter().map(|v| (v.point, v.rule)).collect()
+    }
+
+    #[test]
+    fn rule_one_is_a_point_beyond_three_sigma() {
+        assert_eq!(rules(&[0.5, 3.5, -3.2]), vec![(1, 1), (2, 1)]);
+        assert!(rules(&[3.0, -3.0]).is_empty());
+    }
+
+    #[test]
+    fn rule_two_is_two_of_three_beyond_two_sigma() {
+        assert_eq!(rules(&[0.0, 2.5, 0.5, 2.5]), vec![(3, 2)]);
+        // on opposite sides they do not count together
+        assert!(rules(&[0.0, 2.5, 0.5, -2.5]).is_empty());
+    }
+
+    #[test]
+    fn rule_three_is_four_of_five_beyond_one_sigma() {
+        assert_eq!(rules(&[1.5, 1.5, 0.2, 1.5, 1.5]), vec![(4, 3)]);
+        assert!(rules(&[1.5, 1.5, 0.2, -0.2, 1.5]).is_empty());
+    }
+
+    #[test]
+    fn rule_four_is_eight_in_a_row_on_one_side() {
+        assert_eq!(rules(&[0.5; 9]), vec![(7, 4), (8, 4)]);
+        assert_eq!(rules(&[-0.5; 8]), vec![(7, 4)]);
+        assert!(rules(&[0.5, -0.5, 0.5, -0.5, 0.5, -0.5, 0.5, -0.5]).is_empty());
+    }
+
+    #[test]
+    fn a_point_on_the_center_line_breaks_a_run() {
+        assert!(rules(&[0.5, 0.5, 0.5, 0.0, 0.5, 0.5, 0.5, 0.5]).is_empty());
+        assert!(rules(&[0.0; 8]).is_empty());
+    }
+
+    #[test]
+    fn a_run_ending_on_the_center_line_is_not_flagged() {
+        assert!(rules(&[0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.0]).is_empty());
+        assert!(rules(&[-0.5, -0.5, -0.5, -0.5, -0.5, -0.5, -0.5, -0.0]).is_empty());
+    }
+
+    #[test]
+    fn subgroups_of_different_sizes_are_refused() {
+        let subgroup = |name: &str, size: usize| (name.to_string(), vec![1.0; size]);
+        assert_eq!(subgroup_size(&[subgroup("A", 5), subgroup("B", 5)]), Ok(5));
+        let error: String = subgroup_size(&[subgroup("A", 5), subgroup("B", 4), subgroup("C", 5)]).unwrap_err();
+        assert!(error.starts_with("Subgroups hold 4 to 5 values"));
+        assert!(error.contains("Short: B."));
+        assert!(subgroup_size(&[subgroup("A", 11), subgroup("B", 11)]).is_err());
+    }
+
+    #[test]
+    fn no_spread_no_violations() {
+        assert!(western_electric(&[1.0, 5.0, 9.0], 5.0, 0.0).is_empty());
+    }
+}
+
+/* <-- Tests */
@@ -0,0 +1,384 @@
+/* --> Summary of Contents
+
+	Keeps user names and passwords out of connection strings.
+
+	pub fn split_credentials(&str) -> (String, Option<Credentials>)
+		Remove UID/PWD style keys from an ODBC connection string
+
+	pub fn apply_credentials(&str, &Credentials) -> String
+		Append credentials to a connection string at connect time only
+
+	pub fn redact(&str) -> String
+		Mask password values in any text bound for logs, history or
+		error messages
+
+	CredentialStore
+		Profile name -> Credentials, kept in credentials.dat sealed with a
+		master passphrase (see crypto.rs). Only ever decrypted in memory.
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use crate::app_paths;
+use crate::crypto;
+use crate::ini_file::IniDocument;
+use std::{collections::HashMap, fs, io::ErrorKind, path::PathBuf};
+
+/* <-- Imports */
+/* --> Const */
+
+const STORE_FILE: &str = "credentials.dat";
+const USER_KEYS: [&str; 3] = ["uid", "user id", "user"];
+const PASSWORD_KEYS: [&str; 3] = ["pwd", "password", "pass"];
+const REDACTED: &str = "********";
+
+/* <-- Const */
+/* --> Enums */
+
+// how a saved profile obtains its login
+#[derive(Clone, Copy, PartialEq)]
+pub enum CredentialMode {
+    None,   // integrated security, sqlite files, or credentials the DSN already holds
+    Prompt, // ask every time the profile connects
+    Stored, // read from the encrypted credential store
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Default)]
+pub struct Credentials {
+    pub user: String,
+    pub password: String,
+}
+
+pub struct CredentialStore {
+    entries: HashMap<String, Credentials>,
+    passphrase: String,
+    path: PathBuf,
+}
+
+impl CredentialMode {
+    pub fn key(&self) -> &'static str {
+        match self {
+            CredentialMode::None => "none",
+            CredentialMode::Prompt => "prompt",
+            CredentialMode::Stored => "stored",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "prompt" => CredentialMode::Prompt,
+            "stored" => CredentialMode::Stored,
+            _ => CredentialMode::None,
+        }
+    }
+}
+
+impl CredentialStore {
+    pub fn exists() -> bool {
+        app_paths::config_file(STORE_FILE).exists()
+    }
+
+    // decrypt the store, or start an empty one if it has never been created
+    pub fn unlock(passphrase: &str) -> Result<Self, String> {
+        let path: PathBuf = app_paths::config_file(STORE_FILE);
+        let mut store: CredentialStore = CredentialStore {
+            entries: HashMap::new(),
+            passphrase: passphrase.to_string(),
+            path,
+        };
+
+        let sealed: Vec<u8> = match fs::read(&store.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(store),
+            Err(e) => return Err(format!("Unable to read the credential store: {}", e)),
+        };
+        let plaintext: Vec<u8> = crypto::open(passphrase, &sealed)?;
+        let text: String = String::from_utf8(plaintext)
+            .map_err(|_| String::from("The credential store is damaged."))?;
+
+        for (name, section) in IniDocument::parse(&text).sections_with_prefix("cred:") {
+            store.entries.insert(
+                name.to_string(),
+                Credentials {
+                    user: section.get("user").unwrap_or("").to_string(),
+                    password: section.get("password").unwrap_or("").to_string(),
+                },
+            );
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut doc: IniDocument = IniDocument::default();
+        let mut names: Vec<&String> = self.entries.keys().collect();
+        names.sort();
+        for name in names {
+            let creds: &Credentials = &self.entries[name];
+            doc.push_section(&format!("cred:{}", name))
+                .set("user", &creds.user)
+                .set("password", &creds.password);
+        }
+        fs::write(&self.path, crypto::seal(&self.passphrase, doc.to_string().as_bytes())?)
+            .map_err(|e| format!("Unable to save the credential store: {}", e))
+    }
+
+    pub fn get(&self, profile: &str) -> Option<&Credentials> {
+        self.entries.get(profile)
+    }
+
+    pub fn set(&mut self, profile: &str, creds: Credentials) {
+        self.entries.insert(profile.to_string(), creds);
+    }
+
+    pub fn remove(&mut self, profile: &str) {
+        self.entries.remove(profile);
+    }
+
+    pub fn rename(&mut self, old_name: &str, new_name: &str) {
+        if let Some(creds) = self.entries.remove(old_name) {
+            self.entries.insert(new_name.to_string(), creds);
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+// split "Key=Value;Key={va;lue};" into (key, raw value) pairs, honouring ODBC brace quoting
+fn parse_pairs(conn_str: &str) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    let mut chars = conn_str.chars().peekable();
+
+    loop {
+        let mut key: String = String::new();
+        for ch in chars.by_ref() {
+            if ch == '=' || ch == ';' {
+                if ch == ';' {
+                    key.clear();
+                    continue;
+                }
+                break;
+            }
+            key.push(ch);
+        }
+        if key.trim().is_empty() && chars.peek().is_none() {
+            break;
+        }
+
+        let mut value: String = String::new();
+        if chars.peek() == Some(&'{') {
+            value.push(chars.next().unwrap());
+            while let Some(ch) = chars.next() {
+                value.push(ch);
+                if ch == '}' {
+                    if chars.peek() == Some(&'}') {
+                        value.push(chars.next().unwrap());
+                    } else {
+                        break;
+                    }
+                }
+            }
+            // anything up to the separator belongs to the same pair
+            for ch in chars.by_ref() {
+                if ch == ';' {
+                    break;
+                }
+                value.push(ch);
+            }
+        } else {
+            for ch in chars.by_ref() {
+                if ch == ';' {
+                    break;
+                }
+                value.push(ch);
+            }
+        }
+        pairs.push((key.trim().to_string(), value));
+        if chars.peek().is_none() {
+            break;
+        }
+    }
+    pairs
+}
+
+fn unquote(value: &str) -> String {
+    let trimmed: &str = value.trim();
+    if trimmed.starts_with('{') && trimmed.ends_with('}') && trimmed.len() >= 2 {
+        trimmed[1..trimmed.len() - 1].replace("}}", "}")
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn quote(value: &str) -> String {
+    if value.contains([';', '{', '}', '=']) || value.starts_with(' ') || value.ends_with(' ') {
+        format!("{{{}}}", value.replace('}', "}}"))
+    } else {
+        value.to_string()
+    }
+}
+
+fn is_user_key(key: &str) -> bool {
+    USER_KEYS.contains(&key.to_ascii_lowercase().as_str())
+}
+
+fn is_password_key(key: &str) -> bool {
+    PASSWORD_KEYS.contains(&key.to_ascii_lowercase().as_str())
+}
+
+pub fn split_credentials(conn_str: &str) -> (String, Option<Credentials>) {
+    let mut creds: Credentials = Credentials::default();
+    let mut found: bool = false;
+    let mut kept: Vec<String> = Vec::new();
+
+    for (key, value) in parse_pairs(conn_str) {
+        if is_user_key(&key) {
+            creds.user = unquote(&value);
+            found = true;
+        } else if is_password_key(&key) {
+            creds.password = unquote(&value);
+            found = true;
+        } else if !key.is_empty() {
+            kept.push(format!("{}={}", key, value));
+        }
+    }
+
+    if !found {
+        return (conn_str.to_string(), None);
+    }
+    let mut stripped: String = kept.join(";");
+    if !stripped.is_empty() {
+        stripped.push(';');
+    }
+    (stripped, Some(creds))
+}
+
+pub fn apply_credentials(conn_str: &str, creds: &Credentials) -> String {
+    let (mut full, _) = split_credentials(conn_str);
+    if !full.is_empty() && !full.ends_with(';') {
+        full.push(';');
+    }
+    if !creds.user.is_empty() {
+        full.push_str(&format!("UID={};", quote(&creds.user)));
+    }
+    if !creds.password.is_empty() {
+        full.push_str(&format!("PWD={};", quote(&creds.password)));
+    }
+    full
+}
+
+// length of a "{...}" value including the braces, "}}" is an escaped brace
+fn braced_len(value: &str) -> usize {
+    let bytes: &[u8] = value.as_bytes();
+    let mut index: usize = 1;
+    while index < bytes.len() {
+        if bytes[index] == b'}' {
+            if bytes.get(index + 1) == Some(&b'}') {
+                index += 2;
+                continue;
+            }
+            return index + 1;
+        }
+        index += 1;
+    }
+    bytes.len()
+}
+
+pub fn redact(text: &str) -> String {
+    let lower: String = text.to_ascii_lowercase();
+    let mut out: String = String::with_capacity(text.len());
+    let mut index: usize = 0;
+
+    while index < text.len() {
+        // find the next "<password key>=" that starts a key (beginning, or after ';' / whitespace / quote).
+        // "PWD = value" is accepted by drivers too, blanks around the '=' are kept and the value hidden
+        let next_match: Option<(usize, usize)> = PASSWORD_KEYS
+            .iter()
+            .filter_map(|key| {
+                let mut search_from: usize = index;
+                while let Some(found) = lower[search_from..].find(key) {
+                    let start: usize = search_from + found;
+                    let after_key: usize = start + key.len();
+                    let boundary: bool = start == 0
+                        || matches!(lower.as_bytes()[start - 1], b';' | b' ' | b'\t' | b'"' | b'\'' | b'\n');
+                    let equals: &str = lower[after_key..].trim_start_matches([' ', '\t']);
+                    if boundary && equals.starts_with('=') {
+                        let value: &str = equals[1..].trim_start_matches([' ', '\t']);
+                        return Some((start, lower.len() - value.len()));
+                    }
+                    search_from = after_key;
+                }
+                None
+            })
+            .min_by_key(|(start, _)| *start);
+
+        let value_start: usize = match next_match {
+            Some((_, value_start)) => value_start,
+            None => {
+                out.push_str(&text[index..]);
+                break;
+            }
+        };
+        out.push_str(&text[index..value_start]);
+        out.push_str(REDACTED);
+
+        // skip the value: braced, or up to ';' / quote / whitespace
+        let rest: &str = &text[value_start..];
+        let value_len: usize = if rest.starts_with('{') {
+            braced_len(rest)
+        } else {
+            rest.find([';', '"', '\'', ' ', '\n', '\t']).unwrap_or(rest.len())
+        };
+        index = value_start + value_len;
+    }
+    out
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_moves_the_login_out() {
+        let (stripped, creds) = split_credentials("Driver={SQL Server};Server=db01;UID=qa;PWD={p;w}}d};Database=dv;");
+        let creds: Credentials = creds.unwrap();
+        assert_eq!(stripped, "Driver={SQL Server};Server=db01;Database=dv;");
+        assert_eq!(creds.user, "qa");
+        assert_eq!(creds.password, "p;w}d");
+    }
+
+    #[test]
+    fn split_without_login_leaves_the_string() {
+        let (stripped, creds) = split_credentials("DSN=production;");
+        assert_eq!(stripped, "DSN=production;");
+        assert!(creds.is_none());
+    }
+
+    #[test]
+    fn apply_quotes_what_needs_it() {
+        let creds: Credentials = Credentials {
+            user: String::from("qa"),
+            password: String::from("a;b"),
+        };
+        assert_eq!(apply_credentials("DSN=dv", &creds), "DSN=dv;UID=qa;PWD={a;b};");
+    }
+
+    #[test]
+    fn redact_hides_password_values() {
+        assert_eq!(redact("Server=db01;PWD=secret;Database=dv"), "Server=db01;PWD=********;Database=dv");
+        assert_eq!(redact("Password={se;cret}}x}; UID=qa"), "Password=********; UID=qa");
+        assert_eq!(redact("login failed: 'pass=hunter2'"), "login failed: 'pass=********'");
+        assert_eq!(redact("Server=db01;PWD =secret"), "Server=db01;PWD =********");
+        assert_eq!(redact("PWD = secret;UID=qa"), "PWD = ********;UID=qa");
+        // not a key of its own
+        assert_eq!(redact("bypwd=abc;passwordless"), "bypwd=abc;passwordless");
+    }
+}
+
+/* <-- Tests */
@@ -0,0 +1,116 @@
+/* --> Summary of Contents
+
+	Sealing the local credential store with vetted primitives:
+	PBKDF2-HMAC-SHA256 (pbkdf2, sha2) derives the key from the master
+	passphrase, ChaCha20-Poly1305 (chacha20poly1305) encrypts and
+	authenticates. Only the DVC1 container is ours:
+
+		"DVC1" | salt (16) | nonce (12) | ciphertext | tag (16)
+
+	The header is authenticated along with the ciphertext.
+
+	pub fn seal(passphrase, plaintext) -> Result<Vec<u8>, String>
+	pub fn open(passphrase, sealed) -> Result<Vec<u8>, String>
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::{thread_rng, RngCore};
+use sha2::Sha256;
+
+/* <-- Imports */
+/* --> Const */
+
+const SEAL_MAGIC: &[u8; 4] = b"DVC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+// OWASP's figure for PBKDF2-HMAC-SHA256. The tests check the container, not the cost
+#[cfg(not(test))]
+const KDF_ITERATIONS: u32 = 600_000;
+#[cfg(test)]
+const KDF_ITERATIONS: u32 = 1_000;
+
+/* <-- Const */
+/* --> Functions */
+
+fn cipher(passphrase: &str, salt: &[u8]) -> ChaCha20Poly1305 {
+    let mut key: [u8; 32] = [0; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KDF_ITERATIONS, &mut key);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+}
+
+pub fn seal(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt: [u8; SALT_LEN] = [0; SALT_LEN];
+    let mut nonce: [u8; NONCE_LEN] = [0; NONCE_LEN];
+    thread_rng().fill_bytes(&mut salt);
+    thread_rng().fill_bytes(&mut nonce);
+
+    let mut sealed: Vec<u8> = Vec::with_capacity(SEAL_MAGIC.len() + SALT_LEN + NONCE_LEN + plaintext.len() + TAG_LEN);
+    sealed.extend_from_slice(SEAL_MAGIC);
+    sealed.extend_from_slice(&salt);
+    sealed.extend_from_slice(&nonce);
+    let body: Vec<u8> = cipher(passphrase, &salt)
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad: &sealed })
+        .map_err(|_| String::from("Unable to encrypt the credential store."))?;
+    sealed.extend_from_slice(&body);
+    Ok(sealed)
+}
+
+pub fn open(passphrase: &str, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    let header_len: usize = SEAL_MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if sealed.len() < header_len + TAG_LEN || &sealed[..SEAL_MAGIC.len()] != SEAL_MAGIC {
+        return Err(String::from("The credential store is damaged or not a DaedriVictus file."));
+    }
+
+    let (header, body) = sealed.split_at(header_len);
+    let salt: &[u8] = &header[SEAL_MAGIC.len()..SEAL_MAGIC.len() + SALT_LEN];
+    let nonce: &[u8] = &header[SEAL_MAGIC.len() + SALT_LEN..];
+    // a changed byte and a wrong passphrase fail the tag the same way
+    cipher(passphrase, salt)
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: body, aad: header })
+        .map_err(|_| String::from("Wrong master passphrase, or the credential store is damaged."))
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_round_trip_and_wrong_passphrase() {
+        let sealed: Vec<u8> = seal("master", b"secret").unwrap();
+        assert_eq!(open("master", &sealed).unwrap(), b"secret");
+        assert!(open("other", &sealed).is_err());
+    }
+
+    #[test]
+    fn the_container_is_dvc1_with_a_fresh_salt_and_nonce() {
+        let first: Vec<u8> = seal("master", b"secret").unwrap();
+        let second: Vec<u8> = seal("master", b"secret").unwrap();
+        assert_eq!(&first[..4], b"DVC1");
+        assert_eq!(first.len(), 4 + SALT_LEN + NONCE_LEN + b"secret".len() + TAG_LEN);
+        assert_ne!(first[4..4 + SALT_LEN + NONCE_LEN], second[4..4 + SALT_LEN + NONCE_LEN]);
+    }
+
+    #[test]
+    fn a_changed_byte_anywhere_is_refused() {
+        let sealed: Vec<u8> = seal("master", b"secret").unwrap();
+        // magic, salt, nonce, ciphertext and tag
+        for index in [0, 4, 4 + SALT_LEN, 4 + SALT_LEN + NONCE_LEN, sealed.len() - 1] {
+            let mut damaged: Vec<u8> = sealed.clone();
+            damaged[index] ^= 1;
+            assert!(open("master", &damaged).is_err(), "byte {}", index);
+        }
+        assert!(open("master", &sealed[..sealed.len() - 1]).is_err());
+        assert!(open("master", b"DVC1").is_err());
+    }
+}
+
+/* <-- Tests */
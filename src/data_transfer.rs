@@ -0,0 +1,787 @@
+/* --> Summary of Contents
+
+	Copy rows from a query on one connection into a table on another,
+	e.g. the production instrument list into the local SQLite copy
+
+	pub fn wizard(&[TransferSide], usize, &mut Option<String>)
+		Source, destination and load mode, then the column mapping, then
+		the load with a progress window and the error log at the end.
+		Every load is one entry of the audit log, named after the analyst.
+		A read-only source is read through the read-only entry points, a
+		read-only destination is refused and emptying a production table
+		asks first, as statement_guard.rs does in the editor.
+
+	pub fn default_mappings(&RecordSet, Dialect) -> Vec<ColumnMapping>
+		One mapping per source column, typed for the destination.
+
+	pub struct Loader
+		Create, empty or keep the table, then insert the rows the source
+		streams in (cli::stream_rows) in batches, one transaction per
+		batch, so no load is held in memory whole. A row that fails is
+		logged and skipped, the callback gets the rows read so far and
+		stops the load by answering false.
+
+	pub fn error_log(&TransferReport) -> String
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    button::{Button, CheckButton},
+    dialog,
+    enums::{Align, Font},
+    frame::Frame,
+    group::{Flex, Scroll},
+    input::{Input, IntInput, MultilineInput},
+    menu::Choice,
+    prelude::{ButtonExt, DisplayExt, GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    text::{TextBuffer, TextDisplay},
+    window::Window,
+};
+use odbc::ffi::SqlDataType;
+
+use crate::audit_log::{self, Action, AuditEntry};
+use crate::cli;
+use crate::credentials;
+use crate::learning_fltk::{center, escape_menu_label};
+use crate::odbc_interface;
+use crate::schema_diff::{self, quote, quote_literal, quote_table};
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, RecordSet, SqlData, SqlType};
+use crate::sql_format::Dialect;
+use crate::sql_lexer;
+use crate::sqlite3_interface::{self, SqliteBinary, SqliteFloat, SqliteInteger, SqliteNull, SqliteString, Type};
+use crate::statement_guard::{self, Safety};
+
+/* <-- Imports */
+/* --> Const */
+
+const DEFAULT_BATCH: usize = 500;
+const ROW_HEIGHT: i32 = 30;
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LoadMode {
+    Create,
+    Append,
+    TruncateAndLoad,
+}
+
+#[derive(Clone, Copy)]
+enum DialogMessage {
+    SourceChanged,
+    Next,
+    Cancel,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone)]
+pub struct TransferSide {
+    pub label: String,
+    pub connection: Option<(ConnectionBase, String)>,
+    pub sql: String, // the tab's selection or current statement
+    pub safety: Safety,
+}
+
+#[derive(Clone, Debug)]
+pub struct ColumnMapping {
+    pub source: String,
+    pub target: String,
+    pub data_type: String, // only used when the table is created
+    pub include: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct TransferPlan {
+    pub table: String,
+    pub mode: LoadMode,
+    pub mappings: Vec<ColumnMapping>,
+    pub batch_size: usize,
+}
+
+#[derive(Default, Debug)]
+pub struct TransferReport {
+    pub total: usize, // rows read from the source
+    pub written: usize,
+    pub failures: Vec<(usize, String)>, // 1 based source row, error
+    pub stopped: Option<String>,        // cancelled or the destination failed
+    pub prepared: bool,                 // the table was created or emptied, rows may have been written
+}
+
+// a load under way, the source hands it its rows batch by batch
+pub struct Loader<'a> {
+    pub plan: TransferPlan,
+    pub report: TransferReport,
+    connection_type: &'a ConnectionBase,
+    connection: &'a str,
+}
+
+struct ProgressWindow {
+    win: Window,
+    status: Frame,
+    rcvr: Receiver<DialogMessage>,
+}
+
+struct Endpoints {
+    source: usize,
+    sql: String,
+    destination: usize,
+    table: String,
+    mode: LoadMode,
+    batch_size: usize,
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+impl LoadMode {
+    fn label(&self) -> &'static str {
+        match self {
+            LoadMode::Create => "Create the table",
+            LoadMode::Append => "Append to the table",
+            LoadMode::TruncateAndLoad => "Empty the table, then load",
+        }
+    }
+}
+
+impl TransferReport {
+    pub fn summary(&self) -> String {
+        let mut text: String = format!("{} of {} rows written", self.written, self.total);
+        if !self.failures.is_empty() {
+            text.push_str(&format!(", {} failed", self.failures.len()));
+        }
+        if let Some(reason) = &self.stopped {
+            text.push_str(&format!(". Stopped: {}", reason));
+        }
+        text
+    }
+}
+
+// a bare table name reads the whole table
+fn source_sql(text: &str) -> String {
+    let trimmed: &str = text.trim().trim_end_matches(';').trim();
+    match trimmed.contains(char::is_whitespace) {
+        true => trimmed.to_string(),
+        false => format!("SELECT * FROM {}", trimmed),
+    }
+}
+
+fn odbc_type(data_type: &SqlDataType, dialect: Dialect) -> &'static str {
+    match (data_type, dialect) {
+        (
+            SqlDataType::SQL_INTEGER | SqlDataType::SQL_SMALLINT | SqlDataType::SQL_EXT_TINYINT | SqlDataType::SQL_EXT_BIGINT | SqlDataType::SQL_EXT_BIT,
+            Dialect::Sqlite,
+        ) => "INTEGER",
+        (SqlDataType::SQL_FLOAT | SqlDataType::SQL_REAL | SqlDataType::SQL_DOUBLE, Dialect::Sqlite) => "REAL",
+        (SqlDataType::SQL_NUMERIC | SqlDataType::SQL_DECIMAL, Dialect::Sqlite) => "NUMERIC",
+        (SqlDataType::SQL_EXT_BINARY | SqlDataType::SQL_EXT_VARBINARY | SqlDataType::SQL_EXT_LONGVARBINARY, Dialect::Sqlite) => "BLOB",
+        (_, Dialect::Sqlite) => "TEXT",
+        (SqlDataType::SQL_INTEGER | SqlDataType::SQL_SMALLINT | SqlDataType::SQL_EXT_TINYINT, Dialect::TSql) => "int",
+        (SqlDataType::SQL_EXT_BIT, Dialect::TSql) => "bit",
+        (SqlDataType::SQL_EXT_BIGINT, Dialect::TSql) => "bigint",
+        (SqlDataType::SQL_FLOAT | SqlDataType::SQL_REAL | SqlDataType::SQL_DOUBLE, Dialect::TSql) => "float",
+        (SqlDataType::SQL_NUMERIC | SqlDataType::SQL_DECIMAL, Dialect::TSql) => "decimal(18, 4)",
+        (SqlDataType::SQL_EXT_BINARY | SqlDataType::SQL_EXT_VARBINARY | SqlDataType::SQL_EXT_LONGVARBINARY, Dialect::TSql) => "varbinary(max)",
+        (SqlDataType::SQL_DATE, Dialect::TSql) => "date",
+        (SqlDataType::SQL_DATETIME | SqlDataType::SQL_TIMESTAMP | SqlDataType::SQL_EXT_TIMESTAMP, Dialect::TSql) => "datetime2",
+        (_, Dialect::TSql) => "nvarchar(max)",
+    }
+}
+
+fn sqlite_type(data_type: &Type, dialect: Dialect) -> &'static str {
+    match (data_type, dialect) {
+        (Type::Integer, Dialect::Sqlite) => "INTEGER",
+        (Type::Float, Dialect::Sqlite) => "REAL",
+        (Type::Binary, Dialect::Sqlite) => "BLOB",
+        (_, Dialect::Sqlite) => "TEXT",
+        (Type::Integer, Dialect::TSql) => "bigint",
+        (Type::Float, Dialect::TSql) => "float",
+        (Type::Binary, Dialect::TSql) => "varbinary(max)",
+        (_, Dialect::TSql) => "nvarchar(max)",
+    }
+}
+
+pub fn default_mappings(record_set: &RecordSet, dialect: Dialect) -> Vec<ColumnMapping> {
+    record_set
+        .column_order
+        .iter()
+        .map(|column| {
+            // SQLite reports the type of the first row only, a NULL there says nothing about the column
+            let sqlite_sample: Option<Type> = record_set.records.iter().find_map(|r| match r.columns.get(column) {
+                Some(Some(SqlData::Sqlite(SqliteNull))) | Some(None) | None => None,
+                Some(Some(SqlData::Sqlite(value))) => Some(value.kind()),
+                Some(Some(SqlData::Odbc(_))) => None,
+            });
+            let data_type: &str = match (record_set.column_info.get(column), sqlite_sample) {
+                (_, Some(sample)) => sqlite_type(&sample, dialect),
+                (Some(SqlType::Odbc(t)), None) => odbc_type(t, dialect),
+                (Some(SqlType::Sqlite(t)), None) => sqlite_type(t, dialect),
+                (None, None) => sqlite_type(&Type::String, dialect),
+            };
+            ColumnMapping {
+                source: column.clone(),
+                target: column.clone(),
+                data_type: data_type.to_string(),
+                include: true,
+            }
+        })
+        .collect()
+}
+
+// the value as the destination column wants it, ODBC hands every value over as text
+fn sqlite_value(data: Option<&SqlData>, data_type: &str) -> sqlite::Value {
+    match data {
+        None | Some(SqlData::Sqlite(SqliteNull)) => SqliteNull,
+        Some(SqlData::Sqlite(value)) => value.clone(),
+        Some(SqlData::Odbc(text)) if text == "Null" => SqliteNull,
+        Some(SqlData::Odbc(text)) => {
+            let number: &str = text.trim();
+            match (schema_diff::affinity(data_type), number.parse::<i64>(), number.parse::<f64>()) {
+                ("INTEGER" | "NUMERIC", Ok(i), _) => SqliteInteger(i),
+                ("INTEGER" | "NUMERIC" | "REAL", _, Ok(f)) => SqliteFloat(f),
+                _ => SqliteString(text.clone()),
+            }
+        }
+    }
+}
+
+fn tsql_literal(data: Option<&SqlData>, data_type: &str) -> String {
+    let text_literal = |text: &str| format!("N{}", quote_literal(text));
+    match data {
+        None | Some(SqlData::Sqlite(SqliteNull)) => String::from("NULL"),
+        Some(SqlData::Sqlite(SqliteInteger(i))) => i.to_string(),
+        Some(SqlData::Sqlite(SqliteFloat(f))) if f.is_finite() => f.to_string(),
+        Some(SqlData::Sqlite(SqliteFloat(f))) => text_literal(&f.to_string()),
+        Some(SqlData::Sqlite(SqliteBinary(bytes))) => {
+            format!("0x{}", bytes.iter().map(|b| format!("{:02X}", b)).collect::<String>())
+        }
+        Some(SqlData::Sqlite(SqliteString(text))) => text_literal(text),
+        Some(SqlData::Odbc(text)) if text == "Null" => String::from("NULL"),
+        Some(SqlData::Odbc(text)) if schema_diff::affinity(data_type) != "TEXT" && sql_lexer::is_number_literal(text.trim()) => {
+            text.trim().to_string()
+        }
+        Some(SqlData::Odbc(text)) => text_literal(text),
+    }
+}
+
+fn create_quote_table(plan: &TransferPlan, dialect: Dialect) -> String {
+    let columns: Vec<String> = plan
+        .mappings
+        .iter()
+        .filter(|m| m.include)
+        .map(|m| format!("    {} {}", quote(&m.target, dialect), m.data_type))
+        .collect();
+    format!("CREATE TABLE {} (\n{}\n);", quote_table(&plan.table, dialect), columns.join(",\n"))
+}
+
+// the column names of an existing table, an empty select works on both backends
+pub fn destination_columns(connection_type: &ConnectionBase, connection: &str, table: &str) -> Result<Vec<String>, String> {
+    let sql: String = format!("SELECT * FROM {} WHERE 1 = 0", quote_table(table, Dialect::of(connection_type)));
+    Ok(cli::run_request(connection_type, connection, QueryType::UserDefined(sql))?.column_order)
+}
+
+// what runs before the rows, None when appending
+fn prepare_sql(plan: &TransferPlan, dialect: Dialect) -> Option<String> {
+    match (plan.mode, dialect) {
+        (LoadMode::Create, _) => Some(create_quote_table(plan, dialect)),
+        (LoadMode::Append, _) => None,
+        (LoadMode::TruncateAndLoad, Dialect::Sqlite) => Some(format!("DELETE FROM {}", quote_table(&plan.table, dialect))),
+        (LoadMode::TruncateAndLoad, Dialect::TSql) => Some(format!("TRUNCATE TABLE {}", quote_table(&plan.table, dialect))),
+    }
+}
+
+fn prepare_table(plan: &TransferPlan, connection_type: &ConnectionBase, connection: &str) -> Result<(), String> {
+    match prepare_sql(plan, Dialect::of(connection_type)) {
+        Some(sql) => cli::run_request(connection_type, connection, QueryType::UserDefined(sql)).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+impl<'a> Loader<'a> {
+    // the table is created or emptied here, a failure stops the load before its first row
+    pub fn start(plan: TransferPlan, connection_type: &'a ConnectionBase, connection: &'a str) -> Loader<'a> {
+        let mut report: TransferReport = TransferReport::default();
+        if !plan.mappings.iter().any(|m| m.include) {
+            report.stopped = Some(String::from("no columns to copy"));
+        } else if let Err(e) = prepare_table(&plan, connection_type, connection) {
+            report.stopped = Some(credentials::redact(&e));
+        } else {
+            report.prepared = true;
+        }
+        Loader { plan, report, connection_type, connection }
+    }
+
+    // false once the load has stopped, the source stops reading then
+    pub fn load(&mut self, rows: &RecordSet, progress: &mut dyn FnMut(usize) -> bool) -> bool {
+        let dialect: Dialect = Dialect::of(self.connection_type);
+        let mappings: Vec<&ColumnMapping> = self.plan.mappings.iter().filter(|m| m.include).collect();
+        let table: String = quote_table(&self.plan.table, dialect);
+        let column_list: String = mappings.iter().map(|m| quote(&m.target, dialect)).collect::<Vec<String>>().join(", ");
+
+        for batch in rows.records.chunks(self.plan.batch_size.max(1)) {
+            if self.report.stopped.is_some() {
+                break;
+            }
+            let done: usize = self.report.total;
+            self.report.total += batch.len();
+            let outcome: Result<Vec<(usize, String)>, String> = match self.connection_type {
+                ConnectionBase::Sqlite => {
+                    let insert: String = format!("INSERT INTO {} ({}) VALUES ({})", table, column_list, vec!["?"; mappings.len()].join(", "));
+                    let values: Vec<Vec<sqlite::Value>> = batch
+                        .iter()
+                        .map(|record| mappings.iter().map(|m| sqlite_value(record.columns.get(&m.source).and_then(|d| d.as_ref()), &m.data_type)).collect())
+                        .collect();
+                    sqlite3_interface::insert_batch(self.connection, &insert, &values).map_err(|e| e.to_string())
+                }
+                ConnectionBase::Odbc => {
+                    let statements: Vec<String> = batch
+                        .iter()
+                        .map(|record| {
+                            let values: Vec<String> = mappings
+                                .iter()
+                                .map(|m| tsql_literal(record.columns.get(&m.source).and_then(|d| d.as_ref()), &m.data_type))
+                                .collect();
+                            format!("INSERT INTO {} ({}) VALUES ({})", table, column_list, values.join(", "))
+                        })
+                        .collect();
+                    odbc_interface::execute_batch(self.connection.to_string(), &statements).map_err(|e| e.to_string())
+                }
+            };
+            match outcome {
+                Ok(failures) => {
+                    self.report.written += batch.len() - failures.len();
+                    self.report.failures.extend(failures.into_iter().map(|(index, e)| (done + index + 1, e)));
+                }
+                // the batch was rolled back whole, later batches would fail the same way
+                Err(e) => {
+                    let lost: String = format!("rows {} to {} were not written: {}", done + 1, self.report.total, e);
+                    self.report.stopped = Some(credentials::redact(&lost));
+                }
+            }
+            if self.report.stopped.is_none() && !progress(self.report.total) {
+                self.report.stopped = Some(String::from("cancelled"));
+            }
+        }
+        self.report.stopped.is_none()
+    }
+}
+
+// one entry per load like a MERGE or TRUNCATE, the rows themselves are not captured
+fn audit_entry(plan: &TransferPlan, report: &TransferReport, source: &str, sql: &str, target_label: &str, analyst: &str) -> AuditEntry {
+    AuditEntry {
+        analyst: analyst.to_string(),
+        os_user: audit_log::os_user(),
+        connection: target_label.to_string(),
+        table_name: plan.table.clone(),
+        action: Action::Other.name().to_string(),
+        note: format!("Transfer from {}, {}: {}", source, plan.mode.label().to_lowercase(), report.summary()),
+        statement: sql.to_string(),
+        ..Default::default()
+    }
+}
+
+pub fn error_log(report: &TransferReport) -> String {
+    let mut lines: Vec<String> = vec![report.summary()];
+    lines.extend(report.failures.iter().map(|(row, e)| format!("row {}: {}", row, e)));
+    lines.join("\n") + "\n"
+}
+
+fn alert(message: &str) {
+    let (x, y): (i32, i32) = center();
+    dialog::alert(x - 200, y - 100, message);
+}
+
+pub fn wizard(sides: &[TransferSide], active: usize, analyst: &mut Option<String>) {
+    let endpoints: Endpoints = match ask_endpoints(sides, active) {
+        Some(endpoints) => endpoints,
+        None => return,
+    };
+    let (source_type, source_connection) = match &sides[endpoints.source].connection {
+        Some(connection) => connection.clone(),
+        None => return alert(&format!("{} is not connected", sides[endpoints.source].label)),
+    };
+    let (target_type, target_connection) = match &sides[endpoints.destination].connection {
+        Some(connection) => connection.clone(),
+        None => return alert(&format!("{} is not connected", sides[endpoints.destination].label)),
+    };
+    let (source_safety, target_safety): (Safety, Safety) = (sides[endpoints.source].safety, sides[endpoints.destination].safety);
+    if target_safety.read_only {
+        return alert(&format!("{} is read-only, nothing can be loaded into it", sides[endpoints.destination].label));
+    }
+
+    // the source SQL is checked like the editor's before it runs
+    let sql: String = source_sql(&endpoints.sql);
+    if source_safety.read_only {
+        if let Err(e) = statement_guard::refuse_writes(source_safety, &sql) {
+            return alert(&format!("{}: {}", sides[endpoints.source].label, e));
+        }
+    } else if !statement_guard::confirm_destructive(source_safety, &source_type, &source_connection, &sql) {
+        return;
+    }
+    // a second connection cannot commit into a SQLite file a statement is still reading, the same file is read whole first
+    let same_file: bool = matches!((&source_type, &target_type), (ConnectionBase::Sqlite, ConnectionBase::Sqlite)) && source_connection == target_connection;
+    let read_size: usize = if same_file { usize::MAX } else { endpoints.batch_size };
+
+    // the first batch gives the columns to map, the load starts once they are
+    let mut loader: Option<Loader> = None;
+    let mut window: Option<ProgressWindow> = None;
+    let mut analyst_name: String = String::new();
+    let streamed: Result<(), String> =
+        cli::stream_rows(&source_type, &source_connection, source_safety.read_only, &sql, read_size, &mut |rows: RecordSet| {
+            if loader.is_none() {
+                let plan: TransferPlan = match ask_plan(&endpoints, &rows, &target_type, &target_connection, target_safety) {
+                    Some(plan) => plan,
+                    None => return false,
+                };
+                analyst_name = audit_log::resolve_analyst(analyst, &target_type, &target_connection);
+                loader = Some(Loader::start(plan, &target_type, &target_connection));
+                window = Some(ProgressWindow::open());
+            }
+            match (loader.as_mut(), window.as_mut()) {
+                (Some(loader), Some(window)) => loader.load(&rows, &mut |done| window.update(done)),
+                _ => false,
+            }
+        });
+    if let Some(window) = window.as_mut() {
+        window.close();
+    }
+    let loader: Loader = match (loader, streamed) {
+        (Some(loader), Ok(())) => loader,
+        (Some(mut loader), Err(e)) => {
+            if loader.report.stopped.is_none() {
+                loader.report.stopped = Some(format!("{}: {}", sides[endpoints.source].label, credentials::redact(&e)));
+            }
+            loader
+        }
+        (None, Ok(())) => return,
+        (None, Err(e)) => return alert(&format!("{}: {}", sides[endpoints.source].label, credentials::redact(&e))),
+    };
+    if loader.report.prepared {
+        let target_label: String = audit_log::connection_name(&target_type, &target_connection);
+        let source: String = audit_log::connection_name(&source_type, &source_connection);
+        audit_log::write(&[audit_entry(&loader.plan, &loader.report, &source, &sql, &target_label, &analyst_name)]);
+    }
+    show_report(&loader.report, &loader.plan.table);
+}
+
+// the column mapping for the source's first batch, None when the user cancels or the table cannot be read
+fn ask_plan(
+    endpoints: &Endpoints,
+    rows: &RecordSet,
+    target_type: &ConnectionBase,
+    target_connection: &str,
+    target_safety: Safety,
+) -> Option<TransferPlan> {
+    let mut mappings: Vec<ColumnMapping> = default_mappings(rows, Dialect::of(target_type));
+    // an existing table decides the names, source columns it lacks start switched off
+    let existing: Vec<String> = match endpoints.mode {
+        LoadMode::Create => Vec::new(),
+        _ => match destination_columns(target_type, target_connection, &endpoints.table) {
+            Ok(columns) => columns,
+            Err(e) => {
+                alert(&format!("{}: {}", endpoints.table, credentials::redact(&e)));
+                return None;
+            }
+        },
+    };
+    for mapping in mappings.iter_mut().filter(|_| !existing.is_empty()) {
+        match existing.iter().find(|c| c.eq_ignore_ascii_case(&mapping.source)) {
+            Some(column) => mapping.target = column.clone(),
+            None => mapping.include = false,
+        }
+    }
+    if !ask_mappings(&mut mappings, endpoints.mode, &existing) {
+        return None;
+    }
+    let plan: TransferPlan = TransferPlan {
+        table: endpoints.table.clone(),
+        mode: endpoints.mode,
+        mappings,
+        batch_size: endpoints.batch_size,
+    };
+    // emptying a production table is asked like any other TRUNCATE
+    if plan.mode == LoadMode::TruncateAndLoad && target_safety.production {
+        let empty: String = prepare_sql(&plan, Dialect::of(target_type)).unwrap_or_default();
+        if !statement_guard::confirm_destructive(target_safety, target_type, target_connection, &empty) {
+            return None;
+        }
+    }
+    Some(plan)
+}
+
+fn ask_endpoints(sides: &[TransferSide], active: usize) -> Option<Endpoints> {
+    if sides.is_empty() {
+        return None;
+    }
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+    let mut win: Window = Window::default()
+        .with_size(640, 370)
+        .with_label("Transfer Data")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut source: Choice = Choice::new(100, 10, 530, 25, "From");
+    let mut sql: MultilineInput = MultilineInput::new(100, 40, 530, 140, "SQL or table");
+    sql.set_align(Align::Left | Align::Top | Align::Wrap);
+    let mut destination: Choice = Choice::new(100, 190, 530, 25, "To");
+    let table: Input = Input::new(100, 220, 530, 25, "Table");
+    let mut mode: Choice = Choice::new(100, 250, 530, 25, "Load");
+    let mut batch: IntInput = IntInput::new(100, 280, 100, 25, "Batch size");
+    batch.set_tooltip("Rows per transaction");
+    batch.set_value(&DEFAULT_BATCH.to_string());
+    for choice in [&mut source, &mut destination] {
+        for side in sides {
+            choice.add_choice(&escape_menu_label(&side.label.replace('|', " ")));
+        }
+    }
+    for load in [LoadMode::Create, LoadMode::Append, LoadMode::TruncateAndLoad] {
+        mode.add_choice(load.label());
+    }
+    let destination_index: usize = if sides.len() > 1 && active == 0 { 1 } else { 0 };
+    source.set_value(active as i32);
+    destination.set_value(destination_index as i32);
+    mode.set_value(1);
+    sql.set_value(&sides[active].sql);
+    source.emit(sndr, DialogMessage::SourceChanged);
+
+    let mut next_butn: Button = Button::new(470, 330, 75, 28, "&Next");
+    next_butn.emit(sndr, DialogMessage::Next);
+    let mut cancel_butn: Button = Button::new(555, 330, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+    win.end();
+    win.show();
+
+    let mut endpoints: Option<Endpoints> = None;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::SourceChanged) => {
+                if let Some(side) = usize::try_from(source.value()).ok().and_then(|i| sides.get(i)) {
+                    sql.set_value(&side.sql);
+                }
+            }
+            Some(DialogMessage::Next) => {
+                if sql.value().trim().is_empty() || table.value().trim().is_empty() {
+                    alert("Enter the source SQL and the destination table.");
+                    continue;
+                }
+                if let (Ok(from), Ok(to)) = (usize::try_from(source.value()), usize::try_from(destination.value())) {
+                    endpoints = Some(Endpoints {
+                        source: from,
+                        sql: sql.value(),
+                        destination: to,
+                        table: table.value().trim().to_string(),
+                        mode: match mode.value() {
+                            0 => LoadMode::Create,
+                            2 => LoadMode::TruncateAndLoad,
+                            _ => LoadMode::Append,
+                        },
+                        batch_size: batch.value().parse::<usize>().unwrap_or(DEFAULT_BATCH).max(1),
+                    });
+                    win.hide();
+                }
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            None => {}
+        }
+    }
+    endpoints
+}
+
+// one row per source column: copy it or not, the destination column and, for a new table, its type
+fn ask_mappings(mappings: &mut [ColumnMapping], mode: LoadMode, existing: &[String]) -> bool {
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+    let mut win: Window = Window::default()
+        .with_size(640, 420)
+        .with_label("Transfer Data - Columns")
+        .center_screen();
+    win.make_modal(true);
+
+    for (x, w, label) in [(10, 210, "Source column"), (225, 210, "Destination column"), (440, 180, "Type")] {
+        let mut header: Frame = Frame::new(x, 5, w, 25, None).with_label(label);
+        header.set_align(Align::Inside | Align::Left);
+    }
+    let scroll: Scroll = Scroll::new(5, 30, 630, 340, None);
+    let mut rows: Vec<(CheckButton, Input, Input)> = Vec::new();
+    for (index, mapping) in mappings.iter().enumerate() {
+        let y: i32 = 35 + index as i32 * ROW_HEIGHT;
+        let mut include: CheckButton = CheckButton::new(10, y, 210, 25, None);
+        include.set_label(&escape_menu_label(&mapping.source));
+        include.set_checked(mapping.include);
+        let mut target: Input = Input::new(225, y, 210, 25, None);
+        target.set_value(&mapping.target);
+        let mut data_type: Input = Input::new(440, y, 180, 25, None);
+        data_type.set_value(&mapping.data_type);
+        if mode != LoadMode::Create {
+            data_type.deactivate();
+        }
+        rows.push((include, target, data_type));
+    }
+    scroll.end();
+
+    let mut start_butn: Button = Button::new(470, 380, 75, 28, "&Start");
+    start_butn.emit(sndr, DialogMessage::Next);
+    let mut cancel_butn: Button = Button::new(555, 380, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+    win.end();
+    win.show();
+
+    let mut accepted: bool = false;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::Next) => {
+                for (mapping, (include, target, data_type)) in mappings.iter_mut().zip(&rows) {
+                    mapping.include = include.is_checked();
+                    mapping.target = target.value().trim().to_string();
+                    mapping.data_type = data_type.value().trim().to_string();
+                }
+                let unknown: Vec<&str> = mappings
+                    .iter()
+                    .filter(|m| m.include && !existing.is_empty() && !existing.iter().any(|c| c.eq_ignore_ascii_case(&m.target)))
+                    .map(|m| m.target.as_str())
+                    .collect();
+                if !mappings.iter().any(|m| m.include) {
+                    alert("Select at least one column.");
+                } else if mappings.iter().any(|m| m.include && m.target.is_empty()) {
+                    alert("Every selected column needs a destination name.");
+                } else if !unknown.is_empty() {
+                    alert(&format!("The table has no column {}", unknown.join(", ")));
+                } else {
+                    accepted = true;
+                    win.hide();
+                }
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            _ => {}
+        }
+    }
+    accepted
+}
+
+impl ProgressWindow {
+    fn open() -> ProgressWindow {
+        let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+        let mut win: Window = Window::default()
+            .with_size(420, 80)
+            .with_label("Transferring...")
+            .center_screen();
+        win.make_modal(true);
+        let status: Frame = Frame::new(10, 10, 400, 25, None);
+        let mut cancel_butn: Button = Button::new(335, 45, 75, 28, "Cancel");
+        cancel_butn.emit(sndr, DialogMessage::Cancel);
+        win.end();
+        win.show();
+        ProgressWindow { win, status, rcvr }
+    }
+
+    // the window is redrawn between batches and Cancel is seen there, the total is not known until the end
+    fn update(&mut self, done: usize) -> bool {
+        self.status.set_label(&format!("{} rows read", done));
+        app::flush();
+        let _ = app::wait_for(0.0);
+        !matches!(self.rcvr.recv(), Some(DialogMessage::Cancel))
+    }
+
+    fn close(&mut self) {
+        self.win.hide();
+    }
+}
+
+fn show_report(report: &TransferReport, table: &str) {
+    if report.failures.is_empty() && report.stopped.is_none() {
+        let (x, y): (i32, i32) = center();
+        dialog::message(x - 200, y - 100, &format!("{}: {}", table, report.summary()));
+        return;
+    }
+    let mut win: Window = Window::default()
+        .with_size(640, 400)
+        .with_label(&format!("Transfer into {}", table))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+    let mut log: TextDisplay = TextDisplay::default();
+    log.set_buffer(TextBuffer::default());
+    log.set_text_font(Font::Courier);
+    let mut buttons: Flex = Flex::default().row();
+    Frame::default();
+    let mut save_butn: Button = Button::default().with_label("&Save log...");
+    buttons.fixed(&save_butn, 100);
+    buttons.end();
+    layout.fixed(&buttons, 30);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    let text: String = error_log(report);
+    if let Some(mut buffer) = log.buffer() {
+        buffer.set_text(&text);
+    }
+    save_butn.set_callback(move |_| {
+        let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+        chooser.set_title("Save the error log");
+        chooser.set_filter("*.txt");
+        chooser.set_option(dialog::FileDialogOptions::SaveAsConfirm);
+        chooser.show();
+        let path: String = chooser.filename().to_string_lossy().into_owned();
+        if path.is_empty() {
+            return;
+        }
+        if let Err(e) = std::fs::write(&path, &text) {
+            println!("Unable to write {}: {}", path, e);
+        }
+    });
+    win.show();
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn literals() {
+        let odbc = |text: &str| SqlData::Odbc(text.to_string());
+        assert_eq!(tsql_literal(None, "INT"), "NULL");
+        assert_eq!(tsql_literal(Some(&odbc("Null")), "INT"), "NULL");
+        assert_eq!(tsql_literal(Some(&odbc(" 12.5 ")), "FLOAT"), "12.5");
+        assert_eq!(tsql_literal(Some(&odbc("12.5")), "NVARCHAR(50)"), "N'12.5'");
+        assert_eq!(tsql_literal(Some(&odbc("O'Brien")), "NVARCHAR(50)"), "N'O''Brien'");
+        for text in ["inf", "NaN", "infinity", "1e5000"] {
+            assert_eq!(tsql_literal(Some(&odbc(text)), "FLOAT"), format!("N'{}'", text));
+        }
+        assert_eq!(tsql_literal(Some(&SqlData::Sqlite(SqliteFloat(f64::INFINITY))), "FLOAT"), "N'inf'");
+        assert_eq!(tsql_literal(Some(&SqlData::Sqlite(SqliteFloat(1.5))), "FLOAT"), "1.5");
+        assert_eq!(tsql_literal(Some(&SqlData::Sqlite(SqliteBinary(vec![0, 255]))), "VARBINARY(MAX)"), "0x00FF");
+    }
+
+    #[test]
+    fn a_load_is_one_audit_entry() {
+        let plan: TransferPlan = TransferPlan {
+            table: String::from("measuring_instruments"),
+            mode: LoadMode::TruncateAndLoad,
+            mappings: Vec::new(),
+            batch_size: DEFAULT_BATCH,
+        };
+        let report: TransferReport = TransferReport {
+            total: 120,
+            written: 118,
+            failures: vec![(7, String::from("bad date")), (9, String::from("bad date"))],
+            prepared: true,
+            ..Default::default()
+        };
+        let entry: AuditEntry = audit_entry(&plan, &report, "PROD (ODBC)", "SELECT * FROM instruments", "local.db (SQLite)", "J. Smith");
+        assert_eq!((entry.action.as_str(), entry.table_name.as_str(), entry.analyst.as_str()), ("OTHER", "measuring_instruments", "J. Smith"));
+        assert_eq!(entry.connection, "local.db (SQLite)");
+        assert_eq!(entry.note, "Transfer from PROD (ODBC), empty the table, then load: 118 of 120 rows written, 2 failed");
+        assert_eq!(entry.statement, "SELECT * FROM instruments");
+    }
+}
+
+/* <-- Tests */
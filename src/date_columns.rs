@@ -0,0 +1,724 @@
+/* --> Summary of Contents
+
+	Dates kept as text (or Excel serial numbers) read as dates
+
+	DateSettings
+		Per-column format overrides and the sentinel values that mean "no
+		date" ('1/1/1900' is the default of the calibration columns),
+		kept in date_columns.ini.
+
+	pub fn parse(&str, DateFormat) -> Option<i64>
+	pub fn parse_any(&str) -> Option<i64>
+	pub fn iso(i64) -> String
+	pub fn today() -> i64
+		Days since 1970-01-01 from M/D/YYYY, D/M/YYYY, YYYY-MM-DD (a time
+		after the date is ignored) or an Excel serial, and back to ISO.
+
+	RecordSet::date_columns(&DateSettings) -> DateColumns
+		The date columns of a result: the override when there is one,
+		otherwise the format nearly every value parses with. Numbers only
+		count as Excel serials in columns named like a date.
+
+	RecordSet::sort_by_column(&str, bool, &DateColumns)
+	RecordSet::filtered(&RowFilter, &DateColumns) -> RecordSet
+	RecordSet::with_iso_dates(&DateColumns) -> RecordSet
+		Dates compare as dates, numbers as numbers, the rest as text;
+		NULL and sentinels sort last. with_iso_dates is what exports
+		write: YYYY-MM-DD, sentinels as NULL, unreadable text unchanged.
+
+	pub fn parse_filter(&str, &[String]) -> Result<RowFilter, String>
+		"Calib Due Date < 1/1/2024", "Model contains 500", "Calib Date = null"
+
+	pub fn edit_settings(&mut DateSettings, Option<&RecordSet>) -> bool
+		Tools/Date Columns: a format per column of the current result
+		(Detect leaves it to the sampling) and the sentinel list
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    button::Button,
+    enums::Align,
+    frame::Frame,
+    group::Scroll,
+    input::Input,
+    menu::Choice,
+    prelude::{GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    window::Window,
+};
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::app_paths;
+use crate::learning_fltk::escape_menu_label;
+use crate::ini_file::{IniDocument, IniSection};
+use crate::sql_aux_funcs::{Record, RecordSet, SqlData, SqlType};
+use crate::sqlite3_interface::{SqliteFloat, SqliteInteger, SqliteNull, SqliteString, Type};
+
+/* <-- Imports */
+/* --> Const */
+
+const SETTINGS_FILE: &str = "date_columns.ini";
+const COLUMN_PREFIX: &str = "column:";
+const SENTINEL_SECTION: &str = "sentinels";
+const DEFAULT_SENTINELS: [&str; 4] = ["1/1/1900", "0", "12/31/9999", "1/1/1753"];
+
+const EXCEL_EPOCH: i64 = -25569; // serial 0 is 1899-12-30
+const EXCEL_MAX: f64 = 2958465.0; // 9999-12-31
+const SAMPLE_SIZE: usize = 500;
+const MAX_MISSES: f64 = 0.05; // a few typos do not stop a column from being a date column
+const ROW_HEIGHT: i32 = 30;
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DateFormat {
+    MonthDayYear,
+    DayMonthYear,
+    Iso,
+    ExcelSerial,
+    NotADate, // an override that turns detection off for a column
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FilterOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+#[derive(Clone, Copy)]
+enum DialogMessage {
+    Save,
+    Cancel,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct DateSettings {
+    pub overrides: HashMap<String, DateFormat>, // lower case column name
+    pub sentinels: Vec<String>,
+    path: PathBuf,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DateColumns {
+    pub formats: HashMap<String, DateFormat>, // column -> format, date columns only
+    sentinel_texts: Vec<String>,
+    sentinel_days: Vec<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RowFilter {
+    pub column: String,
+    pub op: FilterOp,
+    pub value: Option<String>, // None: the filter is against NULL
+}
+
+impl DateFormat {
+    pub const ALL: [DateFormat; 5] = [
+        DateFormat::MonthDayYear,
+        DateFormat::DayMonthYear,
+        DateFormat::Iso,
+        DateFormat::ExcelSerial,
+        DateFormat::NotADate,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DateFormat::MonthDayYear => "M/D/YYYY",
+            DateFormat::DayMonthYear => "D/M/YYYY",
+            DateFormat::Iso => "YYYY-MM-DD",
+            DateFormat::ExcelSerial => "Excel serial",
+            DateFormat::NotADate => "Not a date",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DateFormat> {
+        DateFormat::ALL.into_iter().find(|f| f.name().eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+impl DateSettings {
+    pub fn load() -> Self {
+        Self::load_from(app_paths::config_file(SETTINGS_FILE))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let doc: IniDocument = match IniDocument::load(&path) {
+            Ok(doc) => doc,
+            Err(e) => {
+                println!("Unable to read {}: {}", path.display(), e);
+                IniDocument::default()
+            }
+        };
+        let overrides: HashMap<String, DateFormat> = doc
+            .sections_with_prefix(COLUMN_PREFIX)
+            .filter_map(|(column, section)| Some((column.to_lowercase(), DateFormat::from_name(section.get("format")?)?)))
+            .collect();
+        let sentinels: Vec<String> = match doc.section(SENTINEL_SECTION) {
+            Some(section) => section.get_all("value").map(String::from).collect(),
+            None => DEFAULT_SENTINELS.iter().map(|s| s.to_string()).collect(),
+        };
+        DateSettings { overrides, sentinels, path }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut doc: IniDocument = IniDocument::default();
+        let sentinels: &mut IniSection = doc.push_section(SENTINEL_SECTION);
+        for sentinel in &self.sentinels {
+            sentinels.append("value", sentinel);
+        }
+        let mut columns: Vec<(&String, &DateFormat)> = self.overrides.iter().collect();
+        columns.sort_by_key(|(column, _)| column.as_str());
+        for (column, format) in columns {
+            doc.push_section(&format!("{}{}", COLUMN_PREFIX, column)).set("format", format.name());
+        }
+        doc.save(&self.path).map_err(|e| format!("Unable to save {}: {}", self.path.display(), e))
+    }
+
+    pub fn is_sentinel(&self, text: &str) -> bool {
+        self.columns_template().is_sentinel(text)
+    }
+
+    fn columns_template(&self) -> DateColumns {
+        DateColumns {
+            formats: HashMap::new(),
+            sentinel_texts: self.sentinels.iter().map(|s| s.trim().to_lowercase()).collect(),
+            sentinel_days: self.sentinels.iter().filter_map(|s| parse_any(s)).collect(),
+        }
+    }
+}
+
+impl DateColumns {
+    pub fn is_date(&self, column: &str) -> bool {
+        self.formats.contains_key(column)
+    }
+
+    // text that stands for "no date"; '1/1/1900 00:00:00' counts as '1/1/1900' because the day is compared
+    pub fn is_sentinel(&self, text: &str) -> bool {
+        let trimmed: String = text.trim().to_lowercase();
+        self.sentinel_texts.contains(&trimmed) || parse_any(&trimmed).is_some_and(|day| self.sentinel_days.contains(&day))
+    }
+
+    // the day of a cell in a date column, None for NULL, sentinels and values that do not parse
+    pub fn date(&self, record: &Record, column: &str) -> Option<i64> {
+        let format: DateFormat = *self.formats.get(column)?;
+        let text: String = record.text(column)?;
+        if self.is_sentinel(&text) {
+            return None;
+        }
+        parse(&text, format)
+    }
+}
+
+impl RowFilter {
+    pub fn matches(&self, record: &Record, dates: &DateColumns) -> bool {
+        let cell: Option<String> = record.text(&self.column);
+        let value: &str = match &self.value {
+            // "= null" also finds the sentinels of a date column
+            None => {
+                let is_null: bool = match dates.is_date(&self.column) {
+                    true => dates.date(record, &self.column).is_none(),
+                    false => cell.is_none(),
+                };
+                return if self.op == FilterOp::Ne { !is_null } else { is_null };
+            }
+            Some(value) => value,
+        };
+        if self.op == FilterOp::Contains {
+            return cell.is_some_and(|c| c.to_lowercase().contains(&value.to_lowercase()));
+        }
+        let ordering: Option<Ordering> = match (dates.formats.get(&self.column), parse_any(value)) {
+            (Some(format), Some(wanted)) => dates.date(record, &self.column).map(|day| day.cmp(&parse(value, *format).unwrap_or(wanted))),
+            _ => cell.map(|c| compare_text(&c, value)),
+        };
+        match (ordering, self.op) {
+            (None, FilterOp::Ne) => true,
+            (None, _) => false,
+            (Some(o), FilterOp::Eq) => o == Ordering::Equal,
+            (Some(o), FilterOp::Ne) => o != Ordering::Equal,
+            (Some(o), FilterOp::Lt) => o == Ordering::Less,
+            (Some(o), FilterOp::Le) => o != Ordering::Greater,
+            (Some(o), FilterOp::Gt) => o == Ordering::Greater,
+            (Some(o), FilterOp::Ge) => o != Ordering::Less,
+            (Some(_), FilterOp::Contains) => false,
+        }
+    }
+}
+
+impl RecordSet {
+    pub fn date_columns(&self, settings: &DateSettings) -> DateColumns {
+        let mut dates: DateColumns = settings.columns_template();
+        for column in &self.column_order {
+            let format: Option<DateFormat> = match settings.overrides.get(&column.to_lowercase()) {
+                Some(DateFormat::NotADate) => None,
+                Some(format) => Some(*format),
+                None => detect(self, column, &dates),
+            };
+            if let Some(format) = format {
+                dates.formats.insert(column.clone(), format);
+            }
+        }
+        dates
+    }
+
+    // NULL and sentinels go last in either direction
+    pub fn sort_by_column(&mut self, column: &str, descending: bool, dates: &DateColumns) {
+        let is_date: bool = dates.is_date(column);
+        self.records.sort_by(|a, b| {
+            let ordering: Option<Ordering> = match is_date {
+                true => match (dates.date(a, column), dates.date(b, column)) {
+                    (Some(x), Some(y)) => Some(x.cmp(&y)),
+                    (x, y) => return x.is_none().cmp(&y.is_none()),
+                },
+                false => match (a.text(column), b.text(column)) {
+                    (Some(x), Some(y)) => Some(compare_text(&x, &y)),
+                    (x, y) => return x.is_none().cmp(&y.is_none()),
+                },
+            };
+            let ordering: Ordering = ordering.unwrap_or(Ordering::Equal);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+
+    pub fn filtered(&self, filter: &RowFilter, dates: &DateColumns) -> RecordSet {
+        RecordSet {
+            column_info: self.column_info.clone(),
+            column_order: self.column_order.clone(),
+            records: self.records.iter().filter(|r| filter.matches(r, dates)).cloned().collect(),
+        }
+    }
+
+    pub fn with_iso_dates(&self, dates: &DateColumns) -> RecordSet {
+        let mut record_set: RecordSet = self.clone();
+        for column in dates.formats.keys() {
+            record_set.column_info.insert(column.clone(), SqlType::Sqlite(Type::String));
+        }
+        for (record, original) in record_set.records.iter_mut().zip(&self.records) {
+            for column in dates.formats.keys() {
+                // a typo is written as it was typed, it is not a date to convert
+                let value: SqlData = match (dates.date(original, column), original.text(column)) {
+                    (Some(day), _) => SqlData::Sqlite(SqliteString(iso(day))),
+                    (None, Some(text)) if !text.trim().is_empty() && !dates.is_sentinel(&text) => SqlData::Sqlite(SqliteString(text)),
+                    (None, _) => SqlData::Sqlite(SqliteNull),
+                };
+                record.columns.insert(column.clone(), Some(value));
+            }
+        }
+        record_set
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+// numbers by value, the rest case-insensitive
+fn compare_text(left: &str, right: &str) -> Ordering {
+    match (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+        (Ok(l), Ok(r)) => l.partial_cmp(&r).unwrap_or(Ordering::Equal),
+        _ => left.to_lowercase().cmp(&right.to_lowercase()),
+    }
+}
+
+fn names_a_date(column: &str) -> bool {
+    let lower: String = column.to_lowercase();
+    lower.contains("date") || lower.contains("due") || lower.ends_with("_on") || lower.ends_with(" on")
+}
+
+fn detect(record_set: &RecordSet, column: &str, dates: &DateColumns) -> Option<DateFormat> {
+    let mut numeric: bool = false;
+    let values: Vec<String> = record_set
+        .records
+        .iter()
+        .filter_map(|r| {
+            let value: &SqlData = r.columns.get(column)?.as_ref()?;
+            numeric |= matches!(value, SqlData::Sqlite(SqliteInteger(_) | SqliteFloat(_)));
+            r.text(column)
+        })
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty() && !dates.is_sentinel(v))
+        .take(SAMPLE_SIZE)
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    let looks_numeric: bool = numeric || values.iter().all(|v| v.parse::<f64>().is_ok());
+    let candidates: Vec<DateFormat> = match looks_numeric {
+        // plain numbers are only dates where the column says so
+        true if names_a_date(column) => vec![DateFormat::ExcelSerial],
+        true => return None,
+        // month first unless a first part over 12 says otherwise
+        false => vec![DateFormat::Iso, DateFormat::MonthDayYear, DateFormat::DayMonthYear],
+    };
+    let allowed: usize = (values.len() as f64 * MAX_MISSES).floor() as usize;
+    candidates
+        .into_iter()
+        .find(|format| values.iter().filter(|v| parse(v, *format).is_none()).count() <= allowed)
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y: i64 = if month <= 2 { year - 1 } else { year };
+    let era: i64 = y.div_euclid(400);
+    let year_of_era: i64 = y - era * 400;
+    let day_of_year: i64 = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era: i64 = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z: i64 = days + 719468;
+    let era: i64 = z.div_euclid(146097);
+    let day_of_era: i64 = z - era * 146097;
+    let year_of_era: i64 = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year: i64 = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp: i64 = (5 * day_of_year + 2) / 153;
+    let day: i64 = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month: i64 = if mp < 10 { mp + 3 } else { mp - 9 };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+pub fn today() -> i64 {
+    let seconds: u64 = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    (seconds / 86400) as i64
+}
+
+pub fn iso(days: i64) -> String {
+    let (year, month, day): (i64, i64, i64) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn valid_day(year: i64, month: i64, day: i64) -> bool {
+    let leap: bool = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    let last: i64 = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return false,
+    };
+    (1..=last).contains(&day) && (1..=9999).contains(&year)
+}
+
+// two digit years: 00-49 are 20xx, 50-99 are 19xx
+fn full_year(year: &str) -> Option<i64> {
+    let value: i64 = year.parse::<i64>().ok()?;
+    match year.len() {
+        2 if value < 50 => Some(2000 + value),
+        2 => Some(1900 + value),
+        4 => Some(value),
+        _ => None,
+    }
+}
+
+pub fn parse(text: &str, format: DateFormat) -> Option<i64> {
+    let trimmed: &str = text.trim();
+    if format == DateFormat::ExcelSerial {
+        let serial: f64 = trimmed.parse::<f64>().ok()?;
+        return (1.0..=EXCEL_MAX).contains(&serial).then(|| serial.floor() as i64 + EXCEL_EPOCH);
+    }
+    // the time part of '2022-06-01 00:00:00.000' or '2022-06-01T08:30'
+    let date: &str = trimmed.split([' ', 'T']).next()?;
+    let parts: Vec<&str> = date.split(['/', '-', '.']).collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let (year, month, day): (i64, i64, i64) = match format {
+        DateFormat::Iso if parts[0].len() == 4 => (full_year(parts[0])?, parts[1].parse().ok()?, parts[2].parse().ok()?),
+        DateFormat::MonthDayYear if parts[0].len() <= 2 => (full_year(parts[2])?, parts[0].parse().ok()?, parts[1].parse().ok()?),
+        DateFormat::DayMonthYear if parts[0].len() <= 2 => (full_year(parts[2])?, parts[1].parse().ok()?, parts[0].parse().ok()?),
+        _ => return None,
+    };
+    valid_day(year, month, day).then(|| days_from_civil(year, month, day))
+}
+
+// a date typed by hand: ISO first, then month first as the sample data is
+pub fn parse_any(text: &str) -> Option<i64> {
+    [DateFormat::Iso, DateFormat::MonthDayYear, DateFormat::DayMonthYear]
+        .into_iter()
+        .find_map(|format| parse(text, format))
+}
+
+pub fn parse_filter(text: &str, columns: &[String]) -> Result<RowFilter, String> {
+    let trimmed: &str = text.trim();
+    let lower: String = trimmed.to_lowercase();
+    // the longest matching name, "Calib Due Date" rather than "Calib"
+    let mut by_length: Vec<&String> = columns.iter().collect();
+    by_length.sort_by_key(|c| std::cmp::Reverse(c.len()));
+    let (column, rest): (&String, &str) = by_length
+        .into_iter()
+        .find_map(|c| {
+            let unquoted: Option<&str> = [("\"", "\""), ("[", "]"), ("", "")].into_iter().find_map(|(open, close)| {
+                let quoted: String = format!("{}{}{}", open, c.to_lowercase(), close);
+                lower.starts_with(&quoted).then(|| &trimmed[quoted.len()..])
+            });
+            unquoted.map(|rest| (c, rest))
+        })
+        .ok_or_else(|| format!("The filter has to start with a column name: {}", trimmed))?;
+
+    let rest: &str = rest.trim_start();
+    let (op, value): (FilterOp, &str) = [
+        ("<=", FilterOp::Le),
+        (">=", FilterOp::Ge),
+        ("<>", FilterOp::Ne),
+        ("!=", FilterOp::Ne),
+        ("=", FilterOp::Eq),
+        ("<", FilterOp::Lt),
+        (">", FilterOp::Gt),
+        ("contains ", FilterOp::Contains),
+    ]
+    .into_iter()
+    .find_map(|(symbol, op)| rest.to_lowercase().starts_with(symbol).then(|| (op, &rest[symbol.len()..])))
+    .ok_or_else(|| format!("Expected =, <>, <, <=, >, >= or contains after {}", column))?;
+
+    let value: &str = value.trim();
+    let value: &str = value
+        .strip_prefix('\'')
+        .and_then(|v| v.strip_suffix('\''))
+        .unwrap_or(value);
+    Ok(RowFilter {
+        column: column.clone(),
+        op,
+        value: if value.eq_ignore_ascii_case("null") { None } else { Some(value.to_string()) },
+    })
+}
+
+// columns of the current result first, then overrides for columns it does not have
+pub fn edit_settings(settings: &mut DateSettings, record_set: Option<&RecordSet>) -> bool {
+    let detected: DateColumns = record_set.map(|rs| rs.date_columns(&DateSettings { overrides: HashMap::new(), ..settings.clone() })).unwrap_or_default();
+    let mut columns: Vec<String> = record_set.map(|rs| rs.column_order.clone()).unwrap_or_default();
+    let mut others: Vec<String> = settings
+        .overrides
+        .keys()
+        .filter(|column| !columns.iter().any(|c| c.eq_ignore_ascii_case(column)))
+        .cloned()
+        .collect();
+    others.sort();
+    columns.extend(others);
+
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+    let mut win: Window = Window::default()
+        .with_size(520, 460)
+        .with_label("Date Columns")
+        .center_screen();
+    win.make_modal(true);
+
+    for (x, w, label) in [(10, 230, "Column"), (245, 150, "Format"), (400, 110, "Detected")] {
+        let mut header: Frame = Frame::new(x, 5, w, 25, None).with_label(label);
+        header.set_align(Align::Inside | Align::Left);
+    }
+    let scroll: Scroll = Scroll::new(5, 30, 510, 320, None);
+    let mut choices: Vec<Choice> = Vec::new();
+    for (index, column) in columns.iter().enumerate() {
+        let y: i32 = 35 + index as i32 * ROW_HEIGHT;
+        let mut name: Frame = Frame::new(10, y, 230, 25, None).with_label(&column.replace('&', "&&").replace('@', "@@"));
+        name.set_align(Align::Inside | Align::Left | Align::Clip);
+        let mut choice: Choice = Choice::new(245, y, 150, 25, None);
+        choice.add_choice("Detect");
+        for format in DateFormat::ALL {
+            choice.add_choice(&escape_menu_label(format.name()));
+        }
+        let current: Option<DateFormat> = settings.overrides.get(&column.to_lowercase()).copied();
+        choice.set_value(current.and_then(|f| DateFormat::ALL.iter().position(|a| *a == f)).map_or(0, |i| i as i32 + 1));
+        let mut found: Frame = Frame::new(400, y, 110, 25, None)
+            .with_label(detected.formats.get(column).map_or("-", |f| f.name()));
+        found.set_align(Align::Inside | Align::Left);
+        choices.push(choice);
+    }
+    scroll.end();
+
+    let mut sentinels: Input = Input::new(10, 380, 500, 25, None)
+        .with_label("Values that mean no date, separated by ;");
+    sentinels.set_align(Align::TopLeft);
+    sentinels.set_value(&settings.sentinels.join("; "));
+
+    let mut save_butn: Button = Button::new(350, 420, 75, 28, "&Save");
+    save_butn.emit(sndr, DialogMessage::Save);
+    let mut cancel_butn: Button = Button::new(435, 420, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+    win.end();
+    win.show();
+
+    let mut accepted: bool = false;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::Save) => {
+                for (column, choice) in columns.iter().zip(&choices) {
+                    match choice.value() {
+                        index if index >= 1 => settings.overrides.insert(column.to_lowercase(), DateFormat::ALL[index as usize - 1]),
+                        _ => settings.overrides.remove(&column.to_lowercase()),
+                    };
+                }
+                settings.sentinels = sentinels
+                    .value()
+                    .split(';')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect();
+                accepted = true;
+                win.hide();
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            None => {}
+        }
+    }
+    accepted
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> DateSettings {
+        DateSettings {
+            overrides: HashMap::new(),
+            sentinels: DEFAULT_SENTINELS.iter().map(|s| s.to_string()).collect(),
+            path: PathBuf::new(),
+        }
+    }
+
+    fn text(value: &str) -> Option<SqlData> {
+        Some(SqlData::Sqlite(SqliteString(value.to_string())))
+    }
+
+    fn record_set(columns: &[&str], rows: Vec<Vec<Option<SqlData>>>) -> RecordSet {
+        RecordSet {
+            column_info: HashMap::new(),
+            column_order: columns.iter().map(|c| c.to_string()).collect(),
+            records: rows
+                .into_iter()
+                .map(|row| Record {
+                    columns: columns.iter().map(|c| c.to_string()).zip(row).collect(),
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parses_each_format() {
+        let pi_day: Option<i64> = parse("2024-03-14", DateFormat::Iso);
+        assert_eq!(pi_day.map(iso), Some(String::from("2024-03-14")));
+        assert_eq!(parse("3/14/2024", DateFormat::MonthDayYear), pi_day);
+        assert_eq!(parse("14.03.2024", DateFormat::DayMonthYear), pi_day);
+        assert_eq!(parse("2024-03-14 00:00:00.000", DateFormat::Iso), pi_day);
+        assert_eq!(parse("2024-03-14T08:30", DateFormat::Iso), pi_day);
+        assert_eq!(parse("45365", DateFormat::ExcelSerial), pi_day);
+        assert_eq!(parse("45365.75", DateFormat::ExcelSerial), pi_day);
+        assert_eq!(iso(0), "1970-01-01");
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_day() {
+        assert_eq!(parse("2/30/2024", DateFormat::MonthDayYear), None);
+        assert_eq!(parse("2023-02-29", DateFormat::Iso), None);
+        assert!(parse("2024-02-29", DateFormat::Iso).is_some());
+        assert_eq!(parse("14/3/2024", DateFormat::MonthDayYear), None);
+        assert_eq!(parse("2024-03-14", DateFormat::MonthDayYear), None);
+        assert_eq!(parse("0", DateFormat::ExcelSerial), None);
+        assert_eq!(parse("3/14", DateFormat::MonthDayYear), None);
+        assert_eq!(parse("Pending", DateFormat::Iso), None);
+    }
+
+    #[test]
+    fn two_digit_years() {
+        assert_eq!(parse("1/2/49", DateFormat::MonthDayYear).map(iso), Some(String::from("2049-01-02")));
+        assert_eq!(parse("1/2/50", DateFormat::MonthDayYear).map(iso), Some(String::from("1950-01-02")));
+    }
+
+    #[test]
+    fn sentinels_compare_by_day() {
+        let settings: DateSettings = settings();
+        assert!(settings.is_sentinel("1/1/1900"));
+        assert!(settings.is_sentinel(" 1900-01-01 00:00:00 "));
+        assert!(!settings.is_sentinel("1/2/1900"));
+    }
+
+    #[test]
+    fn detects_the_format_nearly_every_value_parses_with() {
+        let rows: Vec<Vec<Option<SqlData>>> = vec![
+            vec![text("3/14/2024"), text("14/3/2024"), Some(SqlData::Sqlite(SqliteInteger(45365))), Some(SqlData::Sqlite(SqliteInteger(7)))],
+            vec![text("12/1/2023"), text("1/12/2023"), Some(SqlData::Sqlite(SqliteInteger(45261))), Some(SqlData::Sqlite(SqliteInteger(8)))],
+            vec![text("1/1/1900"), text("1/1/1900"), None, None],
+        ];
+        let record_set: RecordSet = record_set(&["Calib Date", "Received", "Due", "Serial"], rows);
+        let dates: DateColumns = record_set.date_columns(&settings());
+        assert_eq!(dates.formats.get("Calib Date"), Some(&DateFormat::MonthDayYear));
+        assert_eq!(dates.formats.get("Received"), Some(&DateFormat::DayMonthYear));
+        // numbers only count as serials in a column named like a date
+        assert_eq!(dates.formats.get("Due"), Some(&DateFormat::ExcelSerial));
+        assert!(!dates.is_date("Serial"));
+    }
+
+    #[test]
+    fn overrides_win_over_detection() {
+        let record_set: RecordSet = record_set(&["Calib Date", "Lot"], vec![vec![text("3/4/2024"), text("2024-01-05")]]);
+        let mut settings: DateSettings = settings();
+        settings.overrides.insert(String::from("calib date"), DateFormat::DayMonthYear);
+        settings.overrides.insert(String::from("lot"), DateFormat::NotADate);
+        let dates: DateColumns = record_set.date_columns(&settings);
+        assert_eq!(dates.formats.get("Calib Date"), Some(&DateFormat::DayMonthYear));
+        assert!(!dates.is_date("Lot"));
+        assert_eq!(dates.date(&record_set.records[0], "Calib Date").map(iso), Some(String::from("2024-04-03")));
+    }
+
+    #[test]
+    fn exports_and_sorts_dates_as_dates() {
+        let rows: Vec<Vec<Option<SqlData>>> = ["3/14/2024", "1/1/1900", "12/1/2023", "12/1/2O23", "1/5/2024"]
+            .iter()
+            .map(|v| vec![text(v)])
+            .collect();
+        let mut record_set: RecordSet = record_set(&["Calib Date"], rows);
+        // one typo in four is too many for detection
+        assert!(!record_set.date_columns(&settings()).is_date("Calib Date"));
+        let mut settings: DateSettings = settings();
+        settings.overrides.insert(String::from("calib date"), DateFormat::MonthDayYear);
+        let dates: DateColumns = record_set.date_columns(&settings);
+        let exported: Vec<Option<String>> = record_set.with_iso_dates(&dates).records.iter().map(|r| r.text("Calib Date")).collect();
+        assert_eq!(
+            exported,
+            vec![
+                Some(String::from("2024-03-14")),
+                None,
+                Some(String::from("2023-12-01")),
+                Some(String::from("12/1/2O23")),
+                Some(String::from("2024-01-05")),
+            ]
+        );
+        record_set.sort_by_column("Calib Date", false, &dates);
+        let sorted: Vec<String> = record_set.records.iter().filter_map(|r| r.text("Calib Date")).collect();
+        assert_eq!(sorted[..3], ["12/1/2023", "1/5/2024", "3/14/2024"]);
+    }
+
+    #[test]
+    fn filters() {
+        let columns: Vec<String> = vec![String::from("Calib"), String::from("Calib Due Date")];
+        let filter: RowFilter = parse_filter("Calib Due Date < '1/1/2024'", &columns).unwrap();
+        assert_eq!((filter.column.as_str(), filter.op, filter.value.as_deref()), ("Calib Due Date", FilterOp::Lt, Some("1/1/2024")));
+        let filter: RowFilter = parse_filter("[calib] = null", &columns).unwrap();
+        assert_eq!((filter.column.as_str(), filter.op, filter.value), ("Calib", FilterOp::Eq, None));
+        assert!(parse_filter("Model contains 500", &columns).is_err());
+    }
+}
+
+/* <-- Tests */
@@ -0,0 +1,631 @@
+/* --> Summary of Contents
+
+	File paths stored in results (MSet_Log.file_path) found on disk
+
+	cd_folders maps folder_name to folder_location. A stored path is
+	resolved against it two ways:
+		RM_Folder\backup\x.csv
+			relative, the first part names a folder
+		B:\Production\Closures\400 Style\RM\Backup\x.csv
+			absolute on a drive this machine may not have; the folder
+			whose location ends in Production\Closures\400 Style\RM
+			supplies the real root (\\Lighthouse2020\Data Import\...)
+	file_paths.ini maps prefixes on top of that, for shares mounted
+	elsewhere:
+		[map:\\Lighthouse2020\Data Import]
+		to=/mnt/lighthouse
+
+	pub fn load_folders(&ConnectionBase, &str) -> Vec<Folder>
+	pub fn path_columns(&RecordSet) -> Vec<String>
+		Columns whose values mostly look like file paths
+
+	pub fn resolve(&str, &[Folder], &PathSettings) -> ResolvedPath
+		The first candidate that exists, else the best guess
+
+	pub fn verify(&RecordSet, &[String], &[Folder], &PathSettings) -> RecordSet
+	pub fn show_verify_report(&str, RecordSet)
+		Every path of the result checked, missing files first, with an
+		Export button
+
+	PreviewPanel
+		Side panel of the record grid: where a clicked path resolves to,
+		missing files flagged, text / CSV / image files previewed, and an
+		Open button for the program the system uses for the file
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    button::Button,
+    dialog,
+    draw,
+    enums::{Align, Color, Font, FrameType},
+    frame::Frame,
+    group::Flex,
+    image::SharedImage,
+    prelude::{DisplayExt, GroupExt, ImageExt, TableExt, WidgetBase, WidgetExt, WindowExt},
+    table::{Table, TableContext},
+    text::{TextBuffer, TextDisplay},
+    window::Window,
+};
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::app_paths;
+use crate::cli;
+use crate::ini_file::IniDocument;
+use crate::record_export::{self, ExportFormat};
+use crate::schema_diff::quote;
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, Record, RecordSet, SqlData, SqlType};
+use crate::sql_format::Dialect;
+use crate::sqlite3_interface::{SqliteString, Type};
+use crate::table_render::{self, Layout, TableOptions};
+
+/* <-- Imports */
+/* --> Const */
+
+const SETTINGS_FILE: &str = "file_paths.ini";
+const MAP_PREFIX: &str = "map:";
+const SAMPLE_SIZE: usize = 50;
+const PREVIEW_BYTES: u64 = 64 * 1024;
+const PREVIEW_ROWS: usize = 200;
+const PANEL_WIDTH: i32 = 340;
+const TEXT_EXTENSIONS: [&str; 6] = ["txt", "log", "ini", "xml", "json", "dat"];
+const IMAGE_EXTENSIONS: [&str; 7] = ["png", "jpg", "jpeg", "gif", "bmp", "svg", "ico"];
+const REPORT_COLUMNS: [&str; 6] = ["Row", "Column", "Stored path", "Resolved path", "Folder", "Status"];
+
+/* <-- Const */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct Folder {
+    pub name: String,
+    pub location: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PathSettings {
+    pub mappings: Vec<(String, String)>, // prefix, replacement
+}
+
+#[derive(Clone, Debug)]
+pub struct ResolvedPath {
+    pub stored: String,
+    pub resolved: String,
+    pub folder: Option<String>, // the cd_folders entry used
+    pub exists: bool,
+}
+
+#[derive(Clone)]
+pub struct PreviewPanel {
+    group: Flex,
+    status: Frame,
+    text: TextDisplay,
+    image: Frame,
+    open_butn: Button,
+}
+
+impl PathSettings {
+    pub fn load() -> Self {
+        let path: PathBuf = app_paths::config_file(SETTINGS_FILE);
+        let doc: IniDocument = match IniDocument::load(&path) {
+            Ok(doc) => doc,
+            Err(e) => {
+                println!("Unable to read {}: {}", path.display(), e);
+                IniDocument::default()
+            }
+        };
+        let mappings: Vec<(String, String)> = doc
+            .sections_with_prefix(MAP_PREFIX)
+            .filter_map(|(prefix, section)| Some((prefix.to_string(), section.get("to")?.to_string())))
+            .collect();
+        PathSettings { mappings }
+    }
+
+    fn apply(&self, path: &str) -> String {
+        for (prefix, replacement) in &self.mappings {
+            if path.len() >= prefix.len() && path.is_char_boundary(prefix.len()) && path[..prefix.len()].eq_ignore_ascii_case(prefix) {
+                return format!("{}{}", replacement, &path[prefix.len()..]);
+            }
+        }
+        path.to_string()
+    }
+}
+
+impl PreviewPanel {
+    // goes at the right of `parent`, a Flex row, hidden until a path is shown
+    pub fn build(parent: &mut Flex) -> PreviewPanel {
+        let mut group: Flex = Flex::default().column();
+        let mut status: Frame = Frame::default();
+        status.set_align(Align::Inside | Align::Left | Align::Wrap);
+        status.set_frame(FrameType::FlatBox);
+        group.fixed(&status, 60);
+        let mut text: TextDisplay = TextDisplay::default();
+        text.set_buffer(TextBuffer::default());
+        text.set_text_font(Font::Courier);
+        text.set_text_size(11);
+        let mut image: Frame = Frame::default();
+        image.set_frame(FrameType::DownBox);
+        let mut buttons: Flex = Flex::default().row();
+        Frame::default();
+        let open_butn: Button = Button::default().with_label("&Open");
+        let mut close_butn: Button = Button::default().with_label("Close");
+        buttons.fixed(&open_butn, 70);
+        buttons.fixed(&close_butn, 70);
+        buttons.end();
+        group.fixed(&buttons, 28);
+        group.end();
+        parent.fixed(&group, PANEL_WIDTH);
+        group.hide();
+
+        close_butn.set_callback({
+            let mut group: Flex = group.clone();
+            let parent: Flex = parent.clone();
+            move |_| {
+                group.hide();
+                parent.layout();
+            }
+        });
+        PreviewPanel { group, status, text, image, open_butn }
+    }
+
+    pub fn show(&mut self, path: &ResolvedPath) {
+        let folder: String = path.folder.as_ref().map_or(String::new(), |f| format!(" (via {})", f));
+        let (color, state): (Color, &str) = match path.exists {
+            true => (Color::from_rgb(200, 235, 200), "Found"),
+            false => (Color::from_rgb(255, 190, 190), "Missing"),
+        };
+        self.status.set_color(color);
+        self.status.set_label(&format!("{}{}\n{}", state, folder, path.resolved).replace('@', "@@").replace('&', "&&"));
+
+        let extension: String = Path::new(&path.resolved)
+            .extension()
+            .map_or(String::new(), |e| e.to_string_lossy().to_lowercase());
+        self.image.set_image(None::<SharedImage>);
+        self.image.hide();
+        self.text.show();
+        self.group.show();
+        // the grid gives up the room the panel takes
+        if let Some(mut parent) = self.group.parent() {
+            if let Some(flex) = Flex::from_dyn_widget(&parent) {
+                flex.layout();
+            }
+            parent.redraw();
+        }
+        let preview: String = match (path.exists, extension.as_str()) {
+            (false, _) => String::from("The file was not found."),
+            (true, "csv") => read_start(&path.resolved).map_or_else(|e| e, |text| csv_preview(&text)),
+            (true, ext) if TEXT_EXTENSIONS.contains(&ext) => read_start(&path.resolved).unwrap_or_else(|e| e),
+            (true, ext) if IMAGE_EXTENSIONS.contains(&ext) => match SharedImage::load(&path.resolved) {
+                Ok(mut image) => {
+                    // the frame takes the place of the text, its size is known once laid out
+                    self.text.hide();
+                    self.image.show();
+                    self.group.layout();
+                    image.scale(self.image.w() - 4, self.image.h() - 4, true, true);
+                    self.image.set_image(Some(image));
+                    self.image.redraw();
+                    String::new()
+                }
+                Err(e) => format!("Unable to load the image: {:?}", e),
+            },
+            (true, _) => String::from("No preview for this kind of file, Open shows it in its program."),
+        };
+        if let Some(mut buffer) = self.text.buffer() {
+            buffer.set_text(&preview);
+        }
+
+        let target: String = path.resolved.clone();
+        self.open_butn.set_callback(move |_| {
+            if let Err(e) = open_externally(&target) {
+                println!("Unable to open {}: {}", target, e);
+            }
+        });
+        if path.exists {
+            self.open_butn.activate();
+        } else {
+            self.open_butn.deactivate();
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn text(record: &Record, column: &str) -> String {
+    record.text(column).unwrap_or_default().trim().to_string()
+}
+
+pub fn load_folders(connection_type: &ConnectionBase, connection: &str) -> Vec<Folder> {
+    let dialect: Dialect = Dialect::of(connection_type);
+    let sql: String = format!(
+        "SELECT {}, {} FROM {}",
+        quote("folder_name", dialect),
+        quote("folder_location", dialect),
+        quote("cd_folders", dialect)
+    );
+    match cli::run_request(connection_type, connection, QueryType::UserDefined(sql)) {
+        Ok(record_set) => record_set
+            .records
+            .iter()
+            .map(|r| Folder { name: text(r, "folder_name"), location: text(r, "folder_location") })
+            .filter(|f| !f.name.is_empty() && !f.location.is_empty())
+            .collect(),
+        // a connection without cd_folders still resolves absolute paths
+        Err(_) => Vec::new(),
+    }
+}
+
+fn parts(path: &str) -> Vec<&str> {
+    path.split(['\\', '/']).filter(|p| !p.is_empty()).collect()
+}
+
+// C:\..., \\server\share\..., /home/... or a relative path with a file name at the end
+pub fn looks_like_path(value: &str) -> bool {
+    let value: &str = value.trim();
+    let drive: bool = value.len() > 2 && value.as_bytes()[1] == b':' && matches!(value.as_bytes()[2], b'\\' | b'/');
+    let rooted: bool = drive || value.starts_with("\\\\") || value.starts_with('/');
+    let named: bool = parts(value).last().is_some_and(|name| name.contains('.') && !name.ends_with('.'));
+    (rooted || value.contains(['\\', '/'])) && named && !value.contains("://")
+}
+
+pub fn path_columns(record_set: &RecordSet) -> Vec<String> {
+    record_set
+        .column_order
+        .iter()
+        .filter(|column| {
+            let values: Vec<String> = record_set
+                .records
+                .iter()
+                .filter_map(|r| r.text(column))
+                .filter(|v| !v.trim().is_empty())
+                .take(SAMPLE_SIZE)
+                .collect();
+            !values.is_empty() && values.iter().filter(|v| looks_like_path(v)).count() * 2 >= values.len()
+        })
+        .cloned()
+        .collect()
+}
+
+// the root of an absolute path: drive, \\server\share or /
+fn root_length(path_parts: &[&str], stored: &str) -> usize {
+    if stored.starts_with("\\\\") || stored.starts_with("//") {
+        2.min(path_parts.len())
+    } else if path_parts.first().is_some_and(|p| p.len() == 2 && p.ends_with(':')) {
+        1
+    } else {
+        0
+    }
+}
+
+fn native(path: &str) -> String {
+    if cfg!(windows) {
+        path.replace('/', "\\")
+    } else {
+        path.replace('\\', "/")
+    }
+}
+
+fn join(location: &str, rest: &[&str]) -> String {
+    let mut joined: String = location.trim_end_matches(['\\', '/']).to_string();
+    for part in rest {
+        joined.push('\\');
+        joined.push_str(part);
+    }
+    joined
+}
+
+pub fn resolve(stored: &str, folders: &[Folder], settings: &PathSettings) -> ResolvedPath {
+    let stored: &str = stored.trim().trim_matches('"');
+    let stored_parts: Vec<&str> = parts(stored);
+    let rooted: bool = stored.starts_with(['\\', '/']) || stored.get(1..2) == Some(":");
+    let mut candidates: Vec<(String, Option<String>)> = Vec::new();
+
+    match rooted {
+        true => {
+            candidates.push((stored.to_string(), None));
+            // the folder whose location ends the way this path begins, the longest overlap wins
+            let tail: &[&str] = &stored_parts[root_length(&stored_parts, stored)..];
+            let mut best: Option<(usize, &Folder)> = None;
+            for folder in folders {
+                let location: Vec<&str> = parts(&folder.location);
+                for start in 0..location.len() {
+                    let overlap: &[&str] = &location[start..];
+                    let matches: bool = overlap.len() <= tail.len()
+                        && overlap.iter().zip(tail).all(|(a, b)| a.eq_ignore_ascii_case(b));
+                    if matches && best.is_none_or(|(length, _)| overlap.len() > length) {
+                        best = Some((overlap.len(), folder));
+                    }
+                    if matches {
+                        break;
+                    }
+                }
+            }
+            if let Some((length, folder)) = best {
+                candidates.insert(0, (join(&folder.location, &tail[length..]), Some(folder.name.clone())));
+            }
+        }
+        false => {
+            if let Some(folder) = stored_parts.first().and_then(|first| folders.iter().find(|f| f.name.eq_ignore_ascii_case(first))) {
+                candidates.push((join(&folder.location, &stored_parts[1..]), Some(folder.name.clone())));
+            }
+            candidates.push((stored.to_string(), None));
+        }
+    }
+
+    let candidates: Vec<(String, Option<String>)> = candidates
+        .into_iter()
+        .map(|(path, folder)| (native(&settings.apply(&path)), folder))
+        .collect();
+    let found: Option<&(String, Option<String>)> = candidates.iter().find(|(path, _)| Path::new(path).is_file());
+    let (resolved, folder): (String, Option<String>) = found.unwrap_or(&candidates[0]).clone();
+    ResolvedPath { stored: stored.to_string(), resolved, folder, exists: found.is_some() }
+}
+
+pub fn verify(record_set: &RecordSet, columns: &[String], folders: &[Folder], settings: &PathSettings) -> RecordSet {
+    let mut report: RecordSet = RecordSet::default();
+    for column in REPORT_COLUMNS {
+        report.column_order.push(column.to_string());
+        report.column_info.insert(column.to_string(), SqlType::Sqlite(Type::String));
+    }
+    let mut rows: Vec<(bool, Record)> = Vec::new();
+    for (index, record) in record_set.records.iter().enumerate() {
+        for column in columns {
+            let stored: String = match record.text(column) {
+                Some(stored) if !stored.trim().is_empty() => stored,
+                _ => continue,
+            };
+            let path: ResolvedPath = resolve(&stored, folders, settings);
+            let cells: [String; 6] = [
+                (index + 1).to_string(),
+                column.clone(),
+                path.stored,
+                path.resolved,
+                path.folder.unwrap_or_default(),
+                String::from(if path.exists { "Found" } else { "Missing" }),
+            ];
+            let mut row: Record = Record::default();
+            for (name, cell) in REPORT_COLUMNS.iter().zip(cells) {
+                row.columns.insert(name.to_string(), Some(SqlData::Sqlite(SqliteString(cell))));
+            }
+            rows.push((path.exists, row));
+        }
+    }
+    // missing files first, otherwise in result order
+    rows.sort_by_key(|(exists, _)| *exists);
+    report.records = rows.into_iter().map(|(_, row)| row).collect();
+    report
+}
+
+fn read_start(path: &str) -> Result<String, String> {
+    let mut bytes: Vec<u8> = Vec::new();
+    fs::File::open(path)
+        .and_then(|file| file.take(PREVIEW_BYTES).read_to_end(&mut bytes))
+        .map_err(|e| format!("Unable to read the file: {}", e))?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// one CSV line, quoted fields may hold commas and doubled quotes
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields: Vec<String> = vec![String::new()];
+    let mut quoted: bool = false;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match (ch, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(String::new()),
+            (ch, _) => fields.last_mut().unwrap().push(ch),
+        }
+    }
+    fields
+}
+
+// the first rows drawn as a table, the first line is the header
+fn csv_preview(text: &str) -> String {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header: Vec<String> = match lines.next() {
+        Some(line) => csv_fields(line),
+        None => return String::new(),
+    };
+    let mut record_set: RecordSet = RecordSet::default();
+    for (index, name) in header.iter().enumerate() {
+        // blank or repeated headers still need a column of their own
+        let name: String = if name.trim().is_empty() || record_set.column_order.contains(name) { format!("{} #{}", name, index + 1) } else { name.clone() };
+        record_set.column_info.insert(name.clone(), SqlType::Sqlite(Type::String));
+        record_set.column_order.push(name);
+    }
+    for line in lines.take(PREVIEW_ROWS) {
+        let mut record: Record = Record::default();
+        for (name, value) in record_set.column_order.iter().zip(csv_fields(line)) {
+            record.columns.insert(name.clone(), Some(SqlData::Sqlite(SqliteString(value))));
+        }
+        record_set.records.push(record);
+    }
+    table_render::render(&record_set, &TableOptions { layout: Layout::Grid, max_cell_width: 24, max_width: None })
+}
+
+fn open_externally(path: &str) -> std::io::Result<()> {
+    // explorer opens a file with its associated program and takes the path as one argument,
+    // cmd /C start would parse &, ^ and % in a stored path as shell syntax
+    let mut command: Command = if cfg!(windows) {
+        Command::new("explorer.exe")
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path).spawn().map(|_| ())
+}
+
+pub fn show_verify_report(label: &str, report: RecordSet) {
+    let missing: usize = report.records.iter().filter(|r| r.text("Status").as_deref() == Some("Missing")).count();
+    let mut win: Window = Window::default()
+        .with_size(1000, 560)
+        .with_label(&format!("File Paths: {}", label))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let mut summary: Frame = Frame::default().with_label(&format!("{} of {} files missing", missing, report.records.len()));
+    summary.set_align(Align::Inside | Align::Left);
+    layout.fixed(&summary, 25);
+    let mut table: Table = Table::default();
+    let mut buttons: Flex = Flex::default().row();
+    Frame::default();
+    let mut export_butn: Button = Button::default().with_label("&Export...");
+    buttons.fixed(&export_butn, 90);
+    buttons.end();
+    layout.fixed(&buttons, 30);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    let rows: Vec<Vec<String>> = report
+        .records
+        .iter()
+        .map(|r| REPORT_COLUMNS.iter().map(|c| r.text(c).unwrap_or_default()).collect())
+        .collect();
+    table.set_rows(rows.len() as i32);
+    table.set_cols(REPORT_COLUMNS.len() as i32);
+    table.set_col_header(true);
+    table.set_col_resize(true);
+    table.set_row_height_all(22);
+    for (index, width) in [50, 90, 330, 330, 120, 70].into_iter().enumerate() {
+        table.set_col_width(index as i32, width);
+    }
+    table.end();
+    table.draw_cell(move |_, context, row, col, x, y, w, h| match context {
+        TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+        TableContext::ColHeader => {
+            draw::draw_box(FrameType::ThinUpBox, x, y, w, h, Color::FrameDefault);
+            draw::set_draw_color(Color::Black);
+            draw::draw_text2(REPORT_COLUMNS[col as usize], x + 4, y, w - 8, h, Align::Left);
+        }
+        TableContext::Cell => {
+            let cells: &Vec<String> = &rows[row as usize];
+            let background: Color = if cells[5] == "Missing" { Color::from_rgb(255, 210, 210) } else { Color::White };
+            draw::push_clip(x, y, w, h);
+            draw::draw_rect_fill(x, y, w, h, background);
+            draw::set_draw_color(Color::Black);
+            draw::draw_text2(&cells[col as usize], x + 4, y, w - 8, h, Align::Left);
+            draw::set_draw_color(Color::Light2);
+            draw::draw_rect(x, y, w, h);
+            draw::pop_clip();
+        }
+        _ => {}
+    });
+
+    export_butn.set_callback(move |_| {
+        let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+        chooser.set_title("Export the path report");
+        chooser.set_filter("CSV\t*.csv\nTSV\t*.tsv\nJSON\t*.json\nText table\t*.txt");
+        chooser.set_option(dialog::FileDialogOptions::SaveAsConfirm);
+        chooser.show();
+        let path: String = chooser.filename().to_string_lossy().into_owned();
+        if path.is_empty() {
+            return;
+        }
+        let format: ExportFormat = ExportFormat::from_path(&path)
+            .or_else(|| path.ends_with(".txt").then_some(ExportFormat::Table))
+            .unwrap_or(ExportFormat::Csv);
+        match fs::write(&path, record_export::render(&report, format)) {
+            Ok(_) => summary.set_label(&format!("Report written to {}", path)),
+            Err(e) => println!("Unable to write {}: {}", path, e),
+        }
+    });
+    win.show();
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a fresh directory with `files` (relative, '/' separated) created in it
+    fn temp_tree(name: &str, files: &[&str]) -> PathBuf {
+        let root: PathBuf = std::env::temp_dir().join(format!("dv_paths_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for file in files {
+            let path: PathBuf = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "a,b\n").unwrap();
+        }
+        root
+    }
+
+    fn folder(name: &str, location: &Path) -> Folder {
+        Folder { name: name.to_string(), location: location.display().to_string() }
+    }
+
+    #[test]
+    fn recognizes_paths() {
+        for value in ["C:\\data\\x.csv", "\\\\server\\share\\x.txt", "/home/qa/x.log", "RM_Folder\\backup\\x.csv"] {
+            assert!(looks_like_path(value), "{}", value);
+        }
+        for value in ["x.csv", "1.5", "https://host/x.html", "C:\\data\\", "n/a"] {
+            assert!(!looks_like_path(value), "{}", value);
+        }
+    }
+
+    #[test]
+    fn relative_paths_start_with_a_folder_name() {
+        let root: PathBuf = temp_tree("relative", &["rm/backup/x.csv"]);
+        let folders: Vec<Folder> = vec![folder("RM_Folder", &root.join("rm"))];
+        let path: ResolvedPath = resolve("rm_folder\\backup\\x.csv", &folders, &PathSettings::default());
+        assert!(path.exists, "{}", path.resolved);
+        assert_eq!(path.folder.as_deref(), Some("RM_Folder"));
+        assert!(Path::new(&path.resolved).is_file());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn absolute_paths_move_to_the_folder_that_ends_like_them() {
+        let root: PathBuf = temp_tree("absolute", &["Production/Closures/RM/Backup/x.csv"]);
+        let folders: Vec<Folder> = vec![
+            folder("Closures", &root.join("Production").join("Closures")),
+            folder("RM", &root.join("Production").join("Closures").join("RM")),
+        ];
+        let path: ResolvedPath = resolve("B:\\Production\\Closures\\RM\\Backup\\x.csv", &folders, &PathSettings::default());
+        assert!(path.exists, "{}", path.resolved);
+        // the longest overlap wins
+        assert_eq!(path.folder.as_deref(), Some("RM"));
+
+        let missing: ResolvedPath = resolve("B:\\Production\\Closures\\RM\\Backup\\gone.csv", &folders, &PathSettings::default());
+        assert!(!missing.exists);
+        assert_eq!(missing.folder.as_deref(), Some("RM"));
+        assert!(missing.resolved.ends_with("gone.csv"));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn mappings_replace_a_prefix_without_regard_to_case() {
+        let root: PathBuf = temp_tree("mapped", &["import/x.csv"]);
+        let settings: PathSettings = PathSettings {
+            mappings: vec![(String::from("\\\\Lighthouse2020\\Data Import"), root.join("import").display().to_string())],
+        };
+        let path: ResolvedPath = resolve("\\\\LIGHTHOUSE2020\\data import\\x.csv", &[], &settings);
+        assert!(path.exists, "{}", path.resolved);
+        assert_eq!(path.folder, None);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn unknown_paths_stay_as_stored() {
+        let path: ResolvedPath = resolve(" \"nowhere/x.csv\" ", &[], &PathSettings::default());
+        assert_eq!(path.stored, "nowhere/x.csv");
+        assert_eq!(path.resolved, native("nowhere/x.csv"));
+        assert!(!path.exists && path.folder.is_none());
+    }
+}
+
+/* <-- Tests */
@@ -0,0 +1,239 @@
+/* --> Summary of Contents
+
+	IniDocument
+		Minimal reader/writer for the `[section]` / `key=value` files used
+		to persist settings. Section and key order is preserved so files
+		stay diff friendly. Lines starting with '#' or ';' are comments.
+		Only the first '=' splits a line, values may contain '=' and ';'
+		(connection strings do).
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use std::{fs, io, path::Path};
+
+/* <-- Imports */
+/* --> Structs */
+
+#[derive(Clone, Default)]
+pub struct IniDocument {
+    pub sections: Vec<IniSection>,
+}
+
+#[derive(Clone, Default)]
+pub struct IniSection {
+    pub name: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl IniDocument {
+    pub fn parse(text: &str) -> Self {
+        let mut doc: IniDocument = IniDocument::default();
+
+        for raw_line in text.lines() {
+            let line: &str = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                doc.sections.push(IniSection {
+                    name: unescape(&line[1..line.len() - 1]),
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                // keys before the first section land in an unnamed section
+                if doc.sections.is_empty() {
+                    doc.sections.push(IniSection::default());
+                }
+                doc.sections
+                    .last_mut()
+                    .unwrap()
+                    .entries
+                    .push((key.trim().to_string(), unescape(value.trim())));
+            }
+        }
+        doc
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn section(&self, name: &str) -> Option<&IniSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+
+    // sections sharing a "prefix:" are used as a list, e.g. [profile:Production]
+    pub fn sections_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a IniSection)> + 'a {
+        self.sections
+            .iter()
+            .filter_map(move |s| s.name.strip_prefix(prefix).map(|rest| (rest, s)))
+    }
+
+    pub fn push_section(&mut self, name: &str) -> &mut IniSection {
+        self.sections.push(IniSection {
+            name: name.to_string(),
+            entries: Vec::new(),
+        });
+        self.sections.last_mut().unwrap()
+    }
+}
+
+impl std::fmt::Display for IniDocument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for section in &self.sections {
+            if !section.name.is_empty() {
+                writeln!(f, "[{}]", escape(&section.name))?;
+            }
+            for (key, value) in &section.entries {
+                writeln!(f, "{}={}", key, escape(value))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl IniSection {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn get_bool(&self, key: &str) -> bool {
+        matches!(self.get(key), Some("1") | Some("true") | Some("yes"))
+    }
+
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> &mut Self {
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value.to_string(),
+            None => self.entries.push((key.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    // repeated keys, for lists such as recent=...
+    pub fn append(&mut self, key: &str, value: &str) -> &mut Self {
+        self.entries.push((key.to_string(), value.to_string()));
+        self
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+// newlines and backslashes are escaped so multi-line values (editor text) survive a round trip
+fn escape(value: &str) -> String {
+    let mut out: String = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+fn unescape(value: &str) -> String {
+    let mut out: String = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('\\') => out.push('\\'),
+            // hand edited windows paths (C:\data\dv.db) keep their backslashes
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sections_comments_and_values() {
+        let doc: IniDocument = IniDocument::parse(
+            "top=1\n# comment\n; also a comment\n[profile:Production]\nconnection = DSN=prod;Trusted_Connection=yes\nrecent=a\nrecent=b\n\n[empty]\n",
+        );
+        assert_eq!(doc.sections.len(), 3);
+        assert_eq!(doc.sections[0].name, "");
+        assert_eq!(doc.sections[0].get("top"), Some("1"));
+        let profile: &IniSection = doc.section("profile:Production").unwrap();
+        // only the first '=' splits the line
+        assert_eq!(profile.get("connection"), Some("DSN=prod;Trusted_Connection=yes"));
+        assert_eq!(profile.get_all("recent").collect::<Vec<&str>>(), vec!["a", "b"]);
+        assert!(doc.section("empty").unwrap().entries.is_empty());
+        let names: Vec<&str> = doc.sections_with_prefix("profile:").map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["Production"]);
+    }
+
+    #[test]
+    fn escaped_values_survive_a_round_trip() {
+        let mut doc: IniDocument = IniDocument::default();
+        doc.push_section("tab\n1")
+            .set("editor", "select *\r\nfrom t\twhere a = '\\n'")
+            .set("path", "C:\\data\\dv.db")
+            .set("flag", "yes");
+        let text: String = doc.to_string();
+        assert!(!text.contains('\t'));
+        let read: IniDocument = IniDocument::parse(&text);
+        let section: &IniSection = read.section("tab\n1").unwrap();
+        assert_eq!(section.get("editor"), Some("select *\r\nfrom t\twhere a = '\\n'"));
+        assert_eq!(section.get("path"), Some("C:\\data\\dv.db"));
+        assert!(section.get_bool("flag"));
+    }
+
+    #[test]
+    fn hand_edited_backslashes_are_kept() {
+        assert_eq!(unescape("C:\\data\\dv.db"), "C:\\data\\dv.db");
+        assert_eq!(unescape("trailing\\"), "trailing\\");
+        assert_eq!(unescape("a\\nb"), "a\nb");
+    }
+
+    #[test]
+    fn set_replaces_and_append_repeats() {
+        let mut section: IniSection = IniSection::default();
+        section.set("a", "1").set("a", "2").append("b", "x").append("b", "y");
+        assert_eq!(section.get("a"), Some("2"));
+        assert_eq!(section.entries.len(), 3);
+    }
+}
+
+/* <-- Tests */
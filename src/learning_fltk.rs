@@ -10,11 +10,12 @@ use fltk::{
     button::Button,
     dialog, enums,
     enums::{Color, Shortcut},
-    group::{Flex, Pack},
+    group::{Flex, Pack, Tabs},
     input::{Input, MultilineInput},
     menu::{MenuBar, MenuFlag, MenuItem},
     output::MultilineOutput,
-    prelude::{GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt, TableExt},
+    prelude::{DisplayExt, GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt, TableExt},
+    text::TextEditor,
     window,
 };
 
@@ -27,6 +28,30 @@ use crate::sql_aux_funcs::{
     ConnectionBase, Request, QueryType,
 };
 use crate::AuxFuncs;
+use crate::audit_log::{self, Capture};
+use crate::connection_profiles::{ConnectionProfile, ProfileStore};
+use crate::credentials::{self, CredentialStore};
+use crate::profile_manager;
+use crate::library_panel;
+use crate::sql_editor;
+use crate::session_tab::{self, SessionTab};
+use crate::statement_guard;
+use crate::sql_completion;
+use crate::sql_format::{self, Dialect, FormatOptions, KeywordCase};
+use crate::query_plan;
+use crate::result_diff::{self, DiffRequest, DiffSource};
+use crate::schema_diff::{self, SchemaChange, SchemaSnapshot};
+use crate::data_transfer::{self, TransferSide};
+use crate::calibration_dashboard;
+use crate::capability::{self, Capability, CapabilityRequest, Spec};
+use crate::control_chart::{self, ChartRequest};
+use crate::date_columns::{self, DateColumns, DateSettings};
+use crate::file_paths::{self, Folder, PathSettings, ResolvedPath};
+use crate::product_lookup;
+use crate::record_export::{self, ExportFormat};
+use crate::cli;
+use crate::query_library::{QueryLibrary, SavedQuery};
+use crate::workspace::{self, WorkspaceState};
 use rand::{thread_rng, Rng};
 
 /* <-- Imports */
@@ -34,13 +59,43 @@ use rand::{thread_rng, Rng};
 
 #[derive(Clone)]
 pub enum Message {
-    Query(QueryType, FetchFlag),
-    FillGrid(i32),
-    Save,
-    ClearGrid,
+    Query(usize, QueryType, FetchFlag), // tab id first, see session_tab.rs
+    FillGrid(usize, i32),
+    Save(usize),
+    ClearGrid(usize),
     RandomNumber(usize, u64),
     LaunchObserver,
     SqlServerPacket(Option<i32>),
+    ProfileManager,
+    ConnectProfile(String),
+    LibrarySelect(String),
+    LibrarySave,
+    LibraryDelete,
+    LibraryImport,
+    LibraryExport,
+    Complete(usize),
+    RunStatement(usize),
+    FormatSql,
+    KeywordCase(KeywordCase),
+    Explain(usize),
+    NewTab,
+    CloseTab,
+    TabChanged,
+    Autosave,
+    CompareResults,
+    CompareSchemas,
+    TransferData,
+    CalibrationDashboard,
+    ShowRecord(usize, String), // tab id, the SELECT for one record
+    SortGrid(usize, i32),      // tab id, record grid column
+    FilterGrid(usize),
+    DateColumns,
+    CapabilityAnalysis,
+    ControlChart,
+    PreviewCell(usize, i32, i32), // tab id, record grid row and column
+    VerifyPaths,
+    ProductLookup,
+    AuditLog,
 }
 
 #[derive(Clone)]
@@ -55,10 +110,19 @@ pub enum FetchFlag {
 struct FltkHost {
     fltk_app: App,
     fltk_windows: Vec<fltk::window::Window>,
-    conn: Connection,
+    sessions: Vec<SessionTab>, // one per workspace tab, each with its own connection
+    next_session_id: usize,
     sender: Option<Sender<Message>>,
     receiver: Option<Receiver<Message>>,
-    smart_tables: HashMap<String, fltk_table::SmartTable>,
+    profiles: ProfileStore,
+    credentials: Option<CredentialStore>, // unlocked for the session once the master passphrase is entered
+    library: Option<QueryLibrary>,
+    library_index: HashMap<String, i64>, // sidebar tree path -> saved query id
+    keyword_case: KeywordCase,
+    last_autosave: Option<WorkspaceState>, // what the autosave file holds, skips writes when nothing changed
+    date_settings: DateSettings,
+    path_settings: PathSettings,
+    analyst: Option<String>, // who the audit log names, asked before the first change
 }
 
 impl FltkHost {
@@ -66,228 +130,101 @@ impl FltkHost {
         FltkHost {
             fltk_app: App::default().with_scheme(Scheme::Oxy),
             fltk_windows: Vec::new(),
-            conn: Connection {
-                record_set: None,
-                connection: None,
-                result_code: None,
-                result_details: None,
-                connection_type: None,
-            },
+            sessions: Vec::new(),
+            next_session_id: 1,
             receiver: None,
             sender: None,
-            smart_tables: HashMap::new(),
+            profiles: ProfileStore::load(),
+            credentials: None,
+            library: match QueryLibrary::open_default() {
+                Ok(library) => Some(library),
+                Err(e) => {
+                    println!("Query library unavailable: {}", e.message.unwrap_or_default());
+                    None
+                }
+            },
+            library_index: HashMap::new(),
+            keyword_case: KeywordCase::Upper,
+            last_autosave: None,
+            date_settings: DateSettings::load(),
+            path_settings: PathSettings::load(),
+            analyst: None,
         }
     }
 
     fn construct(&mut self) -> Result<(), String> {
+        // event handling, message passing
+        {
+            let (a, b) = channel::<Message>();
+            self.sender = Some(a);
+            self.receiver = Some(b);
+        }
+
         self.fltk_windows.push(window::Window::default()
         .with_id("sql_window")
         .with_size(1280, 760)
         .center_screen());
 
         MenuBar::default().with_id("main_menu").with_size(1280, 30).with_pos(0, 0); //main menu
-        fltk::app::widget_from_id::<fltk::menu::MenuBar>("main_menu").as_mut().unwrap().add_choice("File|Option");
-
-        Flex::default().with_id("record_grid_group").with_size(1000, 650).below_of(fltk::app::widget_from_id::<fltk::menu::MenuBar>("main_menu").as_ref().unwrap(), 5);
-        fltk::app::widget_from_id::<fltk::group::Flex>("record_grid_group").as_ref().unwrap().begin();
-
-        self.smart_tables.insert(String::from("record_grid"), SmartTable::default()
-        .size_of_parent()
-        .with_opts(TableOpts {
-            rows: 0,
-            cols: 0,
-            editable: false,
-            cell_font_size: 9,
-            header_font_size: 10,
-            cell_border_color: enums::Color::Light2,
-            ..Default::default()
-        }));
-
-        fltk::app::widget_from_id::<fltk::group::Flex>("record_grid_group").as_ref().unwrap().end();
-
-        Flex::default()
-        .with_id("tables_grid_group")
-        .with_size(276, 325)
-        .right_of(fltk::app::widget_from_id::<fltk::group::Flex>("record_grid_group").as_ref().unwrap(), 5);
-    
-        fltk::app::widget_from_id::<fltk::group::Flex>("tables_grid_group").as_ref().unwrap().begin();
-    
-        self.smart_tables.insert(String::from("tables_grid"), SmartTable::default()
-        .with_size(276, 325)
-        .with_opts(TableOpts {
-            rows: 20,
-            cols: 1,
-            editable: false,
-            cell_font_size: 9,
-            header_font_size: 10,
-            cell_border_color: enums::Color::Light2,
-            ..Default::default()
-        }));
-    
-        fltk::app::widget_from_id::<fltk::group::Flex>("tables_grid_group").as_ref().unwrap().end();
-
-        Flex::default()
-        .with_id("columns_grid_group")
-        .with_size(276, 325)
-        .below_of(fltk::app::widget_from_id::<fltk::group::Flex>("tables_grid_group").as_ref().unwrap(), 5);
-
-        fltk::app::widget_from_id::<fltk::group::Flex>("columns_grid_group").as_ref().unwrap().begin();
-
-        self.smart_tables.insert(String::from("columns_grid"), SmartTable::default()
-        .with_size(276, 325)
-        .with_opts(TableOpts {
-            rows: 20,
-            cols: 1,
-            editable: false,
-            cell_font_size: 9,
-            header_font_size: 10,
-            cell_border_color: enums::Color::Light2,
-            ..Default::default()
-        }));
-
-        fltk::app::widget_from_id::<fltk::group::Flex>("columns_grid_group").as_ref().unwrap().end();
 
+        fltk::tree::Tree::default()
+            .with_id("library_tree")
+            .with_size(220, 610)
+            .below_of(fltk::app::widget_from_id::<fltk::menu::MenuBar>("main_menu").as_ref().unwrap(), 5);
+        fltk::app::widget_from_id::<fltk::tree::Tree>("library_tree").as_mut().unwrap().set_show_root(false);
         Button::default()
-            .with_size(75, 30)
-            .below_of(fltk::app::widget_from_id::<fltk::group::Flex>("record_grid_group").as_ref().unwrap(), 3)
-            .with_label("&Page")
-            .with_id("pages_butn");
-        
-        Input::default()
-            .with_id("pages_input")
-            .with_size(75, 30)
-            .below_of(fltk::app::widget_from_id::<fltk::button::Button>("pages_butn").as_ref().unwrap(), 3);
-        fltk::app::widget_from_id::<fltk::input::Input>("pages_input").as_mut().unwrap().set_value("1");
-        
+            .with_id("library_save_butn")
+            .with_size(108, 35)
+            .below_of(fltk::app::widget_from_id::<fltk::tree::Tree>("library_tree").as_ref().unwrap(), 5)
+            .with_label("Save &Query");
         Button::default()
-            .with_id("query_butn")
-            .with_size(75, 30)
-            .right_of(fltk::app::widget_from_id::<fltk::button::Button>("pages_butn").as_ref().unwrap(), 4)
-            .with_label("&Submit");
-        Button::default()
-            .with_id("clear_butn")
-            .with_size(75, 30)
-            .below_of(fltk::app::widget_from_id::<fltk::button::Button>("query_butn").as_ref().unwrap(), 3)
-            .with_label("&Clear");
-        MultilineInput::default()
-            .with_id("text_input")
-            .with_size(836, 63)
-            .right_of(fltk::app::widget_from_id::<fltk::button::Button>("query_butn").as_ref().unwrap(), 4);
-        Button::default()
-            .with_id("tables_butn")
-            .with_size(75, 63)
-            .right_of(fltk::app::widget_from_id::<fltk::input::MultilineInput>("text_input").as_ref().unwrap(), 4)
-            .with_label("&Tables");
-        Button::default()
-            .with_id("save_butn")
-            .with_size(75, 63)
-            .right_of(fltk::app::widget_from_id::<fltk::button::Button>("tables_butn").as_ref().unwrap(), 4)
-            .with_label("Sa&ve");
-        Button::default()
-            .with_id("observer_butn")
-            .with_size(75, 63)
-            .right_of(fltk::app::widget_from_id::<fltk::button::Button>("save_butn").as_ref().unwrap(), 4)
-            .with_label("Observe");
+            .with_id("library_delete_butn")
+            .with_size(108, 35)
+            .right_of(fltk::app::widget_from_id::<fltk::button::Button>("library_save_butn").as_ref().unwrap(), 4)
+            .with_label("Delete");
 
+        // one tab per connection, each with its own editor, grids and schema browser
+        let mut session_tabs: Tabs = Tabs::default()
+            .with_id("session_tabs")
+            .with_size(1281, 745)
+            .right_of(fltk::app::widget_from_id::<fltk::tree::Tree>("library_tree").as_ref().unwrap(), 5);
+        session_tabs.end();
+        session_tabs.emit(self.sender.as_ref().unwrap().clone(), Message::TabChanged);
 
         // event handling, message passing
 
-        {
-            let (a, b) = channel::<Message>();
-            self.sender = Some(a);
-            self.receiver = Some(b);
-        }
-
-        let query_butn_sndr:    Sender<Message> = self.sender.as_ref().unwrap().clone();
-        let save_butn_sndr:     Sender<Message> = self.sender.as_ref().unwrap().clone();
-        let tables_butn_sndr:   Sender<Message> = self.sender.as_ref().unwrap().clone();
-        let pages_butn_sndr:    Sender<Message> = self.sender.as_ref().unwrap().clone();
-        let clear_butn_sndr:    Sender<Message> = self.sender.as_ref().unwrap().clone();
-        let observer_butn_sndr: Sender<Message> = self.sender.as_ref().unwrap().clone();
         let sql_selector_sndr:  Sender<Message> = self.sender.as_ref().unwrap().clone();
-        let tables_grid_sndr:   Sender<Message> = self.sender.as_ref().unwrap().clone();
+        let library_tree_sndr:  Sender<Message> = self.sender.as_ref().unwrap().clone();
 
-        fltk::app::widget_from_id::<fltk::button::Button>("query_butn")
+        fltk::app::widget_from_id::<fltk::button::Button>("library_save_butn")
         .as_mut()
         .unwrap()
-        .set_callback({
-            move |_| {
-                query_butn_sndr.send(Message::Query(QueryType::UserDefined(fltk::app::widget_from_id::<fltk::input::MultilineInput>("text_input").as_ref().unwrap().value().clone()), FetchFlag::False));
-                query_butn_sndr.send(Message::FillGrid(1));
-            }
-        });
-    
-        fltk::app::widget_from_id::<fltk::button::Button>("clear_butn")
-        .as_mut()
-        .unwrap()
-        .set_callback({
-            move |_| {
-                clear_butn_sndr.send(Message::ClearGrid);
-            }
-        });
-    
-        fltk::app::widget_from_id::<fltk::button::Button>("save_butn")
-        .as_mut()
-        .unwrap()
-        .set_callback({
-            move |_| {
-                save_butn_sndr.send(Message::Save);
-            }
-        });
-    
-        fltk::app::widget_from_id::<fltk::button::Button>("tables_butn")
-        .as_mut()
-        .unwrap()
-        .handle(move |_, ev: fltk::enums::Event| {
-            match ev {
-                fltk::enums::Event::Push => {
-                    tables_butn_sndr.send(Message::Query(QueryType::SqlFunction(Request::Tables(2)), FetchFlag::False));
-                    tables_butn_sndr.send(Message::FillGrid(2));
-                    true
-                },
-                _ => false,
-            }
-        });
-    
-        fltk::app::widget_from_id::<fltk::button::Button>("pages_butn")
+        .emit(self.sender.as_ref().unwrap().clone(), Message::LibrarySave);
+
+        fltk::app::widget_from_id::<fltk::button::Button>("library_delete_butn")
         .as_mut()
         .unwrap()
-        .set_callback({
-            move |_| {
-                pages_butn_sndr.send(Message::FillGrid(1));
-            }
-        });
-    
-        fltk::app::widget_from_id::<fltk::button::Button>("observer_butn")
+        .emit(self.sender.as_ref().unwrap().clone(), Message::LibraryDelete);
+
+        // one click on a saved query loads and runs it
+        fltk::app::widget_from_id::<fltk::tree::Tree>("library_tree")
         .as_mut()
         .unwrap()
-        .set_callback({
-            move |_| {
-                observer_butn_sndr.send(Message::LaunchObserver);
+        .set_callback(move |tree| {
+            if tree.callback_reason() == fltk::tree::TreeReason::Selected {
+                if let Some(path) = library_panel::selected_path(tree) {
+                    library_tree_sndr.send(Message::LibrarySelect(path));
+                }
             }
         });
 
-        self.smart_tables.get_mut("tables_grid")
-        .unwrap()
-        .handle(move |tables_grid_ref, ev: fltk::enums::Event| {
-            match ev {
-                fltk::enums::Event::Push => {
-                    if tables_grid_ref.get_selection() != (-1, -1, -1, -1) {
-                        tables_grid_sndr.send(Message::Query(QueryType::SqlFunction(Request::Columns(String::new())), FetchFlag::True));
-                        tables_grid_sndr.send(Message::FillGrid(3));
-                    }
-                    true
-                },
-                _ => false,
-            }
-        });
-    
-        fltk::app::widget_from_id::<fltk::menu::MenuBar>("main_menu")
-        .as_mut()
-        .unwrap()
-        .set_callback(move |m| match m.choice() {
-            Some(T) => match &*T {
-                "File" => {
+        {
+            let mut main_menu = fltk::app::widget_from_id::<fltk::menu::MenuBar>("main_menu").unwrap();
+            main_menu.add(
+                "File/Quick Connect...",
+                Shortcut::Ctrl | 'o',
+                MenuFlag::Normal,
+                move |_| {
                     let (x, y): (i32, i32) = center();
                     let choice = dialog::choice2(
                         x - 200,
@@ -301,14 +238,136 @@ impl FltkHost {
                         Some(2) | Some(1) => sql_selector_sndr.send(Message::SqlServerPacket(choice)),
                         _ => {},
                     }
-    
-                }
-                "Option" => (),
-                &_ => (),
-            },
-            None => (),
-        });
-    
+                },
+            );
+            main_menu.add_emit(
+                "File/Connection Manager...",
+                Shortcut::Ctrl | 'm',
+                MenuFlag::MenuDivider,
+                self.sender.as_ref().unwrap().clone(),
+                Message::ProfileManager,
+            );
+            main_menu.add("File/Recent", Shortcut::None, MenuFlag::Submenu, |_| {});
+            main_menu.add_emit(
+                "File/New Tab",
+                Shortcut::Ctrl | 't',
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::NewTab,
+            );
+            main_menu.add_emit(
+                "File/Close Tab",
+                Shortcut::Ctrl | 'w',
+                MenuFlag::MenuDivider,
+                self.sender.as_ref().unwrap().clone(),
+                Message::CloseTab,
+            );
+            main_menu.add_emit(
+                "File/Query Library/Import...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::LibraryImport,
+            );
+            main_menu.add_emit(
+                "File/Query Library/Export...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::LibraryExport,
+            );
+            main_menu.add_emit(
+                "Edit/Format SQL",
+                Shortcut::Ctrl | Shortcut::Shift | 'f',
+                MenuFlag::MenuDivider,
+                self.sender.as_ref().unwrap().clone(),
+                Message::FormatSql,
+            );
+            for (label, case) in [
+                ("Edit/Keyword Case/UPPER", KeywordCase::Upper),
+                ("Edit/Keyword Case/lower", KeywordCase::Lower),
+                ("Edit/Keyword Case/As Typed", KeywordCase::Preserve),
+            ] {
+                main_menu.add_emit(label, Shortcut::None, MenuFlag::Radio, self.sender.as_ref().unwrap().clone(), Message::KeywordCase(case));
+            }
+            if let Some(mut item) = main_menu.find_item("Edit/Keyword Case/UPPER") {
+                item.set();
+            }
+            main_menu.add_emit(
+                "Tools/Compare Results...",
+                Shortcut::Ctrl | Shortcut::Shift | 'd',
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::CompareResults,
+            );
+            main_menu.add_emit(
+                "Tools/Compare Schemas...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::CompareSchemas,
+            );
+            main_menu.add_emit(
+                "Tools/Transfer Data...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::TransferData,
+            );
+            main_menu.add_emit(
+                "Tools/Calibration Due...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::CalibrationDashboard,
+            );
+            main_menu.add_emit(
+                "Tools/Date Columns...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::DateColumns,
+            );
+            main_menu.add_emit(
+                "Tools/Capability Analysis...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::CapabilityAnalysis,
+            );
+            main_menu.add_emit(
+                "Tools/Control Chart...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::ControlChart,
+            );
+            main_menu.add_emit(
+                "Tools/Verify File Paths...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::VerifyPaths,
+            );
+            main_menu.add_emit(
+                "Tools/Product Lookup...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::ProductLookup,
+            );
+            main_menu.add_emit(
+                "Tools/Audit Log...",
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::AuditLog,
+            );
+            main_menu.add("Option", Shortcut::None, MenuFlag::Normal, |_| {});
+        }
+        self.refresh_recent_menu();
+        self.refresh_library();
+
         self.fltk_windows[0].end();
         self.fltk_windows[0].show();
 
@@ -322,7 +381,18 @@ impl FltkHost {
                 children_bounds.2,
                 children_bounds.3,
             );
+            fltk::app::widget_from_id::<fltk::menu::MenuBar>("main_menu").unwrap().set_size(children_bounds.2, 30);
         }
+        self.fltk_windows[0].make_resizable(true);
+
+        // tabs come back after the window is up, reconnecting may ask for logins
+        self.restore_workspace();
+        self.last_autosave = Some(self.workspace_state());
+        let autosave_sndr: Sender<Message> = self.sender.as_ref().unwrap().clone();
+        fltk::app::add_timeout3(workspace::AUTOSAVE_SECONDS, move |handle| {
+            autosave_sndr.send(Message::Autosave);
+            fltk::app::repeat_timeout3(workspace::AUTOSAVE_SECONDS, handle);
+        });
 
 //        let mut workers: Vec<JoinHandle<()>> = Vec::<JoinHandle<()>>::new();
 //        let mut outputs: Vec<MultilineOutput> = Vec::new();
@@ -332,17 +402,21 @@ impl FltkHost {
     fn event_loop(&mut self) -> Result<(), String> {
         while self.fltk_app.wait() {
             match self.receiver.as_ref().unwrap().recv() {
-                Some(Message::Query(mut query, fetch_flag)) => {
-                    let db_name = match self.conn.connection_type.as_ref() {
-                        Some(crate::sql_aux_funcs::ConnectionBase::Odbc) | Some(crate::sql_aux_funcs::ConnectionBase::Sqlite) => self.conn.connection.clone().unwrap(),
+                Some(Message::Query(tab, mut query, fetch_flag)) => {
+                    let session: &mut SessionTab = match self.session(tab) {
+                        Some(session) => session,
+                        None => continue, // the tab was closed before its message arrived
+                    };
+                    let db_name = match session.conn.connection_type.as_ref() {
+                        Some(crate::sql_aux_funcs::ConnectionBase::Odbc) | Some(crate::sql_aux_funcs::ConnectionBase::Sqlite) => session.conn.connection.clone().unwrap(),
                         None => { String::from("None") },
                     };
-                    if self.smart_tables.get_mut("tables_grid").unwrap().get_selection() != (-1, -1, -1, -1) {
+                    if session.smart_tables.get_mut("tables_grid").unwrap().get_selection() != (-1, -1, -1, -1) {
                         let mut table_name: String = String::new();
                         table_name = match fetch_flag {
                             FetchFlag::True => {
-                                let (row, col, _, _) =  self.smart_tables.get("tables_grid").unwrap().get_selection();
-                                self.smart_tables.get("tables_grid").unwrap().cell_value(row, col)
+                                let (row, col, _, _) =  session.smart_tables.get("tables_grid").unwrap().get_selection();
+                                session.smart_tables.get("tables_grid").unwrap().cell_value(row, col)
                             },
                             FetchFlag::False => { String::new() }
                         };
@@ -352,19 +426,47 @@ impl FltkHost {
                         let mut c: String = String::from("select top 1 * from ;");
                         c.insert_str(20, &table_name[..]);
                         query = QueryType::SqlFunction(Request::Columns(table_name));       
-                        self.smart_tables.get_mut("tables_grid").unwrap().unset_selection();
+                        session.smart_tables.get_mut("tables_grid").unwrap().unset_selection();
                     };
-                    match attempt_query(query, &db_name[..], self.conn.connection_type.as_ref()) {
+                    let request: QueryType = query.clone();
+                    // a read-only connection refuses writes, DROP, TRUNCATE and DELETE / UPDATE without WHERE ask first
+                    if let (QueryType::UserDefined(sql), Some(connection_type)) = (&request, session.conn.connection_type.as_ref()) {
+                        if let Err(e) = statement_guard::refuse_writes(session.conn.safety, sql) {
+                            println!("{}", e);
+                            let (x, y) = center();
+                            dialog::alert(x - 200, y - 100, &e);
+                            continue;
+                        }
+                        if !statement_guard::confirm_destructive(session.conn.safety, connection_type, &db_name, sql) {
+                            println!("Cancelled, the statement was not run");
+                            continue;
+                        }
+                    }
+                    // old values of the rows a change touches, for the audit log
+                    let captures: Vec<Capture> = match (&request, session.conn.connection_type.as_ref()) {
+                        (QueryType::UserDefined(sql), Some(connection_type)) => audit_log::capture(connection_type, &db_name, sql),
+                        _ => Vec::new(),
+                    };
+                    match attempt_query(query, &db_name[..], session.conn.connection_type.as_ref(), session.conn.safety.read_only) {
                         Ok(value) => {
-                            self.conn.assemble_rs(value);
+                            session.remember_schema(&request, &value);
+                            session.conn.assemble_rs(value);
                         }
                         Err(E) => {
-                            println!("{}", E);
+                            println!("{}", credentials::redact(&E));
                         }
                     }
+                    if !captures.is_empty() {
+                        self.audit(tab, captures);
+                    }
                 },
-                Some(Message::Save) => {
-                    let the_data: String = AuxFuncs::translateStringVecToCSV(&self.smart_tables.get("record_grid").unwrap().data());
+                Some(Message::Save(tab)) => {
+                    // the rows as the grid shows them, text dates written as YYYY-MM-DD
+                    let settings: DateSettings = self.date_settings.clone();
+                    let the_data: String = match self.session(tab).and_then(|session| session.grid_view(&settings)) {
+                        Some((view, dates)) => record_export::render(&view.with_iso_dates(&dates), ExportFormat::Csv),
+                        None => String::new(),
+                    };
     
                     if the_data.len() > 0 {
                         match sqlite3::save_results(the_data) {
@@ -377,19 +479,23 @@ impl FltkHost {
                         };
                     };
                 },
-                Some(Message::FillGrid(table_index)) => {
-                    if self.conn.result_code == Some(-1) {
+                Some(Message::FillGrid(tab, table_index)) => {
+                    let session: &mut SessionTab = match self.session(tab) {
+                        Some(session) => session,
+                        None => continue,
+                    };
+                    if session.conn.result_code == Some(-1) {
                         //check that the query didn't error out
-                        match self.conn.result_details.as_ref() {
+                        match session.conn.result_details.as_ref() {
                             Some(details) => println!("{}", details),
                             None => println!("Empty error message"),
                         }
-                        self.conn.result_code = None;
-                        self.conn.result_details = None;
+                        session.conn.result_code = None;
+                        session.conn.result_details = None;
                     }
     
                     //then fill the grid with the recordset because it passed
-                    if self.conn.result_code == Some(1) {
+                    if session.conn.result_code == Some(1) {
                        /*   // I'm passing the full recordset currently. I'll worry about re-implementing pages at a later time
                         let page_index: usize = fltk::app::widget_from_id::<fltk::input::Input>("pages_input")
                             .unwrap()
@@ -398,23 +504,51 @@ impl FltkHost {
                             .unwrap(); */
                         
                         let x: &mut SmartTable = match table_index {
-                            1 => {  self.smart_tables.get_mut("record_grid").unwrap() },
-                            2 => {  self.smart_tables.get_mut("tables_grid").unwrap() },
-                            3 => {  self.smart_tables.get_mut("columns_grid").unwrap() },
-                            _ => {  self.smart_tables.get_mut("record_grid").unwrap() },
+                            2 => {  session.smart_tables.get_mut("tables_grid").unwrap() },
+                            3 => {  session.smart_tables.get_mut("columns_grid").unwrap() },
+                            _ => {  // the record grid shows the result filtered and sorted
+                                self.refill_record_grid(tab);
+                                continue;
+                            },
                         };
     
                         //slice the recordset into a single page
                         //fill the grid with only <= 50 records
-                        let page_of_records: Vec<Record> = match self.conn.record_set {
+                        let page_of_records: Vec<Record> = match session.conn.record_set {
                             Some(ref rs) => rs.records.clone(),
                             None => { Vec::<Record>::new() },
                         };
-                        fill_table(&self.conn.record_set.clone().unwrap(), x, page_of_records);
+                        fill_table(&session.conn.record_set.clone().unwrap(), x, page_of_records);
+                    }
+                },
+                Some(Message::SortGrid(tab, column)) => {
+                    if let Some(session) = self.session(tab) {
+                        let name: String = session.smart_tables.get_mut("record_grid").unwrap().col_header_value(column);
+                        session.sort_by(name);
                     }
+                    self.refill_record_grid(tab);
                 },
-                Some(Message::ClearGrid) => {
-                    clear_table(self.smart_tables.get_mut("record_grid").unwrap());
+                Some(Message::FilterGrid(tab)) => {
+                    let problem: Option<String> = self.session(tab).and_then(|session| session.filter().err());
+                    match problem {
+                        Some(e) => {
+                            let (x, y): (i32, i32) = center();
+                            dialog::alert(x - 200, y - 100, &e);
+                        }
+                        None => self.refill_record_grid(tab),
+                    }
+                },
+                Some(Message::DateColumns) => self.date_columns(),
+                Some(Message::CapabilityAnalysis) => self.capability_analysis(),
+                Some(Message::ControlChart) => self.control_chart(),
+                Some(Message::PreviewCell(tab, row, column)) => self.preview_cell(tab, row, column),
+                Some(Message::VerifyPaths) => self.verify_paths(),
+                Some(Message::ProductLookup) => self.product_lookup(),
+                Some(Message::AuditLog) => audit_log::show_audit_log(),
+                Some(Message::ClearGrid(tab)) => {
+                    if let Some(session) = self.session(tab) {
+                        clear_table(session.smart_tables.get_mut("record_grid").unwrap());
+                    }
                 },
                 Some(Message::RandomNumber(_indx, _num)) => {
 //                    outputs[indx].set_value(&num.to_string()[..]);    // Not worrying about implementing multi threaded jobs at this time, commenting out
@@ -423,34 +557,841 @@ impl FltkHost {
 //                    spawn_observer(&mut self, &mut outputs, &mut workers, main_app_sender.clone()); // Not worrying about implementing multi threaded jobs at this time, commenting out
                 },
                 Some(Message::SqlServerPacket(packet)) => {
-                    match packet { //sqlite
+                    // quick connect replaces the connection of the visible tab only
+                    let target: Option<(ConnectionBase, String)> = match packet { //sqlite
                         Some(2) => match self.select_file() {
-                            Ok(selected_file) => {
-                                self.conn.connection_type = Some(ConnectionBase::Sqlite);
-                                self.conn.connection = Some(String::from(selected_file));
+                            Ok(selected_file) => Some((ConnectionBase::Sqlite, selected_file)),
+                            Err(E) => {
+                                println!("Invalid operation during file selection, {E:?}");
+                                None
                             }
-                            Err(E) => println!("Invalid operation during file selection, {E:?}"),
                         }, 
                         Some(1) => { // Odbc
                             let conn_str: String = self.input_conn_str();
-                            self.conn.connection_type = Some(ConnectionBase::Odbc);
-                            self.conn.connection = Some(String::from(conn_str));
                             println!("odbc selected");
-                            self.conn.record_set = Some(RecordSet::default());
+                            Some((ConnectionBase::Odbc, conn_str))
+                        }
+                        _ => None,
+                    };
+                    if let Some((connection_type, connection)) = target {
+                        self.connect_quick(connection_type, connection);
+                    }
+                },
+                Some(Message::ProfileManager) => {
+                    if let Some(profile) = profile_manager::show_manager(&mut self.profiles, &mut self.credentials) {
+                        self.connect_profile(&profile);
+                    }
+                    self.refresh_recent_menu();
+                },
+                Some(Message::LibrarySelect(path)) => {
+                    // folders are in the tree too, only saved queries have an id
+                    let query: Option<SavedQuery> = match (self.library_index.get(&path), self.library.as_ref()) {
+                        (Some(id), Some(library)) => library.get(*id).unwrap_or(None),
+                        _ => None,
+                    };
+                    if let Some(query) = query {
+                        self.run_saved_query(&query);
+                    }
+                },
+                Some(Message::LibrarySave) => self.save_to_library(),
+                Some(Message::LibraryDelete) => self.delete_from_library(),
+                Some(Message::LibraryImport) => {
+                    if let Some(path) = dialog::file_chooser("Import a query library", "*.db", ".", false) {
+                        if let Some(library) = self.library.as_ref() {
+                            match library.import(std::path::Path::new(&path)) {
+                                Ok(count) => println!("Imported {} queries from {}", count, path),
+                                Err(e) => println!("Import failed: {}", e.message.unwrap_or_default()),
+                            }
+                        }
+                        self.refresh_library();
+                    }
+                },
+                Some(Message::LibraryExport) => {
+                    let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+                    chooser.set_title("Export the query library");
+                    chooser.set_filter("*.db");
+                    chooser.set_preset_file("query_library_export.db");
+                    chooser.show();
+                    let target: std::path::PathBuf = chooser.filename();
+                    if !target.as_os_str().is_empty() {
+                        if let Some(library) = self.library.as_ref() {
+                            match library.export(&target) {
+                                Ok(_) => save_successful(),
+                                Err(e) => println!("Export failed: {}", e.message.unwrap_or_default()),
+                            }
+                        }
+                    }
+                },
+                Some(Message::Complete(tab)) => self.complete_at_cursor(tab),
+                Some(Message::FormatSql) => {
+                    let keyword_case: KeywordCase = self.keyword_case;
+                    if let Some(session) = self.active_session() {
+                        let options: FormatOptions = FormatOptions {
+                            dialect: match session.conn.connection_type {
+                                Some(ConnectionBase::Odbc) => Dialect::TSql,
+                                _ => Dialect::Sqlite,
+                            },
+                            keyword_case,
+                            ..Default::default()
+                        };
+                        sql_editor::replace_selection_or_all(&mut session.editor, |sql| sql_format::format_sql(sql, &options));
+                    }
+                },
+                Some(Message::KeywordCase(case)) => self.keyword_case = case,
+                Some(Message::Explain(tab)) => self.explain_statement(tab),
+                Some(Message::RunStatement(tab)) => {
+                    // Ctrl+Enter: the selection, or just the statement under the cursor
+                    let sql: String = match self.session(tab) {
+                        Some(session) => sql_editor::selection_or_statement(&session.editor),
+                        None => String::new(),
+                    };
+                    if !sql.trim().is_empty() {
+                        let sndr: Sender<Message> = self.sender.as_ref().unwrap().clone();
+                        sndr.send(Message::Query(tab, QueryType::UserDefined(sql), FetchFlag::False));
+                        sndr.send(Message::FillGrid(tab, 1));
+                    }
+                },
+                Some(Message::NewTab) => self.open_tab(),
+                Some(Message::CloseTab) => self.close_tab(),
+                Some(Message::TabChanged) => self.refresh_title(),
+                Some(Message::CompareResults) => self.compare_results(),
+                Some(Message::CompareSchemas) => self.compare_schemas(),
+                Some(Message::TransferData) => self.transfer_data(),
+                Some(Message::CalibrationDashboard) => self.calibration_dashboard(),
+                Some(Message::ShowRecord(tab, sql)) => {
+                    let session: &mut SessionTab = match self.session(tab) {
+                        Some(session) => session,
+                        None => continue,
+                    };
+                    sql_editor::set_text(&mut session.editor, &sql);
+                    let page = session.page.clone();
+                    let mut tabs: Tabs = fltk::app::widget_from_id::<Tabs>("session_tabs").unwrap();
+                    if tabs.set_value(&page).is_ok() {
+                        tabs.redraw();
+                        self.refresh_title();
+                    }
+                    let sndr: Sender<Message> = self.sender.as_ref().unwrap().clone();
+                    sndr.send(Message::Query(tab, QueryType::UserDefined(sql), FetchFlag::False));
+                    sndr.send(Message::FillGrid(tab, 1));
+                },
+                Some(Message::Autosave) => {
+                    let state: WorkspaceState = self.workspace_state();
+                    if self.last_autosave.as_ref() != Some(&state) {
+                        match workspace::write_autosave(&state) {
+                            Ok(_) => self.last_autosave = Some(state),
+                            Err(e) => println!("{}", e),
                         }
-                        _ => (),
                     }
-                    if self.conn.connection != None {
-                        fltk::app::widget_from_id::<fltk::button::Button>("tables_butn").unwrap().handle_event(fltk::enums::Event::Push);
+                },
+                Some(Message::ConnectProfile(name)) => {
+                    match self.profiles.get(&name).cloned() {
+                        Some(profile) => self.connect_profile(&profile),
+                        None => println!("No saved connection named {}", name),
                     }
                 },
                 None => {},
             }
         }
+        // a clean exit replaces the autosave with the regular snapshot
+        match workspace::save(&self.workspace_state()) {
+            Ok(_) => workspace::clear_autosave(),
+            Err(e) => println!("{}", e),
+        }
         println!("exited ui event loop");
         Ok(())
     }
 
+    // a connection typed in or picked as a file, not from a saved profile
+    fn connect_quick(&mut self, connection_type: ConnectionBase, connection: String) {
+        let sndr: Sender<Message> = self.sender.as_ref().unwrap().clone();
+        if let Some(session) = self.active_session() {
+            session.reset_connection();
+            session.conn.connection_type = Some(connection_type);
+            session.conn.connection = Some(connection);
+            session.conn.record_set = Some(RecordSet::default());
+            session.refresh_label();
+            session_tab::send_tables_request(&sndr, session.id);
+        }
+        self.refresh_title();
+    }
+
+    fn connect_profile(&mut self, profile: &ConnectionProfile) {
+        // the login is applied to the in-memory connection string only, profiles never hold passwords
+        let login = match profile_manager::login_for(profile, &mut self.credentials) {
+            Ok(login) => login,
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        };
+        let sndr: Sender<Message> = self.sender.as_ref().unwrap().clone();
+        match self.active_session() {
+            Some(session) => {
+                session.reset_connection();
+                session.conn.connection_type = Some(profile.connection_base());
+                session.conn.connection = Some(profile_manager::connection_string_for(profile, login.as_ref()));
+                session.conn.profile_name = Some(profile.name.clone());
+                session.conn.safety = profile.safety();
+                session.conn.record_set = Some(RecordSet::default());
+                session.refresh_label();
+                session_tab::send_tables_request(&sndr, session.id);
+            }
+            None => return,
+        }
+        self.refresh_title();
+
+        self.profiles.touch_recent(&profile.name);
+        if let Err(e) = self.profiles.save() {
+            println!("{}", e);
+        }
+        self.refresh_recent_menu();
+    }
+
+    // plan of the selection or the statement under the cursor
+    fn explain_statement(&mut self, tab: usize) {
+        let session: &mut SessionTab = match self.session(tab) {
+            Some(session) => session,
+            None => return,
+        };
+        let sql: String = sql_editor::selection_or_statement(&session.editor);
+        let db_name: String = match (session.conn.connection.clone(), sql.trim().is_empty()) {
+            (Some(db_name), false) => db_name,
+            _ => return,
+        };
+        let steps: Vec<query_plan::PlanStep> = match session.conn.connection_type {
+            Some(ConnectionBase::Sqlite) => match sqlite3::explain_query_plan(db_name, sql.clone()) {
+                Ok(record_set) => query_plan::from_sqlite(&record_set),
+                Err(e) => {
+                    println!("{}", e.message.unwrap_or_default());
+                    return;
+                }
+            },
+            // SHOWPLAN_TEXT is SQL Server only, other drivers answer with an error
+            Some(ConnectionBase::Odbc) => match crate::odbc_interface::explain(db_name, sql.clone()) {
+                Ok(record_set) => query_plan::from_showplan(&record_set),
+                Err(e) => {
+                    println!("{}", credentials::redact(&e.to_string()));
+                    Vec::new()
+                }
+            },
+            None => return,
+        };
+        query_plan::show_plan(&sql, &steps);
+    }
+
+    // run a query on each of two tabs, possibly two backends, and show what differs
+    fn compare_results(&mut self) {
+        let sources: Vec<DiffSource> = self
+            .sessions
+            .iter()
+            .map(|s| DiffSource {
+                tab: s.id,
+                label: s.label(),
+                sql: sql_editor::selection_or_statement(&s.editor),
+                safety: s.conn.safety,
+            })
+            .collect();
+        let active_id: Option<usize> = self.active_session().map(|s| s.id);
+        let active: usize = sources.iter().position(|s| Some(s.tab) == active_id).unwrap_or(0);
+        let request: DiffRequest = match result_diff::ask_sources(&sources, active) {
+            Some(request) => request,
+            None => return,
+        };
+
+        let mut results: Vec<RecordSet> = Vec::new();
+        for side in [&request.left, &request.right] {
+            let conn: Option<(ConnectionBase, String)> = self
+                .sessions
+                .iter()
+                .find(|s| s.id == side.tab)
+                .and_then(|s| Some((s.conn.connection_type.clone()?, s.conn.connection.clone()?)));
+            let outcome: Result<RecordSet, String> = match conn {
+                Some((connection_type, connection)) if side.safety.read_only => {
+                    statement_guard::refuse_writes(side.safety, &side.sql).and_then(|_| {
+                        cli::run_read_only(&connection_type, &connection, QueryType::UserDefined(side.sql.clone()))
+                    })
+                }
+                Some((connection_type, connection)) => {
+                    if !statement_guard::confirm_destructive(side.safety, &connection_type, &connection, &side.sql) {
+                        return;
+                    }
+                    // the SQL is whatever the editor holds, a change in it is logged like one run from the editor
+                    let captures: Vec<Capture> = audit_log::capture(&connection_type, &connection, &side.sql);
+                    let outcome: Result<RecordSet, String> =
+                        cli::run_request(&connection_type, &connection, QueryType::UserDefined(side.sql.clone()));
+                    if !captures.is_empty() {
+                        self.audit(side.tab, captures);
+                    }
+                    outcome
+                }
+                None => Err(String::from("not connected")),
+            };
+            match outcome {
+                Ok(record_set) => results.push(record_set),
+                Err(e) => {
+                    let (x, y): (i32, i32) = center();
+                    dialog::alert(x - 200, y - 100, &format!("{}: {}", side.label, credentials::redact(&e)));
+                    return;
+                }
+            }
+        }
+        match result_diff::diff(&results[0], &results[1], &request.keys) {
+            Ok(diff) => result_diff::show_diff(&diff, &request.left.label, &request.right.label),
+            Err(e) => {
+                let (x, y): (i32, i32) = center();
+                dialog::alert(x - 200, y - 100, &e);
+            }
+        }
+    }
+
+    fn compare_schemas(&mut self) {
+        let labels: Vec<String> = self.sessions.iter().map(|s| s.label()).collect();
+        let active_id: Option<usize> = self.active_session().map(|s| s.id);
+        let active: usize = self.sessions.iter().position(|s| Some(s.id) == active_id).unwrap_or(0);
+        let (source, target): (usize, usize) = match schema_diff::ask_connections(&labels, active) {
+            Some(chosen) => chosen,
+            None => return,
+        };
+
+        let mut snapshots: Vec<SchemaSnapshot> = Vec::new();
+        for index in [source, target] {
+            let session: &SessionTab = &self.sessions[index];
+            let outcome: Result<SchemaSnapshot, String> =
+                match (session.conn.connection_type.clone(), session.conn.connection.clone()) {
+                    (Some(connection_type), Some(connection)) => schema_diff::load(&connection_type, &connection),
+                    _ => Err(String::from("not connected")),
+                };
+            match outcome {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => {
+                    let (x, y): (i32, i32) = center();
+                    dialog::alert(x - 200, y - 100, &format!("{}: {}", labels[index], credentials::redact(&e)));
+                    return;
+                }
+            }
+        }
+        let changes: Vec<SchemaChange> = schema_diff::compare(&snapshots[0], &snapshots[1]);
+        let script: String = schema_diff::migration_script(&changes, &snapshots[0], &snapshots[1]);
+        schema_diff::show_schema_diff(&labels[source], &labels[target], &changes, &script);
+    }
+
+    fn transfer_data(&mut self) {
+        let sides: Vec<TransferSide> = self
+            .sessions
+            .iter()
+            .map(|s| TransferSide {
+                label: s.label(),
+                connection: s.conn.connection_type.clone().zip(s.conn.connection.clone()),
+                sql: sql_editor::selection_or_statement(&s.editor),
+                safety: s.conn.safety,
+            })
+            .collect();
+        let active_id: Option<usize> = self.active_session().map(|s| s.id);
+        let active: usize = self.sessions.iter().position(|s| Some(s.id) == active_id).unwrap_or(0);
+        data_transfer::wizard(&sides, active, &mut self.analyst);
+    }
+
+    fn calibration_dashboard(&mut self) {
+        let (tab, label, connection) = match self.active_session() {
+            Some(session) => (
+                session.id,
+                session.label(),
+                session.conn.connection_type.clone().zip(session.conn.connection.clone()),
+            ),
+            None => return,
+        };
+        let (x, y): (i32, i32) = center();
+        let (connection_type, connection): (ConnectionBase, String) = match connection {
+            Some(connection) => connection,
+            None => {
+                dialog::alert(x - 200, y - 100, "Connect the tab to the instrument database first.");
+                return;
+            }
+        };
+        let table: String = match dialog::input(x - 200, y - 100, "Instrument table", "measuring_instruments") {
+            Some(table) if !table.trim().is_empty() => table.trim().to_string(),
+            _ => return,
+        };
+        match calibration_dashboard::load(&connection_type, &connection, &table, &self.date_settings) {
+            Ok(instruments) => calibration_dashboard::show_dashboard(
+                &label,
+                &connection_type,
+                &table,
+                instruments,
+                tab,
+                self.sender.as_ref().unwrap().clone(),
+            ),
+            Err(e) => dialog::alert(x - 200, y - 100, &credentials::redact(&e)),
+        }
+    }
+
+    // filtered and sorted as the tab asks, dates ordered as dates
+    fn refill_record_grid(&mut self, tab: usize) {
+        let settings: DateSettings = self.date_settings.clone();
+        let session: &mut SessionTab = match self.session(tab) {
+            Some(session) => session,
+            None => return,
+        };
+        if let Some((view, _)) = session.grid_view(&settings) {
+            let records: Vec<Record> = view.records.clone();
+            fill_table(&view, session.smart_tables.get_mut("record_grid").unwrap(), records);
+        }
+    }
+
+    fn date_columns(&mut self) {
+        let (tab, record_set) = match self.active_session() {
+            Some(session) => (session.id, session.conn.record_set.clone()),
+            None => (0, None),
+        };
+        if !date_columns::edit_settings(&mut self.date_settings, record_set.as_ref()) {
+            return;
+        }
+        if let Err(e) = self.date_settings.save() {
+            println!("{}", e);
+        }
+        if record_set.is_some() {
+            self.refill_record_grid(tab);
+        }
+    }
+
+    // the measurements are the active tab's result, the specs may come from another tab
+    fn capability_analysis(&mut self) {
+        let (x, y): (i32, i32) = center();
+        let (label, record_set) = match self.active_session() {
+            Some(session) => (session.label(), session.conn.record_set.clone()),
+            None => return,
+        };
+        let record_set: RecordSet = match record_set {
+            Some(record_set) if !record_set.records.is_empty() => record_set,
+            _ => {
+                dialog::alert(x - 200, y - 100, "Run the query with the measurements first, the analysis reads its result.");
+                return;
+            }
+        };
+        let tabs: Vec<String> = self.sessions.iter().map(|s| s.label()).collect();
+        let active_id: Option<usize> = self.active_session().map(|s| s.id);
+        let active: usize = self.sessions.iter().position(|s| Some(s.id) == active_id).unwrap_or(0);
+        let request: CapabilityRequest = match capability::ask_request(&record_set, &tabs, active) {
+            Some(request) => request,
+            None => return,
+        };
+
+        let specs_conn: Option<(ConnectionBase, String)> = self
+            .sessions
+            .get(request.specs_tab)
+            .and_then(|s| Some((s.conn.connection_type.clone()?, s.conn.connection.clone()?)));
+        let specs: Vec<Spec> = match specs_conn {
+            Some((connection_type, connection)) => {
+                match capability::load_specs(&connection_type, &connection, &capability::products(&record_set, &request)) {
+                    Ok(specs) => specs,
+                    Err(e) => {
+                        dialog::alert(x - 200, y - 100, &format!("Unable to read the specs: {}", credentials::redact(&e)));
+                        return;
+                    }
+                }
+            }
+            None => {
+                dialog::alert(x - 200, y - 100, "The tab chosen for the specs is not connected.");
+                return;
+            }
+        };
+        let capabilities: Vec<Capability> = capability::analyze(capability::samples(&record_set, &request, &specs), &specs);
+        if capabilities.is_empty() {
+            dialog::alert(x - 200, y - 100, "No numeric measurements were found for the chosen columns.");
+            return;
+        }
+        capability::show_report(&label, capabilities);
+    }
+
+    fn control_chart(&mut self) {
+        let (x, y): (i32, i32) = center();
+        let (label, record_set) = match self.active_session() {
+            Some(session) => (session.label(), session.conn.record_set.clone()),
+            None => return,
+        };
+        let record_set: RecordSet = match record_set {
+            Some(record_set) if !record_set.records.is_empty() => record_set,
+            _ => {
+                dialog::alert(x - 200, y - 100, "Run the query with the measurements first, the chart reads its result.");
+                return;
+            }
+        };
+        let tabs: Vec<String> = self.sessions.iter().map(|s| s.label()).collect();
+        let active_id: Option<usize> = self.active_session().map(|s| s.id);
+        let active: usize = self.sessions.iter().position(|s| Some(s.id) == active_id).unwrap_or(0);
+        let request: ChartRequest = match control_chart::ask_request(&record_set, &tabs, active) {
+            Some(request) => request,
+            None => return,
+        };
+
+        // spec limits are optional, the control limits come from the data
+        let specs_conn: Option<(ConnectionBase, String)> = self
+            .sessions
+            .get(request.specs_tab)
+            .and_then(|s| Some((s.conn.connection_type.clone()?, s.conn.connection.clone()?)));
+        let spec: Option<Spec> = match specs_conn {
+            Some((connection_type, connection)) if !request.product.is_empty() => {
+                match capability::load_specs(&connection_type, &connection, std::slice::from_ref(&request.product)) {
+                    Ok(specs) => specs.into_iter().find(|s| s.dimension.eq_ignore_ascii_case(&request.dimension)),
+                    Err(e) => {
+                        println!("Unable to read the specs: {}", credentials::redact(&e));
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        match control_chart::build(&record_set, &request, spec.as_ref()) {
+            Ok(chart) => control_chart::show_chart(&label, chart),
+            Err(e) => dialog::alert(x - 200, y - 100, &e),
+        }
+    }
+
+    // a path cell of the record grid shows its file in the preview panel
+    fn preview_cell(&mut self, tab: usize, row: i32, column: i32) {
+        let settings: PathSettings = self.path_settings.clone();
+        let session: &mut SessionTab = match self.session(tab) {
+            Some(session) => session,
+            None => return,
+        };
+        let record_grid: &mut SmartTable = session.smart_tables.get_mut("record_grid").unwrap();
+        if row < 0 || column < 0 || row >= record_grid.rows() || column >= record_grid.cols() {
+            return;
+        }
+        let name: String = record_grid.col_header_value(column);
+        let value: String = record_grid.cell_value(row, column);
+        let path_column: bool = session
+            .conn
+            .record_set
+            .as_ref()
+            .is_some_and(|rs| file_paths::path_columns(rs).contains(&name));
+        if value.trim().is_empty() || !(path_column || file_paths::looks_like_path(&value)) {
+            return;
+        }
+        let folders: Vec<Folder> = session.path_folders();
+        let path: ResolvedPath = file_paths::resolve(&value, &folders, &settings);
+        session.preview.show(&path);
+    }
+
+    fn verify_paths(&mut self) {
+        let (x, y): (i32, i32) = center();
+        let settings: PathSettings = self.path_settings.clone();
+        let session: &mut SessionTab = match self.active_session() {
+            Some(session) => session,
+            None => return,
+        };
+        let record_set: RecordSet = match session.conn.record_set.clone() {
+            Some(record_set) if !record_set.records.is_empty() => record_set,
+            _ => {
+                dialog::alert(x - 200, y - 100, "Run the query with the file paths first, e.g. SELECT * FROM MSet_Log.");
+                return;
+            }
+        };
+        let columns: Vec<String> = file_paths::path_columns(&record_set);
+        if columns.is_empty() {
+            dialog::alert(x - 200, y - 100, "The result has no column of file paths.");
+            return;
+        }
+        let folders: Vec<Folder> = session.path_folders();
+        let label: String = session.label();
+        file_paths::show_verify_report(&label, file_paths::verify(&record_set, &columns, &folders, &settings));
+    }
+
+    fn audit(&mut self, tab: usize, captures: Vec<Capture>) {
+        let (connection_type, connection) = match self.session(tab).and_then(|s| s.conn.connection_type.clone().zip(s.conn.connection.clone())) {
+            Some(connection) => connection,
+            None => return,
+        };
+        let analyst: String = audit_log::resolve_analyst(&mut self.analyst, &connection_type, &connection);
+        let label: String = audit_log::connection_name(&connection_type, &connection);
+        audit_log::write(&audit_log::changes(&connection_type, &connection, &label, &analyst, captures));
+    }
+
+    fn product_lookup(&mut self) {
+        let (label, connection) = match self.active_session() {
+            Some(session) => (session.label(), session.conn.connection_type.clone().zip(session.conn.connection.clone())),
+            None => return,
+        };
+        match connection {
+            Some((connection_type, connection)) => product_lookup::show_lookup(&label, &connection_type, &connection),
+            None => {
+                let (x, y): (i32, i32) = center();
+                dialog::alert(x - 200, y - 100, "Connect the tab to the product database first.");
+            }
+        }
+    }
+
+    fn complete_at_cursor(&mut self, tab: usize) {
+        let session: &mut SessionTab = match self.session(tab) {
+            Some(session) => session,
+            None => return,
+        };
+        let sql: String = sql_editor::text(&session.editor);
+        let cursor: usize = usize::try_from(session.editor.insert_position()).unwrap_or(0);
+        let context = sql_completion::completion_context(&sql, cursor);
+
+        // fetch what the cache is missing the same way the schema browser does
+        if let Some(db_name) = session.conn.connection.clone() {
+            let mut requests: Vec<Request> = Vec::new();
+            if session.schema_cache.tables().is_empty() {
+                requests.push(Request::Tables(2));
+            }
+            let mut wanted: Vec<String> = context.tables.iter().map(|t| t.table.clone()).collect();
+            if let Some(qualifier) = &context.qualifier {
+                wanted.push(qualifier.clone());
+            }
+            for table in wanted {
+                if !session.schema_cache.has_columns(&table) && (session.schema_cache.tables().is_empty() || session.schema_cache.tables().iter().any(|t| t.eq_ignore_ascii_case(&table))) {
+                    requests.push(Request::Columns(table));
+                }
+            }
+            for request in requests {
+                let request: QueryType = QueryType::SqlFunction(request);
+                if let Ok(record_set) = attempt_query(request.clone(), &db_name, session.conn.connection_type.as_ref(), session.conn.safety.read_only) {
+                    session.remember_schema(&request, &record_set);
+                }
+            }
+        }
+
+        let completions = sql_completion::candidates(&session.schema_cache, &context);
+        if let Some(index) = sql_completion::show_popup(&session.editor, &completions) {
+            sql_editor::replace_before_cursor(&mut session.editor, context.prefix_start, &completions[index].text);
+        }
+    }
+
+    fn session(&mut self, id: usize) -> Option<&mut SessionTab> {
+        self.sessions.iter_mut().find(|s| s.id == id)
+    }
+
+    // the tab on top, which menu and sidebar actions apply to
+    fn active_session(&mut self) -> Option<&mut SessionTab> {
+        let visible = fltk::app::widget_from_id::<Tabs>("session_tabs")?.value()?;
+        self.sessions.iter_mut().find(|s| s.page.is_same(&visible))
+    }
+
+    fn open_tab(&mut self) {
+        let mut tabs: Tabs = fltk::app::widget_from_id::<Tabs>("session_tabs").unwrap();
+        let session: SessionTab = session_tab::build(self.next_session_id, &mut tabs, self.sender.as_ref().unwrap());
+        self.next_session_id += 1;
+        if let Err(e) = tabs.set_value(&session.page) {
+            println!("Unable to show the new tab: {:?}", e);
+        }
+        tabs.redraw();
+        self.sessions.push(session);
+        self.refresh_title();
+    }
+
+    // the workspace always keeps one tab, closing the last one leaves a fresh one behind
+    fn close_tab(&mut self) {
+        let index: usize = match self.active_session().map(|s| s.id) {
+            Some(id) => self.sessions.iter().position(|s| s.id == id).unwrap(),
+            None => return,
+        };
+        let session: SessionTab = self.sessions.remove(index);
+        let mut tabs: Tabs = fltk::app::widget_from_id::<Tabs>("session_tabs").unwrap();
+        tabs.remove(&session.page);
+        fltk::group::Group::delete(session.page);
+        match self.sessions.get(index.min(self.sessions.len().saturating_sub(1))) {
+            Some(next) => {
+                if let Err(e) = tabs.set_value(&next.page) {
+                    println!("Unable to switch tabs: {:?}", e);
+                }
+                tabs.redraw();
+                self.refresh_title();
+            }
+            None => self.open_tab(),
+        }
+    }
+
+    fn workspace_state(&mut self) -> WorkspaceState {
+        let active: Option<usize> = self.active_session().map(|s| s.id);
+        let window = &self.fltk_windows[0];
+        WorkspaceState {
+            window: Some((window.x(), window.y(), window.w(), window.h())),
+            active_tab: self.sessions.iter().position(|s| Some(s.id) == active).unwrap_or(0),
+            tabs: self.sessions.iter().map(|s| s.state()).collect(),
+        }
+    }
+
+    // reopen the tabs of the last session, logins are asked for again rather than stored
+    fn restore_workspace(&mut self) {
+        let (state, recovered) = workspace::load().unwrap_or_default();
+        if let Some((x, y, w, h)) = state.window {
+            self.fltk_windows[0].resize(x, y, w, h);
+        }
+
+        for tab in &state.tabs {
+            self.open_tab();
+            if let Some(session) = self.active_session() {
+                session.restore_layout(tab);
+            }
+            match (&tab.profile_name, tab.connection_base()) {
+                (Some(name), _) => match self.profiles.get(name).cloned() {
+                    Some(profile) => self.connect_profile(&profile),
+                    None => println!("No saved connection named {}, the tab was restored without it", name),
+                },
+                (None, Some(connection_type)) => {
+                    let connection: String = match &tab.user {
+                        Some(user) => match profile_manager::prompt_credentials(&tab.target, user) {
+                            Ok(login) => credentials::apply_credentials(&tab.target, &login),
+                            Err(e) => {
+                                println!("{}", e);
+                                continue;
+                            }
+                        },
+                        None => tab.target.clone(),
+                    };
+                    self.connect_quick(connection_type, connection);
+                }
+                (None, None) => {}
+            }
+        }
+        if self.sessions.is_empty() {
+            self.open_tab();
+        }
+
+        let mut tabs: Tabs = fltk::app::widget_from_id::<Tabs>("session_tabs").unwrap();
+        if let Some(session) = self.sessions.get(state.active_tab) {
+            if let Err(e) = tabs.set_value(&session.page) {
+                println!("Unable to switch tabs: {:?}", e);
+            }
+        }
+        self.refresh_title();
+        if recovered {
+            println!("The last session did not exit cleanly, editor text was restored from its autosave");
+        }
+    }
+
+    // the window title names the connection of the visible tab
+    fn refresh_title(&mut self) {
+        let title: String = match self.active_session() {
+            Some(session) if session.conn.connection_type.is_some() => format!("DaedriVictus - {}", session.label()),
+            _ => String::from("DaedriVictus"),
+        };
+        self.fltk_windows[0].set_label(&title);
+    }
+
+    fn refresh_library(&mut self) {
+        if let Some(library) = self.library.as_ref() {
+            let mut tree = fltk::app::widget_from_id::<fltk::tree::Tree>("library_tree").unwrap();
+            self.library_index = library_panel::refresh_tree(&mut tree, library);
+        }
+    }
+
+    // load a saved query into the editor, fill in its parameters and run it
+    fn run_saved_query(&mut self, query: &SavedQuery) {
+        let tab: usize = match self.active_session() {
+            Some(session) => {
+                sql_editor::set_text(&mut session.editor, &query.sql);
+                session.id
+            }
+            None => return,
+        };
+        let values = match library_panel::prompt_parameters(query) {
+            Some(values) => values,
+            None => return,
+        };
+        let sql: String = crate::query_library::substitute_parameters(&query.sql, &values);
+        let sndr: Sender<Message> = self.sender.as_ref().unwrap().clone();
+        sndr.send(Message::Query(tab, QueryType::UserDefined(sql), FetchFlag::False));
+        sndr.send(Message::FillGrid(tab, 1));
+    }
+
+    fn save_to_library(&mut self) {
+        let sql: String = match self.active_session() {
+            Some(session) => sql_editor::text(&session.editor),
+            None => return,
+        };
+        let library: &QueryLibrary = match self.library.as_ref() {
+            Some(library) => library,
+            None => return,
+        };
+        if sql.trim().is_empty() {
+            return;
+        }
+
+        // start from the selected saved query (or the folder it sits in) so edits update it in place
+        let tree = fltk::app::widget_from_id::<fltk::tree::Tree>("library_tree").unwrap();
+        let mut initial: SavedQuery = match library_panel::selected_path(&tree) {
+            Some(path) => match self.library_index.get(&path) {
+                Some(id) => library.get(*id).unwrap_or(None).unwrap_or_default(),
+                None => SavedQuery {
+                    folder_path: library_panel::folder_from_path(&path),
+                    ..Default::default()
+                },
+            },
+            None => SavedQuery::default(),
+        };
+        initial.sql = sql;
+
+        if let Some(mut query) = library_panel::save_dialog(initial) {
+            // same folder and name as an existing query replaces it, anything else is a new entry
+            query.id = library
+                .queries()
+                .unwrap_or_default()
+                .iter()
+                .find(|q| q.folder_path == query.folder_path && q.name == query.name)
+                .and_then(|q| q.id);
+            if let Err(e) = library.save(&query) {
+                println!("Unable to save the query: {}", e.message.unwrap_or_default());
+            }
+            self.refresh_library();
+        }
+    }
+
+    fn delete_from_library(&mut self) {
+        let tree = fltk::app::widget_from_id::<fltk::tree::Tree>("library_tree").unwrap();
+        let path: String = match library_panel::selected_path(&tree) {
+            Some(path) => path,
+            None => return,
+        };
+        let library: &QueryLibrary = match self.library.as_ref() {
+            Some(library) => library,
+            None => return,
+        };
+        let (x, y): (i32, i32) = center();
+        let result = match self.library_index.get(&path) {
+            Some(id) => {
+                if dialog::choice2(x - 200, y - 100, &format!("Delete saved query {}?", path), "Cancel", "Delete", "") != Some(1) {
+                    return;
+                }
+                library.delete_query(*id)
+            }
+            None => {
+                if dialog::choice2(x - 200, y - 100, &format!("Delete folder {} and every query in it?", path), "Cancel", "Delete", "") != Some(1) {
+                    return;
+                }
+                library.delete_folder(&path)
+            }
+        };
+        if let Err(e) = result {
+            println!("Unable to delete {}: {}", path, e.message.unwrap_or_default());
+        }
+        self.refresh_library();
+    }
+
+    // rebuild File/Recent from the profile store, most recently used first
+    fn refresh_recent_menu(&mut self) {
+        let mut main_menu = fltk::app::widget_from_id::<fltk::menu::MenuBar>("main_menu").unwrap();
+        let recent_index: i32 = main_menu.find_index("File/Recent");
+        if recent_index < 0 {
+            return;
+        }
+        if let Err(e) = main_menu.clear_submenu(recent_index) {
+            println!("Unable to rebuild the recent connections menu: {:?}", e);
+            return;
+        }
+        for name in &self.profiles.recent {
+            main_menu.add_emit(
+                &format!("File/Recent/{}", escape_menu_label(name)),
+                Shortcut::None,
+                MenuFlag::Normal,
+                self.sender.as_ref().unwrap().clone(),
+                Message::ConnectProfile(name.clone()),
+            );
+        }
+    }
+
     fn select_file(&mut self) -> Result<String, std::io::Error> {
         let mut fi =
             dialog::FileChooser::new(".", "*.db", dialog::FileChooserType::Single, "Select a DB");
@@ -525,7 +1466,19 @@ fn save_successful() {
     );
 }
 
-fn center() -> (i32, i32) {
+// '/', '&', '\\' and '_' are special in fltk menu paths
+pub fn escape_menu_label(label: &str) -> String {
+    let mut escaped: String = String::with_capacity(label.len());
+    for ch in label.chars() {
+        if matches!(ch, '/' | '&' | '\\' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+pub fn center() -> (i32, i32) {
     let ss: (f64, f64) = fltk::app::screen_size();
     ((ss.0 / 2.0) as i32, (ss.1 / 2.0) as i32)
 }
@@ -534,17 +1487,30 @@ fn attempt_query(
     request: QueryType,
     db_name: &str,
     db_interface: Option<&ConnectionBase>,
+    read_only: bool,
 ) -> Result<RecordSet, String> {
     let result = match db_interface {
+        Some(&ConnectionBase::Odbc) if read_only => {
+            match crate::odbc_interface::read_only_entry_point(String::from(db_name), request) {
+                Ok(r) => r,
+                Err(e) => { println!("{}", credentials::redact(&e.to_string())); RecordSet::default() },
+            }
+        },
+        Some(&ConnectionBase::Sqlite) if read_only => {
+            match crate::sqlite3_interface::read_only_query(String::from(db_name), request) {
+                Ok(r) => r,
+                Err(e) => { println!("{}", credentials::redact(&e.to_string())); RecordSet::default() },
+            }
+        },
         Some(&ConnectionBase::Odbc) => { 
             match crate::odbc_interface::entry_point(String::from(db_name), request) {
                 Ok(r) => r,
-                Err(_) => RecordSet::default(),
+                Err(e) => { println!("{}", credentials::redact(&e.to_string())); RecordSet::default() },
         } }, // need to implement similar error handling here for ODBC
         Some(&ConnectionBase::Sqlite) => { 
             match crate::sqlite3_interface::query_interface(String::from(db_name), request) {
                 Ok(r) => r,
-                Err(e) => { println!("{}", e.message.unwrap()); RecordSet::default() },
+                Err(e) => { println!("{}", credentials::redact(&e.to_string())); RecordSet::default() },
             }
         },
         None => { return Err(String::from("Oops")) },
@@ -649,7 +1615,7 @@ fn resize_columns<'a>(
     }
 }
 
-fn fill_table(
+pub fn fill_table(
     record_set: &RecordSet,
     mut table: &mut SmartTable,
     paged_records: Vec<Record>,
@@ -0,0 +1,211 @@
+/* --> Summary of Contents
+
+	Sidebar and dialogs for the saved query library (query_library.rs)
+
+	pub fn refresh_tree(&mut Tree, &QueryLibrary) -> HashMap<String, i64>
+		Rebuild the sidebar tree, returns tree path -> query id
+
+	pub fn save_dialog(SavedQuery) -> Option<SavedQuery>
+		Folder, name, description and parameter defaults for a query
+
+	pub fn prompt_parameters(&SavedQuery) -> Option<HashMap<String, String>>
+		Ask for each :parameter, offering the saved default
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app,
+    button::{Button, ReturnButton},
+    dialog,
+    enums::{Align, Color},
+    frame::Frame,
+    input::{Input, MultilineInput},
+    prelude::{GroupExt, InputExt, WidgetBase, WidgetExt, WindowExt},
+    tree::Tree,
+    window::Window,
+};
+use std::collections::HashMap;
+
+use crate::learning_fltk::center;
+use crate::query_library::{self, QueryLibrary, SavedQuery};
+
+/* <-- Imports */
+/* --> Functions */
+
+// '/' separates tree levels, so names carrying one are escaped the way Fl_Tree expects
+pub fn escape_tree_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('/', "\\/")
+}
+
+fn tree_path(folder_path: &str, name: &str) -> String {
+    let mut path: String = folder_path
+        .split('/')
+        .filter(|p| !p.trim().is_empty())
+        .map(escape_tree_label)
+        .collect::<Vec<String>>()
+        .join("/");
+    if !path.is_empty() {
+        path.push('/');
+    }
+    path.push_str(&escape_tree_label(name));
+    path
+}
+
+// inverse of tree_path() for a folder item, back to the "A/B" form the library stores
+pub fn folder_from_path(path: &str) -> String {
+    let mut folder: String = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => folder.extend(chars.next()),
+            _ => folder.push(c),
+        }
+    }
+    folder
+}
+
+pub fn refresh_tree(tree: &mut Tree, library: &QueryLibrary) -> HashMap<String, i64> {
+    let mut index: HashMap<String, i64> = HashMap::new();
+    tree.clear();
+
+    // folders first so empty ones still show up
+    if let Ok(folders) = library.folders() {
+        for folder in &folders {
+            if let Ok(path) = library.folder_path(Some(folder.id)) {
+                let escaped: String = path.split('/').map(escape_tree_label).collect::<Vec<String>>().join("/");
+                tree.add(&escaped);
+            }
+        }
+    }
+
+    match library.queries() {
+        Ok(queries) => {
+            for query in queries {
+                let path: String = tree_path(&query.folder_path, &query.name);
+                if let Some(mut item) = tree.add(&path) {
+                    item.set_label_fgcolor(Color::DarkBlue);
+                }
+                if let Some(id) = query.id {
+                    index.insert(path, id);
+                }
+            }
+        }
+        Err(e) => println!("Unable to read the query library: {}", e.message.unwrap_or_default()),
+    }
+    tree.redraw();
+    index
+}
+
+// the tree path of the selected item, as used for the keys of refresh_tree()
+pub fn selected_path(tree: &Tree) -> Option<String> {
+    let item = tree.first_selected_item()?;
+    if item.is_root() {
+        return None;
+    }
+    tree.item_pathname(&item).ok()
+}
+
+pub fn save_dialog(initial: SavedQuery) -> Option<SavedQuery> {
+    let mut win: Window = Window::default()
+        .with_size(520, 400)
+        .with_label("Save to Query Library")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut folder: Input = Input::new(100, 10, 410, 25, "Folder");
+    folder.set_value(&initial.folder_path);
+    folder.set_tooltip("Use / for sub folders, e.g. Calibration/Monthly");
+    let mut name: Input = Input::new(100, 45, 410, 25, "Name");
+    name.set_value(&initial.name);
+    let mut description: MultilineInput = MultilineInput::new(100, 80, 410, 90, "Description");
+    description.set_value(&initial.description);
+
+    // one "name=default" line per :parameter found in the SQL
+    let mut defaults: MultilineInput = MultilineInput::new(100, 200, 410, 140, "Defaults");
+    let mut hint: Frame = Frame::new(100, 175, 410, 20, "Default values for :parameters, one name=value per line");
+    hint.set_align(Align::Inside | Align::Left);
+    hint.set_label_size(11);
+    let detected: Vec<String> = query_library::detect_parameters(&initial.sql);
+    let mut default_lines: Vec<String> = Vec::new();
+    for param in &detected {
+        let value: &str = initial
+            .params
+            .iter()
+            .find(|(n, _)| n == param)
+            .map(|(_, v)| v.as_str())
+            .unwrap_or("");
+        default_lines.push(format!("{}={}", param, value));
+    }
+    defaults.set_value(&default_lines.join("\n"));
+    if detected.is_empty() {
+        defaults.deactivate();
+    }
+
+    let mut ok: ReturnButton = ReturnButton::new(330, 360, 85, 30, "Save");
+    let mut cancel: Button = Button::new(425, 360, 85, 30, "Cancel");
+    win.end();
+    win.show();
+
+    let (sndr, rcvr) = app::channel::<bool>();
+    ok.emit(sndr, true);
+    cancel.emit(sndr, false);
+
+    let mut result: Option<SavedQuery> = None;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(true) => {
+                if name.value().trim().is_empty() {
+                    let (x, y): (i32, i32) = center();
+                    dialog::alert(x - 200, y - 100, "A saved query needs a name.");
+                    continue;
+                }
+                let params: Vec<(String, String)> = defaults
+                    .value()
+                    .lines()
+                    .filter_map(|line| {
+                        let (key, value) = line.split_once('=').unwrap_or((line, ""));
+                        let key: &str = key.trim().trim_start_matches(':');
+                        if detected.iter().any(|d| d == key) {
+                            Some((key.to_string(), value.trim().to_string()))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect();
+                result = Some(SavedQuery {
+                    id: initial.id,
+                    folder_path: folder.value().trim().trim_matches('/').to_string(),
+                    name: name.value().trim().to_string(),
+                    description: description.value(),
+                    sql: initial.sql.clone(),
+                    params,
+                });
+                win.hide();
+            }
+            Some(false) => win.hide(),
+            None => {}
+        }
+    }
+    Window::delete(win);
+    result
+}
+
+pub fn prompt_parameters(query: &SavedQuery) -> Option<HashMap<String, String>> {
+    let mut values: HashMap<String, String> = HashMap::new();
+    let (x, y): (i32, i32) = center();
+    for name in query_library::detect_parameters(&query.sql) {
+        let default: &str = query
+            .params
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+            .unwrap_or("");
+        let value: String = dialog::input(x - 200, y - 100, &format!("{}: value for :{}", query.name, name), default)?;
+        values.insert(name, value);
+    }
+    Some(values)
+}
+
+/* <-- Functions */
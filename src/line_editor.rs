@@ -0,0 +1,421 @@
+/* --> Summary of Contents
+
+	Terminal line input for the REPL (repl.rs)
+
+	LineEditor
+		read_line with cursor movement, word / line kills and a history
+		kept in a file between runs. On a unix terminal the keys are read
+		in raw mode; elsewhere (windows consoles, pipes) the line comes
+		from the console as typed, which has its own editing keys.
+
+		Left / Right, Home / End, Ctrl+A / Ctrl+E    move
+		Up / Down                                    history
+		Backspace, Delete, Ctrl+U, Ctrl+K, Ctrl+W    delete
+		Ctrl+C                                       drop the line
+		Ctrl+D on an empty line                      end of input
+		Ctrl+L                                       clear the screen
+
+	pub fn read_password(&str) -> io::Result<Option<String>>
+		A line without echo, for logins
+
+	pub fn terminal_width() -> Option<usize>
+		Columns of the terminal on stdout, $COLUMNS when it is set
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use std::{
+    env, fs,
+    io::{self, BufRead, Read, Write},
+    path::PathBuf,
+};
+
+/* <-- Imports */
+/* --> Const */
+
+const MAX_HISTORY: usize = 500;
+
+/* <-- Const */
+/* --> Enums */
+
+pub enum ReadResult {
+    Line(String),
+    Interrupted, // Ctrl+C, the caller drops what it collected so far
+    Eof,
+}
+
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    Ctrl(u8), // the letter, e.g. b'c' for Ctrl+C
+    Closed,   // the terminal went away
+    Unknown,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+pub struct LineEditor {
+    history: Vec<String>,
+    history_path: Option<PathBuf>,
+}
+
+impl LineEditor {
+    pub fn new(history_path: Option<PathBuf>) -> Self {
+        let history: Vec<String> = match &history_path {
+            Some(path) => fs::read_to_string(path)
+                .map(|text| text.lines().filter(|l| !l.trim().is_empty()).map(String::from).collect())
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        LineEditor { history, history_path }
+    }
+
+    pub fn add_history(&mut self, line: &str) {
+        let line: &str = line.trim_end();
+        if line.trim().is_empty() || self.history.last().is_some_and(|l| l == line) {
+            return;
+        }
+        self.history.push(line.to_string());
+        if self.history.len() > MAX_HISTORY {
+            self.history.drain(..self.history.len() - MAX_HISTORY);
+        }
+    }
+
+    pub fn save_history(&self) -> Result<(), String> {
+        match &self.history_path {
+            Some(path) => fs::write(path, self.history.join("\n") + "\n")
+                .map_err(|e| format!("Unable to save the history to {}: {}", path.display(), e)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<ReadResult> {
+        match terminal::RawMode::enable() {
+            Some(raw) => {
+                let result = self.edit(prompt);
+                drop(raw);
+                result
+            }
+            None => read_cooked(prompt),
+        }
+    }
+
+    fn edit(&mut self, prompt: &str) -> io::Result<ReadResult> {
+        let mut line: Vec<char> = Vec::new();
+        let mut cursor: usize = 0;
+        // history[history.len()] is the line being typed
+        let mut recall: usize = self.history.len();
+        let mut typed: Vec<char> = Vec::new();
+
+        redraw(prompt, &line, cursor)?;
+        loop {
+            match read_key()? {
+                Key::Enter => {
+                    print!("\r\n");
+                    io::stdout().flush()?;
+                    return Ok(ReadResult::Line(line.into_iter().collect()));
+                }
+                Key::Ctrl(b'c') => {
+                    print!("^C\r\n");
+                    io::stdout().flush()?;
+                    return Ok(ReadResult::Interrupted);
+                }
+                Key::Closed => return Ok(ReadResult::Eof),
+                Key::Ctrl(b'd') if line.is_empty() => {
+                    print!("\r\n");
+                    io::stdout().flush()?;
+                    return Ok(ReadResult::Eof);
+                }
+                Key::Char(c) => {
+                    line.insert(cursor, c);
+                    cursor += 1;
+                }
+                // tabs would throw off the cursor position, they are typed as spaces
+                Key::Ctrl(b'i') => {
+                    line.splice(cursor..cursor, "    ".chars());
+                    cursor += 4;
+                }
+                Key::Backspace if cursor > 0 => {
+                    cursor -= 1;
+                    line.remove(cursor);
+                }
+                Key::Delete | Key::Ctrl(b'd') if cursor < line.len() => {
+                    line.remove(cursor);
+                }
+                Key::Left | Key::Ctrl(b'b') => cursor = cursor.saturating_sub(1),
+                Key::Right | Key::Ctrl(b'f') => cursor = (cursor + 1).min(line.len()),
+                Key::Home | Key::Ctrl(b'a') => cursor = 0,
+                Key::End | Key::Ctrl(b'e') => cursor = line.len(),
+                Key::Ctrl(b'u') => {
+                    line.drain(..cursor);
+                    cursor = 0;
+                }
+                Key::Ctrl(b'k') => line.truncate(cursor),
+                Key::Ctrl(b'w') => {
+                    // the word before the cursor and the spaces after it
+                    let mut start: usize = cursor;
+                    while start > 0 && line[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    while start > 0 && !line[start - 1].is_whitespace() {
+                        start -= 1;
+                    }
+                    line.drain(start..cursor);
+                    cursor = start;
+                }
+                Key::Ctrl(b'l') => print!("\x1b[2J\x1b[H"),
+                Key::Up | Key::Ctrl(b'p') if recall > 0 => {
+                    if recall == self.history.len() {
+                        typed = line.clone();
+                    }
+                    recall -= 1;
+                    line = self.history[recall].chars().collect();
+                    cursor = line.len();
+                }
+                Key::Down | Key::Ctrl(b'n') if recall < self.history.len() => {
+                    recall += 1;
+                    line = match self.history.get(recall) {
+                        Some(entry) => entry.chars().collect(),
+                        None => typed.clone(),
+                    };
+                    cursor = line.len();
+                }
+                _ => continue,
+            }
+            redraw(prompt, &line, cursor)?;
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn redraw(prompt: &str, line: &[char], cursor: usize) -> io::Result<()> {
+    let text: String = line.iter().collect();
+    let mut out = io::stdout().lock();
+    // back to the start of the row, rewrite it, clear what is left of the old text
+    write!(out, "\r{}{}\x1b[K", prompt, text)?;
+    let back: usize = line.len() - cursor;
+    if back > 0 {
+        write!(out, "\x1b[{}D", back)?;
+    }
+    out.flush()
+}
+
+fn read_byte() -> io::Result<Option<u8>> {
+    let mut byte: [u8; 1] = [0];
+    match io::stdin().lock().read(&mut byte)? {
+        0 => Ok(None),
+        _ => Ok(Some(byte[0])),
+    }
+}
+
+fn read_key() -> io::Result<Key> {
+    let first: u8 = match read_byte()? {
+        Some(b) => b,
+        None => return Ok(Key::Closed),
+    };
+    Ok(match first {
+        b'\r' | b'\n' => Key::Enter,
+        127 | 8 => Key::Backspace,
+        0x1b => match (read_byte()?, read_byte()?) {
+            (Some(b'[') | Some(b'O'), Some(b'A')) => Key::Up,
+            (Some(b'[') | Some(b'O'), Some(b'B')) => Key::Down,
+            (Some(b'[') | Some(b'O'), Some(b'C')) => Key::Right,
+            (Some(b'[') | Some(b'O'), Some(b'D')) => Key::Left,
+            (Some(b'[') | Some(b'O'), Some(b'H')) => Key::Home,
+            (Some(b'[') | Some(b'O'), Some(b'F')) => Key::End,
+            // ESC [ n ~ forms
+            (Some(b'['), Some(digit)) if digit.is_ascii_digit() => match (digit, read_byte()?) {
+                (b'1' | b'7', Some(b'~')) => Key::Home,
+                (b'4' | b'8', Some(b'~')) => Key::End,
+                (b'3', Some(b'~')) => Key::Delete,
+                _ => Key::Unknown,
+            },
+            _ => Key::Unknown,
+        },
+        1..=26 => Key::Ctrl(first - 1 + b'a'),
+        _ if first < 0x80 => Key::Char(first as char),
+        _ => {
+            // the rest of a utf-8 sequence
+            let length: usize = match first {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => return Ok(Key::Unknown),
+            };
+            let mut bytes: Vec<u8> = vec![first];
+            for _ in 1..length {
+                match read_byte()? {
+                    Some(b) => bytes.push(b),
+                    None => break,
+                }
+            }
+            match std::str::from_utf8(&bytes).ok().and_then(|s| s.chars().next()) {
+                Some(c) => Key::Char(c),
+                None => Key::Unknown,
+            }
+        }
+    })
+}
+
+// line input when there is no raw mode, the console or the pipe does the editing
+fn read_cooked(prompt: &str) -> io::Result<ReadResult> {
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut line: String = String::new();
+    match io::stdin().lock().read_line(&mut line)? {
+        0 => Ok(ReadResult::Eof),
+        _ => Ok(ReadResult::Line(line.trim_end_matches(['\r', '\n']).to_string())),
+    }
+}
+
+pub fn read_password(prompt: &str) -> io::Result<Option<String>> {
+    let echo_off: Option<terminal::RawMode> = terminal::RawMode::without_echo();
+    print!("{}", prompt);
+    io::stdout().flush()?;
+    let mut line: String = String::new();
+    let read: usize = io::stdin().lock().read_line(&mut line)?;
+    if echo_off.is_some() {
+        println!();
+    }
+    drop(echo_off);
+    Ok((read > 0).then(|| line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+pub fn terminal_width() -> Option<usize> {
+    match env::var("COLUMNS").ok().and_then(|c| c.trim().parse::<usize>().ok()) {
+        Some(columns) if columns > 0 => Some(columns),
+        _ => terminal::width(),
+    }
+}
+
+#[cfg(unix)]
+mod terminal {
+    // termios settings are put back when the guard drops
+    pub struct RawMode {
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        fn apply(change: impl FnOnce(&mut libc::termios)) -> Option<Self> {
+            unsafe {
+                if libc::isatty(libc::STDIN_FILENO) != 1 {
+                    return None;
+                }
+                let mut original: libc::termios = std::mem::zeroed();
+                if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                    return None;
+                }
+                let mut changed: libc::termios = original;
+                change(&mut changed);
+                if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &changed) != 0 {
+                    return None;
+                }
+                Some(RawMode { original })
+            }
+        }
+
+        // byte at a time, no echo, Ctrl+C arrives as a key instead of a signal
+        pub fn enable() -> Option<Self> {
+            Self::apply(|t| {
+                t.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+                t.c_iflag &= !(libc::IXON | libc::ICRNL);
+                t.c_cc[libc::VMIN] = 1;
+                t.c_cc[libc::VTIME] = 0;
+            })
+        }
+
+        pub fn without_echo() -> Option<Self> {
+            Self::apply(|t| t.c_lflag &= !libc::ECHO)
+        }
+    }
+
+    pub fn width() -> Option<usize> {
+        unsafe {
+            let mut size: libc::winsize = std::mem::zeroed();
+            if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) != 0 || size.ws_col == 0 {
+                return None;
+            }
+            Some(usize::from(size.ws_col))
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original);
+            }
+        }
+    }
+}
+
+// the console has no raw mode the editor could use, lines are read whole. Echo can be switched off
+#[cfg(windows)]
+mod terminal {
+    use windows_sys::Win32::System::Console::{GetConsoleMode, GetStdHandle, SetConsoleMode, CONSOLE_MODE, ENABLE_ECHO_INPUT, STD_INPUT_HANDLE};
+
+    pub struct RawMode {
+        original: CONSOLE_MODE,
+    }
+
+    impl RawMode {
+        pub fn enable() -> Option<Self> {
+            None
+        }
+
+        pub fn without_echo() -> Option<Self> {
+            unsafe {
+                let input = GetStdHandle(STD_INPUT_HANDLE);
+                let mut original: CONSOLE_MODE = 0;
+                // input redirected from a file or pipe is not a console, there is no echo to turn off
+                if GetConsoleMode(input, &mut original) == 0 || SetConsoleMode(input, original & !ENABLE_ECHO_INPUT) == 0 {
+                    return None;
+                }
+                Some(RawMode { original })
+            }
+        }
+    }
+
+    pub fn width() -> Option<usize> {
+        None
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe {
+                SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), self.original);
+            }
+        }
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod terminal {
+    pub struct RawMode;
+
+    impl RawMode {
+        pub fn enable() -> Option<Self> {
+            None
+        }
+
+        pub fn without_echo() -> Option<Self> {
+            None
+        }
+    }
+
+    pub fn width() -> Option<usize> {
+        None
+    }
+}
+
+/* <-- Functions */
@@ -8,16 +8,52 @@
 /* --> imports */
 
 mod AuxFuncs;
+mod app_paths;
+mod audit_log;
+mod calibration_dashboard;
+mod capability;
+mod cli;
+mod connection_profiles;
+mod control_chart;
+mod credentials;
+mod crypto;
+mod data_transfer;
+mod date_columns;
+mod file_paths;
 mod fltk_messages;
+mod ini_file;
 mod learning_fltk;
+mod library_panel;
+mod line_editor;
 mod odbc_interface;
+mod product_lookup;
+mod profile_manager;
+mod query_library;
+mod query_plan;
+mod record_export;
+mod repl;
+mod result_diff;
+mod schema_diff;
+mod session_tab;
 mod sql_aux_funcs;
+mod sql_completion;
+mod sql_editor;
+mod sql_format;
+mod sql_lexer;
 mod sqlite3_interface;
+mod statement_guard;
+mod table_render;
+mod workspace;
 
 /* <--  imports */
 /* -->  Functions */
 
 fn main() -> Result<(), String> {
+    // any argument means headless, the window is never created (cron, no display)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
     learning_fltk::entry_point()
 }
 
@@ -3,13 +3,23 @@
 use crate::sql_aux_funcs::{Record, RecordSet, SqlData, SqlType, Translate,
     QueryType, Request};
 pub use odbc::{
-    create_environment_v3, odbc_safe::AutocommitOn, Connection, Data, DiagnosticRecord, Executed,
+    create_environment_v3, odbc_safe::{AutocommitOff, AutocommitOn}, Connection, Data, DiagnosticRecord, Executed,
     NoData, ResultSetState, Statement, Version3, Handle,
 };
-use odbc::{odbc_safe::ResultSet, ColumnDescriptor};
-use std::{collections::HashMap, io, ptr::null_mut, ptr::*};
+use odbc::{odbc_safe::ResultSet, ColumnDescriptor, GetDiagRec};
+use std::{cell::Cell, collections::HashMap, io, ptr::null_mut, ptr::*};
 
 /* <-- Imports */
+/* --> Const */
+
+const SQL_MODE_READ_ONLY: usize = 1;
+
+thread_local! {
+    // a session's connection borrows its environment, the one environment lives as long as the program
+    static ENVIRONMENT: Cell<Option<&'static odbc::Environment<odbc::odbc_safe::Odbc3>>> = const { Cell::new(None) };
+}
+
+/* <-- Const */
 /* --> Structs */
 
 /* <-- Structs */
@@ -37,6 +47,230 @@ pub fn connect(
     execute_statement(&conn, request)
 }
 
+// a read-only profile. statement_guard.rs refuses writes before they are sent, a driver
+// that will not take SQL_ATTR_ACCESS_MODE fails the request instead of running it read-write
+pub fn read_only_entry_point(
+    dsn: String,
+    request: QueryType,
+) -> Result<RecordSet, DiagnosticRecord> {
+    let environment: odbc::Environment<odbc::odbc_safe::Odbc3> = create_environment_v3().map_err(|e| e.unwrap())?;
+    let conn = environment.connect_with_connection_string(&dsn)?;
+    set_read_only(&conn)?;
+    execute_statement(&conn, request)
+}
+
+fn set_read_only(conn: &Connection<'_, AutocommitOn>) -> Result<(), DiagnosticRecord> {
+    // the odbc crate has no wrapper for SQLSetConnectAttr
+    let set = unsafe {
+        odbc::ffi::SQLSetConnectAttr(
+            conn.handle(),
+            odbc::ffi::SQL_ATTR_ACCESS_MODE,
+            SQL_MODE_READ_ONLY as odbc::ffi::SQLPOINTER,
+            0,
+        )
+    };
+    if set != odbc::ffi::SQL_SUCCESS && set != odbc::ffi::SQL_SUCCESS_WITH_INFO {
+        return Err(conn.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty));
+    }
+    Ok(())
+}
+
+fn environment() -> Result<&'static odbc::Environment<odbc::odbc_safe::Odbc3>, DiagnosticRecord> {
+    if let Some(environment) = ENVIRONMENT.with(Cell::get) {
+        return Ok(environment);
+    }
+    let environment: &'static odbc::Environment<odbc::odbc_safe::Odbc3> =
+        Box::leak(Box::new(create_environment_v3().map_err(|e| e.unwrap_or_else(DiagnosticRecord::empty))?));
+    ENVIRONMENT.with(|cell| cell.set(Some(environment)));
+    Ok(environment)
+}
+
+// one connection for a CLI script or a REPL session (cli.rs), read-only like read_only_entry_point
+pub fn open_session(dsn: &str, read_only: bool) -> Result<Connection<'static, AutocommitOn>, DiagnosticRecord> {
+    let conn: Connection<'static, AutocommitOn> = environment()?.connect_with_connection_string(dsn)?;
+    if read_only {
+        set_read_only(&conn)?;
+    }
+    // without the row counts of INSERT and UPDATE a batch's first result is its first result set.
+    // drivers other than SQL Server do not know the setting and run without it
+    if let Ok(stmt) = Statement::with_parent(&conn) {
+        if let Ok(Data(stmt)) = stmt.exec_direct("SET NOCOUNT ON") {
+            let _ = stmt.close_cursor();
+        }
+    }
+    Ok(conn)
+}
+
+pub fn session_request(conn: &Connection<'_, AutocommitOn>, request: QueryType) -> Result<RecordSet, DiagnosticRecord> {
+    match request {
+        QueryType::UserDefined(sql) => run_batch(conn, &sql),
+        QueryType::SqlFunction(_) => execute_statement(conn, request),
+    }
+}
+
+// a whole T-SQL batch, its variables live until its end. Every result is walked so an error
+// in a later statement is not lost, the last result set with columns is kept
+fn run_batch(conn: &Connection<'_, AutocommitOn>, sql: &str) -> Result<RecordSet, DiagnosticRecord> {
+    let stmt: Statement<'_, '_, odbc::Allocated, odbc::NoResult, AutocommitOn> = Statement::with_parent(conn)?;
+    let mut last: RecordSet = RecordSet::default();
+    match stmt.exec_direct(sql)? {
+        Data(mut stmt) => loop {
+            if stmt.num_result_cols()? > 0 {
+                last = read_rows(&mut stmt)?;
+            }
+            // the odbc crate has no wrapper for SQLMoreResults
+            match unsafe { odbc::ffi::SQLMoreResults(stmt.handle()) } {
+                odbc::ffi::SQL_SUCCESS | odbc::ffi::SQL_SUCCESS_WITH_INFO => continue,
+                odbc::ffi::SQL_NO_DATA => break,
+                _ => return Err(stmt.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty)),
+            }
+        },
+        // the crate cannot fetch from here, with NOCOUNT on nothing is left but the errors
+        NoData(stmt) => loop {
+            match unsafe { odbc::ffi::SQLMoreResults(stmt.handle()) } {
+                odbc::ffi::SQL_SUCCESS | odbc::ffi::SQL_SUCCESS_WITH_INFO => continue,
+                odbc::ffi::SQL_NO_DATA => break,
+                _ => return Err(stmt.get_diag_rec(1).unwrap_or_else(DiagnosticRecord::empty)),
+            }
+        },
+    }
+    Ok(last)
+}
+
+pub fn test_connection(dsn: String) -> Result<(), DiagnosticRecord> {
+    let environment: odbc::Environment<odbc::odbc_safe::Odbc3> = create_environment_v3().map_err(|e| e.unwrap())?;
+    let conn = environment.connect_with_connection_string(&dsn)?;
+    conn.disconnect()?;
+    Ok(())
+}
+
+// SQL Server only: with SHOWPLAN_TEXT on the server describes the plan instead of running the query.
+// every statement answers with two result sets, its text and then its plan. the rows come back as
+// (ResultSet, StmtText) so query_plan.rs can tell them apart
+pub fn explain(dsn: String, query: String) -> Result<RecordSet, DiagnosticRecord> {
+    let environment: odbc::Environment<odbc::odbc_safe::Odbc3> = create_environment_v3().map_err(|e| e.unwrap())?;
+    let conn = environment.connect_with_connection_string(&dsn)?;
+    let stmt: Statement<'_, '_, odbc::Allocated, odbc::NoResult, AutocommitOn> = Statement::with_parent(&conn)?;
+
+    // SET SHOWPLAN_TEXT has to be alone in its batch
+    let stmt = match stmt.exec_direct("SET SHOWPLAN_TEXT ON")? {
+        Data(stmt) => stmt.close_cursor()?,
+        NoData(stmt) => stmt,
+    };
+
+    let mut recordset: RecordSet = RecordSet {
+        column_info: HashMap::new(),
+        column_order: vec![String::from("ResultSet"), String::from("StmtText")],
+        records: Vec::new(),
+    };
+    let stmt = match stmt.exec_direct(&query)? {
+        Data(mut stmt) => {
+            let mut result_set: usize = 0;
+            loop {
+                while let Some(mut cursor) = stmt.fetch()? {
+                    let text: String = cursor.get_data::<&str>(1)?.unwrap_or("").to_string();
+                    recordset.records.push(Record {
+                        columns: HashMap::from([
+                            (String::from("ResultSet"), Some(SqlData::Odbc(result_set.to_string()))),
+                            (String::from("StmtText"), Some(SqlData::Odbc(text))),
+                        ]),
+                        data_type: Some(crate::sql_aux_funcs::ConnectionBase::Odbc),
+                    });
+                }
+                // the odbc crate has no wrapper for SQLMoreResults
+                let more = unsafe { odbc::ffi::SQLMoreResults(stmt.handle()) };
+                if more != odbc::ffi::SQL_SUCCESS && more != odbc::ffi::SQL_SUCCESS_WITH_INFO {
+                    break;
+                }
+                result_set += 1;
+            }
+            stmt.close_cursor()?
+        }
+        NoData(stmt) => stmt,
+    };
+    stmt.exec_direct("SET SHOWPLAN_TEXT OFF")?;
+    Ok(recordset)
+}
+
+// the statements in one transaction, a statement that fails is skipped and reported as (index, error).
+// values are written into the statements, the crate's bind_parameter borrows each value for the statement's life.
+// an error SQL Server rolls the transaction back for, or dooms it with, loses the whole batch and is the Err
+pub fn execute_batch(dsn: String, statements: &[String]) -> Result<Vec<(usize, String)>, DiagnosticRecord> {
+    let environment: odbc::Environment<odbc::odbc_safe::Odbc3> = create_environment_v3().map_err(|e| e.unwrap())?;
+    let conn = environment.connect_with_connection_string(&dsn)?;
+    let mut conn = match conn.disable_autocommit() {
+        Ok(conn) => conn,
+        Err(_) => return Err(DiagnosticRecord::empty()),
+    };
+    let mut failures: Vec<(usize, String)> = Vec::new();
+    for (index, sql) in statements.iter().enumerate() {
+        let stmt: Statement<'_, '_, odbc::Allocated, odbc::NoResult, AutocommitOff> = Statement::with_parent(&conn)?;
+        let error: DiagnosticRecord = match stmt.exec_direct(sql) {
+            Ok(Data(stmt)) => { stmt.close_cursor()?; continue },
+            Ok(NoData(_)) => continue,
+            Err(e) => e,
+        };
+        // later statements would start a new transaction and commit without the earlier rows
+        match transaction_state(&conn) {
+            Some(-1) => {
+                let _ = conn.rollback();
+                return Err(error);
+            }
+            Some(0) if failures.len() < index => return Err(error),
+            _ => failures.push((index, error.to_string())),
+        }
+    }
+    if let Err(e) = conn.commit() {
+        let _ = conn.rollback();
+        return Err(e);
+    }
+    Ok(failures)
+}
+
+// SELECT XACT_STATE(): 1 the transaction can commit, 0 there is none, -1 it can only roll back.
+// None from drivers other than SQL Server
+fn transaction_state(conn: &Connection<'_, AutocommitOff>) -> Option<i16> {
+    let stmt: Statement<'_, '_, odbc::Allocated, odbc::NoResult, AutocommitOff> = Statement::with_parent(conn).ok()?;
+    match stmt.exec_direct("SELECT XACT_STATE()").ok()? {
+        Data(mut stmt) => {
+            let state: Option<i16> = stmt.fetch().ok()??.get_data::<i16>(1).ok()?;
+            state
+        }
+        NoData(_) => None,
+    }
+}
+
+// the rows of the query in batches of batch_size, the first batch carries the columns even when there
+// are no rows. each answers false to stop, the query is read as it is loaded instead of all at once
+pub fn stream_rows(
+    dsn: &str,
+    read_only: bool,
+    sql: &str,
+    batch_size: usize,
+    each: &mut dyn FnMut(RecordSet) -> bool,
+) -> Result<(), DiagnosticRecord> {
+    let conn: Connection<'static, AutocommitOn> = open_session(dsn, read_only)?;
+    let stmt: Statement<'_, '_, odbc::Allocated, odbc::NoResult, AutocommitOn> = Statement::with_parent(&conn)?;
+    let mut stmt = match stmt.exec_direct(sql)? {
+        Data(stmt) => stmt,
+        NoData(_) => {
+            each(RecordSet::default());
+            return Ok(());
+        }
+    };
+    let mut columns: RecordSet = RecordSet::default();
+    columns.construct_odbc(&mut stmt)?;
+    let mut first: bool = true;
+    loop {
+        let mut batch: RecordSet = RecordSet { records: Vec::new(), ..columns.clone() };
+        let more: bool = fetch_rows(&mut stmt, &mut batch, batch_size.max(1))?;
+        if (batch.records.is_empty() && !first) || !each(batch) || !more {
+            return Ok(());
+        }
+        first = false;
+    }
+}
+
 fn execute_statement<'env>(
     conn: &Connection<'env, AutocommitOn>,
     request: QueryType,
@@ -108,42 +342,7 @@ fn resultset_to_recordset(
 
     match rss {
         Data(mut stmt) => {
-            
-            recordset.construct_odbc(&mut stmt)?;
-            let cols = stmt.num_result_cols()?;
-
-            while let Some(mut cursor) = stmt.fetch()? {
-                //.fetch() grabs another row of data. create a record here
-                let mut rec: Record = Record {
-                    columns: HashMap::new(),
-                    data_type: Some(crate::sql_aux_funcs::ConnectionBase::Odbc),
-                };
-                rec.construct(&recordset.column_info);
-
-                for i in 1..=cols {
-                    let result = cursor.get_data::<&str>(i as u16)?;
-
-                    match result {
-                        Some(val) => rec.add(
-                            recordset
-                                .column_order
-                                .get((i - 1) as usize)
-                                .unwrap()
-                                .clone(),
-                            SqlData::Odbc(String::from(val)),
-                        ),
-                        None => rec.add(
-                            recordset
-                                .column_order
-                                .get((i - 1) as usize)
-                                .unwrap()
-                                .clone(),
-                            SqlData::Odbc(String::from("Null")),
-                        ),
-                    };
-                }
-                recordset.add(rec);
-            }
+            recordset = read_rows(&mut stmt)?;
         }
         NoData(_) => { // force a null recordset here
              /*
@@ -157,6 +356,61 @@ fn resultset_to_recordset(
     Ok(recordset)
 }
 
+// the rows of the result the statement is on
+fn read_rows(stmt: &mut Statement<'_, '_, odbc::Executed, odbc::HasResult, AutocommitOn>) -> Result<RecordSet, DiagnosticRecord> {
+    let mut recordset: RecordSet = RecordSet::default();
+    recordset.construct_odbc(stmt)?;
+    fetch_rows(stmt, &mut recordset, usize::MAX)?;
+    Ok(recordset)
+}
+
+// at most limit more rows into the record set, true when the limit stopped the fetch
+fn fetch_rows(
+    stmt: &mut Statement<'_, '_, odbc::Executed, odbc::HasResult, AutocommitOn>,
+    recordset: &mut RecordSet,
+    limit: usize,
+) -> Result<bool, DiagnosticRecord> {
+    let cols = stmt.num_result_cols()?;
+
+    for _ in 0..limit {
+        //.fetch() grabs another row of data. create a record here
+        let mut cursor = match stmt.fetch()? {
+            Some(cursor) => cursor,
+            None => return Ok(false),
+        };
+        let mut rec: Record = Record {
+            columns: HashMap::new(),
+            data_type: Some(crate::sql_aux_funcs::ConnectionBase::Odbc),
+        };
+        rec.construct(&recordset.column_info);
+
+        for i in 1..=cols {
+            let result = cursor.get_data::<&str>(i as u16)?;
+
+            match result {
+                Some(val) => rec.add(
+                    recordset
+                        .column_order
+                        .get((i - 1) as usize)
+                        .unwrap()
+                        .clone(),
+                    SqlData::Odbc(String::from(val)),
+                ),
+                None => rec.add(
+                    recordset
+                        .column_order
+                        .get((i - 1) as usize)
+                        .unwrap()
+                        .clone(),
+                    SqlData::Odbc(String::from("Null")),
+                ),
+            };
+        }
+        recordset.add(rec);
+    }
+    Ok(true)
+}
+
 fn sqlcolumns(mut rs: RecordSet) -> RecordSet {
     let mut new_rs: RecordSet = RecordSet {
         column_info: HashMap::new(),
@@ -0,0 +1,299 @@
+/* --> Summary of Contents
+
+	Product lookup: everything about one product on one screen
+
+	A product is found by mrp_prod_code, sfol_prod_code or part_no. Its
+	prod_code leads to the other tables:
+		specs         every dimension with its tolerances
+		MSet_Log      the most recent measurement sets
+		cd_molds      the molds those measurement sets were run on
+		ogp_runtimes  the OGP runtime of its prod_type
+
+	pub fn load(&ConnectionBase, &str, &str) -> Result<ProductSheet, String>
+		Fails when no product matches. A related table the connection
+		does not have leaves its section empty with a note.
+
+	pub fn show_lookup(&str, &ConnectionBase, &str)
+		Window with the code input, the product row and a grid per
+		related table. Looking up another code reuses the window.
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    button::Button,
+    enums::{Align, CallbackTrigger, Color, FrameType},
+    frame::Frame,
+    group::Flex,
+    input::Input,
+    prelude::{GroupExt, InputExt, WidgetBase, WidgetExt, WindowExt},
+    window::Window,
+};
+use fltk_table::SmartTable;
+
+use crate::cli;
+use crate::credentials;
+use crate::learning_fltk::fill_table;
+use crate::schema_diff::{quote, quote_literal};
+use crate::session_tab::{escape_label, grid_opts};
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, RecordSet};
+use crate::sql_format::Dialect;
+
+/* <-- Imports */
+/* --> Const */
+
+const RECENT_LOG: usize = 25;
+
+/* <-- Const */
+/* --> Structs */
+
+#[derive(Clone, Default)]
+pub struct ProductSheet {
+    pub products: RecordSet, // every product matching the code, the first is shown
+    pub specs: RecordSet,
+    pub molds: RecordSet,
+    pub recent_log: RecordSet,
+    pub runtimes: RecordSet,
+    pub notes: Vec<String>, // sections that could not be read
+}
+
+// a title over a grid, the title carries the row count
+#[derive(Clone)]
+struct Section {
+    title: Frame,
+    grid: SmartTable,
+    name: &'static str,
+}
+
+impl ProductSheet {
+    pub fn prod_code(&self) -> String {
+        self.products.records.first().and_then(|r| r.text("mrp_prod_code")).unwrap_or_default().trim().to_string()
+    }
+
+    // "mrp_prod_code: CI020   prod_type: CRC INNER   ...", the columns as the table has them
+    pub fn product_line(&self) -> String {
+        let record = match self.products.records.first() {
+            Some(record) => record,
+            None => return String::new(),
+        };
+        let fields: Vec<String> = self
+            .products
+            .column_order
+            .iter()
+            .filter(|column| !column.eq_ignore_ascii_case("index"))
+            .map(|column| format!("{}: {}", column, record.text(column).unwrap_or_default().trim()))
+            .collect();
+        let mut line: String = fields.join("   ");
+        if self.products.records.len() > 1 {
+            let others: Vec<String> = self.products.records[1..]
+                .iter()
+                .filter_map(|r| r.text("mrp_prod_code"))
+                .map(|code| code.trim().to_string())
+                .collect();
+            line.push_str(&format!("\nAlso matching: {}", others.join(", ")));
+        }
+        line
+    }
+}
+
+impl Section {
+    fn build(parent: &mut Flex, name: &'static str) -> Section {
+        let mut title: Frame = Frame::default().with_label(name);
+        title.set_align(Align::Inside | Align::Left);
+        parent.fixed(&title, 20);
+        let grid: SmartTable = SmartTable::default().with_opts(grid_opts(0, 0));
+        Section { title, grid, name }
+    }
+
+    fn fill(&mut self, record_set: &RecordSet) {
+        self.title.set_label(&format!("{} ({})", self.name, record_set.records.len()));
+        fill_table(record_set, &mut self.grid, record_set.records.clone());
+        self.grid.redraw();
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn recent_log_sql(dialect: Dialect, prod_code: &str) -> String {
+    let (top, limit): (String, String) = match dialect {
+        Dialect::TSql => (format!("TOP {} ", RECENT_LOG), String::new()),
+        Dialect::Sqlite => (String::new(), format!(" LIMIT {}", RECENT_LOG)),
+    };
+    format!(
+        "SELECT {}* FROM {} WHERE {} = {} ORDER BY {} DESC{}",
+        top,
+        quote("MSet_Log", dialect),
+        quote("prod_code", dialect),
+        quote_literal(prod_code),
+        quote("date_time", dialect),
+        limit
+    )
+}
+
+pub fn load(connection_type: &ConnectionBase, connection: &str, key: &str) -> Result<ProductSheet, String> {
+    let dialect: Dialect = Dialect::of(connection_type);
+    let key: String = key.trim().to_uppercase();
+    if key.is_empty() {
+        return Err(String::from("Enter a product code or part number."));
+    }
+    let mut matches: Vec<String> = ["mrp_prod_code", "sfol_prod_code"]
+        .iter()
+        .map(|column| format!("UPPER({}) = {}", quote(column, dialect), quote_literal(&key)))
+        .collect();
+    if let Ok(part_no) = key.parse::<i64>() {
+        matches.push(format!("{} = {}", quote("part_no", dialect), part_no));
+    }
+    let products_sql: String = format!(
+        "SELECT * FROM {} WHERE {} ORDER BY {}",
+        quote("products", dialect),
+        matches.join(" OR "),
+        quote("mrp_prod_code", dialect)
+    );
+    let mut sheet: ProductSheet = ProductSheet {
+        products: cli::run_request(connection_type, connection, QueryType::UserDefined(products_sql))?,
+        ..Default::default()
+    };
+    if sheet.products.records.is_empty() {
+        return Err(format!("No product has the code or part number {}.", key));
+    }
+
+    let prod_code: String = sheet.prod_code();
+    let prod_type: String = sheet.products.records[0].text("prod_type").unwrap_or_default().trim().to_uppercase();
+    let queries: [(&str, String); 4] = [
+        (
+            "specs",
+            format!(
+                "SELECT * FROM {} WHERE {} = {} ORDER BY {}",
+                quote("specs", dialect),
+                quote("prod_code", dialect),
+                quote_literal(&prod_code),
+                quote("dimension", dialect)
+            ),
+        ),
+        // cd_molds only lists mold numbers, the log says which ones ran this product
+        (
+            "cd_molds",
+            format!(
+                "SELECT * FROM {} WHERE {} IN (SELECT {} FROM {} WHERE {} = {}) ORDER BY {}",
+                quote("cd_molds", dialect),
+                quote("mold_no", dialect),
+                quote("mold_id", dialect),
+                quote("MSet_Log", dialect),
+                quote("prod_code", dialect),
+                quote_literal(&prod_code),
+                quote("mold_no", dialect)
+            ),
+        ),
+        ("MSet_Log", recent_log_sql(dialect, &prod_code)),
+        // prod_type is not spelled the same way in both tables
+        (
+            "ogp_runtimes",
+            format!(
+                "SELECT * FROM {} WHERE UPPER({}) = {}",
+                quote("ogp_runtimes", dialect),
+                quote("prod_type", dialect),
+                quote_literal(&prod_type)
+            ),
+        ),
+    ];
+    for (table, sql) in queries {
+        let record_set: RecordSet = match cli::run_request(connection_type, connection, QueryType::UserDefined(sql)) {
+            Ok(record_set) => record_set,
+            Err(e) => {
+                sheet.notes.push(format!("{}: {}", table, credentials::redact(&e)));
+                RecordSet::default()
+            }
+        };
+        match table {
+            "specs" => sheet.specs = record_set,
+            "cd_molds" => sheet.molds = record_set,
+            "MSet_Log" => sheet.recent_log = record_set,
+            _ => sheet.runtimes = record_set,
+        }
+    }
+    Ok(sheet)
+}
+
+pub fn show_lookup(label: &str, connection_type: &ConnectionBase, connection: &str) {
+    let mut win: Window = Window::default()
+        .with_size(1000, 720)
+        .with_label(&format!("Product Lookup: {}", label))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let mut key_row: Flex = Flex::default().row();
+    let mut key_label: Frame = Frame::default().with_label("Product code or part no");
+    key_label.set_align(Align::Inside | Align::Right);
+    key_row.fixed(&key_label, 170);
+    let mut key_input: Input = Input::default();
+    key_row.fixed(&key_input, 200);
+    let mut lookup_butn: Button = Button::default().with_label("&Look up");
+    key_row.fixed(&lookup_butn, 90);
+    Frame::default();
+    key_row.end();
+    layout.fixed(&key_row, 30);
+
+    let mut product: Frame = Frame::default();
+    product.set_align(Align::Inside | Align::Left | Align::Top | Align::Wrap);
+    product.set_frame(FrameType::FlatBox);
+    layout.fixed(&product, 55);
+
+    // specs on the left, molds and runtime on the right, the log across the bottom
+    let mut middle: Flex = Flex::default().row();
+    let mut left: Flex = Flex::default().column();
+    let specs: Section = Section::build(&mut left, "Specs");
+    left.end();
+    let mut right: Flex = Flex::default().column();
+    let molds: Section = Section::build(&mut right, "Molds");
+    let runtimes: Section = Section::build(&mut right, "OGP runtime");
+    right.fixed(&*runtimes.grid, 70);
+    right.end();
+    middle.fixed(&right, 300);
+    middle.end();
+    let mut bottom: Flex = Flex::default().column();
+    let recent_log: Section = Section::build(&mut bottom, "Recent measurement sets");
+    bottom.end();
+    layout.fixed(&bottom, 240);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    let mut lookup = {
+        let (connection_type, connection): (ConnectionBase, String) = (connection_type.clone(), connection.to_string());
+        let key_input: Input = key_input.clone();
+        let mut sections: [Section; 4] = [specs, molds, recent_log, runtimes];
+        move || {
+            let sheet: ProductSheet = match load(&connection_type, &connection, &key_input.value()) {
+                Ok(sheet) => sheet,
+                Err(e) => {
+                    product.set_color(Color::from_rgb(255, 210, 210));
+                    product.set_label(&escape_label(&credentials::redact(&e)));
+                    ProductSheet::default()
+                }
+            };
+            if !sheet.products.records.is_empty() {
+                let mut text: String = sheet.product_line();
+                for note in &sheet.notes {
+                    text.push_str(&format!("\nNot available, {}", note));
+                }
+                product.set_color(Color::BackGround);
+                product.set_label(&escape_label(&text));
+            }
+            for (section, record_set) in sections.iter_mut().zip([&sheet.specs, &sheet.molds, &sheet.recent_log, &sheet.runtimes]) {
+                section.fill(record_set);
+            }
+        }
+    };
+    lookup_butn.set_callback({
+        let mut lookup = lookup.clone();
+        move |_| lookup()
+    });
+    key_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+    key_input.set_callback(move |_| lookup());
+    win.show();
+    let _ = key_input.take_focus();
+}
+
+/* <-- Functions */
@@ -0,0 +1,506 @@
+/* --> Summary of Contents
+
+	pub fn show_manager(&mut ProfileStore, &mut Option<CredentialStore>) -> Option<ConnectionProfile>
+		Modal dialog to create, edit, test and delete saved connection
+		profiles. Returns the profile the user chose to connect with.
+
+	pub fn unlock_store(&mut Option<CredentialStore>) -> bool
+		Ask for the master passphrase once per session, creating the
+		encrypted store on first use.
+
+	pub fn login_for(&ConnectionProfile, &mut Option<CredentialStore>) -> Result<Option<Credentials>, String>
+		Resolve the login for a profile at connect time: prompt, read the
+		encrypted store, or nothing at all.
+
+	pub fn prompt_credentials(&str, &str) -> Result<Credentials, String>
+		Ask for a user and password, e.g. for a restored quick connect
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    browser::HoldBrowser,
+    button::{Button, CheckButton},
+    dialog,
+    enums::{Align, Color, FrameType},
+    frame::Frame,
+    input::{Input, SecretInput},
+    menu::Choice,
+    prelude::{BrowserExt, GroupExt, InputExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    window::Window,
+};
+
+use crate::connection_profiles::{self, ConnectionProfile, ProfileStore, ProfileTarget};
+use crate::credentials::{self, CredentialMode, CredentialStore, Credentials};
+use crate::learning_fltk::center;
+
+/* <-- Imports */
+/* --> Enums */
+
+#[derive(Clone, Copy)]
+enum ManagerMessage {
+    Select,
+    TypeChanged,
+    New,
+    Save,
+    Test,
+    Delete,
+    Browse,
+    Connect,
+    Close,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+// widgets that make up the edit form on the right side of the dialog
+struct ProfileForm {
+    name: Input,
+    kind: Choice,
+    target: Input,
+    browse: Button,
+    login: Choice,
+    user: Input,
+    password: SecretInput,
+    read_only: CheckButton,
+    production: CheckButton,
+    status: Frame,
+}
+
+impl ProfileForm {
+    fn fill(&mut self, profile: &ConnectionProfile, store: &Option<CredentialStore>) {
+        self.name.set_value(&profile.name);
+        self.kind.set_value(match profile.target {
+            ProfileTarget::SqliteFile(_) => 0,
+            ProfileTarget::Dsn(_) => 1,
+            ProfileTarget::DriverString(_) => 2,
+        });
+        self.target.set_value(profile.target.value());
+        self.login.set_value(login_index(profile.login));
+        self.user.set_value(&profile.user);
+        self.read_only.set_checked(profile.read_only);
+        self.production.set_checked(profile.production);
+        // the password field only shows that one is stored, never the password itself
+        let stored: bool = store.as_ref().and_then(|s| s.get(&profile.name)).is_some();
+        self.password.set_value("");
+        self.password.set_tooltip(if stored {
+            "A password is saved. Leave blank to keep it."
+        } else {
+            "Saved encrypted with the master passphrase"
+        });
+        self.sync_widgets();
+        self.set_status("", Color::Black);
+    }
+
+    fn clear(&mut self) {
+        self.name.set_value("");
+        self.kind.set_value(0);
+        self.target.set_value("");
+        self.login.set_value(0);
+        self.user.set_value("");
+        self.password.set_value("");
+        self.read_only.set_checked(false);
+        self.production.set_checked(false);
+        self.sync_widgets();
+        self.set_status("", Color::Black);
+    }
+
+    fn read(&self) -> ConnectionProfile {
+        let value: String = self.target.value().trim().to_string();
+        let target: ProfileTarget = match self.kind.value() {
+            1 => ProfileTarget::Dsn(value),
+            2 => ProfileTarget::DriverString(value),
+            _ => ProfileTarget::SqliteFile(value),
+        };
+        let login: CredentialMode = match target {
+            ProfileTarget::SqliteFile(_) => CredentialMode::None,
+            _ => match self.login.value() {
+                1 => CredentialMode::Prompt,
+                2 => CredentialMode::Stored,
+                _ => CredentialMode::None,
+            },
+        };
+        ConnectionProfile {
+            name: self.name.value().trim().to_string(),
+            target,
+            login,
+            user: self.user.value().trim().to_string(),
+            read_only: self.read_only.is_checked(),
+            production: self.production.is_checked(),
+        }
+    }
+
+    // a password typed straight into the connection string is lifted into the login fields
+    fn lift_credentials(&mut self) {
+        if self.kind.value() != 2 {
+            return;
+        }
+        if let (stripped, Some(creds)) = credentials::split_credentials(&self.target.value()) {
+            self.target.set_value(&stripped);
+            if !creds.user.is_empty() {
+                self.user.set_value(&creds.user);
+            }
+            if !creds.password.is_empty() {
+                self.password.set_value(&creds.password);
+                self.login.set_value(login_index(CredentialMode::Stored));
+            } else if self.login.value() == 0 {
+                self.login.set_value(login_index(CredentialMode::Prompt));
+            }
+            self.sync_widgets();
+        }
+    }
+
+    // the file browser only makes sense for sqlite profiles, logins only for odbc
+    fn sync_widgets(&mut self) {
+        if self.kind.value() == 0 {
+            self.browse.activate();
+            self.login.deactivate();
+            self.user.deactivate();
+            self.password.deactivate();
+        } else {
+            self.browse.deactivate();
+            self.login.activate();
+            match self.login.value() {
+                0 => {
+                    self.user.deactivate();
+                    self.password.deactivate();
+                }
+                1 => {
+                    self.user.activate();
+                    self.password.deactivate();
+                }
+                _ => {
+                    self.user.activate();
+                    self.password.activate();
+                }
+            }
+        }
+    }
+
+    fn set_status(&mut self, text: &str, color: Color) {
+        self.status.set_label_color(color);
+        self.status.set_label(text);
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn show_manager(store: &mut ProfileStore, cred_store: &mut Option<CredentialStore>) -> Option<ConnectionProfile> {
+    let (sndr, rcvr): (Sender<ManagerMessage>, Receiver<ManagerMessage>) = channel::<ManagerMessage>();
+
+    let mut win: Window = Window::default()
+        .with_size(600, 400)
+        .with_label("Connection Manager")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut list: HoldBrowser = HoldBrowser::new(10, 10, 200, 380, None);
+    list.emit(sndr, ManagerMessage::Select);
+
+    let mut form: ProfileForm = ProfileForm {
+        name: Input::new(300, 10, 290, 25, "Name"),
+        kind: Choice::new(300, 45, 290, 25, "Type"),
+        target: Input::new(300, 80, 235, 25, "Target"),
+        browse: Button::new(540, 80, 50, 25, "..."),
+        login: Choice::new(300, 115, 290, 25, "Login"),
+        user: Input::new(300, 150, 290, 25, "User"),
+        password: SecretInput::new(300, 185, 290, 25, "Password"),
+        read_only: CheckButton::new(300, 215, 110, 25, "Read only"),
+        production: CheckButton::new(420, 215, 110, 25, "Production"),
+        status: Frame::new(220, 245, 370, 105, None),
+    };
+    form.kind.add_choice("SQLite file|ODBC DSN|ODBC connection string");
+    form.kind.set_value(0);
+    form.kind.emit(sndr, ManagerMessage::TypeChanged);
+    form.target.set_tooltip("SQLite: path to the .db file\nDSN: data source name\nConnection string: Driver={...};Server=...;Database=...;\nLeave UID/PWD out, use the login fields below.");
+    form.browse.emit(sndr, ManagerMessage::Browse);
+    form.login.add_choice("None / integrated|Prompt at connect|Saved (encrypted)");
+    form.login.set_value(0);
+    form.login.emit(sndr, ManagerMessage::TypeChanged);
+    form.read_only.set_tooltip("Open the connection read-only and refuse statements that write");
+    form.production.set_tooltip("Ask before a DELETE without WHERE");
+    form.status.set_frame(FrameType::ThinDownBox);
+    form.status.set_align(Align::Inside | Align::TopLeft | Align::Wrap);
+    form.sync_widgets();
+
+    let buttons: [(&str, ManagerMessage); 6] = [
+        ("&New", ManagerMessage::New),
+        ("&Save", ManagerMessage::Save),
+        ("&Test", ManagerMessage::Test),
+        ("&Delete", ManagerMessage::Delete),
+        ("&Connect", ManagerMessage::Connect),
+        ("Close", ManagerMessage::Close),
+    ];
+    for (index, (label, message)) in buttons.iter().enumerate() {
+        let mut butn: Button = Button::new(220 + (index as i32 * 62), 360, 58, 30, None).with_label(label);
+        butn.emit(sndr, *message);
+    }
+
+    win.end();
+    win.show();
+
+    refresh_list(&mut list, store, None);
+    // name of the profile loaded into the form, None while creating a new one
+    let mut editing: Option<String> = None;
+    let mut chosen: Option<ConnectionProfile> = None;
+
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(ManagerMessage::Select) => {
+                if let Some(name) = list.selected_text() {
+                    if let Some(profile) = store.get(&name) {
+                        form.fill(profile, cred_store);
+                        editing = Some(name);
+                    }
+                }
+            }
+            Some(ManagerMessage::TypeChanged) => form.sync_widgets(),
+            Some(ManagerMessage::New) => {
+                refresh_list(&mut list, store, None);
+                form.clear();
+                editing = None;
+            }
+            Some(ManagerMessage::Save) => {
+                form.lift_credentials();
+                match save_profile(store, cred_store, editing.as_deref(), &form) {
+                    Ok(name) => {
+                        refresh_list(&mut list, store, Some(&name));
+                        form.password.set_value("");
+                        form.set_status("Saved.", Color::DarkGreen);
+                        editing = Some(name);
+                    }
+                    Err(e) => form.set_status(&e, Color::DarkRed),
+                }
+            }
+            Some(ManagerMessage::Test) => {
+                form.lift_credentials();
+                let profile: ConnectionProfile = form.read();
+                let login: Result<Option<Credentials>, String> = if form.password.value().is_empty() {
+                    login_for(&profile, cred_store)
+                } else {
+                    Ok(Some(Credentials {
+                        user: form.user.value().trim().to_string(),
+                        password: form.password.value(),
+                    }))
+                };
+                match login {
+                    Ok(creds) => {
+                        form.set_status("Testing...", Color::Black);
+                        app::flush();
+                        match connection_profiles::test_profile(&profile, connection_string_for(&profile, creds.as_ref())) {
+                            Ok(_) => form.set_status("Connection succeeded.", Color::DarkGreen),
+                            Err(e) => form.set_status(&format!("Connection failed.\n{}", e), Color::DarkRed),
+                        }
+                    }
+                    Err(e) => form.set_status(&e, Color::DarkRed),
+                }
+            }
+            Some(ManagerMessage::Delete) => {
+                if let Some(name) = editing.take() {
+                    let (x, y): (i32, i32) = center();
+                    let confirm = dialog::choice2(x - 200, y - 100, &format!("Delete profile \"{}\"?", name), "Cancel", "Delete", "");
+                    if confirm == Some(1) {
+                        store.remove(&name);
+                        let mut result: Result<(), String> = store.save();
+                        if let Some(creds) = cred_store.as_mut() {
+                            creds.remove(&name);
+                            result = result.and(creds.save());
+                        }
+                        match result {
+                            Ok(_) => form.set_status("Deleted.", Color::Black),
+                            Err(e) => form.set_status(&e, Color::DarkRed),
+                        }
+                        refresh_list(&mut list, store, None);
+                        form.clear();
+                    } else {
+                        editing = Some(name);
+                    }
+                }
+            }
+            Some(ManagerMessage::Browse) => {
+                if let Some(path) = dialog::file_chooser("Select a DB", "*.{db,sqlite,sqlite3}", ".", false) {
+                    form.target.set_value(&path);
+                    if form.name.value().trim().is_empty() {
+                        let stem: String = std::path::Path::new(&path)
+                            .file_stem()
+                            .map(|s| s.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                        form.name.set_value(&stem);
+                    }
+                }
+            }
+            Some(ManagerMessage::Connect) => {
+                form.lift_credentials();
+                // connecting with unsaved edits keeps them, the same as pressing Save first
+                match save_profile(store, cred_store, editing.as_deref(), &form) {
+                    Ok(_) => {
+                        chosen = Some(form.read());
+                        win.hide();
+                    }
+                    Err(e) => form.set_status(&e, Color::DarkRed),
+                }
+            }
+            Some(ManagerMessage::Close) => win.hide(),
+            None => {}
+        }
+    }
+
+    Window::delete(win);
+    chosen
+}
+
+fn save_profile(
+    store: &mut ProfileStore,
+    cred_store: &mut Option<CredentialStore>,
+    editing: Option<&str>,
+    form: &ProfileForm,
+) -> Result<String, String> {
+    let profile: ConnectionProfile = form.read();
+    connection_profiles::validate_profile(&profile)?;
+    let renamed_onto_other: bool = editing != Some(profile.name.as_str()) && store.get(&profile.name).is_some();
+    if renamed_onto_other {
+        return Err(format!("A profile named \"{}\" already exists.", profile.name));
+    }
+
+    // keep the encrypted store in step with the profile
+    let password: String = form.password.value();
+    let needs_store: bool = (profile.login == CredentialMode::Stored && !password.is_empty())
+        || (editing.is_some() && CredentialStore::exists());
+    if needs_store && unlock_store(cred_store) {
+        let creds: &mut CredentialStore = cred_store.as_mut().unwrap();
+        if let Some(old_name) = editing {
+            creds.rename(old_name, &profile.name);
+        }
+        if profile.login == CredentialMode::Stored {
+            if !password.is_empty() {
+                creds.set(&profile.name, Credentials {
+                    user: profile.user.clone(),
+                    password,
+                });
+            } else if let Some(existing) = creds.get(&profile.name).cloned() {
+                // user name edits still apply to a previously saved password
+                creds.set(&profile.name, Credentials {
+                    user: profile.user.clone(),
+                    password: existing.password,
+                });
+            }
+        } else {
+            creds.remove(&profile.name);
+        }
+        creds.save()?;
+    } else if profile.login == CredentialMode::Stored && !password.is_empty() {
+        return Err(String::from("The password was not saved, the credential store is locked."));
+    }
+
+    let name: String = profile.name.clone();
+    store.upsert(editing, profile);
+    store.save()?;
+    Ok(name)
+}
+
+fn refresh_list(list: &mut HoldBrowser, store: &ProfileStore, select: Option<&str>) {
+    list.clear();
+    for profile in &store.profiles {
+        list.add(&profile.name);
+        if Some(profile.name.as_str()) == select {
+            list.select(list.size());
+        }
+    }
+}
+
+fn login_index(mode: CredentialMode) -> i32 {
+    match mode {
+        CredentialMode::None => 0,
+        CredentialMode::Prompt => 1,
+        CredentialMode::Stored => 2,
+    }
+}
+
+pub fn unlock_store(cred_store: &mut Option<CredentialStore>) -> bool {
+    if cred_store.is_some() {
+        return true;
+    }
+    let (x, y): (i32, i32) = center();
+
+    if !CredentialStore::exists() {
+        let first = match dialog::password(x - 200, y - 100, "Choose a master passphrase for saved passwords", "") {
+            Some(p) if !p.is_empty() => p,
+            _ => return false,
+        };
+        let second: String = dialog::password(x - 200, y - 100, "Enter the master passphrase again", "").unwrap_or_default();
+        if first != second {
+            dialog::alert(x - 200, y - 100, "The passphrases did not match.");
+            return false;
+        }
+        return match CredentialStore::unlock(&first) {
+            Ok(store) => {
+                *cred_store = Some(store);
+                true
+            }
+            Err(e) => {
+                dialog::alert(x - 200, y - 100, &e);
+                false
+            }
+        };
+    }
+
+    for _ in 0..3 {
+        let passphrase: String = match dialog::password(x - 200, y - 100, "Master passphrase", "") {
+            Some(p) => p,
+            None => return false,
+        };
+        match CredentialStore::unlock(&passphrase) {
+            Ok(store) => {
+                *cred_store = Some(store);
+                return true;
+            }
+            Err(e) => dialog::alert(x - 200, y - 100, &e),
+        }
+    }
+    false
+}
+
+pub fn login_for(profile: &ConnectionProfile, cred_store: &mut Option<CredentialStore>) -> Result<Option<Credentials>, String> {
+    match profile.login {
+        CredentialMode::None => Ok(None),
+        CredentialMode::Stored => {
+            if unlock_store(cred_store) {
+                if let Some(creds) = cred_store.as_ref().unwrap().get(&profile.name) {
+                    return Ok(Some(creds.clone()));
+                }
+            }
+            // locked or nothing saved yet, fall back to asking
+            prompt_login(profile)
+        }
+        CredentialMode::Prompt => prompt_login(profile),
+    }
+}
+
+fn prompt_login(profile: &ConnectionProfile) -> Result<Option<Credentials>, String> {
+    prompt_credentials(&profile.name, &profile.user).map(Some)
+}
+
+pub fn prompt_credentials(target: &str, user: &str) -> Result<Credentials, String> {
+    let (x, y): (i32, i32) = center();
+    let user: String = match dialog::input(x - 200, y - 100, &format!("User for {}", target), user) {
+        Some(user) => user,
+        None => return Err(String::from("Login cancelled.")),
+    };
+    let password: String = match dialog::password(x - 200, y - 100, &format!("Password for {}@{}", user, target), "") {
+        Some(password) => password,
+        None => return Err(String::from("Login cancelled.")),
+    };
+    Ok(Credentials { user, password })
+}
+
+pub fn connection_string_for(profile: &ConnectionProfile, creds: Option<&Credentials>) -> String {
+    match creds {
+        Some(creds) => credentials::apply_credentials(&profile.connection_string(), creds),
+        None => profile.connection_string(),
+    }
+}
+
+/* <-- Functions */
@@ -0,0 +1,508 @@
+/* --> Summary of Contents
+
+	QueryLibrary
+		Saved queries kept in a local SQLite file (query_library.db in
+		the config directory). Queries live in folders, carry a
+		description and default values for their :parameters.
+
+	QueryLibrary::export(path) / QueryLibrary::import(path)
+		The whole library as a single .db file, for sharing between
+		analysts. Import merges by folder path and query name. Export
+		refuses the open library's own file and replaces a target only
+		once the copy is complete.
+
+	pub fn detect_parameters(&str) -> Vec<String>
+		:name placeholders in the order they first appear
+
+	pub fn substitute_parameters(&str, &HashMap<String, String>) -> String
+		Replace :name placeholders with SQL literals. Done as text so the
+		same query runs on SQLite and ODBC alike.
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use crate::app_paths;
+use crate::schema_diff::quote_literal;
+use crate::sql_lexer::{self, TokenKind};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/* <-- Imports */
+/* --> Const */
+
+const LIBRARY_FILE: &str = "query_library.db";
+
+const LIBRARY_SCHEMA: &str = "
+    PRAGMA foreign_keys = ON;
+    CREATE TABLE IF NOT EXISTS folders (
+        id INTEGER PRIMARY KEY,
+        parent_id INTEGER REFERENCES folders(id) ON DELETE CASCADE,
+        name TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS queries (
+        id INTEGER PRIMARY KEY,
+        folder_id INTEGER REFERENCES folders(id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        description TEXT NOT NULL DEFAULT '',
+        sql TEXT NOT NULL,
+        updated TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE TABLE IF NOT EXISTS query_params (
+        query_id INTEGER NOT NULL REFERENCES queries(id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        default_value TEXT NOT NULL DEFAULT '',
+        PRIMARY KEY (query_id, name)
+    );";
+
+/* <-- Const */
+/* --> Structs */
+
+#[derive(Clone)]
+pub struct Folder {
+    pub id: i64,
+    pub parent_id: Option<i64>,
+    pub name: String,
+}
+
+#[derive(Clone, Default)]
+pub struct SavedQuery {
+    pub id: Option<i64>, // None until first saved
+    pub folder_path: String, // "Calibration/Monthly", empty for the library root
+    pub name: String,
+    pub description: String,
+    pub sql: String,
+    pub params: Vec<(String, String)>, // (name, default value)
+}
+
+pub struct QueryLibrary {
+    db: sqlite::Connection,
+    path: PathBuf,
+}
+
+impl QueryLibrary {
+    pub fn open_default() -> Result<Self, sqlite::Error> {
+        Self::open(&app_paths::config_file(LIBRARY_FILE))
+    }
+
+    pub fn open(path: &Path) -> Result<Self, sqlite::Error> {
+        let db: sqlite::Connection = sqlite::open(path)?;
+        db.execute(LIBRARY_SCHEMA)?;
+        Ok(QueryLibrary { db, path: path.to_path_buf() })
+    }
+
+    // someone else's library: never created, never changed
+    fn open_read_only(path: &Path) -> Result<Self, sqlite::Error> {
+        let db: sqlite::Connection = sqlite::Connection::open_with_flags(path, sqlite::OpenFlags::new().set_read_only())?;
+        let mut stmt = db.prepare(
+            "select count(*) as tables from sqlite_master where type = 'table' and name in ('folders', 'queries', 'query_params');",
+        )?;
+        stmt.next()?;
+        if stmt.read::<i64, _>("tables")? != 3 {
+            return Err(sqlite::Error {
+                code: None,
+                message: Some(format!("{} is not a query library", path.display())),
+            });
+        }
+        drop(stmt);
+        Ok(QueryLibrary { db, path: path.to_path_buf() })
+    }
+
+    pub fn folders(&self) -> Result<Vec<Folder>, sqlite::Error> {
+        let mut stmt = self.db.prepare("select id, parent_id, name from folders order by name collate nocase;")?;
+        let mut folders: Vec<Folder> = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            folders.push(Folder {
+                id: stmt.read::<i64, _>("id")?,
+                parent_id: stmt.read::<Option<i64>, _>("parent_id")?,
+                name: stmt.read::<String, _>("name")?,
+            });
+        }
+        Ok(folders)
+    }
+
+    // "/" joined names from the root down to `folder_id`
+    pub fn folder_path(&self, folder_id: Option<i64>) -> Result<String, sqlite::Error> {
+        let folders: Vec<Folder> = self.folders()?;
+        let mut parts: Vec<String> = Vec::new();
+        let mut current: Option<i64> = folder_id;
+        while let Some(id) = current {
+            match folders.iter().find(|f| f.id == id) {
+                Some(folder) => {
+                    parts.push(folder.name.clone());
+                    current = folder.parent_id;
+                }
+                None => break,
+            }
+        }
+        parts.reverse();
+        Ok(parts.join("/"))
+    }
+
+    // id of the folder at `path`, None when any folder along it is missing
+    fn find_folder(&self, path: &str) -> Result<Option<i64>, sqlite::Error> {
+        let mut parent: Option<i64> = None;
+        for name in path.split('/').map(str::trim).filter(|n| !n.is_empty()) {
+            let mut find = self.db.prepare(
+                "select id from folders where name = :name and parent_id is :parent;",
+            )?;
+            find.bind((":name", name))?;
+            find.bind((":parent", parent))?;
+            parent = match find.next()? {
+                sqlite::State::Row => Some(find.read::<i64, _>("id")?),
+                sqlite::State::Done => return Ok(None),
+            };
+        }
+        Ok(parent)
+    }
+
+    // find or create every folder along `path`, returns the id of the last one
+    pub fn ensure_folder(&self, path: &str) -> Result<Option<i64>, sqlite::Error> {
+        let mut parent: Option<i64> = None;
+        for name in path.split('/').map(str::trim).filter(|n| !n.is_empty()) {
+            let mut find = self.db.prepare(
+                "select id from folders where name = :name and parent_id is :parent;",
+            )?;
+            find.bind((":name", name))?;
+            find.bind((":parent", parent))?;
+            parent = match find.next()? {
+                sqlite::State::Row => Some(find.read::<i64, _>("id")?),
+                sqlite::State::Done => {
+                    let mut insert = self.db.prepare("insert into folders (parent_id, name) values (:parent, :name);")?;
+                    insert.bind((":parent", parent))?;
+                    insert.bind((":name", name))?;
+                    insert.next()?;
+                    Some(self.last_insert_id()?)
+                }
+            };
+        }
+        Ok(parent)
+    }
+
+    pub fn queries(&self) -> Result<Vec<SavedQuery>, sqlite::Error> {
+        let mut stmt = self.db.prepare(
+            "select id, folder_id, name, description, sql from queries order by name collate nocase;",
+        )?;
+        let mut rows: Vec<(i64, Option<i64>, String, String, String)> = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            rows.push((
+                stmt.read::<i64, _>("id")?,
+                stmt.read::<Option<i64>, _>("folder_id")?,
+                stmt.read::<String, _>("name")?,
+                stmt.read::<String, _>("description")?,
+                stmt.read::<String, _>("sql")?,
+            ));
+        }
+
+        let mut queries: Vec<SavedQuery> = Vec::with_capacity(rows.len());
+        for (id, folder_id, name, description, sql) in rows {
+            queries.push(SavedQuery {
+                id: Some(id),
+                folder_path: self.folder_path(folder_id)?,
+                name,
+                description,
+                sql,
+                params: self.params(id)?,
+            });
+        }
+        Ok(queries)
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<SavedQuery>, sqlite::Error> {
+        Ok(self.queries()?.into_iter().find(|q| q.id == Some(id)))
+    }
+
+    fn params(&self, query_id: i64) -> Result<Vec<(String, String)>, sqlite::Error> {
+        let mut stmt = self.db.prepare(
+            "select name, default_value from query_params where query_id = :id order by rowid;",
+        )?;
+        stmt.bind((":id", query_id))?;
+        let mut params: Vec<(String, String)> = Vec::new();
+        while let sqlite::State::Row = stmt.next()? {
+            params.push((stmt.read::<String, _>("name")?, stmt.read::<String, _>("default_value")?));
+        }
+        Ok(params)
+    }
+
+    // insert, or update in place when query.id is set. returns the query id
+    pub fn save(&self, query: &SavedQuery) -> Result<i64, sqlite::Error> {
+        self.db.execute("begin transaction;")?;
+        let result: Result<i64, sqlite::Error> = self.save_inner(query);
+        match result {
+            Ok(_) => self.db.execute("commit;")?,
+            Err(_) => self.db.execute("rollback;")?,
+        }
+        result
+    }
+
+    fn save_inner(&self, query: &SavedQuery) -> Result<i64, sqlite::Error> {
+        let folder_id: Option<i64> = self.ensure_folder(&query.folder_path)?;
+        let id: i64 = match query.id {
+            Some(id) => {
+                let mut stmt = self.db.prepare(
+                    "update queries set folder_id = :folder, name = :name, description = :description,
+                     sql = :sql, updated = CURRENT_TIMESTAMP where id = :id;",
+                )?;
+                stmt.bind((":folder", folder_id))?;
+                stmt.bind((":name", query.name.as_str()))?;
+                stmt.bind((":description", query.description.as_str()))?;
+                stmt.bind((":sql", query.sql.as_str()))?;
+                stmt.bind((":id", id))?;
+                stmt.next()?;
+                id
+            }
+            None => {
+                let mut stmt = self.db.prepare(
+                    "insert into queries (folder_id, name, description, sql) values (:folder, :name, :description, :sql);",
+                )?;
+                stmt.bind((":folder", folder_id))?;
+                stmt.bind((":name", query.name.as_str()))?;
+                stmt.bind((":description", query.description.as_str()))?;
+                stmt.bind((":sql", query.sql.as_str()))?;
+                stmt.next()?;
+                self.last_insert_id()?
+            }
+        };
+
+        let mut clear = self.db.prepare("delete from query_params where query_id = :id;")?;
+        clear.bind((":id", id))?;
+        clear.next()?;
+        for (name, default_value) in &query.params {
+            let mut stmt = self.db.prepare(
+                "insert or replace into query_params (query_id, name, default_value) values (:id, :name, :value);",
+            )?;
+            stmt.bind((":id", id))?;
+            stmt.bind((":name", name.as_str()))?;
+            stmt.bind((":value", default_value.as_str()))?;
+            stmt.next()?;
+        }
+        Ok(id)
+    }
+
+    pub fn delete_query(&self, id: i64) -> Result<(), sqlite::Error> {
+        let mut stmt = self.db.prepare("delete from queries where id = :id;")?;
+        stmt.bind((":id", id))?;
+        stmt.next()?;
+        Ok(())
+    }
+
+    // removes the folder, its sub folders and every query inside them
+    pub fn delete_folder(&self, path: &str) -> Result<(), sqlite::Error> {
+        if let Some(id) = self.find_folder(path)? {
+            let mut stmt = self.db.prepare("delete from folders where id = :id;")?;
+            stmt.bind((":id", id))?;
+            stmt.next()?;
+        }
+        Ok(())
+    }
+
+    pub fn export(&self, path: &Path) -> Result<(), sqlite::Error> {
+        let failed = |message: String| sqlite::Error { code: None, message: Some(message) };
+        if let (Ok(target), Ok(library)) = (path.canonicalize(), self.path.canonicalize()) {
+            if target == library {
+                return Err(failed(format!("{} is the open query library, export to another file", path.display())));
+            }
+        }
+        // VACUUM INTO refuses to overwrite. The copy goes next to the target and is renamed over it,
+        // a failed export leaves the old file as it was
+        let file_name: String = path.file_name().map_or(String::from("library"), |n| n.to_string_lossy().to_string());
+        let partial: PathBuf = path.with_file_name(format!(".{}.partial", file_name));
+        let _ = fs::remove_file(&partial);
+        let copied: Result<(), sqlite::Error> = self.db.prepare("vacuum into :path;").and_then(|mut stmt| {
+            stmt.bind((":path", partial.to_string_lossy().as_ref()))?;
+            stmt.next().map(|_| ())
+        });
+        let replaced: Result<(), sqlite::Error> =
+            copied.and_then(|_| fs::rename(&partial, path).map_err(|e| failed(format!("Unable to replace {}: {}", path.display(), e))));
+        if replaced.is_err() {
+            let _ = fs::remove_file(&partial);
+        }
+        replaced
+    }
+
+    // merge another library file into this one. queries with the same folder path and
+    // name are replaced, everything else is added. returns the number of queries imported
+    pub fn import(&self, path: &Path) -> Result<usize, sqlite::Error> {
+        let other: QueryLibrary = QueryLibrary::open_read_only(path)?;
+        let incoming: Vec<SavedQuery> = other.queries()?;
+        let existing: Vec<SavedQuery> = self.queries()?;
+
+        for query in &incoming {
+            let mut merged: SavedQuery = query.clone();
+            merged.id = existing
+                .iter()
+                .find(|q| q.folder_path == query.folder_path && q.name == query.name)
+                .and_then(|q| q.id);
+            self.save(&merged)?;
+        }
+        Ok(incoming.len())
+    }
+
+    fn last_insert_id(&self) -> Result<i64, sqlite::Error> {
+        let mut stmt = self.db.prepare("select last_insert_rowid() as id;")?;
+        stmt.next()?;
+        stmt.read::<i64, _>("id")
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn detect_parameters(sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for token in sql_lexer::tokenize(sql) {
+        let text: &str = token.text(sql);
+        if token.kind == TokenKind::Parameter && text.starts_with(':') {
+            let name: String = text[1..].to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+// numbers and NULL go in bare, anything else becomes a quoted string
+pub fn sql_literal(value: &str) -> String {
+    let trimmed: &str = value.trim();
+    if trimmed.eq_ignore_ascii_case("null") {
+        return String::from("NULL");
+    }
+    // part numbers like 007 keep their leading zeros, so they stay text
+    let digits: &str = trimmed.strip_prefix('-').unwrap_or(trimmed);
+    let leading_zero: bool = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+    let numeric: bool = sql_lexer::is_number_literal(trimmed) && !leading_zero;
+    if numeric {
+        trimmed.to_string()
+    } else {
+        quote_literal(value)
+    }
+}
+
+pub fn substitute_parameters(sql: &str, values: &HashMap<String, String>) -> String {
+    let mut out: String = String::with_capacity(sql.len());
+    for token in sql_lexer::tokenize(sql) {
+        let text: &str = token.text(sql);
+        match (token.kind, text.strip_prefix(':')) {
+            (TokenKind::Parameter, Some(name)) if values.contains_key(name) => {
+                out.push_str(&sql_literal(&values[name]));
+            }
+            _ => out.push_str(text),
+        }
+    }
+    out
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path: PathBuf = std::env::temp_dir().join(format!("dv_{}_{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn query(folder_path: &str, name: &str) -> SavedQuery {
+        SavedQuery {
+            folder_path: folder_path.to_string(),
+            name: name.to_string(),
+            sql: String::from("select 1"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(sql_literal("42"), "42");
+        assert_eq!(sql_literal(" -1.5e3 "), "-1.5e3");
+        assert_eq!(sql_literal("null"), "NULL");
+        assert_eq!(sql_literal("007"), "'007'");
+        assert_eq!(sql_literal("O'Brien"), "'O''Brien'");
+        for text in ["inf", "NaN", "infinity", "1e5000", "+5"] {
+            assert_eq!(sql_literal(text), format!("'{}'", text));
+        }
+    }
+
+    #[test]
+    fn parameters_are_found_and_substituted() {
+        let sql: &str = "select * from specs where prod_code = :code and dimension = :dim or prod_code = :code and x = ':code'";
+        assert_eq!(detect_parameters(sql), vec!["code", "dim"]);
+        let values: HashMap<String, String> =
+            [("code", "CI020"), ("dim", "1")].iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        assert_eq!(
+            substitute_parameters(sql, &values),
+            "select * from specs where prod_code = 'CI020' and dimension = 1 or prod_code = 'CI020' and x = ':code'"
+        );
+    }
+
+    #[test]
+    fn deleting_a_missing_folder_creates_nothing() {
+        let path: PathBuf = temp_file("delete_folder");
+        let library: QueryLibrary = QueryLibrary::open(&path).unwrap();
+        library.save(&query("Calibration/Monthly", "due")).unwrap();
+        library.delete_folder("Audit/2026").unwrap();
+        assert_eq!(library.folders().unwrap().len(), 2);
+        library.delete_folder("Calibration").unwrap();
+        assert!(library.folders().unwrap().is_empty());
+        assert!(library.queries().unwrap().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn import_leaves_the_other_file_alone() {
+        let mine: PathBuf = temp_file("import_mine");
+        let theirs: PathBuf = temp_file("import_theirs");
+        let library: QueryLibrary = QueryLibrary::open(&mine).unwrap();
+        // a path that does not exist is not created
+        assert!(library.import(&theirs).is_err());
+        assert!(!theirs.exists());
+        // nor is a database without the library tables changed
+        sqlite::open(&theirs).unwrap().execute("create table other (a);").unwrap();
+        assert!(library.import(&theirs).is_err());
+        std::fs::remove_file(&theirs).unwrap();
+
+        let other: QueryLibrary = QueryLibrary::open(&theirs).unwrap();
+        other.save(&query("Shared", "scrap rate")).unwrap();
+        drop(other);
+        let modified = std::fs::metadata(&theirs).unwrap().modified().unwrap();
+        assert_eq!(library.import(&theirs).unwrap(), 1);
+        assert_eq!(library.queries().unwrap()[0].folder_path, "Shared");
+        assert_eq!(std::fs::metadata(&theirs).unwrap().modified().unwrap(), modified);
+        let _ = std::fs::remove_file(&mine);
+        let _ = std::fs::remove_file(&theirs);
+    }
+
+    #[test]
+    fn export_replaces_a_copy_but_never_the_open_library() {
+        let mine: PathBuf = temp_file("export_mine");
+        let copy: PathBuf = temp_file("export_copy");
+        let library: QueryLibrary = QueryLibrary::open(&mine).unwrap();
+        library.save(&query("Shared", "scrap rate")).unwrap();
+
+        // the library itself, also when named another way
+        let same: PathBuf = mine.parent().unwrap().join(".").join(mine.file_name().unwrap());
+        assert!(library.export(&same).is_err());
+        assert_eq!(library.queries().unwrap().len(), 1);
+
+        std::fs::write(&copy, b"an older export").unwrap();
+        library.export(&copy).unwrap();
+        library.save(&query("Shared", "yield")).unwrap();
+        library.export(&copy).unwrap();
+        assert_eq!(QueryLibrary::open_read_only(&copy).unwrap().queries().unwrap().len(), 2);
+        assert!(!copy.with_file_name(format!(".{}.partial", copy.file_name().unwrap().to_string_lossy())).exists());
+
+        // a folder that does not exist fails without touching anything
+        assert!(library.export(&mine.with_file_name("no_such_dir").join("x.db")).is_err());
+        let _ = std::fs::remove_file(&mine);
+        let _ = std::fs::remove_file(&copy);
+    }
+}
+
+/* <-- Tests */
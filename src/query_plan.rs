@@ -0,0 +1,257 @@
+/* --> Summary of Contents
+
+	Query plans from EXPLAIN QUERY PLAN (SQLite) and SHOWPLAN_TEXT (SQL Server)
+
+	pub fn from_sqlite(&RecordSet) -> Vec<PlanStep>
+	pub fn from_showplan(&RecordSet) -> Vec<PlanStep>
+		Flatten either plan into indented steps and flag the expensive ones:
+		full table scans, temporary indexes the engine had to build because
+		no index fits, sorts without an index and bookmark lookups.
+
+	pub fn show_plan(&str, &[PlanStep])
+		Window with the plan as a tree, flagged steps in color, and a short
+		list of what to look at underneath
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    enums::{Color, Font},
+    group::Flex,
+    output::MultilineOutput,
+    prelude::{GroupExt, InputExt, WidgetBase, WidgetExt, WindowExt},
+    tree::{Tree, TreeItem},
+    window::Window,
+};
+use std::collections::HashMap;
+
+use crate::sql_aux_funcs::{RecordSet, Translate};
+
+/* <-- Imports */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PlanIssue {
+    FullScan,     // every row of the table is read
+    MissingIndex, // the engine built a temporary index / spool because no index fits
+    TempSort,     // ORDER BY / GROUP BY / DISTINCT sorted in a temp b-tree
+    Lookup,       // RID / key lookup, the index used does not cover the query
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct PlanStep {
+    pub depth: usize,
+    pub detail: String,
+    pub table: Option<String>,
+    pub issue: Option<PlanIssue>,
+}
+
+impl PlanIssue {
+    fn label(&self) -> &'static str {
+        match self {
+            PlanIssue::FullScan => "full scan",
+            PlanIssue::MissingIndex => "missing index",
+            PlanIssue::TempSort => "sort without index",
+            PlanIssue::Lookup => "lookup",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            PlanIssue::FullScan | PlanIssue::MissingIndex => Color::Red,
+            PlanIssue::TempSort | PlanIssue::Lookup => Color::from_rgb(176, 112, 0),
+        }
+    }
+
+    fn advice(&self) -> &'static str {
+        match self {
+            PlanIssue::FullScan => "reads every row, an index on the filtered or joined columns would avoid it",
+            PlanIssue::MissingIndex => "the engine builds a temporary index on each run, create a permanent one",
+            PlanIssue::TempSort => "sorted in a temporary structure, an index in the ORDER BY / GROUP BY order would avoid it",
+            PlanIssue::Lookup => "the index used does not cover the query, extra columns are fetched row by row",
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn value(record_set: &RecordSet, row: usize, column: &str) -> String {
+    record_set.records[row]
+        .columns
+        .get(column)
+        .cloned()
+        .flatten()
+        .map(|v| v.translate())
+        .unwrap_or_default()
+}
+
+// rows of (id, parent, notused, detail), a parent id of 0 is the top level
+pub fn from_sqlite(record_set: &RecordSet) -> Vec<PlanStep> {
+    let mut depths: HashMap<String, usize> = HashMap::new();
+    let mut steps: Vec<PlanStep> = Vec::new();
+    for row in 0..record_set.records.len() {
+        let id: String = value(record_set, row, "id");
+        let parent: String = value(record_set, row, "parent");
+        let depth: usize = depths.get(&parent).map_or(0, |d| d + 1);
+        depths.insert(id, depth);
+
+        let detail: String = value(record_set, row, "detail");
+        let (issue, table) = classify_sqlite(&detail);
+        steps.push(PlanStep { depth, detail, table, issue });
+    }
+    steps
+}
+
+fn classify_sqlite(detail: &str) -> (Option<PlanIssue>, Option<String>) {
+    let upper: String = detail.to_uppercase();
+    // "SCAN t" since 3.36, "SCAN TABLE t" before it; the table name is the word after
+    let table_after = |keyword: &str| -> Option<String> {
+        let rest: &str = detail.strip_prefix(keyword)?.trim_start();
+        let rest: &str = rest.strip_prefix("TABLE ").unwrap_or(rest);
+        let name: &str = rest.split_whitespace().next()?;
+        (!name.starts_with('(')).then(|| name.to_string())
+    };
+
+    if upper.contains("AUTOMATIC") {
+        return (Some(PlanIssue::MissingIndex), table_after("SEARCH").or_else(|| table_after("SCAN")));
+    }
+    if upper.starts_with("USE TEMP B-TREE") {
+        return (Some(PlanIssue::TempSort), None);
+    }
+    if upper.starts_with("SCAN ") && !upper.contains(" INDEX") && !upper.contains("CONSTANT ROW") {
+        if let Some(table) = table_after("SCAN") {
+            return (Some(PlanIssue::FullScan), Some(table));
+        }
+    }
+    (None, None)
+}
+
+// rows of (ResultSet, StmtText): even result sets echo the statement, odd ones hold its plan.
+// plan lines nest by the column their "|--" starts at
+pub fn from_showplan(record_set: &RecordSet) -> Vec<PlanStep> {
+    let mut steps: Vec<PlanStep> = Vec::new();
+    let mut indents: Vec<usize> = Vec::new();
+    for row in 0..record_set.records.len() {
+        let text: String = value(record_set, row, "StmtText");
+        let is_plan: bool = value(record_set, row, "ResultSet").parse::<usize>().is_ok_and(|n| n % 2 == 1);
+        if !is_plan {
+            // the statement itself heads its plan
+            indents.clear();
+            steps.push(PlanStep { depth: 0, detail: text.trim().to_string(), table: None, issue: None });
+            continue;
+        }
+
+        let indent: usize = text.find("|--").unwrap_or(0);
+        while indents.last().is_some_and(|i| *i >= indent) {
+            indents.pop();
+        }
+        let depth: usize = indents.len() + 1;
+        indents.push(indent);
+
+        let detail: String = text.trim().trim_start_matches("|--").to_string();
+        let (issue, table) = classify_showplan(&detail);
+        steps.push(PlanStep { depth, detail, table, issue });
+    }
+    steps
+}
+
+fn classify_showplan(detail: &str) -> (Option<PlanIssue>, Option<String>) {
+    let operator: &str = detail.split('(').next().unwrap_or("").trim();
+    let issue: Option<PlanIssue> = match operator {
+        "Table Scan" | "Clustered Index Scan" | "Index Scan" => Some(PlanIssue::FullScan),
+        "Index Spool" | "Table Spool" => Some(PlanIssue::MissingIndex),
+        "Sort" => Some(PlanIssue::TempSort),
+        "RID Lookup" | "Key Lookup" | "Bookmark Lookup" => Some(PlanIssue::Lookup),
+        _ => None,
+    };
+    // OBJECT:([db].[dbo].[MSet_Log].[PK_MSet_Log]) -> MSet_Log
+    let table: Option<String> = detail.split("OBJECT:(").nth(1).and_then(|object| {
+        let object: &str = object.split(')').next()?.split(" AS ").next()?;
+        let parts: Vec<&str> = object
+            .split("].[")
+            .map(|p| p.trim_matches(|c| c == '[' || c == ']'))
+            .collect();
+        match parts.len() {
+            0 | 1 => parts.first().map(|p| p.to_string()),
+            // db.schema.table, plus the index name for index operators
+            _ if operator.contains("Index") || operator.contains("Lookup") => parts.get(parts.len().saturating_sub(2)).map(|p| p.to_string()),
+            _ => parts.last().map(|p| p.to_string()),
+        }
+    });
+    (issue, issue.and(table))
+}
+
+// one line per flagged step, e.g. "full scan of MSet_Log: reads every row, ..."
+pub fn findings(steps: &[PlanStep]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for step in steps {
+        if let Some(issue) = step.issue {
+            let line: String = match &step.table {
+                Some(table) => format!("{} of {}: {}", issue.label(), table, issue.advice()),
+                None => format!("{}: {}", issue.label(), issue.advice()),
+            };
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+    }
+    lines
+}
+
+pub fn show_plan(sql: &str, steps: &[PlanStep]) {
+    let mut win: Window = Window::default()
+        .with_size(760, 520)
+        .with_label("Query Plan")
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let mut tree: Tree = Tree::default();
+    tree.set_show_root(false);
+    tree.set_item_label_font(Font::Courier);
+    let mut notes: MultilineOutput = MultilineOutput::default();
+    layout.fixed(&notes, 120);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    // parents[d] is the last item added at depth d
+    let mut parents: Vec<TreeItem> = Vec::new();
+    if let Some(root) = tree.root() {
+        parents.push(root);
+    }
+    for step in steps {
+        parents.truncate(step.depth + 1);
+        let parent: TreeItem = match parents.last() {
+            Some(parent) => parent.clone(),
+            None => break,
+        };
+        let label: String = match step.issue {
+            Some(issue) => format!("{}    [{}]", step.detail, issue.label()),
+            None => step.detail.clone(),
+        };
+        if let Some(mut item) = tree.insert(&parent, &label, parent.children()) {
+            if let Some(issue) = step.issue {
+                item.set_label_fgcolor(issue.color());
+            }
+            parents.push(item);
+        }
+    }
+
+    let found: Vec<String> = findings(steps);
+    notes.set_value(&if steps.is_empty() {
+        String::from("The database did not return a plan for this statement.")
+    } else if found.is_empty() {
+        String::from("No full scans or missing indexes in this plan.")
+    } else {
+        found.join("\n")
+    });
+    notes.set_tooltip(sql);
+    win.show();
+}
+
+/* <-- Functions */
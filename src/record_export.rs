@@ -0,0 +1,145 @@
+/* --> Summary of Contents
+
+	RecordSet -> text for files and pipes
+
+	ExportFormat
+		csv (RFC 4180 quoting), tsv, json (an array of objects, columns
+		in query order, sqlite numbers as numbers, NULL as null) and table,
+		the box drawn grid of table_render.rs
+
+	pub fn render(&RecordSet, ExportFormat) -> String
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use crate::sql_aux_funcs::{RecordSet, SqlData, Translate};
+use crate::sqlite3_interface::{SqliteFloat, SqliteInteger, SqliteNull};
+use crate::table_render::{self, TableOptions};
+
+/* <-- Imports */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Tsv,
+    Json,
+    Table,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "tsv" | "tab" => Some(ExportFormat::Tsv),
+            "json" => Some(ExportFormat::Json),
+            "table" => Some(ExportFormat::Table),
+            _ => None,
+        }
+    }
+
+    // from the extension of an output path, e.g. report.json
+    pub fn from_path(path: &str) -> Option<Self> {
+        let (_, extension) = path.rsplit_once('.')?;
+        Self::from_name(extension)
+    }
+}
+
+/* <-- Enums */
+/* --> Functions */
+
+// None for NULL, so every format can write it its own way
+fn cell<'a>(record_set: &'a RecordSet, row: usize, column: &str) -> Option<&'a SqlData> {
+    match record_set.records[row].columns.get(column) {
+        Some(Some(SqlData::Sqlite(SqliteNull))) => None,
+        Some(Some(data)) => Some(data),
+        _ => None,
+    }
+}
+
+pub fn render(record_set: &RecordSet, format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Csv => delimited(record_set, ',', csv_field),
+        ExportFormat::Tsv => delimited(record_set, '\t', tsv_field),
+        ExportFormat::Json => json(record_set),
+        ExportFormat::Table => table_render::render(record_set, &TableOptions::default()),
+    }
+}
+
+fn delimited(record_set: &RecordSet, separator: char, field: fn(&str) -> String) -> String {
+    let mut out: String = String::new();
+    // statements without a result set (UPDATE, CREATE ...) write nothing
+    if record_set.column_order.is_empty() {
+        return out;
+    }
+    let header: Vec<String> = record_set.column_order.iter().map(|c| field(c)).collect();
+    out.push_str(&header.join(&separator.to_string()));
+    out.push('\n');
+    for row in 0..record_set.records.len() {
+        let line: Vec<String> = record_set
+            .column_order
+            .iter()
+            .map(|column| cell(record_set, row, column).map_or(String::new(), |data| field(&data.translate())))
+            .collect();
+        out.push_str(&line.join(&separator.to_string()));
+        out.push('\n');
+    }
+    out
+}
+
+// quoted only when needed, doubled quotes inside
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// tsv has no quoting, tabs and line breaks inside a value become spaces
+fn tsv_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+fn json(record_set: &RecordSet) -> String {
+    let mut out: String = String::from("[");
+    for row in 0..record_set.records.len() {
+        out.push_str(if row == 0 { "\n  {" } else { ",\n  {" });
+        for (index, column) in record_set.column_order.iter().enumerate() {
+            if index > 0 {
+                out.push_str(", ");
+            }
+            out.push_str(&json_string(column));
+            out.push_str(": ");
+            match cell(record_set, row, column) {
+                None => out.push_str("null"),
+                Some(SqlData::Sqlite(SqliteInteger(value))) => out.push_str(&value.to_string()),
+                Some(SqlData::Sqlite(SqliteFloat(value))) if value.is_finite() => out.push_str(&value.to_string()),
+                Some(data) => out.push_str(&json_string(&data.translate())),
+            }
+        }
+        out.push('}');
+    }
+    out.push_str(if record_set.records.is_empty() { "]\n" } else { "\n]\n" });
+    out
+}
+
+fn json_string(value: &str) -> String {
+    let mut out: String = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/* <-- Functions */
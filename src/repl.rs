@@ -0,0 +1,296 @@
+/* --> Summary of Contents
+
+	Interactive SQL shell, started with --repl (cli.rs)
+
+	pub fn run(Option<&Target>, Option<ExportFormat>) -> Result<(), String>
+		Read statements until end of input. A statement may span lines and
+		runs once a line ends it with ';' (or a T-SQL GO line). Results go
+		through record_export, so SQLite and ODBC print the same way.
+		The session keeps one connection until .connect or the end, so
+		BEGIN / COMMIT and temp tables last across statements.
+
+	Meta-commands, on a line of their own:
+		.tables               tables of the connection
+		.schema TABLE         columns of a table
+		.mode csv|tsv|json|table
+		.output [FILE]        results to FILE, back to the terminal without one
+		.timer on|off         time each statement
+		.expanded on|off|auto one line per field, auto when rows are too wide
+		.connect PROFILE      switch to a saved connection
+		.help / .quit / .exit
+
+	Lines are kept in repl_history.txt in the config directory.
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::app_paths;
+use crate::audit_log;
+use crate::cli::{self, Session, Target};
+use crate::credentials;
+use crate::line_editor::{self, LineEditor, ReadResult};
+use crate::record_export::{self, ExportFormat};
+use crate::sql_aux_funcs::{QueryType, RecordSet, Request};
+use crate::sql_lexer;
+use crate::statement_guard::{self, Safety};
+use crate::table_render::{self, Layout, TableOptions};
+
+/* <-- Imports */
+/* --> Const */
+
+const HISTORY_FILE: &str = "repl_history.txt";
+const PROMPT: &str = "dv> ";
+const CONTINUATION: &str = "...> ";
+
+const HELP: &str = "\
+.tables               tables of the connection
+.schema TABLE         columns of a table
+.mode csv|tsv|json|table
+.output [FILE]        results to FILE, back to the terminal without one
+.timer on|off         time each statement
+.expanded on|off|auto one line per field, auto when rows are too wide
+.connect PROFILE      switch to a saved connection
+.help                 this text
+.quit / .exit         leave, Ctrl+D does the same
+
+Statements end with ; and may span lines. Ctrl+C drops the statement being typed.";
+
+/* <-- Const */
+/* --> Structs */
+
+struct Shell {
+    session: Option<Session>,
+    label: String, // the connection, shown in the prompt
+    safety: Safety,
+    mode: ExportFormat,
+    layout: Layout,
+    output: Option<(String, File)>,
+    timer: bool,
+}
+
+impl Shell {
+    fn connect(&mut self, target: &Target) {
+        let safety: Safety = cli::target_safety(target);
+        let opened: Result<Session, String> = cli::connect(target, true)
+            .and_then(|(connection_type, connection)| Session::open(&connection_type, &connection, safety.read_only));
+        match opened {
+            Ok(session) => {
+                // the old session closes here, a transaction left open in it is rolled back
+                self.session = Some(session);
+                self.label = target_label(target);
+                self.safety = safety;
+                println!("Connected to {}", self.label);
+            }
+            Err(e) => eprintln!("Error: {}", credentials::redact(&e)),
+        }
+    }
+
+    fn prompt(&self) -> String {
+        match self.session {
+            Some(_) => format!("{} {}", self.label, PROMPT),
+            None => String::from(PROMPT),
+        }
+    }
+
+    // false when the shell should end
+    fn meta_command(&mut self, line: &str) -> bool {
+        let mut words = line.split_whitespace();
+        let command: &str = words.next().unwrap_or_default();
+        let argument: String = words.collect::<Vec<&str>>().join(" ");
+        match (command, argument.as_str()) {
+            (".quit" | ".exit", _) => return false,
+            (".help", _) => println!("{}", HELP),
+            (".tables", _) => {
+                self.request(QueryType::SqlFunction(Request::Tables(2)));
+            }
+            (".schema", "") => eprintln!("Usage: .schema TABLE"),
+            (".schema", table) => {
+                self.request(QueryType::SqlFunction(Request::Columns(table.to_string())));
+            }
+            (".mode", "") => println!("{}", format!("{:?}", self.mode).to_ascii_lowercase()),
+            (".mode", name) => match ExportFormat::from_name(name) {
+                Some(mode) => self.mode = mode,
+                None => eprintln!("Unknown mode {}, use csv, tsv, json or table", name),
+            },
+            (".output", "") => self.output = None,
+            (".output", path) => match File::create(path) {
+                Ok(file) => self.output = Some((path.to_string(), file)),
+                Err(e) => eprintln!("Unable to open {}: {}", path, e),
+            },
+            (".timer", "on") => self.timer = true,
+            (".timer", "off") => self.timer = false,
+            (".timer", _) => eprintln!("Usage: .timer on|off"),
+            (".expanded", "on") => self.layout = Layout::Expanded,
+            (".expanded", "off") => self.layout = Layout::Grid,
+            (".expanded", "auto") => self.layout = Layout::Auto,
+            (".expanded", _) => eprintln!("Usage: .expanded on|off|auto"),
+            (".connect", "") => eprintln!("Usage: .connect PROFILE"),
+            (".connect", profile) => self.connect(&Target::Profile(profile.to_string())),
+            _ => eprintln!("Unknown command {}, .help lists them", command),
+        }
+        true
+    }
+
+    fn run_sql(&mut self, sql: &str) {
+        let pieces: Vec<(usize, usize)> = match &self.session {
+            Some(session) => session.pieces(sql),
+            None => sql_lexer::statement_ranges(sql),
+        };
+        for (start, end) in pieces {
+            // the rest of the script is skipped once a statement fails, like a script run in the editor
+            if !self.request(QueryType::UserDefined(sql[start..end].to_string())) {
+                break;
+            }
+        }
+    }
+
+    fn request(&mut self, request: QueryType) -> bool {
+        let session: &Session = match &self.session {
+            Some(session) => session,
+            None => {
+                eprintln!("Not connected, use .connect PROFILE");
+                return false;
+            }
+        };
+        if let QueryType::UserDefined(sql) = &request {
+            if let Err(e) = statement_guard::check(self.safety, sql, confirm) {
+                eprintln!("Error: {}", e);
+                return false;
+            }
+        }
+        let started: Instant = Instant::now();
+        let result: Result<RecordSet, String> = if self.safety.read_only {
+            session.run(request)
+        } else {
+            audit_log::run_audited(session, &audit_log::os_user(), request)
+        };
+        let elapsed: Duration = started.elapsed();
+        let succeeded: bool = match result {
+            Ok(record_set) => {
+                let text: String = match self.mode {
+                    // sized to the terminal, a file gets the full grid
+                    ExportFormat::Table => table_render::render(
+                        &record_set,
+                        &TableOptions {
+                            layout: self.layout,
+                            max_width: if self.output.is_none() { line_editor::terminal_width() } else { None },
+                            ..Default::default()
+                        },
+                    ),
+                    mode => record_export::render(&record_set, mode),
+                };
+                self.write(&text);
+                true
+            }
+            Err(e) => {
+                eprintln!("Error: {}", credentials::redact(&e));
+                false
+            }
+        };
+        // timings stay on the terminal, they do not belong in an .output file
+        if self.timer {
+            eprintln!("Run Time: {:.3} s", elapsed.as_secs_f64());
+        }
+        succeeded
+    }
+
+    fn write(&mut self, text: &str) {
+        let written: io::Result<()> = match &mut self.output {
+            Some((_, file)) => file.write_all(text.as_bytes()).and_then(|_| file.flush()),
+            None => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(text.as_bytes()).and_then(|_| stdout.flush())
+            }
+        };
+        if let Err(e) = written {
+            let destination: &str = self.output.as_ref().map_or("the terminal", |(path, _)| path.as_str());
+            eprintln!("Unable to write to {}: {}", destination, e);
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+// the guard's question on the terminal, only "yes" runs the statement
+fn confirm(question: &str) -> bool {
+    eprintln!("{}", question);
+    matches!(LineEditor::new(None).read_line("Type yes to run it: "), Ok(ReadResult::Line(answer)) if answer.trim().eq_ignore_ascii_case("yes"))
+}
+
+pub fn run(target: Option<&Target>, mode: Option<ExportFormat>) -> Result<(), String> {
+    let mut editor: LineEditor = LineEditor::new(Some(app_paths::config_file(HISTORY_FILE)));
+    let mut shell: Shell = Shell {
+        session: None,
+        label: String::new(),
+        safety: Safety::default(),
+        mode: mode.unwrap_or(ExportFormat::Table),
+        layout: Layout::Auto,
+        output: None,
+        timer: false,
+    };
+    println!("DaedriVictus shell, .help for commands");
+    if let Some(target) = target {
+        shell.connect(target);
+    }
+
+    let mut buffer: String = String::new();
+    loop {
+        let prompt: String = if buffer.is_empty() { shell.prompt() } else { CONTINUATION.to_string() };
+        let line: String = match editor.read_line(&prompt).map_err(|e| format!("Unable to read the terminal: {}", e))? {
+            ReadResult::Line(line) => line,
+            ReadResult::Interrupted => {
+                buffer.clear();
+                continue;
+            }
+            ReadResult::Eof => break,
+        };
+        editor.add_history(&line);
+
+        if buffer.is_empty() && line.trim_start().starts_with('.') {
+            if !shell.meta_command(line.trim()) {
+                break;
+            }
+            continue;
+        }
+        if buffer.is_empty() && line.trim().is_empty() {
+            continue;
+        }
+        buffer.push_str(&line);
+        buffer.push('\n');
+        if statement_complete(&buffer, &line) {
+            shell.run_sql(&buffer);
+            buffer.clear();
+        }
+    }
+    editor.save_history()
+}
+
+// the last thing typed is a ';' outside strings and comments, or the line is a GO
+fn statement_complete(buffer: &str, line: &str) -> bool {
+    if line.trim().eq_ignore_ascii_case("go") {
+        return true;
+    }
+    sql_lexer::significant(&sql_lexer::tokenize(buffer))
+        .last()
+        .is_some_and(|token| token.text(buffer) == ";")
+}
+
+fn target_label(target: &Target) -> String {
+    match target {
+        Target::Profile(name) => name.clone(),
+        Target::Sqlite(path) => Path::new(path)
+            .file_name()
+            .map_or_else(|| path.clone(), |f| f.to_string_lossy().into_owned()),
+        Target::Odbc(_) => String::from("odbc"),
+    }
+}
+
+/* <-- Functions */
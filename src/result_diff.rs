@@ -0,0 +1,672 @@
+/* --> Summary of Contents
+
+	Compare two result sets row by row, e.g. the ODBC production
+	measuring_instruments against the same table in copy_of_dv.db
+
+	pub fn diff(&RecordSet, &RecordSet, &[String]) -> Result<ResultDiff, String>
+		Rows are matched on the key columns. Column names match without
+		regard to case and values are compared as trimmed text, so "007"
+		and "7" differ. When the column is numeric on both sides numbers
+		compare by value ("5" = "5.00", exactly, big IDs included). NULL
+		only equals NULL, so the two sides may come from different backends.
+
+	pub fn report(&ResultDiff) -> RecordSet
+		The differences as a result set for record_export: one row per
+		added, removed or changed row, changed cells as "old -> new"
+
+	pub fn ask_sources(&[DiffSource], usize) -> Option<DiffRequest>
+		Modal dialog: the tab and SQL for each side and the key columns
+
+	pub fn show_diff(&ResultDiff, &str, &str)
+		Window with the differing rows, changed cells highlighted, and an
+		Export button that writes the report
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    button::Button,
+    dialog,
+    draw,
+    enums::{Align, Color, Font, FrameType},
+    frame::Frame,
+    group::Flex,
+    input::{Input, MultilineInput},
+    menu::Choice,
+    prelude::{GroupExt, InputExt, MenuExt, TableExt, WidgetBase, WidgetExt, WindowExt},
+    table::{Table, TableContext},
+    window::Window,
+};
+use std::collections::{HashMap, HashSet};
+
+use crate::learning_fltk::escape_menu_label;
+use crate::record_export::{self, ExportFormat};
+use crate::sql_aux_funcs::{Record, RecordSet, SqlData, SqlType};
+use crate::sqlite3_interface::{SqliteString, Type};
+use crate::statement_guard::Safety;
+
+/* <-- Imports */
+/* --> Const */
+
+const CHANGE_COLUMN: &str = "change";
+const ARROW: &str = " -> ";
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum RowChange {
+    Added,   // only in the right result
+    Removed, // only in the left result
+    Changed,
+}
+
+#[derive(Clone, Copy)]
+enum DialogMessage {
+    LeftChanged,
+    RightChanged,
+    Compare,
+    Cancel,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug)]
+pub struct RowDiff {
+    pub change: RowChange,
+    pub key: Vec<String>,
+    pub left: Vec<Option<String>>,  // values of ResultDiff::columns, empty for added rows
+    pub right: Vec<Option<String>>, // empty for removed rows
+    pub changed: Vec<usize>,        // indexes into ResultDiff::columns
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ResultDiff {
+    pub keys: Vec<String>,
+    pub columns: Vec<String>, // compared columns, keys first, named as in the left result
+    pub left_only: Vec<String>,
+    pub right_only: Vec<String>,
+    pub rows: Vec<RowDiff>,
+    pub unchanged: usize,
+}
+
+// one side offered by ask_sources: an open tab and the SQL to run on it
+#[derive(Clone)]
+pub struct DiffSource {
+    pub tab: usize,
+    pub label: String,
+    pub sql: String,
+    pub safety: Safety, // of the tab's connection, the SQL is checked like the editor's
+}
+
+pub struct DiffRequest {
+    pub left: DiffSource,
+    pub right: DiffSource,
+    pub keys: Vec<String>,
+}
+
+impl RowChange {
+    fn label(&self) -> &'static str {
+        match self {
+            RowChange::Added => "added",
+            RowChange::Removed => "removed",
+            RowChange::Changed => "changed",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            RowChange::Added => Color::from_rgb(214, 245, 214),
+            RowChange::Removed => Color::from_rgb(250, 215, 215),
+            RowChange::Changed => Color::BackGround2,
+        }
+    }
+}
+
+impl ResultDiff {
+    // "2 added, 1 removed, 3 changed, 120 unchanged"
+    pub fn summary(&self) -> String {
+        let count = |change: RowChange| self.rows.iter().filter(|r| r.change == change).count();
+        let mut summary: String = format!(
+            "{} added, {} removed, {} changed, {} unchanged",
+            count(RowChange::Added),
+            count(RowChange::Removed),
+            count(RowChange::Changed),
+            self.unchanged
+        );
+        if !self.left_only.is_empty() {
+            summary.push_str(&format!("; only left: {}", self.left_only.join(", ")));
+        }
+        if !self.right_only.is_empty() {
+            summary.push_str(&format!("; only right: {}", self.right_only.join(", ")));
+        }
+        summary
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+// the digits of a decimal number without sign or padding noise, "5", "+5.0" and "005.00" give "5".
+// Exact where f64 is not, an ID above 2^53 keeps its last digit. None for anything else, exponents too
+fn decimal_text(text: &str) -> Option<String> {
+    let text: &str = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if (whole.is_empty() && fraction.is_empty()) || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (whole, fraction) = (whole.trim_start_matches('0'), fraction.trim_end_matches('0'));
+    let number: String = match (whole.is_empty(), fraction.is_empty()) {
+        (true, true) => return Some(String::from("0")),
+        (_, true) => whole.to_string(),
+        (true, false) => format!("0.{}", fraction),
+        (false, false) => format!("{}.{}", whole, fraction),
+    };
+    Some(if negative { format!("-{}", number) } else { number })
+}
+
+// CHAR columns come back padded from some drivers. Numbers compare by value only when both columns are numeric
+fn same_value(left: &Option<String>, right: &Option<String>, numeric: bool) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(l), Some(r)) => {
+            let (l, r) = (l.trim(), r.trim());
+            l == r
+                || (numeric
+                    && match (decimal_text(l), decimal_text(r)) {
+                        (Some(a), Some(b)) => a == b,
+                        // 1E-3 from a driver against 0.001
+                        _ => matches!((l.parse::<f64>(), r.parse::<f64>()), (Ok(a), Ok(b)) if a == b),
+                    })
+        }
+        _ => false,
+    }
+}
+
+// the form keys are matched on, so in numeric columns 5 and 5.0 find each other
+fn key_text(value: &Option<String>, numeric: bool) -> String {
+    match value {
+        None => String::from("\u{0}NULL"),
+        Some(text) if numeric => match decimal_text(text) {
+            Some(number) => number,
+            None => match text.trim().parse::<f64>() {
+                Ok(number) if number.is_finite() => number.to_string(),
+                _ => text.trim().to_string(),
+            },
+        },
+        Some(text) => text.trim().to_string(),
+    }
+}
+
+fn find_column<'a>(record_set: &'a RecordSet, name: &str) -> Option<&'a String> {
+    record_set.column_order.iter().find(|c| c.eq_ignore_ascii_case(name.trim()))
+}
+
+pub fn diff(left: &RecordSet, right: &RecordSet, keys: &[String]) -> Result<ResultDiff, String> {
+    if keys.is_empty() {
+        return Err(String::from("Choose at least one key column to match the rows on."));
+    }
+    // (left name, right name) for every compared column, keys first
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for key in keys {
+        let left_name: &String = find_column(left, key).ok_or(format!("Key column {} is not in the left result.", key))?;
+        let right_name: &String = find_column(right, key).ok_or(format!("Key column {} is not in the right result.", key))?;
+        if !pairs.iter().any(|(l, _)| l == left_name) {
+            pairs.push((left_name.clone(), right_name.clone()));
+        }
+    }
+    let key_count: usize = pairs.len();
+    let mut result: ResultDiff = ResultDiff::default();
+    for column in &left.column_order {
+        if pairs.iter().any(|(l, _)| l == column) {
+            continue;
+        }
+        match find_column(right, column) {
+            Some(right_name) => pairs.push((column.clone(), right_name.clone())),
+            None => result.left_only.push(column.clone()),
+        }
+    }
+    result.right_only = right
+        .column_order
+        .iter()
+        .filter(|c| find_column(left, c).is_none())
+        .cloned()
+        .collect();
+    result.keys = pairs[..key_count].iter().map(|(l, _)| l.clone()).collect();
+    result.columns = pairs.iter().map(|(l, _)| l.clone()).collect();
+
+    let row_values = |record: &Record, left_side: bool| -> Vec<Option<String>> {
+        pairs
+            .iter()
+            .map(|(l, r)| record.text(if left_side { l } else { r }))
+            .collect()
+    };
+    // a column is numeric when it is on both sides, an INTEGER id against a TEXT one compares as text
+    let numeric: Vec<bool> = pairs
+        .iter()
+        .map(|(l, r)| {
+            let is_numeric = |record_set: &RecordSet, column: &String| record_set.column_info.get(column).is_some_and(SqlType::is_numeric);
+            is_numeric(left, l) && is_numeric(right, r)
+        })
+        .collect();
+    let row_key = |values: &[Option<String>]| -> Vec<String> {
+        values[..key_count].iter().zip(&numeric).map(|(value, numeric)| key_text(value, *numeric)).collect()
+    };
+
+    let mut right_rows: HashMap<Vec<String>, usize> = HashMap::new();
+    for (index, record) in right.records.iter().enumerate() {
+        if right_rows.insert(row_key(&row_values(record, false)), index).is_some() {
+            return Err(format!("The key ({}) repeats in the right result, choose columns that identify a row.", result.keys.join(", ")));
+        }
+    }
+
+    let mut seen: HashSet<Vec<String>> = HashSet::new();
+    let mut matched: HashSet<usize> = HashSet::new();
+    for record in &left.records {
+        let left_values: Vec<Option<String>> = row_values(record, true);
+        let key: Vec<String> = row_key(&left_values);
+        if !seen.insert(key.clone()) {
+            return Err(format!("The key ({}) repeats in the left result, choose columns that identify a row.", result.keys.join(", ")));
+        }
+        let display_key: Vec<String> = left_values[..key_count].iter().map(|v| v.clone().unwrap_or_default()).collect();
+        match right_rows.get(&key) {
+            Some(index) => {
+                matched.insert(*index);
+                let right_values: Vec<Option<String>> = row_values(&right.records[*index], false);
+                let changed: Vec<usize> = (key_count..pairs.len())
+                    .filter(|i| !same_value(&left_values[*i], &right_values[*i], numeric[*i]))
+                    .collect();
+                if changed.is_empty() {
+                    result.unchanged += 1;
+                } else {
+                    result.rows.push(RowDiff {
+                        change: RowChange::Changed,
+                        key: display_key,
+                        left: left_values,
+                        right: right_values,
+                        changed,
+                    });
+                }
+            }
+            None => result.rows.push(RowDiff {
+                change: RowChange::Removed,
+                key: display_key,
+                left: left_values,
+                right: Vec::new(),
+                changed: Vec::new(),
+            }),
+        }
+    }
+    for (index, record) in right.records.iter().enumerate() {
+        if matched.contains(&index) {
+            continue;
+        }
+        let right_values: Vec<Option<String>> = row_values(record, false);
+        result.rows.push(RowDiff {
+            change: RowChange::Added,
+            key: right_values[..key_count].iter().map(|v| v.clone().unwrap_or_default()).collect(),
+            left: Vec::new(),
+            right: right_values,
+            changed: Vec::new(),
+        });
+    }
+    Ok(result)
+}
+
+fn shown(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| String::from("NULL"))
+}
+
+// the text of one cell: the row's value, or "old -> new" where it changed
+fn cell_text(row: &RowDiff, column: usize) -> String {
+    match row.change {
+        RowChange::Added => shown(&row.right[column]),
+        RowChange::Removed => shown(&row.left[column]),
+        RowChange::Changed if row.changed.contains(&column) => {
+            format!("{}{}{}", shown(&row.left[column]), ARROW, shown(&row.right[column]))
+        }
+        RowChange::Changed => shown(&row.left[column]),
+    }
+}
+
+pub fn report(result: &ResultDiff) -> RecordSet {
+    let mut record_set: RecordSet = RecordSet::default();
+    for column in std::iter::once(CHANGE_COLUMN).chain(result.columns.iter().map(String::as_str)) {
+        record_set.column_order.push(column.to_string());
+        record_set.column_info.insert(column.to_string(), SqlType::Sqlite(Type::String));
+    }
+    for row in &result.rows {
+        let mut record: Record = Record::default();
+        record.columns.insert(CHANGE_COLUMN.to_string(), Some(SqlData::Sqlite(SqliteString(row.change.label().to_string()))));
+        for (index, column) in result.columns.iter().enumerate() {
+            record.columns.insert(column.clone(), Some(SqlData::Sqlite(SqliteString(cell_text(row, index)))));
+        }
+        record_set.records.push(record);
+    }
+    record_set
+}
+
+pub fn ask_sources(sources: &[DiffSource], active: usize) -> Option<DiffRequest> {
+    if sources.is_empty() {
+        return None;
+    }
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+
+    let mut win: Window = Window::default()
+        .with_size(640, 430)
+        .with_label("Compare Results")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut left_tab: Choice = Choice::new(80, 10, 550, 25, "Left");
+    let mut left_sql: MultilineInput = MultilineInput::new(80, 40, 550, 140, "SQL");
+    let mut right_tab: Choice = Choice::new(80, 190, 550, 25, "Right");
+    let mut right_sql: MultilineInput = MultilineInput::new(80, 220, 550, 140, "SQL");
+    let mut keys: Input = Input::new(80, 365, 550, 25, "Key");
+    keys.set_tooltip("Columns that identify a row on both sides, separated by commas");
+    for choice in [&mut left_tab, &mut right_tab] {
+        for source in sources {
+            // '|' separates choices, '/' and '&' are special in menu paths
+            choice.add_choice(&escape_menu_label(&source.label.replace('|', " ")));
+        }
+    }
+    let right_index: usize = if sources.len() > 1 && active == 0 { 1 } else { 0 };
+    left_tab.set_value(active as i32);
+    right_tab.set_value(right_index as i32);
+    left_sql.set_value(&sources[active].sql);
+    right_sql.set_value(&sources[right_index].sql);
+    left_tab.emit(sndr, DialogMessage::LeftChanged);
+    right_tab.emit(sndr, DialogMessage::RightChanged);
+
+    let mut compare_butn: Button = Button::new(470, 395, 75, 28, "&Compare");
+    compare_butn.emit(sndr, DialogMessage::Compare);
+    let mut cancel_butn: Button = Button::new(555, 395, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+
+    win.end();
+    win.show();
+
+    let source_at = |choice: &Choice| usize::try_from(choice.value()).ok().and_then(|i| sources.get(i));
+    let mut request: Option<DiffRequest> = None;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::LeftChanged) => {
+                if let Some(source) = source_at(&left_tab) {
+                    left_sql.set_value(&source.sql);
+                }
+            }
+            Some(DialogMessage::RightChanged) => {
+                if let Some(source) = source_at(&right_tab) {
+                    right_sql.set_value(&source.sql);
+                }
+            }
+            Some(DialogMessage::Compare) => {
+                let key_columns: Vec<String> = keys
+                    .value()
+                    .split(',')
+                    .map(|k| k.trim().to_string())
+                    .filter(|k| !k.is_empty())
+                    .collect();
+                if let (Some(left), Some(right)) = (source_at(&left_tab), source_at(&right_tab)) {
+                    request = Some(DiffRequest {
+                        left: DiffSource { sql: left_sql.value(), ..left.clone() },
+                        right: DiffSource { sql: right_sql.value(), ..right.clone() },
+                        keys: key_columns,
+                    });
+                    win.hide();
+                }
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            None => {}
+        }
+    }
+    request
+}
+
+pub fn show_diff(result: &ResultDiff, left_label: &str, right_label: &str) {
+    let mut win: Window = Window::default()
+        .with_size(900, 560)
+        .with_label(&format!("Compare: {} / {}", left_label, right_label))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let mut summary: Frame = Frame::default().with_label(&result.summary());
+    summary.set_align(Align::Inside | Align::Left);
+    layout.fixed(&summary, 25);
+
+    let mut table: Table = Table::default();
+    let mut buttons: Flex = Flex::default().row();
+    Frame::default();
+    let mut export_butn: Button = Button::default().with_label("&Export...");
+    buttons.fixed(&export_butn, 90);
+    buttons.end();
+    layout.fixed(&buttons, 30);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    // column 0 is the change, then the compared columns
+    let headers: Vec<String> = std::iter::once(String::from(CHANGE_COLUMN)).chain(result.columns.iter().cloned()).collect();
+    let rows: Vec<(RowChange, Vec<(String, bool)>)> = result
+        .rows
+        .iter()
+        .map(|row| {
+            let mut cells: Vec<(String, bool)> = vec![(row.change.label().to_string(), false)];
+            cells.extend((0..result.columns.len()).map(|i| (cell_text(row, i), row.changed.contains(&i))));
+            (row.change, cells)
+        })
+        .collect();
+
+    table.set_rows(rows.len() as i32);
+    table.set_cols(headers.len() as i32);
+    table.set_col_header(true);
+    table.set_col_resize(true);
+    table.set_row_height_all(22);
+    for (index, header) in headers.iter().enumerate() {
+        let widest: usize = rows.iter().map(|(_, cells)| cells[index].0.chars().count()).fold(header.chars().count(), usize::max);
+        table.set_col_width(index as i32, (widest as i32 * 8 + 16).clamp(60, 360));
+    }
+    table.end();
+    table.draw_cell(move |_, context, row, col, x, y, w, h| match context {
+        TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+        TableContext::ColHeader => {
+            draw::draw_box(FrameType::ThinUpBox, x, y, w, h, Color::FrameDefault);
+            draw::set_draw_color(Color::Black);
+            draw::draw_text2(&headers[col as usize], x + 4, y, w - 8, h, Align::Left);
+        }
+        TableContext::Cell => {
+            let (change, cells) = &rows[row as usize];
+            let (text, changed) = &cells[col as usize];
+            // added / removed rows are tinted whole, changed rows only in the cells that differ
+            let background: Color = if *changed { Color::from_rgb(255, 236, 160) } else { change.color() };
+            draw::push_clip(x, y, w, h);
+            draw::draw_rect_fill(x, y, w, h, background);
+            draw::set_draw_color(Color::Black);
+            draw::draw_text2(text, x + 4, y, w - 8, h, Align::Left);
+            draw::set_draw_color(Color::Light2);
+            draw::draw_rect(x, y, w, h);
+            draw::pop_clip();
+        }
+        _ => {}
+    });
+
+    let report_set: RecordSet = report(result);
+    export_butn.set_callback(move |_| {
+        let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+        chooser.set_title("Export the comparison");
+        chooser.set_filter("CSV\t*.csv\nTSV\t*.tsv\nJSON\t*.json\nText table\t*.txt");
+        chooser.set_option(dialog::FileDialogOptions::SaveAsConfirm);
+        chooser.show();
+        let path: String = chooser.filename().to_string_lossy().into_owned();
+        if path.is_empty() {
+            return;
+        }
+        let format: ExportFormat = ExportFormat::from_path(&path)
+            .or_else(|| path.ends_with(".txt").then_some(ExportFormat::Table))
+            .unwrap_or(ExportFormat::Csv);
+        match std::fs::write(&path, record_export::render(&report_set, format)) {
+            Ok(_) => summary.set_label(&format!("Report written to {}", path)),
+            Err(e) => println!("Unable to write {}: {}", path, e),
+        }
+    });
+    win.show();
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql_aux_funcs::Translate;
+    use crate::sqlite3_interface::SqliteNull;
+
+    fn record_set(columns: &[&str], rows: &[&[Option<&str>]]) -> RecordSet {
+        let mut record_set: RecordSet = RecordSet {
+            column_order: columns.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+        for row in rows {
+            let mut record: Record = Record::default();
+            for (column, value) in columns.iter().zip(row.iter()) {
+                let data: SqlData = match value {
+                    Some(text) => SqlData::Sqlite(SqliteString(text.to_string())),
+                    None => SqlData::Sqlite(SqliteNull),
+                };
+                record.columns.insert(column.to_string(), Some(data));
+            }
+            record_set.records.push(record);
+        }
+        record_set
+    }
+
+    // the columns as a numeric type, the way a driver describes DECIMAL or BIGINT
+    fn numeric(mut record_set: RecordSet, columns: &[&str]) -> RecordSet {
+        for column in columns {
+            record_set.column_info.insert(column.to_string(), SqlType::Odbc(odbc::ffi::SqlDataType::SQL_DECIMAL));
+        }
+        record_set
+    }
+
+    fn keys(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_added_removed_and_changed_rows() {
+        let left: RecordSet = record_set(
+            &["id", "name", "due"],
+            &[&[Some("1"), Some("caliper"), Some("2024-01-01")], &[Some("2"), Some("gauge"), None], &[Some("3"), Some("mic"), None]],
+        );
+        let right: RecordSet = record_set(
+            &["ID", "name", "due"],
+            &[&[Some("1"), Some("caliper"), Some("2024-06-01")], &[Some("3"), Some("mic"), None], &[Some("4"), Some("scale"), None]],
+        );
+        let result: ResultDiff = diff(&left, &right, &keys(&["id"])).unwrap();
+        assert_eq!(result.columns, keys(&["id", "name", "due"]));
+        assert_eq!(result.unchanged, 1);
+        let changes: Vec<(RowChange, Vec<String>)> = result.rows.iter().map(|r| (r.change, r.key.clone())).collect();
+        assert_eq!(
+            changes,
+            vec![
+                (RowChange::Changed, keys(&["1"])),
+                (RowChange::Removed, keys(&["2"])),
+                (RowChange::Added, keys(&["4"])),
+            ]
+        );
+        assert_eq!(result.rows[0].changed, vec![2]);
+        assert_eq!(result.summary(), "1 added, 1 removed, 1 changed, 1 unchanged");
+    }
+
+    #[test]
+    fn null_only_equals_null_and_padding_is_ignored() {
+        let left: RecordSet = record_set(&["id", "a", "b"], &[&[Some("1"), None, Some("AB  ")]]);
+        let right: RecordSet = record_set(&["id", "a", "b"], &[&[Some("1"), Some(""), Some("AB")]]);
+        let result: ResultDiff = diff(&left, &right, &keys(&["id"])).unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].changed, vec![1]);
+    }
+
+    #[test]
+    fn text_columns_compare_as_text() {
+        let left: RecordSet = record_set(&["code", "lot"], &[&[Some("007"), Some("5")], &[Some("1e3"), Some("x")]]);
+        let right: RecordSet = record_set(&["code", "lot"], &[&[Some("7"), Some("5")], &[Some("1000"), Some("x")], &[Some("007 "), Some("5.0")]]);
+        let result: ResultDiff = diff(&left, &right, &keys(&["code"])).unwrap();
+        let changes: Vec<(RowChange, Vec<String>)> = result.rows.iter().map(|r| (r.change, r.key.clone())).collect();
+        assert_eq!(
+            changes,
+            vec![
+                (RowChange::Changed, keys(&["007"])),
+                (RowChange::Removed, keys(&["1e3"])),
+                (RowChange::Added, keys(&["7"])),
+                (RowChange::Added, keys(&["1000"])),
+            ]
+        );
+        assert_eq!(result.rows[0].changed, vec![1]);
+    }
+
+    #[test]
+    fn numeric_columns_compare_by_exact_value() {
+        let left: RecordSet = numeric(
+            record_set(&["id", "reading"], &[&[Some("9007199254740993"), Some("5")], &[Some("007"), Some("0.0010")], &[Some("-0"), Some("1E-3")]]),
+            &["id", "reading"],
+        );
+        let right: RecordSet = numeric(
+            record_set(&["id", "reading"], &[&[Some("9007199254740992"), Some("5.00")], &[Some("7.0"), Some(".001")], &[Some("0"), Some("0.001")]]),
+            &["id", "reading"],
+        );
+        let result: ResultDiff = diff(&left, &right, &keys(&["id"])).unwrap();
+        let changes: Vec<(RowChange, Vec<String>)> = result.rows.iter().map(|r| (r.change, r.key.clone())).collect();
+        // 2^53 + 1 and 2^53 are the same f64, they are different rows
+        assert_eq!(
+            changes,
+            vec![(RowChange::Removed, keys(&["9007199254740993"])), (RowChange::Added, keys(&["9007199254740992"]))]
+        );
+        assert_eq!(result.unchanged, 2);
+        // numeric on one side only is compared as text
+        let right: RecordSet = record_set(&["id", "reading"], &[&[Some("7"), Some("0.0010")]]);
+        let left: RecordSet = numeric(record_set(&["id", "reading"], &[&[Some("007"), Some("0.0010")]]), &["id", "reading"]);
+        assert_eq!(diff(&left, &right, &keys(&["id"])).unwrap().unchanged, 0);
+    }
+
+    #[test]
+    fn columns_on_one_side_are_listed_not_compared() {
+        let left: RecordSet = record_set(&["id", "old"], &[&[Some("1"), Some("x")]]);
+        let right: RecordSet = record_set(&["id", "new"], &[&[Some("1"), Some("y")]]);
+        let result: ResultDiff = diff(&left, &right, &keys(&["id"])).unwrap();
+        assert_eq!(result.left_only, keys(&["old"]));
+        assert_eq!(result.right_only, keys(&["new"]));
+        assert_eq!(result.unchanged, 1);
+    }
+
+    #[test]
+    fn missing_or_repeated_keys_are_errors() {
+        let left: RecordSet = record_set(&["id", "name"], &[&[Some("1"), Some("a")], &[Some("1"), Some("b")]]);
+        let right: RecordSet = record_set(&["id", "name"], &[&[Some("1"), Some("a")]]);
+        assert!(diff(&left, &right, &[]).is_err());
+        assert!(diff(&left, &right, &keys(&["serial"])).unwrap_err().contains("left result"));
+        assert!(diff(&left, &right, &keys(&["id"])).unwrap_err().contains("repeats in the left"));
+        assert!(diff(&left, &right, &keys(&["id", "name"])).is_ok());
+    }
+
+    #[test]
+    fn report_shows_old_and_new_values() {
+        let left: RecordSet = record_set(&["id", "name"], &[&[Some("1"), Some("a")]]);
+        let right: RecordSet = record_set(&["id", "name"], &[&[Some("1"), None]]);
+        let report: RecordSet = report(&diff(&left, &right, &keys(&["id"])).unwrap());
+        assert_eq!(report.column_order, keys(&[CHANGE_COLUMN, "id", "name"]));
+        let cell = |column: &str| report.records[0].columns[column].as_ref().unwrap().translate();
+        assert_eq!(cell(CHANGE_COLUMN), "changed");
+        assert_eq!(cell("name"), "a -> NULL");
+    }
+}
+
+/* <-- Tests */
@@ -0,0 +1,1023 @@
+/* --> Summary of Contents
+
+	Compare the table structures of two connections and write the
+	script that migrates one to the other
+
+	pub fn load(&ConnectionBase, &str) -> Result<SchemaSnapshot, String>
+		Tables, columns (type, NOT NULL, default, primary key) and
+		indexes. SQLite through its PRAGMAs, ODBC through
+		INFORMATION_SCHEMA and the SQL Server catalog views. Tables
+		outside dbo are named schema.table, so same-named tables of two
+		schemas stay apart and dbo ones still match SQLite's.
+
+	pub fn compare(&SchemaSnapshot, &SchemaSnapshot) -> Vec<SchemaChange>
+		What the target lacks or has in addition to the source. The
+		sqlb_temp_table_N tables DB Browser for SQLite leaves behind when a
+		table edit is interrupted are reported as copies of the table
+		with the same columns.
+
+	pub fn migration_script(&[SchemaChange], &SchemaSnapshot, &SchemaSnapshot) -> String
+		CREATE / ALTER statements in the target's dialect. SQLite cannot
+		change a column in place, those tables are rebuilt and their rows
+		copied over. Drops are written commented out, to run by hand.
+
+	pub fn affinity(&str) -> &'static str
+		INTEGER, TEXT, BLOB, REAL or NUMERIC, SQLite's reading of a type name
+
+	pub fn ask_connections(&[String], usize) -> Option<(usize, usize)>
+	pub fn show_schema_diff(&str, &str, &[SchemaChange], &str)
+		Pick the source and target tabs; the findings and the script
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, channel, Receiver, Sender},
+    browser::HoldBrowser,
+    button::Button,
+    dialog,
+    enums::Font,
+    frame::Frame,
+    group::Flex,
+    menu::Choice,
+    prelude::{BrowserExt, DisplayExt, GroupExt, MenuExt, WidgetBase, WidgetExt, WindowExt},
+    text::{TextBuffer, TextEditor},
+    window::Window,
+};
+
+use crate::cli;
+use crate::learning_fltk::escape_menu_label;
+use crate::sql_aux_funcs::{ConnectionBase, QueryType, Record, RecordSet};
+use crate::sql_format::Dialect;
+
+/* <-- Imports */
+/* --> Const */
+
+const COPY_PREFIX: &str = "sqlb_temp_table_";
+const REBUILD_SUFFIX: &str = "__migrate";
+const EXPRESSION: &str = "<expression>";
+const DEFAULT_SCHEMA: &str = "dbo";
+
+const TSQL_COLUMNS: &str = "\
+SELECT c.TABLE_SCHEMA, c.TABLE_NAME, c.COLUMN_NAME, c.DATA_TYPE, c.CHARACTER_MAXIMUM_LENGTH, c.NUMERIC_PRECISION, c.NUMERIC_SCALE, c.IS_NULLABLE, c.COLUMN_DEFAULT
+FROM INFORMATION_SCHEMA.COLUMNS c
+JOIN INFORMATION_SCHEMA.TABLES t ON t.TABLE_SCHEMA = c.TABLE_SCHEMA AND t.TABLE_NAME = c.TABLE_NAME
+WHERE t.TABLE_TYPE = 'BASE TABLE'
+ORDER BY c.TABLE_SCHEMA, c.TABLE_NAME, c.ORDINAL_POSITION";
+
+const TSQL_PRIMARY_KEYS: &str = "\
+SELECT k.TABLE_SCHEMA, k.TABLE_NAME, k.COLUMN_NAME
+FROM INFORMATION_SCHEMA.TABLE_CONSTRAINTS tc
+JOIN INFORMATION_SCHEMA.KEY_COLUMN_USAGE k ON k.CONSTRAINT_SCHEMA = tc.CONSTRAINT_SCHEMA AND k.CONSTRAINT_NAME = tc.CONSTRAINT_NAME
+    AND k.TABLE_SCHEMA = tc.TABLE_SCHEMA AND k.TABLE_NAME = tc.TABLE_NAME
+WHERE tc.CONSTRAINT_TYPE = 'PRIMARY KEY'";
+
+const TSQL_INDEXES: &str = "\
+SELECT SCHEMA_NAME(t.schema_id) AS schema_name, t.name AS table_name, i.name AS index_name, i.is_unique, i.is_unique_constraint, c.name AS column_name
+FROM sys.indexes i
+JOIN sys.tables t ON t.object_id = i.object_id
+JOIN sys.index_columns ic ON ic.object_id = i.object_id AND ic.index_id = i.index_id
+JOIN sys.columns c ON c.object_id = ic.object_id AND c.column_id = ic.column_id
+WHERE i.is_primary_key = 0 AND i.type > 0 AND ic.is_included_column = 0
+ORDER BY schema_name, t.name, i.name, ic.key_ordinal";
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Debug)]
+pub enum SchemaChange {
+    MissingTable { table: TableDef, copy_of: Option<String> },
+    ExtraTable { table: TableDef, copy_of: Option<String> },
+    MissingColumn { table: String, column: ColumnDef },
+    ExtraColumn { table: String, column: ColumnDef },
+    ColumnChanged { table: String, source: ColumnDef, target: ColumnDef },
+    MissingIndex { table: String, index: IndexDef },
+    ExtraIndex { table: String, index: IndexDef },
+    IndexChanged { table: String, source: IndexDef, target: IndexDef },
+}
+
+#[derive(Clone, Copy)]
+enum DialogMessage {
+    Compare,
+    Cancel,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnDef {
+    pub name: String,
+    pub data_type: String,
+    pub not_null: bool,
+    pub default: Option<String>,
+    pub primary_key: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexDef {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub unique: bool,
+    pub constraint: bool, // backs a UNIQUE constraint, the engine picked the name
+}
+
+#[derive(Clone, Debug)]
+pub struct TableDef {
+    pub name: String,
+    pub columns: Vec<ColumnDef>,
+    pub indexes: Vec<IndexDef>,
+}
+
+pub struct SchemaSnapshot {
+    pub dialect: Dialect,
+    pub tables: Vec<TableDef>,
+}
+
+impl TableDef {
+    fn column(&self, name: &str) -> Option<&ColumnDef> {
+        self.columns.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    // constraint indexes have generated names, they are matched on what they cover
+    fn index(&self, wanted: &IndexDef) -> Option<&IndexDef> {
+        self.indexes.iter().find(|i| match wanted.constraint {
+            true => i.constraint && i.unique == wanted.unique && same_names(&i.columns, &wanted.columns),
+            false => !i.constraint && i.name.eq_ignore_ascii_case(&wanted.name),
+        })
+    }
+}
+
+impl SchemaSnapshot {
+    fn table(&self, name: &str) -> Option<&TableDef> {
+        self.tables.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+    }
+
+    // the table a sqlb_temp_table_N was copied from, the one with the same columns
+    fn original_of(&self, table: &TableDef, other: &SchemaSnapshot) -> Option<String> {
+        if !table.name.starts_with(COPY_PREFIX) {
+            return None;
+        }
+        let names: Vec<String> = table.columns.iter().map(|c| c.name.clone()).collect();
+        self.tables
+            .iter()
+            .chain(other.tables.iter())
+            .filter(|t| !t.name.starts_with(COPY_PREFIX))
+            .find(|t| same_names(&t.columns.iter().map(|c| c.name.clone()).collect::<Vec<String>>(), &names))
+            .map(|t| t.name.clone())
+    }
+}
+
+impl SchemaChange {
+    pub fn describe(&self) -> String {
+        match self {
+            SchemaChange::MissingTable { table, copy_of: None } => format!("Table {} is missing", table.name),
+            SchemaChange::MissingTable { table, copy_of: Some(original) } => {
+                format!("Table {} is missing, only the source has this copy of {}", table.name, original)
+            }
+            SchemaChange::ExtraTable { table, copy_of: None } => format!("Table {} is only in the target", table.name),
+            SchemaChange::ExtraTable { table, copy_of: Some(original) } => {
+                format!("Table {} is only in the target, a leftover copy of {}", table.name, original)
+            }
+            SchemaChange::MissingColumn { table, column } => {
+                format!("Column {}.{} is missing ({})", table, column.name, column_summary(column))
+            }
+            SchemaChange::ExtraColumn { table, column } => format!("Column {}.{} is only in the target", table, column.name),
+            SchemaChange::ColumnChanged { table, source, target } => {
+                let mut parts: Vec<String> = Vec::new();
+                if normalize_type(&source.data_type) != normalize_type(&target.data_type) {
+                    parts.push(format!("type {} -> {}", shown_type(&target.data_type), shown_type(&source.data_type)));
+                }
+                if source.not_null != target.not_null {
+                    parts.push(String::from(if source.not_null { "NOT NULL added" } else { "NOT NULL dropped" }));
+                }
+                if normalize_default(&source.default) != normalize_default(&target.default) {
+                    parts.push(format!(
+                        "default {} -> {}",
+                        target.default.as_deref().unwrap_or("none"),
+                        source.default.as_deref().unwrap_or("none")
+                    ));
+                }
+                if source.primary_key != target.primary_key {
+                    parts.push(String::from(if source.primary_key { "becomes primary key" } else { "no longer primary key" }));
+                }
+                format!("Column {}.{}: {}", table, source.name, parts.join(", "))
+            }
+            SchemaChange::MissingIndex { table, index } => format!("Index {} is missing", index_summary(table, index)),
+            SchemaChange::ExtraIndex { table, index } => format!("Index {} is only in the target", index_summary(table, index)),
+            SchemaChange::IndexChanged { table, source, target } => {
+                format!("Index {} differs, the target has {}", index_summary(table, source), index_summary(table, target))
+            }
+        }
+    }
+
+    fn table_name(&self) -> &str {
+        match self {
+            SchemaChange::MissingTable { table, .. } | SchemaChange::ExtraTable { table, .. } => &table.name,
+            SchemaChange::MissingColumn { table, .. }
+            | SchemaChange::ExtraColumn { table, .. }
+            | SchemaChange::ColumnChanged { table, .. }
+            | SchemaChange::MissingIndex { table, .. }
+            | SchemaChange::ExtraIndex { table, .. }
+            | SchemaChange::IndexChanged { table, .. } => table,
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn same_names(left: &[String], right: &[String]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| l.eq_ignore_ascii_case(r))
+}
+
+fn shown_type(data_type: &str) -> &str {
+    if data_type.is_empty() {
+        "(none)"
+    } else {
+        data_type
+    }
+}
+
+fn column_summary(column: &ColumnDef) -> String {
+    let mut summary: String = shown_type(&column.data_type).to_string();
+    if column.not_null {
+        summary.push_str(" NOT NULL");
+    }
+    if let Some(default) = &column.default {
+        summary.push_str(&format!(" DEFAULT {}", default));
+    }
+    if column.primary_key {
+        summary.push_str(" PRIMARY KEY");
+    }
+    summary
+}
+
+fn index_summary(table: &str, index: &IndexDef) -> String {
+    let kind: &str = if index.unique { "UNIQUE " } else { "" };
+    match index.constraint {
+        true => format!("{}({}) on {}", kind, index.columns.join(", "), table),
+        false => format!("{} {}({}) on {}", index.name, kind, index.columns.join(", "), table),
+    }
+}
+
+fn normalize_type(data_type: &str) -> String {
+    data_type.split_whitespace().collect::<Vec<&str>>().join(" ").to_uppercase()
+}
+
+// SQLite's type affinity rules, the common ground between a SQLite and a SQL Server column
+pub fn affinity(data_type: &str) -> &'static str {
+    let upper: String = data_type.to_uppercase();
+    if upper.contains("INT") {
+        "INTEGER"
+    } else if upper.contains("CHAR") || upper.contains("CLOB") || upper.contains("TEXT") {
+        "TEXT"
+    } else if upper.is_empty() || upper.contains("BLOB") || upper.contains("BINARY") {
+        "BLOB"
+    } else if upper.contains("REAL") || upper.contains("FLOA") || upper.contains("DOUB") {
+        "REAL"
+    } else {
+        "NUMERIC"
+    }
+}
+
+// SQL Server wraps defaults in parentheses: ((999)), ('Unset')
+fn normalize_default(default: &Option<String>) -> Option<String> {
+    let mut text: &str = default.as_deref()?.trim();
+    while text.starts_with('(') && text.ends_with(')') && text.len() >= 2 {
+        text = text[1..text.len() - 1].trim();
+    }
+    (!text.is_empty() && !text.eq_ignore_ascii_case("null")).then(|| text.to_string())
+}
+
+fn query(connection_type: &ConnectionBase, connection: &str, sql: &str) -> Result<RecordSet, String> {
+    cli::run_request(connection_type, connection, QueryType::UserDefined(sql.to_string()))
+}
+
+fn text(record: &Record, column: &str) -> String {
+    record.text(column).unwrap_or_default()
+}
+
+fn flag(record: &Record, column: &str) -> bool {
+    matches!(text(record, column).trim(), "1" | "true" | "True")
+}
+
+pub fn load(connection_type: &ConnectionBase, connection: &str) -> Result<SchemaSnapshot, String> {
+    match connection_type {
+        ConnectionBase::Sqlite => load_sqlite(connection),
+        ConnectionBase::Odbc => load_tsql(connection),
+    }
+}
+
+fn load_sqlite(connection: &str) -> Result<SchemaSnapshot, String> {
+    let base: ConnectionBase = ConnectionBase::Sqlite;
+    let names: RecordSet = query(&base, connection, "SELECT name FROM sqlite_schema WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")?;
+    let mut tables: Vec<TableDef> = Vec::new();
+    for record in &names.records {
+        let name: String = text(record, "name");
+        let columns: RecordSet = query(&base, connection, &format!("PRAGMA table_info({})", quote(&name, Dialect::Sqlite)))?;
+        let index_list: RecordSet = query(&base, connection, &format!("PRAGMA index_list({})", quote(&name, Dialect::Sqlite)))?;
+        let mut indexes: Vec<IndexDef> = Vec::new();
+        for index in &index_list.records {
+            // "c" CREATE INDEX, "u" UNIQUE constraint, "pk" the primary key (kept on the columns instead)
+            let origin: String = text(index, "origin");
+            if origin == "pk" {
+                continue;
+            }
+            let index_name: String = text(index, "name");
+            let info: RecordSet = query(&base, connection, &format!("PRAGMA index_info({})", quote(&index_name, Dialect::Sqlite)))?;
+            indexes.push(IndexDef {
+                name: index_name,
+                // an index on an expression has no column name
+                columns: info.records.iter().map(|r| r.text("name").unwrap_or(String::from(EXPRESSION))).collect(),
+                unique: flag(index, "unique"),
+                constraint: origin == "u",
+            });
+        }
+        tables.push(TableDef {
+            name,
+            columns: columns
+                .records
+                .iter()
+                .map(|c| ColumnDef {
+                    name: text(c, "name"),
+                    data_type: text(c, "type"),
+                    not_null: flag(c, "notnull"),
+                    default: c.text("dflt_value"),
+                    primary_key: text(c, "pk").trim() != "0",
+                })
+                .collect(),
+            indexes,
+        });
+    }
+    Ok(SchemaSnapshot { dialect: Dialect::Sqlite, tables })
+}
+
+// the default schema goes unnamed, like the tables of a SQLite file
+fn qualified_name(schema: &str, table: &str) -> String {
+    match schema.trim() {
+        "" => table.to_string(),
+        schema if schema.eq_ignore_ascii_case(DEFAULT_SCHEMA) => table.to_string(),
+        schema => format!("{}.{}", schema, table),
+    }
+}
+
+fn load_tsql(connection: &str) -> Result<SchemaSnapshot, String> {
+    let base: ConnectionBase = ConnectionBase::Odbc;
+    let columns: RecordSet = query(&base, connection, TSQL_COLUMNS)?;
+    let keys: RecordSet = query(&base, connection, TSQL_PRIMARY_KEYS)?;
+    // sys.* views are SQL Server only, other drivers are compared without indexes
+    let indexes: RecordSet = query(&base, connection, TSQL_INDEXES).unwrap_or_default();
+    Ok(tsql_snapshot(&columns, &keys, &indexes))
+}
+
+fn tsql_snapshot(columns: &RecordSet, keys: &RecordSet, indexes: &RecordSet) -> SchemaSnapshot {
+    let mut tables: Vec<TableDef> = Vec::new();
+    for record in &columns.records {
+        let table_name: String = qualified_name(&text(record, "TABLE_SCHEMA"), &text(record, "TABLE_NAME"));
+        let column_name: String = text(record, "COLUMN_NAME");
+        let mut data_type: String = text(record, "DATA_TYPE");
+        let length: String = text(record, "CHARACTER_MAXIMUM_LENGTH");
+        if !length.is_empty() {
+            data_type = format!("{}({})", data_type, if length == "-1" { "max" } else { &length });
+        } else if matches!(data_type.to_lowercase().as_str(), "decimal" | "numeric") {
+            data_type = format!("{}({}, {})", data_type, text(record, "NUMERIC_PRECISION"), text(record, "NUMERIC_SCALE"));
+        }
+        let primary_key: bool = keys
+            .records
+            .iter()
+            .any(|k| qualified_name(&text(k, "TABLE_SCHEMA"), &text(k, "TABLE_NAME")) == table_name && text(k, "COLUMN_NAME") == column_name);
+        let column: ColumnDef = ColumnDef {
+            name: column_name,
+            data_type,
+            not_null: text(record, "IS_NULLABLE").eq_ignore_ascii_case("NO"),
+            default: record.text("COLUMN_DEFAULT"),
+            primary_key,
+        };
+        match tables.iter_mut().find(|t| t.name == table_name) {
+            Some(table) => table.columns.push(column),
+            None => tables.push(TableDef { name: table_name, columns: vec![column], indexes: Vec::new() }),
+        }
+    }
+    // one row per indexed column, in key order
+    for record in &indexes.records {
+        let index_name: String = text(record, "index_name");
+        let table_name: String = qualified_name(&text(record, "schema_name"), &text(record, "table_name"));
+        if let Some(table) = tables.iter_mut().find(|t| t.name == table_name) {
+            match table.indexes.iter_mut().find(|i| i.name == index_name) {
+                Some(index) => index.columns.push(text(record, "column_name")),
+                None => table.indexes.push(IndexDef {
+                    name: index_name,
+                    columns: vec![text(record, "column_name")],
+                    unique: flag(record, "is_unique"),
+                    constraint: flag(record, "is_unique_constraint"),
+                }),
+            }
+        }
+    }
+    SchemaSnapshot { dialect: Dialect::TSql, tables }
+}
+
+// the changes that make target look like source
+pub fn compare(source: &SchemaSnapshot, target: &SchemaSnapshot) -> Vec<SchemaChange> {
+    let same_dialect: bool = source.dialect == target.dialect;
+    let mut changes: Vec<SchemaChange> = Vec::new();
+    for table in &source.tables {
+        let existing: &TableDef = match target.table(&table.name) {
+            Some(existing) => existing,
+            None => {
+                changes.push(SchemaChange::MissingTable {
+                    table: table.clone(),
+                    copy_of: source.original_of(table, target),
+                });
+                continue;
+            }
+        };
+        for column in &table.columns {
+            match existing.column(&column.name) {
+                None => changes.push(SchemaChange::MissingColumn { table: existing.name.clone(), column: column.clone() }),
+                Some(current) => {
+                    let type_differs: bool = match same_dialect {
+                        true => normalize_type(&column.data_type) != normalize_type(&current.data_type),
+                        // nvarchar(50) and TEXT hold the same values, only the affinity is compared
+                        false => affinity(&column.data_type) != affinity(&current.data_type),
+                    };
+                    if type_differs
+                        || column.not_null != current.not_null
+                        || column.primary_key != current.primary_key
+                        || normalize_default(&column.default) != normalize_default(&current.default)
+                    {
+                        changes.push(SchemaChange::ColumnChanged {
+                            table: existing.name.clone(),
+                            source: column.clone(),
+                            target: current.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        for column in &existing.columns {
+            if table.column(&column.name).is_none() {
+                changes.push(SchemaChange::ExtraColumn { table: existing.name.clone(), column: column.clone() });
+            }
+        }
+        for index in &table.indexes {
+            match existing.index(index) {
+                None => changes.push(SchemaChange::MissingIndex { table: existing.name.clone(), index: index.clone() }),
+                Some(current) if current.unique != index.unique || !same_names(&current.columns, &index.columns) => {
+                    changes.push(SchemaChange::IndexChanged {
+                        table: existing.name.clone(),
+                        source: index.clone(),
+                        target: current.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for index in &existing.indexes {
+            if table.index(index).is_none() {
+                changes.push(SchemaChange::ExtraIndex { table: existing.name.clone(), index: index.clone() });
+            }
+        }
+    }
+    for table in &target.tables {
+        if source.table(&table.name).is_none() {
+            changes.push(SchemaChange::ExtraTable {
+                table: table.clone(),
+                copy_of: target.original_of(table, source),
+            });
+        }
+    }
+    changes
+}
+
+pub fn quote(name: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Sqlite => format!("\"{}\"", name.replace('"', "\"\"")),
+        Dialect::TSql => format!("[{}]", name.replace(']', "]]")),
+    }
+}
+
+// a text value written into the SQL, both dialects double the single quote
+pub fn quote_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+// schema.table, each part quoted on its own
+pub fn quote_table(name: &str, dialect: Dialect) -> String {
+    name.split('.').map(|part| quote(part, dialect)).collect::<Vec<String>>().join(".")
+}
+
+// a table of the snapshots: schema.table on SQL Server. SQLite has no schemas
+// but attached files, there the name is a single identifier
+fn table_sql(name: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::Sqlite => quote(name, dialect),
+        Dialect::TSql => quote_table(name, dialect),
+    }
+}
+
+fn quote_list(names: &[String], dialect: Dialect) -> String {
+    names.iter().map(|n| quote(n, dialect)).collect::<Vec<String>>().join(", ")
+}
+
+// the source type as the target writes it
+fn target_type(data_type: &str, source: Dialect, target: Dialect) -> String {
+    if source == target || target == Dialect::Sqlite {
+        // SQLite takes any type name and derives the affinity from it
+        return data_type.to_string();
+    }
+    match normalize_type(data_type).as_str() {
+        "" | "TEXT" | "CLOB" => String::from("nvarchar(max)"),
+        "INTEGER" => String::from("int"),
+        "REAL" | "DOUBLE" | "DOUBLE PRECISION" => String::from("float"),
+        "BLOB" => String::from("varbinary(max)"),
+        "NUMERIC" => String::from("decimal(18, 4)"),
+        "BOOLEAN" => String::from("bit"),
+        _ => data_type.to_string(),
+    }
+}
+
+fn column_sql(column: &ColumnDef, source: Dialect, target: Dialect, inline_key: bool) -> String {
+    let mut sql: String = format!("{} {}", quote(&column.name, target), target_type(&column.data_type, source, target));
+    if inline_key {
+        sql.push_str(" PRIMARY KEY");
+    }
+    sql.push_str(if column.not_null { " NOT NULL" } else if target == Dialect::TSql { " NULL" } else { "" });
+    if let Some(default) = normalize_default(&column.default) {
+        sql.push_str(&format!(" DEFAULT {}", default));
+    }
+    sql.trim_end().to_string()
+}
+
+fn create_table_sql(name: &str, columns: &[ColumnDef], unique: &[&IndexDef], source: Dialect, target: Dialect) -> String {
+    let keys: Vec<String> = columns.iter().filter(|c| c.primary_key).map(|c| c.name.clone()).collect();
+    let mut lines: Vec<String> = columns.iter().map(|c| format!("    {}", column_sql(c, source, target, false))).collect();
+    for index in unique {
+        lines.push(format!("    UNIQUE ({})", quote_list(&index.columns, target)));
+    }
+    if !keys.is_empty() {
+        lines.push(format!("    PRIMARY KEY ({})", quote_list(&keys, target)));
+    }
+    format!("CREATE TABLE {} (\n{}\n);", table_sql(name, target), lines.join(",\n"))
+}
+
+fn create_index_sql(table: &str, index: &IndexDef, target: Dialect) -> String {
+    if index.columns.iter().any(|c| c == EXPRESSION) {
+        return format!("-- {} is on an expression, create it by hand", index_summary(table, index));
+    }
+    // constraint indexes got their name from the engine, the copy gets a readable one
+    let name: String = match index.constraint {
+        true => format!("ux_{}_{}", table, index.columns.join("_")).replace([' ', '.'], "_"),
+        false => index.name.clone(),
+    };
+    format!(
+        "CREATE {}INDEX {} ON {} ({});",
+        if index.unique { "UNIQUE " } else { "" },
+        quote(&name, target),
+        table_sql(table, target),
+        quote_list(&index.columns, target)
+    )
+}
+
+fn drop_index_sql(table: &str, index: &IndexDef, target: Dialect) -> String {
+    match target {
+        Dialect::Sqlite => format!("DROP INDEX {};", quote(&index.name, target)),
+        Dialect::TSql => format!("DROP INDEX {} ON {};", quote(&index.name, target), table_sql(table, target)),
+    }
+}
+
+// SQLite changes columns by building the table again and copying the rows over
+fn sqlite_rebuild(source: &TableDef, target: &TableDef, from: Dialect) -> Vec<String> {
+    // target only columns are kept, dropping them stays a decision made by hand
+    let mut columns: Vec<ColumnDef> = source.columns.clone();
+    columns.extend(target.columns.iter().filter(|c| source.column(&c.name).is_none()).cloned());
+    let common: Vec<String> = source
+        .columns
+        .iter()
+        .filter_map(|c| target.column(&c.name).map(|_| c.name.clone()))
+        .chain(target.columns.iter().filter(|c| source.column(&c.name).is_none()).map(|c| c.name.clone()))
+        .collect();
+    let unique: Vec<&IndexDef> = source.indexes.iter().filter(|i| i.constraint).collect();
+    let temporary: String = format!("{}{}", target.name, REBUILD_SUFFIX);
+
+    let mut lines: Vec<String> = vec![format!("-- rebuild {}: SQLite cannot change a column in place", target.name)];
+    lines.push(create_table_sql(&temporary, &columns, &unique, from, Dialect::Sqlite));
+    lines.push(format!(
+        "INSERT INTO {} ({}) SELECT {} FROM {};",
+        quote(&temporary, Dialect::Sqlite),
+        quote_list(&common, Dialect::Sqlite),
+        quote_list(&common, Dialect::Sqlite),
+        quote(&target.name, Dialect::Sqlite)
+    ));
+    lines.push(format!("DROP TABLE {};", quote(&target.name, Dialect::Sqlite)));
+    lines.push(format!("ALTER TABLE {} RENAME TO {};", quote(&temporary, Dialect::Sqlite), quote(&target.name, Dialect::Sqlite)));
+    // indexes go with the old table: the source's, and the target's own ones that are kept
+    for index in source.indexes.iter().chain(target.indexes.iter().filter(|i| source.index(i).is_none())) {
+        if !index.constraint {
+            lines.push(create_index_sql(&target.name, index, Dialect::Sqlite));
+        }
+    }
+    lines
+}
+
+pub fn migration_script(changes: &[SchemaChange], source: &SchemaSnapshot, target: &SchemaSnapshot) -> String {
+    let (from, to): (Dialect, Dialect) = (source.dialect, target.dialect);
+    let mut body: Vec<String> = Vec::new();
+    let mut rebuilt: Vec<String> = Vec::new();
+
+    // tables SQLite has to rebuild: changed columns, and added ones it cannot take with ALTER TABLE
+    if to == Dialect::Sqlite {
+        for change in changes {
+            let needs_rebuild: bool = match change {
+                SchemaChange::ColumnChanged { .. } => true,
+                SchemaChange::MissingColumn { column, .. } => {
+                    column.primary_key || (column.not_null && normalize_default(&column.default).is_none())
+                }
+                _ => false,
+            };
+            if needs_rebuild && !rebuilt.iter().any(|t| t.eq_ignore_ascii_case(change.table_name())) {
+                rebuilt.push(change.table_name().to_string());
+            }
+        }
+    }
+    let is_rebuilt = |table: &str| rebuilt.iter().any(|t| t.eq_ignore_ascii_case(table));
+
+    for change in changes {
+        let table_name: &str = change.table_name();
+        match change {
+            SchemaChange::MissingTable { table, copy_of } => {
+                let unique: Vec<&IndexDef> = table.indexes.iter().filter(|i| i.constraint).collect();
+                let mut statements: Vec<String> = vec![create_table_sql(&table.name, &table.columns, &unique, from, to)];
+                statements.extend(table.indexes.iter().filter(|i| !i.constraint).map(|i| create_index_sql(&table.name, i, to)));
+                match copy_of {
+                    // replicating a leftover copy is not what anyone wants, it stays commented out
+                    Some(original) => {
+                        body.push(format!("-- {} looks like a leftover copy of {}, not created", table.name, original));
+                        body.extend(statements.iter().flat_map(|s| s.lines().map(|l| format!("-- {}", l)).collect::<Vec<String>>()));
+                    }
+                    None => body.extend(statements),
+                }
+            }
+            SchemaChange::ExtraTable { table, copy_of } => {
+                if let Some(original) = copy_of {
+                    body.push(format!("-- {} looks like a leftover copy of {}", table.name, original));
+                }
+                body.push(format!("-- DROP TABLE {};", table_sql(&table.name, to)));
+            }
+            _ if is_rebuilt(table_name) => {}
+            SchemaChange::MissingColumn { table, column } => match to {
+                Dialect::Sqlite => body.push(format!("ALTER TABLE {} ADD COLUMN {};", table_sql(table, to), column_sql(column, from, to, false))),
+                Dialect::TSql => {
+                    if column.not_null && normalize_default(&column.default).is_none() {
+                        body.push(format!("-- {}.{} is NOT NULL without a default, this fails when the table has rows", table, column.name));
+                    }
+                    body.push(format!("ALTER TABLE {} ADD {};", table_sql(table, to), column_sql(column, from, to, false)));
+                }
+            },
+            SchemaChange::ExtraColumn { table, column } => {
+                body.push(format!("-- ALTER TABLE {} DROP COLUMN {};", table_sql(table, to), quote(&column.name, to)));
+            }
+            SchemaChange::ColumnChanged { table, source: wanted, target: current } => {
+                // SQLite tables with changed columns were all rebuilt above, this is SQL Server
+                let column: String = quote(&wanted.name, to);
+                let type_sql: String = target_type(&wanted.data_type, from, to);
+                if normalize_type(&type_sql) != normalize_type(&current.data_type) || wanted.not_null != current.not_null {
+                    body.push(format!(
+                        "ALTER TABLE {} ALTER COLUMN {} {} {};",
+                        table_sql(table, to),
+                        column,
+                        type_sql,
+                        if wanted.not_null { "NOT NULL" } else { "NULL" }
+                    ));
+                }
+                if normalize_default(&wanted.default) != normalize_default(&current.default) {
+                    body.push(format!("-- drop the current default constraint of {}.{} first", table, wanted.name));
+                    if let Some(default) = normalize_default(&wanted.default) {
+                        body.push(format!("ALTER TABLE {} ADD DEFAULT {} FOR {};", table_sql(table, to), default, column));
+                    }
+                }
+                if wanted.primary_key != current.primary_key {
+                    body.push(format!("-- the primary key of {} differs on {}, change the constraint by hand", table, wanted.name));
+                }
+            }
+            SchemaChange::MissingIndex { table, index } => body.push(create_index_sql(table, index, to)),
+            SchemaChange::ExtraIndex { table, index } if index.constraint => {
+                body.push(format!("-- {} comes from a UNIQUE constraint, remove the constraint to drop it", index_summary(table, index)));
+            }
+            SchemaChange::ExtraIndex { table, index } => body.push(format!("-- {}", drop_index_sql(table, index, to))),
+            SchemaChange::IndexChanged { table, source: wanted, target: current } => {
+                body.push(drop_index_sql(table, current, to));
+                body.push(create_index_sql(table, wanted, to));
+            }
+        }
+    }
+    for name in &rebuilt {
+        if let (Some(wanted), Some(current)) = (source.table(name), target.table(name)) {
+            body.extend(sqlite_rebuild(wanted, current, from));
+        }
+    }
+
+    let mut script: Vec<String> = vec![
+        String::from("-- Migration generated by DaedriVictus, review before running."),
+        String::from("-- Drops are commented out, run them by hand once the data is safe."),
+    ];
+    if body.is_empty() {
+        script.push(String::from("-- The structures match, nothing to migrate."));
+        return script.join("\n") + "\n";
+    }
+    // the rebuilds drop tables that others may reference
+    if !rebuilt.is_empty() {
+        script.push(String::from("PRAGMA foreign_keys = OFF;"));
+    }
+    script.push(String::from("BEGIN TRANSACTION;"));
+    script.extend(body);
+    script.push(String::from("COMMIT;"));
+    if !rebuilt.is_empty() {
+        script.push(String::from("PRAGMA foreign_keys = ON;"));
+    }
+    script.join("\n") + "\n"
+}
+
+pub fn ask_connections(labels: &[String], active: usize) -> Option<(usize, usize)> {
+    if labels.is_empty() {
+        return None;
+    }
+    let (sndr, rcvr): (Sender<DialogMessage>, Receiver<DialogMessage>) = channel::<DialogMessage>();
+    let mut win: Window = Window::default()
+        .with_size(480, 125)
+        .with_label("Compare Schemas")
+        .center_screen();
+    win.make_modal(true);
+
+    let mut source: Choice = Choice::new(80, 10, 390, 25, "Source");
+    source.set_tooltip("The structure to keep");
+    let mut target: Choice = Choice::new(80, 45, 390, 25, "Target");
+    target.set_tooltip("The database the migration script changes");
+    for choice in [&mut source, &mut target] {
+        for label in labels {
+            choice.add_choice(&escape_menu_label(&label.replace('|', " ")));
+        }
+    }
+    source.set_value(active as i32);
+    target.set_value(if labels.len() > 1 && active == 0 { 1 } else { 0 });
+
+    let mut compare_butn: Button = Button::new(310, 85, 75, 28, "&Compare");
+    compare_butn.emit(sndr, DialogMessage::Compare);
+    let mut cancel_butn: Button = Button::new(395, 85, 75, 28, "Cancel");
+    cancel_butn.emit(sndr, DialogMessage::Cancel);
+    win.end();
+    win.show();
+
+    let mut chosen: Option<(usize, usize)> = None;
+    while win.shown() {
+        app::wait();
+        match rcvr.recv() {
+            Some(DialogMessage::Compare) => {
+                if let (Ok(s), Ok(t)) = (usize::try_from(source.value()), usize::try_from(target.value())) {
+                    chosen = Some((s, t));
+                    win.hide();
+                }
+            }
+            Some(DialogMessage::Cancel) => win.hide(),
+            None => {}
+        }
+    }
+    chosen
+}
+
+pub fn show_schema_diff(source_label: &str, target_label: &str, changes: &[SchemaChange], script: &str) {
+    let mut win: Window = Window::default()
+        .with_size(860, 620)
+        .with_label(&format!("Schema: {} -> {}", source_label, target_label))
+        .center_screen();
+    let mut layout: Flex = Flex::default_fill().column();
+    layout.set_margin(5);
+
+    let mut findings: HoldBrowser = HoldBrowser::default();
+    layout.fixed(&findings, 200);
+    let mut editor: TextEditor = TextEditor::default();
+    editor.set_buffer(TextBuffer::default());
+    editor.set_text_font(Font::Courier);
+    let mut buttons: Flex = Flex::default().row();
+    let mut status: Frame = Frame::default();
+    let mut copy_butn: Button = Button::default().with_label("Cop&y");
+    let mut save_butn: Button = Button::default().with_label("&Save...");
+    buttons.fixed(&copy_butn, 80);
+    buttons.fixed(&save_butn, 80);
+    buttons.end();
+    layout.fixed(&buttons, 30);
+    layout.end();
+    win.end();
+    win.make_resizable(true);
+
+    if changes.is_empty() {
+        findings.add("The structures match.");
+    }
+    for change in changes {
+        // '@' starts a format code in browser lines
+        findings.add(&change.describe().replace('@', "@@"));
+    }
+    if let Some(mut buffer) = editor.buffer() {
+        buffer.set_text(script);
+    }
+
+    copy_butn.set_callback({
+        let editor: TextEditor = editor.clone();
+        move |_| {
+            if let Some(buffer) = editor.buffer() {
+                app::copy(&buffer.text());
+            }
+        }
+    });
+    save_butn.set_callback(move |_| {
+        let mut chooser = dialog::FileDialog::new(dialog::FileDialogType::BrowseSaveFile);
+        chooser.set_title("Save the migration script");
+        chooser.set_filter("*.sql");
+        chooser.set_option(dialog::FileDialogOptions::SaveAsConfirm);
+        chooser.show();
+        let path: String = chooser.filename().to_string_lossy().into_owned();
+        if path.is_empty() {
+            return;
+        }
+        let text: String = editor.buffer().map(|b| b.text()).unwrap_or_default();
+        match std::fs::write(&path, text) {
+            Ok(_) => status.set_label(&format!("Saved to {}", path)),
+            Err(e) => println!("Unable to write {}: {}", path, e),
+        }
+    });
+    win.show();
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql_aux_funcs::SqlData;
+
+    fn column(name: &str, data_type: &str, not_null: bool, primary_key: bool) -> ColumnDef {
+        ColumnDef { name: name.to_string(), data_type: data_type.to_string(), not_null, default: None, primary_key }
+    }
+
+    fn index(name: &str, columns: &[&str], unique: bool) -> IndexDef {
+        IndexDef { name: name.to_string(), columns: columns.iter().map(|c| c.to_string()).collect(), unique, constraint: false }
+    }
+
+    fn table(name: &str, columns: Vec<ColumnDef>, indexes: Vec<IndexDef>) -> TableDef {
+        TableDef { name: name.to_string(), columns, indexes }
+    }
+
+    fn tools(extra: Vec<ColumnDef>) -> TableDef {
+        let mut columns: Vec<ColumnDef> = vec![column("id", "INTEGER", true, true), column("name", "TEXT", false, false)];
+        columns.extend(extra);
+        table("tools", columns, vec![index("ix_tools_name", &["name"], false)])
+    }
+
+    fn snapshot(dialect: Dialect, tables: Vec<TableDef>) -> SchemaSnapshot {
+        SchemaSnapshot { dialect, tables }
+    }
+
+    // catalog rows as the ODBC driver returns them, every value as text
+    fn rows(columns: &[&str], values: &[&[&str]]) -> RecordSet {
+        let mut record_set: RecordSet = RecordSet { column_order: columns.iter().map(|c| c.to_string()).collect(), ..Default::default() };
+        for row in values {
+            let mut record: Record = Record::default();
+            for (column, value) in columns.iter().zip(row.iter()) {
+                record.columns.insert(column.to_string(), Some(SqlData::Odbc(value.to_string())));
+            }
+            record_set.records.push(record);
+        }
+        record_set
+    }
+
+    #[test]
+    fn identical_structures_have_no_changes() {
+        let source: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![tools(Vec::new())]);
+        let target: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![tools(Vec::new())]);
+        assert!(compare(&source, &target).is_empty());
+        assert!(migration_script(&[], &source, &target).contains("nothing to migrate"));
+    }
+
+    #[test]
+    fn finds_missing_extra_and_changed_parts() {
+        let mut source_tools: TableDef = tools(vec![column("due", "TEXT", false, false)]);
+        source_tools.columns[1].not_null = true;
+        let source: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![source_tools, table("owners", vec![column("id", "INTEGER", true, true)], Vec::new())]);
+        let mut target_tools: TableDef = tools(vec![column("legacy", "TEXT", false, false)]);
+        target_tools.indexes.clear();
+        let target: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![target_tools, table("scratch", Vec::new(), Vec::new())]);
+        let described: Vec<String> = compare(&source, &target).iter().map(SchemaChange::describe).collect();
+        assert_eq!(
+            described,
+            vec![
+                "Column tools.name: NOT NULL added",
+                "Column tools.due is missing (TEXT)",
+                "Column tools.legacy is only in the target",
+                "Index ix_tools_name (name) on tools is missing",
+                "Table owners is missing",
+                "Table scratch is only in the target",
+            ]
+        );
+    }
+
+    #[test]
+    fn across_dialects_only_the_affinity_counts() {
+        let source: SchemaSnapshot = snapshot(Dialect::TSql, vec![table("t", vec![column("name", "nvarchar(50)", false, false)], Vec::new())]);
+        let target: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![table("t", vec![column("name", "TEXT", false, false)], Vec::new())]);
+        assert!(compare(&source, &target).is_empty());
+        let target: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![table("t", vec![column("name", "INTEGER", false, false)], Vec::new())]);
+        assert_eq!(compare(&source, &target).len(), 1);
+    }
+
+    #[test]
+    fn leftover_copies_are_recognized() {
+        let copy: TableDef = table("sqlb_temp_table_3", tools(Vec::new()).columns, Vec::new());
+        let source: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![tools(Vec::new())]);
+        let target: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![tools(Vec::new()), copy]);
+        let changes: Vec<SchemaChange> = compare(&source, &target);
+        assert!(matches!(&changes[..], [SchemaChange::ExtraTable { copy_of: Some(original), .. }] if original == "tools"));
+        assert!(migration_script(&changes, &source, &target).contains("-- DROP TABLE \"sqlb_temp_table_3\";"));
+    }
+
+    #[test]
+    fn sqlite_adds_nullable_columns_and_rebuilds_for_changes() {
+        let source: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![tools(vec![column("due", "TEXT", false, false)])]);
+        let target: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![tools(Vec::new())]);
+        let script: String = migration_script(&compare(&source, &target), &source, &target);
+        assert!(script.contains("ALTER TABLE \"tools\" ADD COLUMN \"due\" TEXT;"), "{}", script);
+        assert!(script.contains("BEGIN TRANSACTION;") && script.ends_with("COMMIT;\n"));
+
+        let source: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![tools(vec![column("due", "TEXT", true, false)])]);
+        let script: String = migration_script(&compare(&source, &target), &source, &target);
+        assert!(script.contains("PRAGMA foreign_keys = OFF;"), "{}", script);
+        assert!(script.contains("INSERT INTO \"tools__migrate\" (\"id\", \"name\") SELECT \"id\", \"name\" FROM \"tools\";"), "{}", script);
+        assert!(script.contains("ALTER TABLE \"tools__migrate\" RENAME TO \"tools\";"));
+        assert!(script.contains("CREATE INDEX \"ix_tools_name\" ON \"tools\" (\"name\");"));
+    }
+
+    #[test]
+    fn tsql_alters_columns_in_place_and_comments_out_drops() {
+        let source: SchemaSnapshot = snapshot(Dialect::Sqlite, vec![table("t", vec![column("id", "INTEGER", true, false), column("note", "TEXT", true, false)], Vec::new())]);
+        let target: SchemaSnapshot = snapshot(
+            Dialect::TSql,
+            vec![table("t", vec![column("id", "int", false, false), column("old", "int", false, false)], vec![index("ix_old", &["old"], false)])],
+        );
+        let script: String = migration_script(&compare(&source, &target), &source, &target);
+        assert!(script.contains("ALTER TABLE [t] ALTER COLUMN [id] int NOT NULL;"), "{}", script);
+        assert!(script.contains("-- t.note is NOT NULL without a default"), "{}", script);
+        assert!(script.contains("ALTER TABLE [t] ADD [note] nvarchar(max) NOT NULL;"), "{}", script);
+        assert!(script.contains("-- ALTER TABLE [t] DROP COLUMN [old];"));
+        assert!(script.contains("-- DROP INDEX [ix_old] ON [t];"));
+    }
+
+    #[test]
+    fn tsql_tables_are_told_apart_by_schema() {
+        let header: [&str; 8] = ["TABLE_SCHEMA", "TABLE_NAME", "COLUMN_NAME", "DATA_TYPE", "CHARACTER_MAXIMUM_LENGTH", "NUMERIC_PRECISION", "NUMERIC_SCALE", "IS_NULLABLE"];
+        let columns: RecordSet = rows(
+            &header,
+            &[
+                &["dbo", "orders", "id", "int", "Null", "10", "0", "NO"],
+                &["dbo", "orders", "note", "nvarchar", "50", "Null", "Null", "YES"],
+                &["sales", "orders", "id", "int", "Null", "10", "0", "NO"],
+                &["sales", "orders", "total", "decimal", "Null", "18", "2", "YES"],
+            ],
+        );
+        // a key of the same name in the other schema does not count
+        let keys: RecordSet = rows(&["TABLE_SCHEMA", "TABLE_NAME", "COLUMN_NAME"], &[&["sales", "orders", "id"]]);
+        let indexes: RecordSet = rows(
+            &["schema_name", "table_name", "index_name", "is_unique", "is_unique_constraint", "column_name"],
+            &[&["dbo", "orders", "ix_note", "0", "0", "note"], &["sales", "orders", "ix_total", "0", "0", "total"]],
+        );
+        let snapshot: SchemaSnapshot = tsql_snapshot(&columns, &keys, &indexes);
+        let names: Vec<&str> = snapshot.tables.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["orders", "sales.orders"]);
+        let (dbo, sales): (&TableDef, &TableDef) = (&snapshot.tables[0], &snapshot.tables[1]);
+        assert!(!dbo.columns[0].primary_key && sales.columns[0].primary_key);
+        assert_eq!(dbo.columns[1].data_type, "nvarchar(50)");
+        assert_eq!(sales.columns[1].data_type, "decimal(18, 2)");
+        assert_eq!(dbo.indexes[0].name, "ix_note");
+        assert_eq!(sales.indexes[0].name, "ix_total");
+    }
+
+    #[test]
+    fn schema_qualified_tables_are_quoted_part_by_part() {
+        let mut orders: TableDef = table("sales.orders", vec![column("id", "int", true, true)], vec![index("ix_id", &["id"], false)]);
+        orders.indexes.push(IndexDef { constraint: true, ..index("", &["id"], true) });
+        let source: SchemaSnapshot = snapshot(Dialect::TSql, vec![orders]);
+        let target: SchemaSnapshot = snapshot(Dialect::TSql, Vec::new());
+        let script: String = migration_script(&compare(&source, &target), &source, &target);
+        assert!(script.contains("CREATE TABLE [sales].[orders] ("), "{}", script);
+        assert!(script.contains("CREATE INDEX [ix_id] ON [sales].[orders] ([id]);"), "{}", script);
+
+        let script: String = migration_script(&compare(&target, &source), &target, &source);
+        assert!(script.contains("-- DROP TABLE [sales].[orders];"), "{}", script);
+        // SQLite has no schemas, the copy keeps the dotted name as one identifier
+        let sqlite: SchemaSnapshot = snapshot(Dialect::Sqlite, Vec::new());
+        let script: String = migration_script(&compare(&source, &sqlite), &source, &sqlite);
+        assert!(script.contains("CREATE TABLE \"sales.orders\" ("), "{}", script);
+    }
+}
+
+/* <-- Tests */
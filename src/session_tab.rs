@@ -0,0 +1,403 @@
+/* --> Summary of Contents
+
+	One tab of the workspace: its own connection, SQL editor, result grid
+	and schema browser. Nothing is shared between tabs, so an ODBC server
+	and a local SQLite copy can stay open side by side.
+
+	pub fn build(usize, &mut Tabs, &Sender<Message>) -> SessionTab
+		Add a tab page to the workspace. Widgets inside the page tag their
+		messages with the tab id so results land in the tab that asked.
+
+	pub fn send_tables_request(&Sender<Message>, usize)
+		Fill the tables grid of a tab, like its Tables button does
+
+	SessionTab::grid_view(&DateSettings) -> Option<(RecordSet, DateColumns)>
+		The result as the record grid shows it: filtered by the filter box
+		and sorted by the last header clicked, dates compared as dates
+
+	SessionTab::preview
+		File panel at the right of the record grid, see file_paths.rs
+
+	SessionTab::state / SessionTab::restore_layout
+		Snapshot for workspace.rs and the way back: editor text, cursor
+		and the grid / editor splitter
+
+	pub fn tab_label(&Connection) -> String
+		"Production (ODBC)", "dv.db (SQLite)" or "Not connected"
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::Sender,
+    button::Button,
+    enums::{self, CallbackTrigger, Event},
+    frame::Frame,
+    group::{Flex, Group, Tabs, Tile},
+    input::Input,
+    table::TableContext,
+    prelude::{DisplayExt, GroupExt, InputExt, TableExt, WidgetBase, WidgetExt},
+    text::TextEditor,
+};
+use fltk_table::{SmartTable, TableOpts};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::date_columns::{self, DateColumns, DateSettings, RowFilter};
+use crate::file_paths::{self, Folder, PreviewPanel};
+use crate::learning_fltk::{FetchFlag, Message};
+use crate::sql_aux_funcs::{Connection, ConnectionBase, QueryType, RecordSet, Request};
+use crate::sql_completion::SchemaCache;
+use crate::sql_editor;
+use crate::statement_guard::Safety;
+use crate::workspace::TabState;
+
+/* <-- Imports */
+/* --> Const */
+
+const TAB_HEADER: i32 = 25;
+const MIN_PANE: i32 = 60;
+
+/* <-- Const */
+/* --> Structs */
+
+pub struct SessionTab {
+    pub id: usize,
+    pub conn: Connection,
+    pub page: Group,
+    pub editor: TextEditor,
+    pub result_pane: Flex, // record grid, above the editor in the tile
+    pub smart_tables: HashMap<String, SmartTable>, // record_grid, tables_grid, columns_grid
+    pub schema_cache: SchemaCache,                 // tables and columns fetched on this connection, for completion
+    pub filter_input: Input,
+    pub sort: Option<(String, bool)>, // column, descending
+    pub preview: PreviewPanel,
+    pub folders: Option<Vec<Folder>>, // cd_folders of this connection, read on the first path clicked
+}
+
+impl SessionTab {
+    pub fn label(&self) -> String {
+        tab_label(&self.conn)
+    }
+
+    // the tab header follows the connection
+    pub fn refresh_label(&mut self) {
+        self.page.set_label(&escape_label(&self.label()));
+        if let Some(mut tabs) = self.page.parent() {
+            tabs.redraw();
+        }
+    }
+
+    // forget everything that belonged to the previous connection
+    pub fn reset_connection(&mut self) {
+        self.conn = empty_connection();
+        self.schema_cache.clear();
+        self.folders = None;
+    }
+
+    // a header clicked twice sorts the other way round
+    pub fn sort_by(&mut self, column: String) {
+        self.sort = match self.sort.take() {
+            Some((current, descending)) if current == column => Some((current, !descending)),
+            _ => Some((column, false)),
+        };
+    }
+
+    pub fn filter(&self) -> Result<Option<RowFilter>, String> {
+        let text: String = self.filter_input.value();
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        let columns: Vec<String> = self.conn.record_set.as_ref().map(|rs| rs.column_order.clone()).unwrap_or_default();
+        date_columns::parse_filter(&text, &columns).map(Some)
+    }
+
+    pub fn grid_view(&mut self, settings: &DateSettings) -> Option<(RecordSet, DateColumns)> {
+        let record_set: &RecordSet = self.conn.record_set.as_ref()?;
+        let dates: DateColumns = record_set.date_columns(settings);
+        let mut view: RecordSet = match self.filter() {
+            Ok(Some(filter)) => record_set.filtered(&filter, &dates),
+            Ok(None) => record_set.clone(),
+            Err(e) => {
+                println!("{}", e);
+                record_set.clone()
+            }
+        };
+        // a new query may not have the column the grid was sorted by
+        match &self.sort {
+            Some((column, descending)) if view.column_order.contains(column) => view.sort_by_column(column, *descending, &dates),
+            Some(_) => self.sort = None,
+            None => {}
+        }
+        Some((view, dates))
+    }
+
+    // cd_folders is read once per connection, an empty list when the table is not there
+    pub fn path_folders(&mut self) -> Vec<Folder> {
+        if self.folders.is_none() {
+            self.folders = match (&self.conn.connection_type, &self.conn.connection) {
+                (Some(connection_type), Some(connection)) => Some(file_paths::load_folders(connection_type, connection)),
+                _ => Some(Vec::new()),
+            };
+        }
+        self.folders.clone().unwrap_or_default()
+    }
+
+    pub fn state(&self) -> TabState {
+        TabState::new(&self.conn, sql_editor::text(&self.editor), self.editor.insert_position(), self.result_pane.h())
+    }
+
+    pub fn restore_layout(&mut self, state: &TabState) {
+        sql_editor::set_text(&mut self.editor, &state.editor_text);
+        let length: i32 = self.editor.buffer().map_or(0, |b| b.length());
+        self.editor.set_insert_position(state.cursor.clamp(0, length));
+        self.editor.show_insert_position();
+
+        // move the splitter, both panes keep a usable height
+        let tile_height: i32 = self.result_pane.h() + self.editor.h();
+        if state.grid_height > 0 {
+            let grid_height: i32 = state.grid_height.clamp(MIN_PANE, tile_height - MIN_PANE);
+            let (x, y, w) = (self.result_pane.x(), self.result_pane.y(), self.result_pane.w());
+            self.result_pane.resize(x, y, w, grid_height);
+            self.editor.resize(x, y + grid_height, w, tile_height - grid_height);
+        }
+    }
+
+    // Tables / Columns results double as the completion cache
+    pub fn remember_schema(&mut self, request: &QueryType, record_set: &RecordSet) {
+        match request {
+            QueryType::SqlFunction(Request::Tables(_)) => self.schema_cache.record_tables(record_set),
+            QueryType::SqlFunction(Request::Columns(table)) => self.schema_cache.record_columns(table, record_set),
+            _ => {}
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn empty_connection() -> Connection {
+    Connection {
+        record_set: None,
+        connection: None,
+        result_code: None,
+        result_details: None,
+        connection_type: None,
+        profile_name: None,
+        safety: Safety::default(),
+    }
+}
+
+pub fn grid_opts(rows: i32, cols: i32) -> TableOpts {
+    TableOpts {
+        rows,
+        cols,
+        editable: false,
+        cell_font_size: 9,
+        header_font_size: 10,
+        cell_border_color: enums::Color::Light2,
+        ..Default::default()
+    }
+}
+
+pub fn build(id: usize, tabs: &mut Tabs, sender: &Sender<Message>) -> SessionTab {
+    let mut smart_tables: HashMap<String, SmartTable> = HashMap::new();
+    // tabs opened later must not leave the workspace as the current group, dialogs would end up inside it
+    let current: Option<Group> = Group::try_current();
+    tabs.begin();
+
+    // the gap between the tabs and the page is where the tab headers are drawn
+    let page: Group = Group::new(tabs.x(), tabs.y() + TAB_HEADER, tabs.w(), tabs.h() - TAB_HEADER, None);
+
+    // grid above the editor, the border between them can be dragged
+    let tile: Tile = Tile::new(page.x(), page.y(), 1000, 650, None);
+
+    // the file preview opens at the right of the grid when a path is clicked
+    let mut record_grid_group: Flex = Flex::new(tile.x(), tile.y(), 1000, 440, None).row();
+    smart_tables.insert(String::from("record_grid"), SmartTable::default().size_of_parent().with_opts(grid_opts(0, 0)));
+    let preview: PreviewPanel = PreviewPanel::build(&mut record_grid_group);
+    record_grid_group.end();
+
+    let completion_sndr: Sender<Message> = sender.clone();
+    let run_statement_sndr: Sender<Message> = sender.clone();
+    let editor: TextEditor = sql_editor::build(
+        &format!("sql_editor_{}", id),
+        1000,
+        210,
+        move || completion_sndr.send(Message::Complete(id)),
+        move || run_statement_sndr.send(Message::RunStatement(id)),
+    )
+    .below_of(&record_grid_group, 0);
+
+    tile.end();
+
+    let tables_grid_group: Flex = Flex::default().with_size(276, 325).right_of(&tile, 5);
+    smart_tables.insert(String::from("tables_grid"), SmartTable::default().with_size(276, 325).with_opts(grid_opts(20, 1)));
+    tables_grid_group.end();
+
+    let columns_grid_group: Flex = Flex::default().with_size(276, 325).below_of(&tables_grid_group, 5);
+    smart_tables.insert(String::from("columns_grid"), SmartTable::default().with_size(276, 325).with_opts(grid_opts(20, 1)));
+    columns_grid_group.end();
+
+    let mut pages_butn: Button = Button::default()
+        .with_size(75, 30)
+        .below_of(&tile, 3)
+        .with_label("&Page");
+    let mut pages_input: Input = Input::default()
+        .with_size(75, 30)
+        .below_of(&pages_butn, 3);
+    pages_input.set_value("1");
+    let mut query_butn: Button = Button::default()
+        .with_size(75, 30)
+        .right_of(&pages_butn, 4)
+        .with_label("&Submit");
+    let mut clear_butn: Button = Button::default()
+        .with_size(75, 30)
+        .below_of(&query_butn, 3)
+        .with_label("&Clear");
+    let mut tables_butn: Button = Button::default()
+        .with_size(75, 63)
+        .right_of(&query_butn, 4)
+        .with_label("&Tables");
+    let mut save_butn: Button = Button::default()
+        .with_size(75, 63)
+        .right_of(&tables_butn, 4)
+        .with_label("Sa&ve");
+    let mut observer_butn: Button = Button::default()
+        .with_size(75, 63)
+        .right_of(&save_butn, 4)
+        .with_label("Observe");
+    let mut explain_butn: Button = Button::default()
+        .with_size(75, 63)
+        .right_of(&observer_butn, 4)
+        .with_label("E&xplain");
+    let mut filter_label: Frame = Frame::default()
+        .with_size(300, 30)
+        .right_of(&explain_butn, 4)
+        .with_label("Filter rows, e.g. Calib Due Date < 1/1/2024");
+    filter_label.set_align(enums::Align::Left | enums::Align::Inside);
+    let mut filter_input: Input = Input::default()
+        .with_size(300, 30)
+        .below_of(&filter_label, 3);
+    filter_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+    filter_input.set_tooltip("<column> =, <>, <, <=, >, >= or contains <value>; \"= null\" finds empty dates");
+
+    page.end();
+    tabs.end();
+    Group::set_current(current.as_ref());
+
+    // event handling, message passing
+
+    let query_butn_sndr:  Sender<Message> = sender.clone();
+    let tables_butn_sndr: Sender<Message> = sender.clone();
+    let tables_grid_sndr: Sender<Message> = sender.clone();
+
+    query_butn.set_callback({
+        let editor: TextEditor = editor.clone();
+        move |_| {
+            query_butn_sndr.send(Message::Query(id, QueryType::UserDefined(sql_editor::text(&editor)), FetchFlag::False));
+            query_butn_sndr.send(Message::FillGrid(id, 1));
+        }
+    });
+    clear_butn.emit(sender.clone(), Message::ClearGrid(id));
+    save_butn.emit(sender.clone(), Message::Save(id));
+    pages_butn.emit(sender.clone(), Message::FillGrid(id, 1));
+    observer_butn.emit(sender.clone(), Message::LaunchObserver);
+    explain_butn.emit(sender.clone(), Message::Explain(id));
+    filter_input.emit(sender.clone(), Message::FilterGrid(id));
+
+    let record_grid_sndr: Sender<Message> = sender.clone();
+    smart_tables.get_mut("record_grid").unwrap().set_callback(move |record_grid_ref| {
+        // the table calls back on push and release, a header click sorts once
+        if fltk::app::event() != Event::Push {
+            return;
+        }
+        match record_grid_ref.callback_context() {
+            TableContext::ColHeader => record_grid_sndr.send(Message::SortGrid(id, record_grid_ref.callback_col())),
+            TableContext::Cell => record_grid_sndr.send(Message::PreviewCell(id, record_grid_ref.callback_row(), record_grid_ref.callback_col())),
+            _ => {}
+        }
+    });
+
+    tables_butn.handle(move |_, ev: Event| match ev {
+        Event::Push => {
+            send_tables_request(&tables_butn_sndr, id);
+            true
+        }
+        _ => false,
+    });
+
+    smart_tables.get_mut("tables_grid")
+    .unwrap()
+    .handle(move |tables_grid_ref, ev: Event| {
+        match ev {
+            Event::Push => {
+                if tables_grid_ref.get_selection() != (-1, -1, -1, -1) {
+                    tables_grid_sndr.send(Message::Query(id, QueryType::SqlFunction(Request::Columns(String::new())), FetchFlag::True));
+                    tables_grid_sndr.send(Message::FillGrid(id, 3));
+                }
+                true
+            },
+            _ => false,
+        }
+    });
+
+    let mut session: SessionTab = SessionTab {
+        id,
+        conn: empty_connection(),
+        page,
+        editor,
+        result_pane: record_grid_group,
+        smart_tables,
+        schema_cache: SchemaCache::default(),
+        filter_input,
+        sort: None,
+        preview,
+        folders: None,
+    };
+    session.refresh_label();
+    session
+}
+
+pub fn send_tables_request(sender: &Sender<Message>, id: usize) {
+    sender.send(Message::Query(id, QueryType::SqlFunction(Request::Tables(2)), FetchFlag::False));
+    sender.send(Message::FillGrid(id, 2));
+}
+
+pub fn tab_label(conn: &Connection) -> String {
+    let kind: &str = match conn.connection_type {
+        Some(ConnectionBase::Odbc) => "ODBC",
+        Some(ConnectionBase::Sqlite) => "SQLite",
+        None => return String::from("Not connected"),
+    };
+    let name: String = match (&conn.profile_name, &conn.connection) {
+        (Some(profile), _) => profile.clone(),
+        (None, Some(target)) => match conn.connection_type {
+            Some(ConnectionBase::Sqlite) => Path::new(target)
+                .file_name()
+                .map_or_else(|| target.clone(), |f| f.to_string_lossy().into_owned()),
+            // quick connect strings may hold a password, only the data source is shown
+            _ => odbc_source(target).unwrap_or_else(|| String::from("connection string")),
+        },
+        (None, None) => return String::from("Not connected"),
+    };
+    format!("{} ({})", name, kind)
+}
+
+// DSN=..., or SERVER=... for driver connection strings
+pub fn odbc_source(conn_str: &str) -> Option<String> {
+    let value_of = |wanted: &str| {
+        conn_str.split(';').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            (key.trim().eq_ignore_ascii_case(wanted) && !value.trim().is_empty()).then(|| value.trim().to_string())
+        })
+    };
+    value_of("dsn").or_else(|| value_of("server"))
+}
+
+// '&' underlines the next character and a leading '@' draws a symbol in fltk labels
+pub fn escape_label(label: &str) -> String {
+    label.replace('&', "&&").replace('@', "@@")
+}
+
+/* <-- Functions */
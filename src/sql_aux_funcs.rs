@@ -2,6 +2,7 @@
 
 use crate::odbc_interface::*;
 use crate::sqlite3_interface::*;
+use crate::statement_guard::Safety;
 use odbc::*;
 use std::{collections::HashMap, error::Error, fmt};
 
@@ -15,6 +16,8 @@ pub struct Connection {
     pub result_code: Option<i32>,
     pub result_details: Option<String>,
     pub connection_type: Option<ConnectionBase>,
+    pub profile_name: Option<String>, // saved profile this connection was opened from
+    pub safety: Safety,                 // from the profile, read-only or production
 }
 
 // Handles all query results (records)
@@ -90,8 +93,7 @@ impl RecordSet {
         &mut self,
         stmt: &mut sqlite::Statement,
     ) -> std::result::Result<(), sqlite::Error> {
-        stmt.next()?;
-
+        // the caller has stepped the statement, the types are those of its first row
         for name in stmt.column_names() {
             let res = stmt.column_type(&String::from(&name[..])[..])?;
 
@@ -100,7 +102,7 @@ impl RecordSet {
             self.column_order.push(String::from(&name[..]));
         }
 
-        Ok(stmt.reset()?)
+        Ok(())
     } //fill fields 'column_count', 'column_info'
 
     pub fn construct_odbc(
@@ -167,6 +169,38 @@ impl Record {
             self.columns.insert(key.clone(), None); // refactor this to actually use the SqlType and point to the real data types?
         }
     }
+
+    // a cell as text, None for NULL on either backend (odbc_interface hands NULL over as "Null")
+    pub fn text(&self, column: &str) -> Option<String> {
+        match self.columns.get(column) {
+            Some(Some(SqlData::Sqlite(SqliteNull))) | Some(None) | None => None,
+            Some(Some(SqlData::Odbc(text))) if text == "Null" => None,
+            Some(Some(data)) => Some(data.translate()),
+        }
+    }
+}
+
+impl SqlType {
+    // a column whose values are numbers, whatever text the backend sends them as
+    pub fn is_numeric(&self) -> bool {
+        use odbc::ffi::SqlDataType;
+        match self {
+            SqlType::Sqlite(data_type) => matches!(data_type, sqlite::Type::Integer | sqlite::Type::Float),
+            SqlType::Odbc(data_type) => matches!(
+                data_type,
+                SqlDataType::SQL_INTEGER
+                    | SqlDataType::SQL_SMALLINT
+                    | SqlDataType::SQL_EXT_TINYINT
+                    | SqlDataType::SQL_EXT_BIGINT
+                    | SqlDataType::SQL_EXT_BIT
+                    | SqlDataType::SQL_NUMERIC
+                    | SqlDataType::SQL_DECIMAL
+                    | SqlDataType::SQL_FLOAT
+                    | SqlDataType::SQL_REAL
+                    | SqlDataType::SQL_DOUBLE
+            ),
+        }
+    }
 }
 
 /*
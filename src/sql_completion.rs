@@ -0,0 +1,308 @@
+/* --> Summary of Contents
+
+	Schema aware completion for the SQL editor
+
+	SchemaCache
+		Table and column names remembered from the Tables / Columns
+		requests, so completion never has to go back to the database
+		for something the schema browser already fetched.
+
+	pub fn completion_context(&str, usize) -> CompletionContext
+		What is being typed at the cursor: the word prefix, an optional
+		alias/table qualifier before a '.', and the tables named in the
+		FROM / JOIN clauses of the statement around the cursor.
+
+	pub fn candidates(&SchemaCache, &CompletionContext) -> Vec<Completion>
+		Columns of the FROM tables first, then tables, functions and keywords
+
+	pub fn show_popup(&TextEditor, &[Completion]) -> Option<usize>
+		Menu of candidates at the text cursor, returns the picked index
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    enums::Shortcut,
+    menu::{MenuButton, MenuFlag},
+    prelude::{DisplayExt, MenuExt, WidgetBase},
+    text::TextEditor,
+};
+use std::collections::HashMap;
+
+use crate::learning_fltk::escape_menu_label;
+use crate::sql_aux_funcs::{RecordSet, Translate};
+use crate::sql_lexer::{self, Token, TokenKind};
+
+/* <-- Imports */
+/* --> Const */
+
+const MAX_CANDIDATES: usize = 40;
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CompletionKind {
+    Column,
+    Table,
+    Function,
+    Keyword,
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Default)]
+pub struct SchemaCache {
+    tables: Vec<String>,
+    columns: HashMap<String, Vec<String>>, // lowercased table name -> column names
+}
+
+// a table named in FROM / JOIN / UPDATE / INTO, with its alias when it has one
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableRef {
+    pub table: String,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CompletionContext {
+    pub prefix: String,
+    pub prefix_start: usize,      // byte offset the completion replaces from
+    pub qualifier: Option<String>, // "m" in "m.na|"
+    pub tables: Vec<TableRef>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Completion {
+    pub text: String,
+    pub kind: CompletionKind,
+}
+
+impl SchemaCache {
+    pub fn clear(&mut self) {
+        self.tables.clear();
+        self.columns.clear();
+    }
+
+    // result of Request::Tables, the table name is the first column on both backends
+    pub fn record_tables(&mut self, record_set: &RecordSet) {
+        self.tables = first_column_values(record_set);
+        self.tables.sort_by_key(|t| t.to_lowercase());
+        self.tables.dedup();
+    }
+
+    // result of Request::Columns(table)
+    pub fn record_columns(&mut self, table: &str, record_set: &RecordSet) {
+        self.columns.insert(table.to_lowercase(), first_column_values(record_set));
+    }
+
+    pub fn tables(&self) -> &[String] {
+        &self.tables
+    }
+
+    pub fn columns(&self, table: &str) -> Option<&Vec<String>> {
+        self.columns.get(&table.to_lowercase())
+    }
+
+    pub fn has_columns(&self, table: &str) -> bool {
+        self.columns.contains_key(&table.to_lowercase())
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn first_column_values(record_set: &RecordSet) -> Vec<String> {
+    let column: &String = match record_set.column_order.first() {
+        Some(column) => column,
+        None => return Vec::new(),
+    };
+    record_set
+        .records
+        .iter()
+        .filter_map(|r| r.columns.get(column).cloned().flatten())
+        .map(|v| v.translate())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+// "[Tool Owner]", "\"x\"" and `x` -> the bare name
+fn unquote(ident: &str) -> String {
+    let bytes: &[u8] = ident.as_bytes();
+    if bytes.len() >= 2 && matches!((bytes[0], bytes[bytes.len() - 1]), (b'[', b']') | (b'"', b'"') | (b'`', b'`')) {
+        ident[1..ident.len() - 1].to_string()
+    } else {
+        ident.to_string()
+    }
+}
+
+fn is_name(token: &Token, sql: &str) -> bool {
+    match token.kind {
+        TokenKind::QuotedIdent => true,
+        TokenKind::Word => !sql_lexer::is_keyword(token.text(sql)),
+        _ => false,
+    }
+}
+
+pub fn completion_context(sql: &str, cursor: usize) -> CompletionContext {
+    let cursor: usize = cursor.min(sql.len());
+    let tokens: Vec<Token> = sql_lexer::tokenize(sql);
+
+    // the word being typed ends at the cursor
+    let mut prefix_start: usize = cursor;
+    if let Some(token) = tokens.iter().find(|t| t.start < cursor && cursor <= t.end) {
+        if token.kind == TokenKind::Word {
+            prefix_start = token.start;
+        }
+    }
+    let prefix: String = sql[prefix_start..cursor].to_string();
+
+    // a '.' right before the prefix makes the word before it a qualifier
+    let before: Vec<&Token> = tokens.iter().filter(|t| t.end <= prefix_start).collect();
+    let qualifier: Option<String> = match before.as_slice() {
+        [.., name, dot] if dot.is_punct(sql, ".") && matches!(name.kind, TokenKind::Word | TokenKind::QuotedIdent) => {
+            Some(unquote(name.text(sql)))
+        }
+        _ => None,
+    };
+
+    // only the statement holding the cursor counts for FROM tables
+    let statement: Vec<Token> = sql_lexer::significant(&tokens);
+    let begin: usize = statement
+        .iter()
+        .rposition(|t| t.end <= cursor && t.is_punct(sql, ";"))
+        .map_or(0, |i| i + 1);
+    let end: usize = statement
+        .iter()
+        .position(|t| t.start >= cursor && t.is_punct(sql, ";"))
+        .unwrap_or(statement.len());
+
+    CompletionContext {
+        prefix,
+        prefix_start,
+        qualifier,
+        tables: table_refs(sql, &statement[begin..end.max(begin)]),
+    }
+}
+
+// tables after FROM, JOIN, UPDATE and INTO, including comma separated FROM lists
+pub fn table_refs(sql: &str, tokens: &[Token]) -> Vec<TableRef> {
+    let mut refs: Vec<TableRef> = Vec::new();
+    let mut i: usize = 0;
+    while i < tokens.len() {
+        let starts_list: bool = ["FROM", "JOIN", "UPDATE", "INTO"].iter().any(|k| tokens[i].is_word(sql, k));
+        i += 1;
+        if !starts_list {
+            continue;
+        }
+        loop {
+            // schema.table -> table
+            let mut table: Option<String> = None;
+            while i < tokens.len() && is_name(&tokens[i], sql) {
+                table = Some(unquote(tokens[i].text(sql)));
+                i += 1;
+                if i < tokens.len() && tokens[i].is_punct(sql, ".") {
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            let table: String = match table {
+                Some(table) => table,
+                None => break, // a sub query or a typo, nothing to complete from
+            };
+            if i < tokens.len() && tokens[i].is_word(sql, "AS") {
+                i += 1;
+            }
+            let mut alias: Option<String> = None;
+            if i < tokens.len() && is_name(&tokens[i], sql) {
+                alias = Some(unquote(tokens[i].text(sql)));
+                i += 1;
+            }
+            refs.push(TableRef { table, alias });
+
+            if i < tokens.len() && tokens[i].is_punct(sql, ",") {
+                i += 1;
+            } else {
+                break;
+            }
+        }
+    }
+    refs
+}
+
+pub fn candidates(cache: &SchemaCache, context: &CompletionContext) -> Vec<Completion> {
+    let prefix: String = context.prefix.to_lowercase();
+    let matches = |name: &str| name.to_lowercase().starts_with(&prefix);
+    let mut found: Vec<Completion> = Vec::new();
+    let push = |found: &mut Vec<Completion>, text: String, kind: CompletionKind| {
+        if matches(&text) && !found.iter().any(|c| c.text.eq_ignore_ascii_case(&text)) {
+            found.push(Completion { text, kind });
+        }
+    };
+
+    if let Some(qualifier) = &context.qualifier {
+        // alias first, then a table named directly: "m.name" or "measuring_instruments.name"
+        let table: Option<&TableRef> = context
+            .tables
+            .iter()
+            .find(|t| t.alias.as_deref().is_some_and(|a| a.eq_ignore_ascii_case(qualifier)))
+            .or_else(|| context.tables.iter().find(|t| t.table.eq_ignore_ascii_case(qualifier)));
+        let table_name: &str = table.map_or(qualifier.as_str(), |t| t.table.as_str());
+        for column in cache.columns(table_name).into_iter().flatten() {
+            push(&mut found, column.clone(), CompletionKind::Column);
+        }
+        found.truncate(MAX_CANDIDATES);
+        return found;
+    }
+
+    for table in &context.tables {
+        for column in cache.columns(&table.table).into_iter().flatten() {
+            push(&mut found, column.clone(), CompletionKind::Column);
+        }
+    }
+    for table in cache.tables() {
+        push(&mut found, table.clone(), CompletionKind::Table);
+    }
+
+    // keywords and functions follow the case the user started typing in
+    let lower: bool = !context.prefix.is_empty() && context.prefix.chars().all(|c| !c.is_ascii_uppercase());
+    let cased = |word: &str| if lower { word.to_lowercase() } else { word.to_string() };
+    for function in sql_lexer::FUNCTIONS {
+        push(&mut found, cased(function), CompletionKind::Function);
+    }
+    // nothing typed yet would list every keyword, which is noise
+    if !prefix.is_empty() {
+        for keyword in sql_lexer::KEYWORDS {
+            push(&mut found, cased(keyword), CompletionKind::Keyword);
+        }
+    }
+
+    found.truncate(MAX_CANDIDATES);
+    found
+}
+
+// popup menu just below the text cursor
+pub fn show_popup(editor: &TextEditor, completions: &[Completion]) -> Option<usize> {
+    if completions.is_empty() {
+        return None;
+    }
+    let (x, y): (i32, i32) = editor.position_to_xy(editor.insert_position());
+    let mut menu: MenuButton = MenuButton::new(x, y, 1, editor.text_size() + 4, None);
+    for completion in completions {
+        let hint: &str = match completion.kind {
+            CompletionKind::Column => "column",
+            CompletionKind::Table => "table",
+            CompletionKind::Function => "function",
+            CompletionKind::Keyword => "keyword",
+        };
+        // text after the tab is drawn right aligned, like a shortcut
+        menu.add(&format!("{}\t{}", escape_menu_label(&completion.text), hint), Shortcut::None, MenuFlag::Normal, |_| {});
+    }
+    let picked: Option<usize> = menu.popup().and_then(|_| usize::try_from(menu.value()).ok());
+    MenuButton::delete(menu);
+    picked
+}
+
+/* <-- Functions */
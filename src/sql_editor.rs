@@ -0,0 +1,307 @@
+/* --> Summary of Contents
+
+	SQL editor pane: a TextEditor with a style buffer fed by sql_lexer.rs
+
+	pub fn build(id: &str, w: i32, h: i32, on_complete: FnMut(), on_run: FnMut()) -> TextEditor
+		Editor with syntax highlighting, line numbers, bracket matching
+		and auto-indent. Placed like any other widget (with_pos etc.)
+		on_complete runs for Ctrl+Space and for a '.' typed after a name,
+		on_run for Ctrl+Enter
+
+	pub fn text(&TextEditor) -> String
+	pub fn set_text(&mut TextEditor, &str)
+		Read and replace the editor contents
+
+	pub fn selection_or_statement(&TextEditor) -> String
+		The selected text, or else the statement under the cursor
+
+	pub fn replace_selection_or_all(&mut TextEditor, FnOnce(&str) -> String)
+		Rewrite the selection, or the whole buffer when nothing is selected
+
+	pub fn replace_before_cursor(&mut TextEditor, usize, &str)
+		Swap the text between a byte offset and the cursor, for completions
+
+	pub fn style_text(&str, Option<usize>) -> String
+		One style character per byte of the text, see STYLE_* below
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::{
+    app::{self, WidgetId},
+    enums::{Color, Event, Font, Key},
+    prelude::{DisplayExt, WidgetBase, WidgetExt},
+    text::{StyleTableEntry, TextBuffer, TextEditor},
+};
+
+use crate::sql_lexer::{self, TokenKind};
+
+/* <-- Imports */
+/* --> Const */
+
+// indexes into styles(), as characters stored in the style buffer
+const STYLE_PLAIN: u8 = b'A';
+const STYLE_KEYWORD: u8 = b'B';
+const STYLE_STRING: u8 = b'C';
+const STYLE_COMMENT: u8 = b'D';
+const STYLE_NUMBER: u8 = b'E';
+const STYLE_BRACKET: u8 = b'F';
+
+const FONT_SIZE: i32 = 13;
+const INDENT: &str = "    ";
+
+/* <-- Const */
+/* --> Functions */
+
+fn styles() -> Vec<StyleTableEntry> {
+    let entry = |color: Color, font: Font| StyleTableEntry {
+        color,
+        font,
+        size: FONT_SIZE,
+    };
+    vec![
+        entry(Color::Foreground, Font::Courier),                // A plain
+        entry(Color::DarkBlue, Font::CourierBold),              // B keyword
+        entry(Color::DarkRed, Font::Courier),                   // C string
+        entry(Color::from_rgb(0, 128, 0), Font::CourierItalic), // D comment
+        entry(Color::DarkMagenta, Font::Courier),               // E number
+        entry(Color::Red, Font::CourierBold),                   // F matched bracket
+    ]
+}
+
+pub fn build<F, R>(id: &str, w: i32, h: i32, mut on_complete: F, mut on_run: R) -> TextEditor
+where
+    F: FnMut() + 'static,
+    R: FnMut() + 'static,
+{
+    let buffer: TextBuffer = TextBuffer::default();
+    let mut style_buffer: TextBuffer = TextBuffer::default();
+
+    let mut editor: TextEditor = TextEditor::default().with_id(id).with_size(w, h);
+    editor.set_buffer(buffer.clone());
+    editor.set_highlight_data(style_buffer.clone(), styles());
+    editor.set_text_font(Font::Courier);
+    editor.set_text_size(FONT_SIZE);
+    editor.set_linenumber_width(40);
+    editor.set_linenumber_size(FONT_SIZE - 2);
+    editor.set_linenumber_fgcolor(Color::Dark2);
+
+    // restyle everything on each edit, queries are small enough that this stays instant
+    {
+        let buffer: TextBuffer = buffer.clone();
+        let editor: TextEditor = editor.clone();
+        let mut buffer_cb: TextBuffer = buffer.clone();
+        buffer_cb.add_modify_callback(move |_, inserted, deleted, _, _| {
+            if inserted > 0 || deleted > 0 {
+                restyle(&buffer, &mut style_buffer, editor.insert_position());
+            }
+        });
+    }
+
+    editor.handle(move |ed, ev| match ev {
+        Event::KeyDown if app::event_key() == Key::Enter || app::event_key() == Key::KPEnter => {
+            if app::is_event_ctrl() {
+                on_run();
+                return true;
+            }
+            if app::is_event_shift() || app::is_event_alt() {
+                return false;
+            }
+            newline_with_indent(ed);
+            true
+        }
+        Event::KeyDown if app::is_event_ctrl() && app::event_key() == Key::from_char(' ') => {
+            on_complete();
+            true
+        }
+        // "alias." offers the columns of the aliased table
+        Event::KeyDown if app::event_text() == "." && !app::is_event_ctrl() => {
+            let pos: i32 = ed.insert_position();
+            let after_name: bool = ed
+                .buffer()
+                .and_then(|b| b.text_range((pos - 1).max(0), pos))
+                .and_then(|c| c.chars().next())
+                .is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | ']' | '"' | '`'));
+            insert_at_cursor(ed, ".");
+            if after_name {
+                on_complete();
+            }
+            true
+        }
+        // the cursor moved, update which bracket pair is highlighted
+        Event::KeyUp | Event::Released => {
+            if let (Some(buffer), Some(mut style_buffer)) = (ed.buffer(), ed.style_buffer()) {
+                restyle(&buffer, &mut style_buffer, ed.insert_position());
+            }
+            false
+        }
+        _ => false,
+    });
+
+    editor
+}
+
+pub fn text(editor: &TextEditor) -> String {
+    editor.buffer().map(|b| b.text()).unwrap_or_default()
+}
+
+pub fn set_text(editor: &mut TextEditor, sql: &str) {
+    if let Some(mut buffer) = editor.buffer() {
+        buffer.set_text(sql);
+        editor.set_insert_position(0);
+    }
+}
+
+pub fn selection_or_statement(editor: &TextEditor) -> String {
+    let buffer: TextBuffer = match editor.buffer() {
+        Some(buffer) => buffer,
+        None => return String::new(),
+    };
+    if buffer.selected() {
+        return buffer.selection_text();
+    }
+    let sql: String = buffer.text();
+    let cursor: usize = usize::try_from(editor.insert_position()).unwrap_or(0);
+    match sql_lexer::statement_at(&sql, cursor) {
+        Some((start, end)) => sql[start..end].to_string(),
+        None => String::new(),
+    }
+}
+
+pub fn replace_selection_or_all<F: FnOnce(&str) -> String>(editor: &mut TextEditor, rewrite: F) {
+    let mut buffer: TextBuffer = match editor.buffer() {
+        Some(buffer) => buffer,
+        None => return,
+    };
+    match buffer.selection_position() {
+        Some((start, end)) if start != end => {
+            let rewritten: String = rewrite(&buffer.selection_text());
+            buffer.replace(start, end, &rewritten);
+            buffer.select(start, start + rewritten.len() as i32);
+            editor.set_insert_position(start);
+        }
+        _ => {
+            let rewritten: String = rewrite(&buffer.text());
+            buffer.set_text(&rewritten);
+            editor.set_insert_position(0);
+        }
+    }
+    editor.show_insert_position();
+}
+
+pub fn replace_before_cursor(editor: &mut TextEditor, start: usize, replacement: &str) {
+    if let Some(mut buffer) = editor.buffer() {
+        let end: i32 = editor.insert_position();
+        let start: i32 = (start as i32).min(end);
+        buffer.replace(start, end, replacement);
+        editor.set_insert_position(start + replacement.len() as i32);
+        editor.show_insert_position();
+    }
+}
+
+// typed text replaces the selection, like the default key handling does
+fn insert_at_cursor(editor: &mut TextEditor, text: &str) {
+    let mut buffer: TextBuffer = match editor.buffer() {
+        Some(buffer) => buffer,
+        None => return,
+    };
+    if let Some((start, _)) = buffer.selection_position() {
+        buffer.remove_selection();
+        editor.set_insert_position(start);
+    }
+    let pos: i32 = editor.insert_position();
+    buffer.insert(pos, text);
+    editor.set_insert_position(pos + text.len() as i32);
+    editor.show_insert_position();
+}
+
+fn restyle(buffer: &TextBuffer, style_buffer: &mut TextBuffer, cursor: i32) {
+    let sql: String = buffer.text();
+    let cursor: Option<usize> = usize::try_from(cursor).ok();
+    style_buffer.set_text(&style_text(&sql, cursor));
+}
+
+pub fn style_text(sql: &str, cursor: Option<usize>) -> String {
+    let mut styled: Vec<u8> = vec![STYLE_PLAIN; sql.len()];
+    let tokens = sql_lexer::tokenize(sql);
+
+    for token in &tokens {
+        let style: u8 = match token.kind {
+            TokenKind::LineComment | TokenKind::BlockComment => STYLE_COMMENT,
+            TokenKind::String => STYLE_STRING,
+            TokenKind::Number => STYLE_NUMBER,
+            TokenKind::Word if sql_lexer::is_keyword(token.text(sql)) => STYLE_KEYWORD,
+            _ => continue,
+        };
+        styled[token.start..token.end].fill(style);
+    }
+
+    if let Some((open, close)) = cursor.and_then(|c| matching_brackets(sql, &tokens, c)) {
+        styled[open] = STYLE_BRACKET;
+        styled[close] = STYLE_BRACKET;
+    }
+
+    // style bytes are ascii, so this never fails
+    String::from_utf8(styled).unwrap_or_default()
+}
+
+// the bracket pair touching the cursor, brackets inside strings and comments are not tokens
+fn matching_brackets(sql: &str, tokens: &[sql_lexer::Token], cursor: usize) -> Option<(usize, usize)> {
+    let brackets: Vec<(usize, bool)> = tokens
+        .iter()
+        .filter(|t| t.is_punct(sql, "(") || t.is_punct(sql, ")"))
+        .map(|t| (t.start, t.is_punct(sql, "(")))
+        .collect();
+
+    // prefer the bracket right after the cursor, then the one right before it
+    let index: usize = brackets
+        .iter()
+        .position(|(pos, _)| *pos == cursor)
+        .or_else(|| brackets.iter().position(|(pos, _)| *pos + 1 == cursor))?;
+
+    let (pos, is_open) = brackets[index];
+    let mut depth: i32 = 0;
+    if is_open {
+        for (other, open) in &brackets[index..] {
+            depth += if *open { 1 } else { -1 };
+            if depth == 0 {
+                return Some((pos, *other));
+            }
+        }
+    } else {
+        for (other, open) in brackets[..=index].iter().rev() {
+            depth += if *open { -1 } else { 1 };
+            if depth == 0 {
+                return Some((*other, pos));
+            }
+        }
+    }
+    None
+}
+
+// keep the indentation of the current line, one level deeper after an opening bracket
+fn newline_with_indent(editor: &mut TextEditor) {
+    let mut buffer: TextBuffer = match editor.buffer() {
+        Some(buffer) => buffer,
+        None => return,
+    };
+    if let Some((start, _)) = buffer.selection_position() {
+        buffer.remove_selection();
+        editor.set_insert_position(start);
+    }
+
+    let pos: i32 = editor.insert_position();
+    let line_start: i32 = buffer.line_start(pos);
+    let before_cursor: String = buffer.text_range(line_start, pos).unwrap_or_default();
+    let mut indent: String = before_cursor
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+    if before_cursor.trim_end().ends_with('(') {
+        indent.push_str(INDENT);
+    }
+
+    insert_at_cursor(editor, &format!("\n{}", indent));
+}
+
+/* <-- Functions */
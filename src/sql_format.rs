@@ -0,0 +1,556 @@
+/* --> Summary of Contents
+
+	SQL formatter for the query editor
+
+	pub fn format_sql(&str, &FormatOptions) -> String
+		Rebuilds the layout of a script from its tokens: every clause on
+		its own line, SELECT / SET / VALUES lists aligned under their first
+		item, AND / OR conditions indented, sub queries indented a level.
+		Comments and string literals are copied through untouched, so the
+		only things that change are whitespace and keyword case.
+
+	Dialect
+		SQLite breaks LIMIT onto its own line, T-SQL keeps TOP on the
+		SELECT line, breaks OFFSET / FETCH and APPLY, and keeps GO batch
+		separators on their own line.
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use crate::sql_aux_funcs::ConnectionBase;
+use crate::sql_lexer::{self, Token, TokenKind};
+
+/* <-- Imports */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Dialect {
+    Sqlite,
+    TSql,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+    Preserve,
+}
+
+// the clause the formatter is currently inside, decides how commas and AND/OR break
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Clause {
+    None,
+    Select,
+    From,
+    Where,
+    GroupBy,
+    OrderBy,
+    Having,
+    Join,
+    Set,
+    Values,
+    Other,
+}
+
+impl Dialect {
+    // ODBC connections are SQL Server here
+    pub fn of(connection_type: &ConnectionBase) -> Dialect {
+        match connection_type {
+            ConnectionBase::Sqlite => Dialect::Sqlite,
+            ConnectionBase::Odbc => Dialect::TSql,
+        }
+    }
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Copy, Debug)]
+pub struct FormatOptions {
+    pub dialect: Dialect,
+    pub keyword_case: KeywordCase,
+    pub indent_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            dialect: Dialect::Sqlite,
+            keyword_case: KeywordCase::Upper,
+            indent_width: 4,
+        }
+    }
+}
+
+// one per open parenthesis, plus the statement itself at the bottom of the stack
+struct Level {
+    subquery: bool,          // clauses only break lines in statements and sub queries
+    indent: usize,           // column clause keywords start at
+    open_indent: usize,      // indentation of the line the '(' was on, where ')' goes back to
+    clause: Clause,
+    list_align: Option<usize>, // column the items of a SELECT / SET / VALUES list line up at
+    between: bool,           // the next AND belongs to BETWEEN, not to the condition list
+}
+
+impl Level {
+    fn statement() -> Self {
+        Level {
+            subquery: true,
+            indent: 0,
+            open_indent: 0,
+            clause: Clause::None,
+            list_align: None,
+            between: false,
+        }
+    }
+}
+
+// output buffer that knows where the current line starts
+#[derive(Default)]
+struct Writer {
+    out: String,
+}
+
+impl Writer {
+    fn current_line(&self) -> &str {
+        match self.out.rfind('\n') {
+            Some(i) => &self.out[i + 1..],
+            None => &self.out,
+        }
+    }
+
+    fn column(&self) -> usize {
+        self.current_line().chars().count()
+    }
+
+    fn line_indent(&self) -> usize {
+        self.current_line().len() - self.current_line().trim_start_matches(' ').len()
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.current_line().trim().is_empty()
+    }
+
+    // start a new line at `indent`, or re-indent the current one when nothing is on it yet
+    fn newline(&mut self, indent: usize) {
+        let trimmed: usize = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.push_str(&" ".repeat(indent));
+    }
+
+    fn blank_line(&mut self) {
+        self.newline(0);
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn write(&mut self, text: &str, space: bool) {
+        if space && !self.at_line_start() {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn format_sql(sql: &str, options: &FormatOptions) -> String {
+    let tokens: Vec<Token> = sql_lexer::tokenize(sql);
+    // comments are kept, whitespace is rebuilt
+    let code: Vec<(usize, Token)> = tokens
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, t)| t.kind != TokenKind::Whitespace)
+        .collect();
+    let next_code = |i: usize| code[i + 1..].iter().map(|(_, t)| *t).find(|t| !t.is_trivia());
+
+    let width: usize = options.indent_width;
+    let mut writer: Writer = Writer::default();
+    let mut levels: Vec<Level> = vec![Level::statement()];
+    let mut prev: Option<Token> = None; // last code token written, for spacing
+    let mut unary: bool = false;        // the previous token was a sign, not a binary operator
+    let mut i: usize = 0;
+
+    while i < code.len() {
+        let (raw_index, token) = code[i];
+        let text: &str = token.text(sql);
+
+        match token.kind {
+            TokenKind::LineComment => {
+                writer.write(text.trim_end(), true);
+                let indent: usize = writer.line_indent();
+                writer.newline(indent);
+                i += 1;
+                continue;
+            }
+            TokenKind::BlockComment => {
+                writer.write(text, true);
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        if options.dialect == Dialect::TSql && sql_lexer::is_batch_separator(sql, &tokens, raw_index) {
+            writer.newline(0);
+            writer.write(&apply_case(text, options.keyword_case), false);
+            writer.blank_line();
+            levels = vec![Level::statement()];
+            prev = None;
+            i += 1;
+            continue;
+        }
+
+        if token.is_punct(sql, ";") && levels.len() == 1 {
+            writer.write(";", false);
+            writer.blank_line();
+            levels = vec![Level::statement()];
+            prev = None;
+            i += 1;
+            continue;
+        }
+
+        // clause keywords start their own line inside statements and sub queries
+        let level: &Level = levels.last().unwrap();
+        if token.kind == TokenKind::Word && level.subquery && !follows_dot(prev, sql) {
+            if let Some((clause, words)) = clause_at(sql, &code, i, prev, level.clause, options.dialect) {
+                let level: &mut Level = levels.last_mut().unwrap();
+                let indent: usize = if clause == Clause::Join && text.eq_ignore_ascii_case("ON") {
+                    level.indent + width
+                } else {
+                    level.indent
+                };
+                writer.newline(indent);
+                let mut written: usize = 0;
+                while written < words && i < code.len() {
+                    let word: &str = code[i].1.text(sql);
+                    writer.write(&case_word(word, code[i].1, options.keyword_case, false), true);
+                    if !code[i].1.is_trivia() {
+                        written += 1;
+                        prev = Some(code[i].1);
+                    }
+                    i += 1;
+                }
+                if clause == Clause::Select {
+                    i = write_select_modifiers(sql, &code, i, &mut writer, &mut prev, options);
+                }
+                level.clause = clause;
+                level.between = false;
+                level.list_align = match clause {
+                    Clause::Select | Clause::Set | Clause::Values => Some(writer.column() + 1),
+                    _ => None,
+                };
+                unary = false;
+                continue;
+            }
+
+            // AND / OR conditions each get a line under WHERE, HAVING and ON
+            if (token.is_word(sql, "AND") || token.is_word(sql, "OR"))
+                && matches!(level.clause, Clause::Where | Clause::Having | Clause::Join)
+            {
+                if level.between && token.is_word(sql, "AND") {
+                    levels.last_mut().unwrap().between = false;
+                } else {
+                    writer.newline(level.indent + width);
+                    writer.write(&case_word(text, token, options.keyword_case, false), false);
+                    prev = Some(token);
+                    i += 1;
+                    continue;
+                }
+            }
+        }
+        if token.is_word(sql, "BETWEEN") {
+            levels.last_mut().unwrap().between = true;
+        }
+
+        if token.is_punct(sql, "(") {
+            let subquery: bool = next_code(i).is_some_and(|t| t.is_word(sql, "SELECT") || t.is_word(sql, "WITH"));
+            // INSERT INTO t (a, b) is a column list, not a call to t()
+            let column_list: bool = code[..i]
+                .iter()
+                .rev()
+                .map(|(_, t)| *t)
+                .filter(|t| !t.is_trivia())
+                .nth(1)
+                .is_some_and(|t| ["INTO", "TABLE", "REFERENCES", "VIEW", "INDEX", "ON"].iter().any(|w| t.is_word(sql, w)));
+            writer.write("(", column_list || needs_space(prev, token, sql, unary));
+            let parent: &Level = levels.last().unwrap();
+            let open_indent: usize = writer.line_indent();
+            levels.push(Level {
+                subquery,
+                indent: if subquery { open_indent + width } else { parent.indent },
+                open_indent,
+                clause: if subquery { Clause::None } else { parent.clause },
+                list_align: None,
+                between: false,
+            });
+            prev = Some(token);
+            unary = false;
+            i += 1;
+            continue;
+        }
+
+        if token.is_punct(sql, ")") {
+            if levels.len() > 1 {
+                let closed: Level = levels.pop().unwrap();
+                if closed.subquery {
+                    writer.newline(closed.open_indent);
+                }
+            }
+            writer.write(")", false);
+            prev = Some(token);
+            unary = false;
+            i += 1;
+            continue;
+        }
+
+        if token.is_punct(sql, ",") {
+            writer.write(",", false);
+            let level: &Level = levels.last().unwrap();
+            if let (true, Some(align)) = (level.subquery, level.list_align) {
+                writer.newline(align);
+            }
+            prev = Some(token);
+            unary = false;
+            i += 1;
+            continue;
+        }
+
+        let space: bool = needs_space(prev, token, sql, unary);
+        let is_call: bool = next_code(i).is_some_and(|t| t.is_punct(sql, "("));
+        writer.write(&case_word(text, token, options.keyword_case, is_call), space);
+        // a sign directly after an operator, a keyword, '(' or ',' binds to the number after it
+        unary = token.kind == TokenKind::Operator
+            && (text == "-" || text == "+")
+            && prev.is_none_or(|p| {
+                matches!(p.kind, TokenKind::Operator)
+                    || p.is_punct(sql, "(")
+                    || p.is_punct(sql, ",")
+                    || (p.kind == TokenKind::Word && sql_lexer::is_keyword(p.text(sql)))
+            });
+        prev = Some(token);
+        i += 1;
+    }
+
+    writer.out.trim_end().to_string()
+}
+
+// the clause starting at code[i], and how many words its keyword phrase takes
+fn clause_at(
+    sql: &str,
+    code: &[(usize, Token)],
+    i: usize,
+    prev: Option<Token>,
+    current: Clause,
+    dialect: Dialect,
+) -> Option<(Clause, usize)> {
+    let word = |offset: usize| -> Option<Token> {
+        code[i..].iter().map(|(_, t)| *t).filter(|t| !t.is_trivia()).nth(offset)
+    };
+    let is = |offset: usize, w: &str| word(offset).is_some_and(|t| t.is_word(sql, w));
+    let prev_is = |w: &str| prev.is_some_and(|t| t.is_word(sql, w));
+    // LIMIT 10 OFFSET 5 reads as one clause in SQLite, OFFSET / FETCH are two in T-SQL
+    let paging: bool = match dialect {
+        Dialect::Sqlite => is(0, "LIMIT"),
+        Dialect::TSql => is(0, "OFFSET") || is(0, "FETCH"),
+    };
+
+    let clause: (Clause, usize) = if is(0, "SELECT") {
+        (Clause::Select, 1)
+    } else if is(0, "FROM") {
+        // DELETE FROM reads as one phrase
+        if prev_is("DELETE") {
+            return None;
+        }
+        (Clause::From, 1)
+    } else if is(0, "WHERE") {
+        (Clause::Where, 1)
+    } else if is(0, "GROUP") && is(1, "BY") {
+        (Clause::GroupBy, 2)
+    } else if is(0, "ORDER") && is(1, "BY") {
+        (Clause::OrderBy, 2)
+    } else if is(0, "HAVING") {
+        (Clause::Having, 1)
+    } else if is(0, "JOIN") {
+        (Clause::Join, 1)
+    } else if ["LEFT", "RIGHT", "FULL", "INNER", "CROSS", "OUTER", "NATURAL"].iter().any(|w| is(0, w)) {
+        // LEFT OUTER JOIN, CROSS APPLY ... up to and including JOIN / APPLY
+        let mut n: usize = 1;
+        while n < 4 && !is(n, "JOIN") && !is(n, "APPLY") && ["OUTER", "INNER", "LEFT"].iter().any(|w| is(n, w)) {
+            n += 1;
+        }
+        let apply: bool = dialect == Dialect::TSql && is(n, "APPLY");
+        if !is(n, "JOIN") && !apply {
+            return None;
+        }
+        (Clause::Join, n + 1)
+    } else if is(0, "ON") && current == Clause::Join {
+        (Clause::Join, 1)
+    } else if ["UNION", "EXCEPT", "INTERSECT"].iter().any(|w| is(0, w)) {
+        (Clause::Other, if is(1, "ALL") { 2 } else { 1 })
+    } else if is(0, "INSERT") || is(0, "DELETE") || is(0, "RETURNING") || (is(0, "UPDATE") && !prev_is("FOR")) {
+        (Clause::Other, 1)
+    } else if is(0, "VALUES") {
+        (Clause::Values, 1)
+    } else if is(0, "SET") {
+        (Clause::Set, 1)
+    } else if is(0, "WITH") {
+        // WITH (NOLOCK) is a table hint, not a common table expression
+        if word(1).is_some_and(|t| t.is_punct(sql, "(")) {
+            return None;
+        }
+        (Clause::Other, 1)
+    } else if paging {
+        (Clause::Other, 1)
+    } else {
+        return None;
+    };
+    Some(clause)
+}
+
+// DISTINCT / ALL, and TOP n [PERCENT] on T-SQL, stay on the SELECT line
+fn write_select_modifiers(
+    sql: &str,
+    code: &[(usize, Token)],
+    mut i: usize,
+    writer: &mut Writer,
+    prev: &mut Option<Token>,
+    options: &FormatOptions,
+) -> usize {
+    while i < code.len() {
+        let token: Token = code[i].1;
+        let take: usize = if token.is_word(sql, "DISTINCT") || token.is_word(sql, "ALL") {
+            1
+        } else if options.dialect == Dialect::TSql && token.is_word(sql, "TOP") {
+            // TOP 10, TOP (10), TOP 10 PERCENT
+            let mut n: usize = 2;
+            if code.get(i + 1).is_some_and(|(_, t)| t.is_punct(sql, "(")) {
+                n = 4;
+            }
+            if code.get(i + n).is_some_and(|(_, t)| t.is_word(sql, "PERCENT")) {
+                n += 1;
+            }
+            n
+        } else {
+            break;
+        };
+        for (_, t) in code.iter().skip(i).take(take) {
+            let space: bool = !(t.is_punct(sql, ")") || prev.is_some_and(|p| p.is_punct(sql, "(")));
+            writer.write(&case_word(t.text(sql), *t, options.keyword_case, false), space);
+            *prev = Some(*t);
+        }
+        i += take;
+    }
+    i
+}
+
+fn follows_dot(prev: Option<Token>, sql: &str) -> bool {
+    prev.is_some_and(|p| p.is_punct(sql, "."))
+}
+
+fn needs_space(prev: Option<Token>, token: Token, sql: &str, unary: bool) -> bool {
+    let prev: Token = match prev {
+        Some(prev) => prev,
+        None => return false,
+    };
+    if unary || prev.is_punct(sql, "(") || prev.is_punct(sql, ".") {
+        return false;
+    }
+    if token.is_punct(sql, ",") || token.is_punct(sql, ")") || token.is_punct(sql, ".") || token.is_punct(sql, ";") {
+        return false;
+    }
+    if token.text(sql) == "::" || prev.text(sql) == "::" {
+        return false;
+    }
+    // function calls keep their bracket: COUNT(*), my_udf(x); keywords like IN ( keep the space
+    if token.is_punct(sql, "(") && prev.kind == TokenKind::Word {
+        let word: &str = prev.text(sql);
+        return sql_lexer::is_keyword(word) && !sql_lexer::is_function(word);
+    }
+    true
+}
+
+// keywords and called functions change case, identifiers and everything else stay as typed.
+// a column named "date" is left alone, DATE('now') is not
+fn case_word(text: &str, token: Token, case: KeywordCase, is_call: bool) -> String {
+    let is_keyword: bool = token.kind == TokenKind::Word && (sql_lexer::is_keyword(text) || (is_call && sql_lexer::is_function(text)));
+    if is_keyword {
+        apply_case(text, case)
+    } else {
+        text.to_string()
+    }
+}
+
+fn apply_case(text: &str, case: KeywordCase) -> String {
+    match case {
+        KeywordCase::Upper => text.to_uppercase(),
+        KeywordCase::Lower => text.to_lowercase(),
+        KeywordCase::Preserve => text.to_string(),
+    }
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(sql: &str, dialect: Dialect, keyword_case: KeywordCase) -> String {
+        format_sql(sql, &FormatOptions { dialect, keyword_case, indent_width: 4 })
+    }
+
+    #[test]
+    fn keyword_case() {
+        assert_eq!(format("SELECT A FROM T LIMIT 10", Dialect::Sqlite, KeywordCase::Lower), "select A\nfrom T\nlimit 10");
+        assert_eq!(
+            format("insert into t (a, b) values (1, 'select')", Dialect::Sqlite, KeywordCase::Preserve),
+            "insert into t (a, b)\nvalues (1, 'select')"
+        );
+    }
+
+    #[test]
+    fn clauses_break_and_lists_align() {
+        assert_eq!(
+            format("select a, b from t where x = 1 and y = 'it''s' order by a", Dialect::Sqlite, KeywordCase::Upper),
+            "SELECT a,\n       b\nFROM t\nWHERE x = 1\n    AND y = 'it''s'\nORDER BY a"
+        );
+        assert_eq!(
+            format("update t set a = 1, b = 'x' where id between 1 and 5 or c is null", Dialect::Sqlite, KeywordCase::Upper),
+            "UPDATE t\nSET a = 1,\n    b = 'x'\nWHERE id BETWEEN 1 AND 5\n    OR c IS NULL"
+        );
+        assert_eq!(
+            format("select a.x, b.y from a inner join b on a.id = b.id left join c on c.id = a.id", Dialect::Sqlite, KeywordCase::Upper),
+            "SELECT a.x,\n       b.y\nFROM a\nINNER JOIN b\n    ON a.id = b.id\nLEFT JOIN c\n    ON c.id = a.id"
+        );
+    }
+
+    #[test]
+    fn sub_queries_indent_and_comments_stay() {
+        assert_eq!(
+            format("select a -- keep me\nfrom t where b in (select b from u where c = 2)", Dialect::Sqlite, KeywordCase::Upper),
+            "SELECT a -- keep me\nFROM t\nWHERE b IN (\n    SELECT b\n    FROM u\n    WHERE c = 2\n)"
+        );
+    }
+
+    #[test]
+    fn top_stays_on_the_select_line() {
+        assert_eq!(
+            format("select top 10 a, count(*) from t group by a", Dialect::TSql, KeywordCase::Upper),
+            "SELECT TOP 10 a,\n              COUNT(*)\nFROM t\nGROUP BY a"
+        );
+        assert_eq!(
+            format("select a from t order by a offset 10 rows fetch next 5 rows only", Dialect::TSql, KeywordCase::Upper),
+            "SELECT a\nFROM t\nORDER BY a\nOFFSET 10 ROWS\nFETCH NEXT 5 ROWS ONLY"
+        );
+        assert_eq!(format("select 1\ngo\nselect 2", Dialect::TSql, KeywordCase::Upper), "SELECT 1\nGO\n\nSELECT 2");
+    }
+}
+
+/* <-- Tests */
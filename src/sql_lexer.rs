@@ -0,0 +1,462 @@
+/* --> Summary of Contents
+
+	pub fn tokenize(&str) -> Vec<Token>
+		Split SQL text into tokens with byte offsets into the source.
+		Understands both dialects we talk to: SQLite and T-SQL
+		('strings', "idents", [idents], `idents`, -- and /* */ comments,
+		:name / @name / ? parameters). Never fails; unterminated
+		strings and comments run to the end of the text.
+
+	pub fn is_keyword(&str) -> bool
+	pub fn is_function(&str) -> bool
+		Case insensitive lookup in KEYWORDS / FUNCTIONS
+
+	pub fn is_number_literal(&str) -> bool
+		-12, 3.5 or 1e-3: safe to put into SQL without quotes. inf, NaN
+		and the like parse as floats but are not SQL numbers.
+
+	pub fn statement_ranges(&str) -> Vec<(usize, usize)>
+		Byte ranges of the statements in a script, split on ';' and on
+		T-SQL "GO" batch lines. Empty and comment only pieces are dropped.
+
+	pub fn batch_ranges(&str) -> Vec<(usize, usize)>
+		The same split on "GO" lines only: the batches a T-SQL script
+		sends whole, its variables live until the end of the batch
+
+	pub fn statement_at(&str, usize) -> Option<(usize, usize)>
+		The statement under the cursor, or the last one before it
+
+Summary of Contents <-- */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TokenKind {
+    Whitespace,
+    LineComment,     // -- to end of line
+    BlockComment,    // /* ... */
+    String,          // 'text', '' escapes a quote
+    QuotedIdent,     // "name", [name] or `name`
+    Number,          // 12, 1.5, .5, 1e-3, 0x1F
+    Word,            // keywords and bare identifiers
+    Parameter,       // :name, @name, $name or ?
+    Punctuation,     // ( ) , . ;
+    Operator,        // = <> <= || + - * / ...
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Copy, Debug)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub start: usize, // byte offsets into the tokenized text
+    pub end: usize,
+}
+
+impl Token {
+    pub fn text<'a>(&self, sql: &'a str) -> &'a str {
+        &sql[self.start..self.end]
+    }
+
+    pub fn is_trivia(&self) -> bool {
+        matches!(self.kind, TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment)
+    }
+
+    // case insensitive keyword check, only true for Word tokens
+    pub fn is_word(&self, sql: &str, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text(sql).eq_ignore_ascii_case(word)
+    }
+
+    pub fn is_punct(&self, sql: &str, punct: &str) -> bool {
+        self.kind == TokenKind::Punctuation && self.text(sql) == punct
+    }
+}
+
+/* <-- Structs */
+/* --> Const */
+
+pub const KEYWORDS: &[&str] = &[
+    "ABORT", "ACTION", "ADD", "AFTER", "ALL", "ALTER", "ANALYZE", "AND", "APPLY", "AS", "ASC", "ATTACH",
+    "AUTOINCREMENT", "BEFORE", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE", "CAST", "CHECK",
+    "COLLATE", "COLUMN", "COMMIT", "CONFLICT", "CONSTRAINT", "CREATE", "CROSS", "CURRENT",
+    "CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP", "DATABASE", "DECLARE", "DEFAULT",
+    "DEFERRABLE", "DEFERRED", "DELETE", "DESC", "DETACH", "DISTINCT", "DO", "DROP", "EACH", "ELSE",
+    "END", "ESCAPE", "EXCEPT", "EXCLUSIVE", "EXEC", "EXECUTE", "EXISTS", "EXPLAIN", "FAIL", "FETCH",
+    "FILTER", "FIRST", "FOLLOWING", "FOR", "FOREIGN", "FROM", "FULL", "GLOB", "GO", "GROUP",
+    "HAVING", "IDENTITY", "IF", "IGNORE", "IMMEDIATE", "IN", "INDEX", "INDEXED", "INITIALLY",
+    "INNER", "INSERT", "INSTEAD", "INTERSECT", "INTO", "IS", "ISNULL", "JOIN", "KEY", "LAST",
+    "LEFT", "LIKE", "LIMIT", "MATCH", "MERGE", "NATURAL", "NEXT", "NO", "NOCOUNT", "NOLOCK", "NOT",
+    "NOTHING", "NOTNULL", "NULL", "NULLS", "OF", "OFFSET", "ON", "ONLY", "OR", "ORDER", "OTHERS",
+    "OUTER", "OVER", "PARTITION", "PERCENT", "PLAN", "PRAGMA", "PRECEDING", "PRIMARY", "PROC", "PROCEDURE",
+    "QUERY", "RAISE", "RANGE", "RECURSIVE", "REFERENCES", "REGEXP", "REINDEX", "RELEASE",
+    "RENAME", "REPLACE", "RESTRICT", "RETURNING", "RIGHT", "ROLLBACK", "ROW", "ROWS", "SAVEPOINT",
+    "SCHEMA", "SELECT", "SET", "TABLE", "TEMP", "TEMPORARY", "THEN", "TIES", "TO", "TOP",
+    "TRANSACTION", "TRAN", "TRIGGER", "TRUNCATE", "UNBOUNDED", "UNION", "UNIQUE", "UPDATE",
+    "USE", "USING", "VACUUM", "VALUES", "VIEW", "VIRTUAL", "WHEN", "WHERE", "WINDOW", "WITH",
+    "WITHOUT",
+];
+
+// built in functions of SQLite and SQL Server that we use, for completion and formatting
+pub const FUNCTIONS: &[&str] = &[
+    "ABS", "AVG", "CAST", "CHARINDEX", "COALESCE", "CONVERT", "COUNT", "DATE", "DATEADD",
+    "DATEDIFF", "DATEPART", "DATETIME", "DAY", "DENSE_RANK", "FORMAT", "GETDATE", "GROUP_CONCAT",
+    "IFNULL", "IIF", "INSTR", "ISNULL", "JULIANDAY", "LAG", "LEAD", "LEN", "LENGTH", "LOWER",
+    "LTRIM", "MAX", "MIN", "MONTH", "NULLIF", "PRINTF", "RANK", "REPLACE", "ROUND", "ROW_NUMBER",
+    "RTRIM", "STRFTIME", "STRING_AGG", "SUBSTR", "SUBSTRING", "SUM", "TIME", "TOTAL", "TRIM",
+    "TYPEOF", "UPPER", "YEAR",
+];
+
+/* <-- Const */
+/* --> Functions */
+
+pub fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|k| k.eq_ignore_ascii_case(word))
+}
+
+pub fn is_function(word: &str) -> bool {
+    FUNCTIONS.iter().any(|f| f.eq_ignore_ascii_case(word))
+}
+
+// optional '-', digits, optional '.' and digits, optional exponent
+pub fn is_number_literal(text: &str) -> bool {
+    let digits = |s: &str| -> usize { s.bytes().take_while(u8::is_ascii_digit).count() };
+    let rest: &str = text.strip_prefix('-').unwrap_or(text);
+    let whole: usize = digits(rest);
+    if whole == 0 {
+        return false;
+    }
+    let mut rest: &str = &rest[whole..];
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let count: usize = digits(fraction);
+        if count == 0 {
+            return false;
+        }
+        rest = &fraction[count..];
+    }
+    if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+        let exponent: &str = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        let count: usize = digits(exponent);
+        // 1e5000 is no longer a number once it reaches a float column
+        if count == 0 || !text.parse::<f64>().is_ok_and(f64::is_finite) {
+            return false;
+        }
+        rest = &exponent[count..];
+    }
+    rest.is_empty()
+}
+
+pub fn tokenize(sql: &str) -> Vec<Token> {
+    let bytes: &[u8] = sql.as_bytes();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut pos: usize = 0;
+
+    while pos < bytes.len() {
+        let start: usize = pos;
+        let c: u8 = bytes[pos];
+        let next: Option<u8> = bytes.get(pos + 1).copied();
+
+        let kind: TokenKind = if c.is_ascii_whitespace() {
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            TokenKind::Whitespace
+        } else if c == b'-' && next == Some(b'-') {
+            while pos < bytes.len() && bytes[pos] != b'\n' {
+                pos += 1;
+            }
+            TokenKind::LineComment
+        } else if c == b'/' && next == Some(b'*') {
+            pos += 2;
+            while pos < bytes.len() && !(bytes[pos] == b'*' && bytes.get(pos + 1) == Some(&b'/')) {
+                pos += 1;
+            }
+            pos = (pos + 2).min(bytes.len());
+            TokenKind::BlockComment
+        } else if c == b'\'' || ((c == b'N' || c == b'n' || c == b'X' || c == b'x') && next == Some(b'\'')) {
+            // N'unicode' (T-SQL) and X'blob' (SQLite) prefixes belong to the literal
+            if c != b'\'' {
+                pos += 1;
+            }
+            pos = skip_quoted(bytes, pos, b'\'');
+            TokenKind::String
+        } else if c == b'"' || c == b'`' {
+            pos = skip_quoted(bytes, pos, c);
+            TokenKind::QuotedIdent
+        } else if c == b'[' {
+            pos = skip_quoted(bytes, pos, b']');
+            TokenKind::QuotedIdent
+        } else if c.is_ascii_digit() || (c == b'.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            pos = skip_number(bytes, pos);
+            TokenKind::Number
+        } else if c == b'?' {
+            pos += 1;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            TokenKind::Parameter
+        } else if (c == b':' || c == b'@' || c == b'$') && next.is_some_and(is_word_start) {
+            // "::" casts and "@@ROWCOUNT" style globals are not parameters
+            pos += 1;
+            while pos < bytes.len() && is_word_byte(bytes[pos]) {
+                pos += 1;
+            }
+            TokenKind::Parameter
+        } else if is_word_start(c) {
+            while pos < bytes.len() && is_word_byte(bytes[pos]) {
+                pos += 1;
+            }
+            TokenKind::Word
+        } else if matches!(c, b'(' | b')' | b',' | b'.' | b';') {
+            pos += 1;
+            TokenKind::Punctuation
+        } else {
+            pos += operator_len(bytes, pos);
+            TokenKind::Operator
+        };
+
+        tokens.push(Token { kind, start, end: pos });
+    }
+    tokens
+}
+
+// tokens without whitespace and comments, for parsers that only care about structure
+pub fn significant(tokens: &[Token]) -> Vec<Token> {
+    tokens.iter().filter(|t| !t.is_trivia()).copied().collect()
+}
+
+pub fn statement_ranges(sql: &str) -> Vec<(usize, usize)> {
+    split(sql, true)
+}
+
+pub fn batch_ranges(sql: &str) -> Vec<(usize, usize)> {
+    split(sql, false)
+}
+
+fn split(sql: &str, on_semicolons: bool) -> Vec<(usize, usize)> {
+    let tokens: Vec<Token> = tokenize(sql);
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut start: usize = 0;
+    let mut has_code: bool = false;
+    let push = |ranges: &mut Vec<(usize, usize)>, start: usize, end: usize, has_code: bool| {
+        if has_code {
+            // trim surrounding whitespace, comments stay with the statement they describe
+            let text: &str = &sql[start..end];
+            let lead: usize = text.len() - text.trim_start().len();
+            let trail: usize = text.len() - text.trim_end().len();
+            ranges.push((start + lead, end - trail));
+        }
+    };
+
+    for (i, token) in tokens.iter().enumerate() {
+        let separator: bool = (on_semicolons && token.is_punct(sql, ";")) || is_batch_separator(sql, &tokens, i);
+        if separator {
+            push(&mut ranges, start, token.start, has_code);
+            start = token.end;
+            has_code = false;
+        } else if !token.is_trivia() {
+            has_code = true;
+        }
+    }
+    push(&mut ranges, start, sql.len(), has_code);
+    ranges
+}
+
+pub fn statement_at(sql: &str, cursor: usize) -> Option<(usize, usize)> {
+    let ranges: Vec<(usize, usize)> = statement_ranges(sql);
+    // a cursor sitting after "...;" or on a blank line belongs to the statement above it
+    ranges
+        .iter()
+        .find(|(start, end)| *start <= cursor && cursor <= *end)
+        .or_else(|| ranges.iter().rev().find(|(_, end)| *end <= cursor))
+        .or_else(|| ranges.first())
+        .copied()
+}
+
+// T-SQL "GO" only separates batches when nothing else shares its line
+pub fn is_batch_separator(sql: &str, tokens: &[Token], index: usize) -> bool {
+    if !tokens[index].is_word(sql, "GO") {
+        return false;
+    }
+    let line_break = |t: &Token| t.kind == TokenKind::Whitespace && t.text(sql).contains('\n');
+    let before: bool = index == 0 || line_break(&tokens[index - 1]);
+    let after: bool = match tokens.get(index + 1) {
+        None => true,
+        Some(t) => line_break(t) || t.kind == TokenKind::LineComment || t.is_punct(sql, ";"),
+    };
+    before && after
+}
+
+fn is_word_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c >= 0x80 || c == b'#'
+}
+
+fn is_word_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c == b'$' || c == b'#' || c >= 0x80
+}
+
+// returns the position after the closing quote; a doubled quote is an escaped quote
+fn skip_quoted(bytes: &[u8], open: usize, close: u8) -> usize {
+    let mut pos: usize = open + 1;
+    while pos < bytes.len() {
+        if bytes[pos] == close {
+            if bytes.get(pos + 1) == Some(&close) {
+                pos += 2;
+                continue;
+            }
+            return pos + 1;
+        }
+        pos += 1;
+    }
+    bytes.len()
+}
+
+fn skip_number(bytes: &[u8], mut pos: usize) -> usize {
+    if bytes[pos] == b'0' && matches!(bytes.get(pos + 1), Some(b'x') | Some(b'X')) {
+        pos += 2;
+        while pos < bytes.len() && bytes[pos].is_ascii_hexdigit() {
+            pos += 1;
+        }
+        return pos;
+    }
+    while pos < bytes.len() && (bytes[pos].is_ascii_digit() || bytes[pos] == b'.') {
+        pos += 1;
+    }
+    if pos < bytes.len() && (bytes[pos] == b'e' || bytes[pos] == b'E') {
+        let mut exp: usize = pos + 1;
+        if exp < bytes.len() && (bytes[exp] == b'+' || bytes[exp] == b'-') {
+            exp += 1;
+        }
+        if exp < bytes.len() && bytes[exp].is_ascii_digit() {
+            pos = exp;
+            while pos < bytes.len() && bytes[pos].is_ascii_digit() {
+                pos += 1;
+            }
+        }
+    }
+    pos
+}
+
+fn operator_len(bytes: &[u8], pos: usize) -> usize {
+    const TWO_CHAR: [&[u8; 2]; 8] = [b"<>", b"<=", b">=", b"!=", b"==", b"||", b"::", b"<<"];
+    if pos + 1 < bytes.len() {
+        let pair: [u8; 2] = [bytes[pos], bytes[pos + 1]];
+        if TWO_CHAR.iter().any(|op| **op == pair) || pair == *b">>" {
+            return 2;
+        }
+    }
+    // keep multi-byte utf-8 characters whole so token offsets stay on char boundaries
+    let mut len: usize = 1;
+    while pos + len < bytes.len() && (bytes[pos + len] & 0xC0) == 0x80 {
+        len += 1;
+    }
+    len
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str) -> Vec<(TokenKind, &str)> {
+        significant(&tokenize(sql)).iter().map(|t| (t.kind, t.text(sql))).collect()
+    }
+
+    #[test]
+    fn tokenizes_both_dialects() {
+        assert_eq!(
+            kinds("SELECT [Calib Date], \"a\"\"b\", `c` FROM t WHERE x = 'it''s' AND y <= @id"),
+            vec![
+                (TokenKind::Word, "SELECT"),
+                (TokenKind::QuotedIdent, "[Calib Date]"),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::QuotedIdent, "\"a\"\"b\""),
+                (TokenKind::Punctuation, ","),
+                (TokenKind::QuotedIdent, "`c`"),
+                (TokenKind::Word, "FROM"),
+                (TokenKind::Word, "t"),
+                (TokenKind::Word, "WHERE"),
+                (TokenKind::Word, "x"),
+                (TokenKind::Operator, "="),
+                (TokenKind::String, "'it''s'"),
+                (TokenKind::Word, "AND"),
+                (TokenKind::Word, "y"),
+                (TokenKind::Operator, "<="),
+                (TokenKind::Parameter, "@id"),
+            ]
+        );
+    }
+
+    #[test]
+    fn numbers_and_parameters() {
+        assert_eq!(
+            kinds("1.5 .5 1e-3 0x1F :name ?"),
+            vec![
+                (TokenKind::Number, "1.5"),
+                (TokenKind::Number, ".5"),
+                (TokenKind::Number, "1e-3"),
+                (TokenKind::Number, "0x1F"),
+                (TokenKind::Parameter, ":name"),
+                (TokenKind::Parameter, "?"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_string_and_comment_run_to_the_end() {
+        let sql: &str = "select 'abc; select 1";
+        assert_eq!(tokenize(sql).last().map(|t| (t.kind, t.text(sql))), Some((TokenKind::String, "'abc; select 1")));
+        let sql: &str = "select 1 /* open";
+        assert_eq!(tokenize(sql).last().map(|t| t.kind), Some(TokenKind::BlockComment));
+    }
+
+    #[test]
+    fn number_literals() {
+        for text in ["0", "-12", "3.5", "1e-3", "-2.5E+10"] {
+            assert!(is_number_literal(text), "{}", text);
+        }
+        for text in ["inf", "-inf", "NaN", "infinity", "1e5000", "+1", ".5", "5.", "1e", "0x1F", "1 ", "12a", ""] {
+            assert!(!is_number_literal(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn statements_split_on_semicolons_not_inside_strings() {
+        let sql: &str = "select ';' as a;\n -- only a comment\n;  update t set a = 1  ;";
+        let texts: Vec<&str> = statement_ranges(sql).iter().map(|(s, e)| &sql[*s..*e]).collect();
+        assert_eq!(texts, vec!["select ';' as a", "update t set a = 1"]);
+    }
+
+    #[test]
+    fn go_separates_batches_only_on_its_own_line() {
+        let sql: &str = "select 1\nGO\nselect go from t\ngo\nselect 2";
+        let texts: Vec<&str> = statement_ranges(sql).iter().map(|(s, e)| &sql[*s..*e]).collect();
+        assert_eq!(texts, vec!["select 1", "select go from t", "select 2"]);
+    }
+
+    #[test]
+    fn batches_keep_their_semicolons() {
+        let sql: &str = "declare @n int = 1;\nselect @n;\nGO\n-- only a comment\ngo\nselect ';'";
+        let texts: Vec<&str> = batch_ranges(sql).iter().map(|(s, e)| &sql[*s..*e]).collect();
+        assert_eq!(texts, vec!["declare @n int = 1;\nselect @n;", "select ';'"]);
+    }
+
+    #[test]
+    fn statement_at_the_cursor() {
+        let sql: &str = "select 1; select 2;\n\n";
+        assert_eq!(statement_at(sql, 3), Some((0, 8)));
+        assert_eq!(statement_at(sql, 12), Some((10, 18)));
+        // after the last statement, on a blank line
+        assert_eq!(statement_at(sql, sql.len()), Some((10, 18)));
+    }
+
+    #[test]
+    fn keywords_and_functions_ignore_case() {
+        assert!(is_keyword("select") && is_keyword("Go"));
+        assert!(is_function("coalesce") && is_function("COUNT"));
+        assert!(!is_keyword("product_id") && !is_function("select"));
+    }
+}
+
+/* <-- Tests */
@@ -9,6 +9,7 @@ pub use sqlite::{
         String as SqliteString,
     },
 };
+use crate::table_render::{self, TableOptions};
 use std::{collections::HashMap, fs, io};
 
 /* <-- Imports */
@@ -21,15 +22,21 @@ use std::{collections::HashMap, fs, io};
 /* --> Functions */
 
 pub fn query_interface(db_name: String, request: QueryType) -> Result<RecordSet, sqlite::Error> {
+    let db_handle = sqlite::open(&db_name)?;
+    run_request(&db_handle, request)
+}
+
+// a request on a connection that stays open, the CLI and REPL session (cli.rs)
+pub fn run_request(db_handle: &sqlite::Connection, request: QueryType) -> Result<RecordSet, sqlite::Error> {
     match request {
         QueryType::SqlFunction(request_type) => {
             match request_type {
-                Request::Tables(_) => get_tables(db_name),
-                Request::Columns(c) => get_columns(db_name, c),
+                Request::Tables(_) => get_tables(db_handle),
+                Request::Columns(c) => get_columns(db_handle, c),
                 Request::Schema(_) => { Ok(RecordSet::default()) },
             }
         },
-        QueryType::UserDefined(s) => { raw_query(db_name, s) },
+        QueryType::UserDefined(s) => { select_from(db_handle, s.trim()) },
     }
 }
 
@@ -44,13 +51,65 @@ pub fn raw_query(db_name: String, query: String) -> Result<RecordSet, sqlite::Er
     Ok(result)
 }
 
-fn get_tables(db_name: String) -> Result<RecordSet, sqlite::Error> {
-    let query: String = String::from("select name from sqlite_schema where type = 'table' and name not like 'sqlite_%';");
-    raw_query(db_name, query)
+// SQLite itself refuses to write to the file, and a missing file is an error instead of a new database
+fn open_read_only(db_name: &str) -> Result<sqlite::Connection, sqlite::Error> {
+    sqlite::Connection::open_with_flags(db_name, sqlite::OpenFlags::new().set_read_only())
+}
+
+pub fn open_session(db_name: &str, read_only: bool) -> Result<sqlite::Connection, sqlite::Error> {
+    if read_only {
+        open_read_only(db_name)
+    } else {
+        sqlite::open(db_name)
+    }
+}
+
+// a read-only profile, the schema browser's requests included
+pub fn read_only_query(db_name: String, request: QueryType) -> Result<RecordSet, sqlite::Error> {
+    let db_handle = open_read_only(&db_name)?;
+    run_request(&db_handle, request)
+}
+
+// rows of (id, parent, notused, detail), see query_plan.rs. A plan never writes,
+// whatever the profile the file is opened read-only
+pub fn explain_query_plan(db_name: String, query: String) -> Result<RecordSet, sqlite::Error> {
+    let db_handle = open_read_only(&db_name)?;
+    select_from(&db_handle, &format!("EXPLAIN QUERY PLAN {}", query.trim().trim_end_matches(';')))
 }
 
-fn get_columns(db_name: String, table_name: String) -> Result<RecordSet, sqlite::Error> {
+// one transaction for the rows, a row that fails is skipped and reported as (index in rows, error)
+pub fn insert_batch(db_name: &str, insert: &str, rows: &[Vec<sqlite::Value>]) -> Result<Vec<(usize, String)>, sqlite::Error> {
+    let db_handle = sqlite::open(db_name)?;
+    db_handle.execute("BEGIN TRANSACTION;")?;
+    let mut failures: Vec<(usize, String)> = Vec::new();
+    {
+        let mut stmt = db_handle.prepare(insert)?;
+        for (index, row) in rows.iter().enumerate() {
+            let outcome = stmt
+                .bind(&row.iter().enumerate().map(|(i, v)| (i + 1, v)).collect::<Vec<(usize, &sqlite::Value)>>()[..])
+                .and_then(|_| stmt.next());
+            if let Err(e) = outcome {
+                failures.push((index, e.to_string()));
+            }
+            // reset hands back the error of the failed step again, it is already logged
+            let _ = stmt.reset();
+        }
+    }
+    db_handle.execute("COMMIT;")?;
+    Ok(failures)
+}
+
+pub fn test_connection(db_name: String) -> Result<(), sqlite::Error> {
     let db_handle = sqlite::open(&db_name)?;
+    db_handle.execute("select count(*) from sqlite_schema;")?;
+    Ok(())
+}
+
+fn get_tables(db_handle: &sqlite::Connection) -> Result<RecordSet, sqlite::Error> {
+    select_from(db_handle, "select name from sqlite_schema where type = 'table' and name not like 'sqlite_%';")
+}
+
+fn get_columns(db_handle: &sqlite::Connection, table_name: String) -> Result<RecordSet, sqlite::Error> {
     let input = format!("select * from {table_name}");
 
     let query: sqlite::Statement = db_handle.prepare(input)?;
@@ -76,69 +135,6 @@ fn get_columns(db_name: String, table_name: String) -> Result<RecordSet, sqlite:
     Ok(record_set)
 }
 
-pub fn cli_query(db_name: String) -> Result<(), sqlite::Error> {
-    //start the db
-    let db_handle = sqlite::open(&db_name)?;
-    let confirmation = &String::from("y")[..];
-    //add rows
-    if db_name == ":memory:" {
-        build_db(&db_handle)?;
-    }
-    loop {
-        let mut state_trigger: String = String::new();
-        let mut save_trigger: String = String::new();
-
-        println!("Enter a query.");
-        let mut query: String = String::new();
-        io::stdin()
-            .read_line(&mut query)
-            .expect("Failed to read line.");
-
-        let result = select_from(&db_handle, query.trim())?;
-
-        let csv_printout: String = print_results(&result);
-        println!("\n==============\nDone printing.\nSave results?(Y/N)");
-
-        io::stdin()
-            .read_line(&mut save_trigger)
-            .expect("Failed to read line.");
-
-        if save_trigger.trim() == confirmation {
-            match save_results(csv_printout) {
-                Ok(_) => println!("Results saved."),
-                Error => println!("Failed to save results.\n{:?}", Error),
-            }
-        }
-        println!("Continue to query?(y/n)");
-        io::stdin()
-            .read_line(&mut state_trigger)
-            .expect("Failed to read line.");
-        if state_trigger.trim() != confirmation {
-            break;
-        }
-    }
-    Ok(())
-}
-
-fn build_db(db_handle: &sqlite::Connection) -> Result<(), sqlite::Error> {
-    let query: &str = "BEGIN TRANSACTION;
-		CREATE TABLE users (name TEXT, age INTEGER, location_id INTEGER, gender_id INTEGER);
-		INSERT INTO users VALUES ('Alice', 42, 1, 1);
-		INSERT INTO users VALUES ('Bob', 69, 2, 2);
-		INSERT INTO users VALUES ('Mark', 50, 3, 2);
-		INSERT INTO users VALUES ('Alex', 5, 4, 1);
-		CREATE TABLE fo_location (location_id INTEGER, location TEXT);
-		INSERT INTO fo_location VALUES (1, 'Denmark');
-		INSERT INTO fo_location VALUES (2, 'Brazil');
-		INSERT INTO fo_location VALUES (3, 'Russia');
-		INSERT INTO fo_location VALUES (4, 'Canada');
-		COMMIT;";
-
-    db_handle.execute(query)?;
-
-    Ok(())
-}
-
 fn select_from(db_handle: &sqlite::Connection, query: &str) -> Result<RecordSet, sqlite::Error> {
     let mut stmt = db_handle.prepare(query)?;
 
@@ -150,79 +146,78 @@ fn select_from(db_handle: &sqlite::Connection, query: &str) -> Result<RecordSet,
         records: Vec::new(),
     };
 
+    // the first step runs the statement and gives the column types, a write runs once
+    let mut state: State = stmt.next()?;
     record_set.construct_sqlite(&mut stmt)?;
 
     //then read recordsets from Sqlite
-    while let Ok(State::Row) = stmt.next() {
-        //new row available
-        //create a new record object
-        let mut current_row: Record = Record {
-            columns: HashMap::new(),
-            data_type: Some(crate::sql_aux_funcs::ConnectionBase::Sqlite),
-        };
-
-        //parse the columns in the row
-        for (name, _) in &record_set.column_info {
-            // 'name' will index the row and fetch columns
-            let read_value = stmt.read::<Option<sqlite::Value>, _>(&name[..])?;
-
-            match read_value {
-                //fetched data from a column
-                Some(value) => {
-                    //'value' is the data in the column
-                    //add value to the Record object
-                    current_row.add(name.clone(), SqlData::Sqlite(value.clone()));
-                }
-
-                //no data found in the column? not even a SqliteNull?
-                None => (),
-            }
-        }
-        record_set.add(current_row);
+    while let State::Row = state {
+        record_set.add(read_row(&stmt, &record_set.column_info)?);
+        state = stmt.next()?;
     }
 
     Ok(record_set)
 }
 
-pub fn print_results(record_set: &RecordSet) -> String {
-    println!("Printing records\n==============\n");
-    let mut text_payload: String = String::new();
-    let mut current_line: String = String::new();
-
-    //first printout is columns
-    for column_name in &record_set.column_order {
-        current_line.push_str(&column_name.to_string()[..]);
-        if &column_name != &record_set.column_order.last().unwrap() {
-            current_line.push(',');
-            current_line.push(' ');
-        } else {
-            current_line.push('\n')
+// the row the statement is on
+fn read_row(stmt: &sqlite::Statement, column_info: &HashMap<String, SqlType>) -> Result<Record, sqlite::Error> {
+    //create a new record object
+    let mut current_row: Record = Record {
+        columns: HashMap::new(),
+        data_type: Some(crate::sql_aux_funcs::ConnectionBase::Sqlite),
+    };
+
+    //parse the columns in the row
+    for name in column_info.keys() {
+        // 'name' will index the row and fetch columns
+        let read_value = stmt.read::<Option<sqlite::Value>, _>(&name[..])?;
+
+        match read_value {
+            //fetched data from a column
+            Some(value) => {
+                //'value' is the data in the column
+                //add value to the Record object
+                current_row.add(name.clone(), SqlData::Sqlite(value.clone()));
+            }
+
+            //no data found in the column? not even a SqliteNull?
+            None => (),
         }
     }
-    text_payload.push_str(&current_line.to_string()[..]);
-
-    //followed by a printout of data
-    for record in &record_set.records {
-        current_line.clear();
-        for v in &record_set.column_order {
-            match record.columns.get(v) {
-                Some(value) => match value {
-                    Some(data) => current_line.push_str(&data.translate()),
-                    None => {}
-                },
-                None => {}
-            }
-            if &v != &record_set.column_order.last().unwrap() {
-                current_line.push(',');
-                current_line.push(' ');
-            } else {
-                current_line.push('\n')
-            }
+    Ok(current_row)
+}
+
+// the rows of the query in batches of batch_size, the first batch carries the columns even when there
+// are no rows. each answers false to stop. the file stays locked for reading until the last row is read
+pub fn stream_rows(
+    db_name: &str,
+    read_only: bool,
+    query: &str,
+    batch_size: usize,
+    each: &mut dyn FnMut(RecordSet) -> bool,
+) -> Result<(), sqlite::Error> {
+    let db_handle = open_session(db_name, read_only)?;
+    let mut stmt = db_handle.prepare(query.trim())?;
+    let mut state: State = stmt.next()?;
+    let mut columns: RecordSet = RecordSet::default();
+    columns.construct_sqlite(&mut stmt)?;
+    let mut first: bool = true;
+    loop {
+        let mut batch: RecordSet = RecordSet { records: Vec::new(), ..columns.clone() };
+        while state == State::Row && batch.records.len() < batch_size.max(1) {
+            batch.add(read_row(&stmt, &batch.column_info)?);
+            state = stmt.next()?;
+        }
+        if (batch.records.is_empty() && !first) || !each(batch) || state == State::Done {
+            return Ok(());
         }
-        text_payload.push_str(&current_line.to_string()[..]);
+        first = false;
     }
+}
 
-    println!("{text_payload}");
+pub fn print_results(record_set: &RecordSet) -> String {
+    let text_payload: String = table_render::render(record_set, &TableOptions::default());
+    print!("{text_payload}");
     text_payload
 }
 
@@ -253,3 +248,103 @@ where
 }
 
 /* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path: PathBuf = std::env::temp_dir().join(format!("dv_sqlite_{}_{}.db", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn user(sql: &str) -> QueryType {
+        QueryType::UserDefined(sql.to_string())
+    }
+
+    #[test]
+    fn read_only_never_creates_a_missing_file() {
+        let path: PathBuf = temp_file("missing");
+        let name: String = path.display().to_string();
+        assert!(read_only_query(name.clone(), user("select 1")).is_err());
+        assert!(read_only_query(name.clone(), QueryType::SqlFunction(Request::Tables(0))).is_err());
+        assert!(read_only_query(name.clone(), QueryType::SqlFunction(Request::Columns(String::from("t")))).is_err());
+        assert!(explain_query_plan(name, String::from("select 1")).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn read_only_reads_and_refuses_writes() {
+        let path: PathBuf = temp_file("existing");
+        let name: String = path.display().to_string();
+        sqlite::open(&name).unwrap().execute("create table t (a integer, b text); insert into t values (1, 'x');").unwrap();
+
+        assert_eq!(read_only_query(name.clone(), user("select * from t")).unwrap().records.len(), 1);
+        let tables: RecordSet = read_only_query(name.clone(), QueryType::SqlFunction(Request::Tables(0))).unwrap();
+        assert_eq!(tables.records[0].text("name").as_deref(), Some("t"));
+        let columns: RecordSet = read_only_query(name.clone(), QueryType::SqlFunction(Request::Columns(String::from("t")))).unwrap();
+        assert_eq!(columns.records.len(), 2);
+        assert!(!explain_query_plan(name.clone(), String::from("select * from t;")).unwrap().records.is_empty());
+        assert!(read_only_query(name.clone(), user("insert into t values (2, 'y')")).is_err());
+        assert_eq!(query_interface(name, user("select * from t")).unwrap().records.len(), 1);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_session_runs_each_statement_once() {
+        let path: PathBuf = temp_file("session");
+        let name: String = path.display().to_string();
+        let db_handle: sqlite::Connection = open_session(&name, false).unwrap();
+        for sql in ["create table t (a integer)", "begin", "insert into t values (1)", "create temp table s (b integer)", "commit"] {
+            run_request(&db_handle, user(sql)).unwrap();
+        }
+        let rows: RecordSet = run_request(&db_handle, user("select a from t")).unwrap();
+        assert_eq!(rows.records.len(), 1);
+        assert_eq!(rows.records[0].text("a").as_deref(), Some("1"));
+        assert!(run_request(&db_handle, user("select * from s")).unwrap().column_order.contains(&String::from("b")));
+        drop(db_handle);
+
+        let read_only: sqlite::Connection = open_session(&name, true).unwrap();
+        assert!(run_request(&read_only, user("insert into t values (2)")).is_err());
+        drop(read_only);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rows_stream_in_batches() {
+        let path: PathBuf = temp_file("stream");
+        let name: String = path.display().to_string();
+        sqlite::open(&name).unwrap().execute("create table t (a integer); insert into t values (1), (2), (3), (4), (5);").unwrap();
+        let mut sizes: Vec<usize> = Vec::new();
+        stream_rows(&name, true, "select a from t order by a", 2, &mut |rows: RecordSet| {
+            assert_eq!(rows.column_order, vec![String::from("a")]);
+            sizes.push(rows.records.len());
+            true
+        })
+        .unwrap();
+        assert_eq!(sizes, vec![2, 2, 1]);
+
+        // the columns come even without rows, and false stops the reading
+        sizes.clear();
+        stream_rows(&name, true, "select a from t where a > 5", 2, &mut |rows: RecordSet| {
+            assert_eq!(rows.column_order, vec![String::from("a")]);
+            sizes.push(rows.records.len());
+            true
+        })
+        .unwrap();
+        assert_eq!(sizes, vec![0]);
+        sizes.clear();
+        stream_rows(&name, true, "select a from t", 2, &mut |rows: RecordSet| {
+            sizes.push(rows.records.len());
+            false
+        })
+        .unwrap();
+        assert_eq!(sizes, vec![2]);
+        fs::remove_file(path).unwrap();
+    }
+}
+
+/* <-- Tests */
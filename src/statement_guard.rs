@@ -0,0 +1,432 @@
+/* --> Summary of Contents
+
+	Safety checks on SQL before it reaches the backend
+
+	pub fn classify(&str) -> StatementClass
+		What one statement does, from its leading keywords: a query, a
+		data change, a schema change, a PRAGMA, transaction / session
+		control or something else. Anything not known to only read
+		counts as a write.
+
+	pub fn refuse_writes(Safety, &str) -> Result<(), String>
+		A read-only connection refuses every statement that writes
+
+	pub fn check(Safety, &str, FnOnce(&str) -> bool) -> Result<(), String>
+		refuse_writes, then a production connection asks before a DELETE
+		without WHERE. The closure is the question: the terminal in the
+		REPL, always no for a headless run.
+
+	pub fn destructive(&str) -> Vec<Destructive>
+	pub fn confirm_destructive(Safety, &ConnectionBase, &str, &str) -> bool
+		DROP, TRUNCATE and DELETE / UPDATE without WHERE found in the
+		text. The editor asks before running them on any connection,
+		naming each table with its row count where a COUNT(*) can tell.
+
+	pub fn ask(&str) -> bool
+		The confirmation dialog of the editor
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use fltk::dialog;
+
+use crate::audit_log::{self, Action, Modification};
+use crate::learning_fltk::center;
+use crate::sql_aux_funcs::ConnectionBase;
+use crate::sql_lexer::{self, Token, TokenKind};
+
+/* <-- Imports */
+/* --> Const */
+
+// PRAGMAs that only report, the rest can change the database or the connection
+const READ_PRAGMAS: [&str; 16] = [
+    "collation_list",
+    "compile_options",
+    "database_list",
+    "foreign_key_check",
+    "foreign_key_list",
+    "function_list",
+    "index_info",
+    "index_list",
+    "index_xinfo",
+    "integrity_check",
+    "module_list",
+    "pragma_list",
+    "quick_check",
+    "table_info",
+    "table_list",
+    "table_xinfo",
+];
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StatementClass {
+    Query,       // SELECT, VALUES, EXPLAIN, WITH ... SELECT
+    Dml,         // INSERT, UPDATE, DELETE, MERGE, REPLACE
+    Ddl,         // CREATE, ALTER, DROP, TRUNCATE, SELECT ... INTO
+    Pragma,      // a PRAGMA that sets something
+    Transaction, // BEGIN, COMMIT, ROLLBACK, SAVEPOINT, SET, USE, DECLARE
+    Other,       // EXEC, ATTACH, VACUUM, IF ... and whatever else
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+// how careful to be with a connection, from its profile
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Safety {
+    pub read_only: bool,
+    pub production: bool,
+}
+
+// one statement that cannot be undone
+#[derive(Clone)]
+pub struct Destructive {
+    pub what: String,    // "DROP TABLE", "TRUNCATE TABLE", "DELETE without WHERE", ...
+    pub target: String,  // as written
+    pub countable: bool, // a table, not a view or an index
+    pub rows: Option<i64>,
+}
+
+impl StatementClass {
+    pub fn is_write(&self) -> bool {
+        !matches!(self, StatementClass::Query | StatementClass::Transaction)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatementClass::Query => "query",
+            StatementClass::Dml => "data change (DML)",
+            StatementClass::Ddl => "schema change (DDL)",
+            StatementClass::Pragma => "PRAGMA",
+            StatementClass::Transaction => "transaction or session statement",
+            StatementClass::Other => "statement that may write",
+        }
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+// true when a depth 0 INTO follows, SELECT ... INTO creates a table in T-SQL
+fn selects_into(sql: &str, tokens: &[Token], start: usize) -> bool {
+    let mut depth: i32 = 0;
+    for token in &tokens[start..] {
+        if token.is_punct(sql, "(") {
+            depth += 1;
+        } else if token.is_punct(sql, ")") {
+            depth -= 1;
+        } else if depth == 0 && token.is_word(sql, "INTO") {
+            return true;
+        }
+    }
+    false
+}
+
+fn classify_pragma(sql: &str, tokens: &[Token]) -> StatementClass {
+    // PRAGMA schema.name, PRAGMA name = value, PRAGMA name(argument)
+    let name: &str = match tokens.get(2) {
+        Some(dot) if dot.is_punct(sql, ".") => tokens.get(3).map_or("", |t| t.text(sql)),
+        _ => tokens.get(1).map_or("", |t| t.text(sql)),
+    };
+    let assigns: bool = tokens.iter().any(|t| t.kind == TokenKind::Operator && t.text(sql) == "=");
+    if !assigns && READ_PRAGMAS.iter().any(|p| p.eq_ignore_ascii_case(name)) {
+        StatementClass::Query
+    } else {
+        StatementClass::Pragma
+    }
+}
+
+pub fn classify(statement: &str) -> StatementClass {
+    let sql: &str = statement;
+    let tokens: Vec<Token> = sql_lexer::significant(&sql_lexer::tokenize(sql));
+    let first: &Token = match tokens.first() {
+        Some(token) => token,
+        None => return StatementClass::Query, // nothing but comments, nothing runs
+    };
+    let query_or_into = |start: usize| if selects_into(sql, &tokens, start) { StatementClass::Ddl } else { StatementClass::Query };
+    match first.text(sql).to_ascii_uppercase().as_str() {
+        "SELECT" | "(" => query_or_into(1),
+        "VALUES" | "EXPLAIN" => StatementClass::Query,
+        // the statement after the common table expressions decides
+        "WITH" => {
+            let mut depth: i32 = 0;
+            for (index, token) in tokens.iter().enumerate().skip(1) {
+                if token.is_punct(sql, "(") {
+                    depth += 1;
+                } else if token.is_punct(sql, ")") {
+                    depth -= 1;
+                } else if depth == 0 && token.is_word(sql, "SELECT") {
+                    return query_or_into(index + 1);
+                } else if depth == 0 && ["INSERT", "UPDATE", "DELETE", "MERGE", "REPLACE"].iter().any(|w| token.is_word(sql, w)) {
+                    return StatementClass::Dml;
+                }
+            }
+            StatementClass::Other
+        }
+        "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "REPLACE" | "UPSERT" => StatementClass::Dml,
+        "CREATE" | "ALTER" | "DROP" | "TRUNCATE" | "RENAME" | "GRANT" | "REVOKE" | "DENY" => StatementClass::Ddl,
+        "PRAGMA" => classify_pragma(sql, &tokens),
+        // a T-SQL BEGIN ... END block can hold anything, only BEGIN TRANSACTION is harmless
+        "BEGIN" => match tokens.get(1) {
+            None => StatementClass::Transaction,
+            Some(next) if ["TRAN", "TRANSACTION", "DEFERRED", "IMMEDIATE", "EXCLUSIVE"].iter().any(|w| next.is_word(sql, w)) => {
+                StatementClass::Transaction
+            }
+            Some(_) => StatementClass::Other,
+        },
+        "COMMIT" | "ROLLBACK" | "END" | "SAVEPOINT" | "RELEASE" | "SET" | "USE" | "DECLARE" | "PRINT" => StatementClass::Transaction,
+        _ => StatementClass::Other,
+    }
+}
+
+// the first statement of the text that writes, with its class
+fn first_write(sql: &str) -> Option<(usize, StatementClass, String)> {
+    sql_lexer::statement_ranges(sql)
+        .iter()
+        .enumerate()
+        .map(|(index, (start, end))| (index, classify(&sql[*start..*end]), sql[*start..*end].trim().to_string()))
+        .find(|(_, class, _)| class.is_write())
+}
+
+// DROP TABLE a, b / DROP VIEW IF EXISTS v / TRUNCATE TABLE t
+fn dropped(sql: &str, tokens: &[Token]) -> Vec<Destructive> {
+    let truncate: bool = tokens.first().is_some_and(|t| t.is_word(sql, "TRUNCATE"));
+    let kind: String = tokens.get(1).map_or(String::new(), |t| t.text(sql).to_ascii_uppercase());
+    let what: String = if truncate { String::from("TRUNCATE TABLE") } else { format!("DROP {}", kind) };
+    let mut index: usize = 2;
+    if tokens.get(index).is_some_and(|t| t.is_word(sql, "IF")) && tokens.get(index + 1).is_some_and(|t| t.is_word(sql, "EXISTS")) {
+        index += 2;
+    }
+    let mut found: Vec<Destructive> = Vec::new();
+    while let Some((target, next)) = audit_log::table_at(sql, tokens, index) {
+        found.push(Destructive {
+            what: what.clone(),
+            countable: kind == "TABLE",
+            target,
+            rows: None,
+        });
+        match tokens.get(next) {
+            Some(comma) if comma.is_punct(sql, ",") => index = next + 1,
+            _ => break,
+        }
+    }
+    found
+}
+
+// DROP, TRUNCATE and a DELETE or UPDATE without WHERE. one that joins other tables is
+// narrowed by the join and not listed
+fn destructive_one(statement: &str) -> Vec<Destructive> {
+    let sql: &str = statement;
+    let tokens: Vec<Token> = sql_lexer::significant(&sql_lexer::tokenize(sql));
+    match tokens.first() {
+        Some(first) if first.is_word(sql, "DROP") || first.is_word(sql, "TRUNCATE") => return dropped(sql, &tokens),
+        _ => {}
+    }
+    let found: Modification = match audit_log::modification(statement) {
+        Some(found) => found,
+        None => return Vec::new(),
+    };
+    let what: &str = match found.action {
+        Action::Delete => "DELETE without WHERE",
+        Action::Update => "UPDATE without WHERE",
+        _ => return Vec::new(),
+    };
+    match (found.table, found.where_clause) {
+        (Some(table), None) => vec![Destructive {
+            what: what.to_string(),
+            target: table,
+            countable: true,
+            rows: None,
+        }],
+        _ => Vec::new(),
+    }
+}
+
+pub fn destructive(sql: &str) -> Vec<Destructive> {
+    sql_lexer::statement_ranges(sql)
+        .iter()
+        .flat_map(|(start, end)| destructive_one(&sql[*start..*end]))
+        .collect()
+}
+
+// SELECT COUNT(*) of every table that is listed, no WHERE to copy since there is none
+pub fn estimate(connection_type: &ConnectionBase, connection: &str, found: &mut [Destructive]) {
+    for item in found.iter_mut().filter(|d| d.countable) {
+        match audit_log::count(connection_type, connection, &item.target, None) {
+            Ok(rows) => item.rows = Some(rows),
+            Err(e) => println!("Unable to count the rows of {}: {}", item.target, e),
+        }
+    }
+}
+
+pub fn refuse_writes(safety: Safety, sql: &str) -> Result<(), String> {
+    if !safety.read_only {
+        return Ok(());
+    }
+    match first_write(sql) {
+        Some((index, class, statement)) => {
+            let shown: String = statement.chars().take(80).collect();
+            Err(format!(
+                "The connection is read-only. Statement {} is a {} and nothing was run:\n{}",
+                index + 1,
+                class.label(),
+                shown
+            ))
+        }
+        None => Ok(()),
+    }
+}
+
+pub fn check(safety: Safety, sql: &str, confirm: impl FnOnce(&str) -> bool) -> Result<(), String> {
+    refuse_writes(safety, sql)?;
+    if safety.production {
+        let tables: Vec<String> = destructive(sql)
+            .into_iter()
+            .filter(|d| d.what.starts_with("DELETE"))
+            .map(|d| d.target)
+            .collect();
+        if !tables.is_empty() {
+            let question: String = format!(
+                "This is a production connection.\nDELETE without WHERE removes every row of {}.\n\nRun it anyway?",
+                tables.join(", ")
+            );
+            if !confirm(&question) {
+                return Err(format!("Not run: DELETE without WHERE on production table {}", tables.join(", ")));
+            }
+        }
+    }
+    Ok(())
+}
+
+// the editor asks on every connection, true when there is nothing to ask about or the user said run
+pub fn confirm_destructive(safety: Safety, connection_type: &ConnectionBase, connection: &str, sql: &str) -> bool {
+    // SQLite runs the first statement of the text, ODBC sends the whole batch
+    let sql: &str = match (connection_type, sql_lexer::statement_ranges(sql).first()) {
+        (ConnectionBase::Sqlite, Some((start, end))) => &sql[*start..*end],
+        _ => sql,
+    };
+    let mut found: Vec<Destructive> = destructive(sql);
+    if found.is_empty() {
+        return true;
+    }
+    estimate(connection_type, connection, &mut found);
+    let lines: Vec<String> = found
+        .iter()
+        .map(|d| match d.rows {
+            Some(rows) => format!("    {}: {}, {} rows", d.what, d.target, rows),
+            None if d.countable => format!("    {}: {}, row count unknown", d.what, d.target),
+            None => format!("    {}: {}", d.what, d.target),
+        })
+        .collect();
+    let question: String = format!(
+        "{}The statement cannot be undone:\n\n{}\n\nRun it anyway?",
+        if safety.production { "This is a production connection.\n" } else { "" },
+        lines.join("\n")
+    );
+    ask(&question)
+}
+
+pub fn ask(question: &str) -> bool {
+    let (x, y) = center();
+    dialog::choice2(x - 200, y - 100, question, "Cancel", "Run", "") == Some(1)
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READ_ONLY: Safety = Safety { read_only: true, production: false };
+    const PRODUCTION: Safety = Safety { read_only: false, production: true };
+
+    #[test]
+    fn reads() {
+        for sql in [
+            "select * from specs",
+            "(select 1) union select 2",
+            "WITH due AS (SELECT 1) SELECT * FROM due",
+            "explain query plan select 1",
+            "VALUES (1)",
+            "PRAGMA table_info(specs)",
+            "pragma main.index_list(specs)",
+            "-- only a comment",
+        ] {
+            assert_eq!(classify(sql), StatementClass::Query, "{}", sql);
+        }
+        for sql in ["BEGIN TRANSACTION", "commit", "SET NOCOUNT ON", "USE Production", "DECLARE @id int"] {
+            assert_eq!(classify(sql), StatementClass::Transaction, "{}", sql);
+        }
+    }
+
+    #[test]
+    fn writes() {
+        let cases: [(&str, StatementClass); 12] = [
+            ("insert into log values (1)", StatementClass::Dml),
+            ("UPDATE specs SET nominal = 1", StatementClass::Dml),
+            ("with old as (select 1) delete from log where id in (select * from old)", StatementClass::Dml),
+            ("MERGE specs AS t USING s ON 1 = 1 WHEN MATCHED THEN DELETE;", StatementClass::Dml),
+            ("drop table specs", StatementClass::Ddl),
+            ("TRUNCATE TABLE log", StatementClass::Ddl),
+            ("select * into backup from specs", StatementClass::Ddl),
+            ("with x as (select 1) select * into y from x", StatementClass::Ddl),
+            ("PRAGMA journal_mode = WAL", StatementClass::Pragma),
+            ("pragma optimize", StatementClass::Pragma),
+            ("BEGIN UPDATE specs SET nominal = 1 END", StatementClass::Other),
+            ("EXEC sp_rename 'a', 'b'", StatementClass::Other),
+        ];
+        for (sql, class) in cases {
+            assert_eq!(classify(sql), class, "{}", sql);
+            assert!(class.is_write());
+        }
+    }
+
+    #[test]
+    fn read_only_refuses_any_write_in_the_text() {
+        assert!(refuse_writes(READ_ONLY, "select 1; pragma table_info(t)").is_ok());
+        let refused: String = refuse_writes(READ_ONLY, "select 1;\nupdate specs set nominal = 1").unwrap_err();
+        assert!(refused.contains("Statement 2 is a data change (DML)"), "{}", refused);
+        assert!(refuse_writes(READ_ONLY, "select 1\nGO\nexec sp_who").is_err());
+        // a semicolon inside a string does not hide the second statement
+        assert!(refuse_writes(READ_ONLY, "select ';'; delete from log where id = 1").is_err());
+        assert!(refuse_writes(Safety::default(), "drop table specs").is_ok());
+    }
+
+    #[test]
+    fn production_asks_before_delete_without_where() {
+        assert!(check(PRODUCTION, "delete from log where id = 1", |_| false).is_ok());
+        assert!(check(PRODUCTION, "delete from log", |_| true).is_ok());
+        assert_eq!(
+            check(PRODUCTION, "delete from log", |_| false).unwrap_err(),
+            "Not run: DELETE without WHERE on production table log"
+        );
+        assert!(check(Safety::default(), "delete from log", |_| false).is_ok());
+        assert!(check(Safety { read_only: true, production: true }, "delete from log", |_| true).is_err());
+    }
+
+    #[test]
+    fn destructive_statements() {
+        let found = |sql: &str| -> Vec<(String, String, bool)> {
+            destructive(sql).into_iter().map(|d| (d.what, d.target, d.countable)).collect()
+        };
+        assert_eq!(
+            found("DROP TABLE IF EXISTS a, [dbo].[b]; drop view v"),
+            vec![
+                (String::from("DROP TABLE"), String::from("a"), true),
+                (String::from("DROP TABLE"), String::from("[dbo].[b]"), true),
+                (String::from("DROP VIEW"), String::from("v"), false),
+            ]
+        );
+        assert_eq!(found("truncate table log"), vec![(String::from("TRUNCATE TABLE"), String::from("log"), true)]);
+        assert_eq!(found("update specs set nominal = 1"), vec![(String::from("UPDATE without WHERE"), String::from("specs"), true)]);
+        assert_eq!(found("DELETE FROM \"log\""), vec![(String::from("DELETE without WHERE"), String::from("\"log\""), true)]);
+        assert!(found("delete from log where id = 1; update specs set a = 1 where id = 2").is_empty());
+        assert!(found("delete l from log l join runs r on r.id = l.run_id").is_empty());
+    }
+}
+
+/* <-- Tests */
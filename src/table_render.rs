@@ -0,0 +1,338 @@
+/* --> Summary of Contents
+
+	RecordSet -> box drawn table for a terminal
+
+	pub fn render(&RecordSet, &TableOptions) -> String
+		┌────┬───────┐
+		│ id │ name  │
+		├────┼───────┤
+		│  1 │ Alice │
+		└────┴───────┘
+		(1 row)
+
+		Columns are sized by display width, so CJK and emoji line up.
+		Cells longer than max_cell_width end in '…'. Numbers are right
+		aligned. The expanded layout prints one "column │ value" line per
+		field instead, for rows too wide for the screen; Auto picks it when
+		the grid would be wider than max_width.
+
+	pub fn display_width(&str) -> usize
+	pub fn truncate(&str, usize) -> String
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::sql_aux_funcs::{RecordSet, SqlData, SqlType, Translate};
+use crate::sqlite3_interface::{SqliteFloat, SqliteInteger, SqliteNull};
+
+/* <-- Imports */
+/* --> Const */
+
+const NULL_TEXT: &str = "NULL";
+const ELLIPSIS: char = '…';
+const MIN_EXPANDED_VALUE: usize = 10;
+
+/* <-- Const */
+/* --> Enums */
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Layout {
+    Grid,
+    Expanded,
+    Auto, // expanded when the grid would not fit in max_width
+}
+
+/* <-- Enums */
+/* --> Structs */
+
+#[derive(Clone, Copy, Debug)]
+pub struct TableOptions {
+    pub layout: Layout,
+    pub max_cell_width: usize,
+    pub max_width: Option<usize>, // the terminal width, None when writing to a file
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions {
+            layout: Layout::Auto,
+            max_cell_width: 40,
+            max_width: None,
+        }
+    }
+}
+
+struct Cell {
+    text: String,
+    numeric: bool,
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+pub fn display_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+// cut to at most `width` columns, the last one is the ellipsis
+pub fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let mut out: String = String::new();
+    let mut used: usize = 0;
+    for ch in text.chars() {
+        let ch_width: usize = ch.width().unwrap_or(0);
+        if used + ch_width + 1 > width {
+            break;
+        }
+        out.push(ch);
+        used += ch_width;
+    }
+    if width > 0 {
+        out.push(ELLIPSIS);
+    }
+    out
+}
+
+fn pad(text: &str, width: usize, right_align: bool) -> String {
+    let fill: String = " ".repeat(width.saturating_sub(display_width(text)));
+    if right_align {
+        fill + text
+    } else {
+        format!("{}{}", text, fill)
+    }
+}
+
+// control characters have no width and would break the borders, line breaks and tabs become spaces
+fn one_line(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect()
+}
+
+fn cells(record_set: &RecordSet) -> Vec<Vec<Option<Cell>>> {
+    record_set
+        .records
+        .iter()
+        .map(|record| {
+            record_set
+                .column_order
+                .iter()
+                .map(|column| match record.columns.get(column) {
+                    Some(Some(SqlData::Sqlite(SqliteNull))) | Some(None) | None => None,
+                    Some(Some(data)) => Some(Cell {
+                        text: one_line(&data.translate()),
+                        numeric: match data {
+                            SqlData::Sqlite(SqliteInteger(_) | SqliteFloat(_)) => true,
+                            // odbc values arrive as text, the column type tells what they are
+                            SqlData::Odbc(text) => {
+                                matches!(record_set.column_info.get(column), Some(t @ SqlType::Odbc(_)) if t.is_numeric())
+                                    && text.trim().parse::<f64>().is_ok()
+                            }
+                            _ => false,
+                        },
+                    }),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn rows_footer(count: usize) -> String {
+    format!("({} row{})\n", count, if count == 1 { "" } else { "s" })
+}
+
+pub fn render(record_set: &RecordSet, options: &TableOptions) -> String {
+    // statements without a result set (UPDATE, CREATE ...) print nothing
+    if record_set.column_order.is_empty() {
+        return String::new();
+    }
+    let rows: Vec<Vec<Option<Cell>>> = cells(record_set);
+    let limit: usize = options.max_cell_width.max(1);
+
+    let headers: Vec<String> = record_set.column_order.iter().map(|c| truncate(&one_line(c), limit)).collect();
+    let texts: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.as_ref().map_or(String::from(NULL_TEXT), |c| truncate(&c.text, limit)))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|index| texts.iter().map(|row| display_width(&row[index])).fold(display_width(&headers[index]), usize::max))
+        .collect();
+    // a column is right aligned when every value in it is a number
+    let numeric: Vec<bool> = (0..headers.len())
+        .map(|index| {
+            let mut values = rows.iter().filter_map(|row| row[index].as_ref()).peekable();
+            values.peek().is_some() && values.all(|c| c.numeric)
+        })
+        .collect();
+
+    let grid_width: usize = widths.iter().map(|w| w + 3).sum::<usize>() + 1;
+    let expanded: bool = match options.layout {
+        Layout::Grid => false,
+        Layout::Expanded => true,
+        Layout::Auto => !rows.is_empty() && options.max_width.is_some_and(|max| grid_width > max),
+    };
+    if expanded {
+        return render_expanded(record_set, &rows, options);
+    }
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+        format!("{}{}{}\n", left, segments.join(middle), right)
+    };
+    let line = |values: &[String], align: &[bool]| {
+        let padded: Vec<String> = values
+            .iter()
+            .zip(&widths)
+            .zip(align)
+            .map(|((value, width), right)| format!(" {} ", pad(value, *width, *right)))
+            .collect();
+        format!("│{}│\n", padded.join("│"))
+    };
+
+    let mut out: String = border("┌", "┬", "┐");
+    out.push_str(&line(&headers, &vec![false; headers.len()]));
+    if !texts.is_empty() {
+        out.push_str(&border("├", "┼", "┤"));
+    }
+    for row in &texts {
+        out.push_str(&line(row, &numeric));
+    }
+    out.push_str(&border("└", "┴", "┘"));
+    out.push_str(&rows_footer(texts.len()));
+    out
+}
+
+// -[ RECORD 1 ]--------
+// id   │ 1
+// name │ Alice
+fn render_expanded(record_set: &RecordSet, rows: &[Vec<Option<Cell>>], options: &TableOptions) -> String {
+    let labels: Vec<String> = record_set.column_order.iter().map(|c| one_line(c)).collect();
+    let label_width: usize = labels.iter().map(|l| display_width(l)).max().unwrap_or(0);
+    // values get the rest of the line, wide rows are the reason for this layout
+    let value_limit: usize = options
+        .max_width
+        .map_or(usize::MAX, |max| max.saturating_sub(label_width + 3).max(MIN_EXPANDED_VALUE));
+
+    let values: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| cell.as_ref().map_or(String::from(NULL_TEXT), |c| truncate(&c.text, value_limit)))
+                .collect()
+        })
+        .collect();
+    let rule_width: usize = values.iter().flatten().map(|v| display_width(v)).max().unwrap_or(0) + label_width + 3;
+
+    let mut out: String = String::new();
+    for (number, values) in values.iter().enumerate() {
+        let title: String = format!("─[ RECORD {} ]", number + 1);
+        out.push_str(&title);
+        out.push_str(&"─".repeat(rule_width.saturating_sub(display_width(&title))));
+        out.push('\n');
+        for (label, value) in labels.iter().zip(values) {
+            out.push_str(&format!("{} │ {}\n", pad(label, label_width, false), value));
+        }
+    }
+    out.push_str(&rows_footer(rows.len()));
+    out
+}
+
+/* <-- Functions */
+/* --> Tests */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql_aux_funcs::Record;
+    use std::collections::HashMap;
+
+    fn record_set(columns: &[&str], rows: Vec<Vec<sqlite::Value>>) -> RecordSet {
+        let mut record_set: RecordSet = RecordSet {
+            column_order: columns.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        };
+        for row in rows {
+            let mut record: Record = Record::default();
+            let values: HashMap<String, Option<SqlData>> =
+                columns.iter().zip(row).map(|(c, v)| (c.to_string(), Some(SqlData::Sqlite(v)))).collect();
+            record.columns = values;
+            record_set.records.push(record);
+        }
+        record_set
+    }
+
+    #[test]
+    fn wide_characters_count_double() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("é"), 1);
+    }
+
+    #[test]
+    fn truncate_keeps_the_width_and_ends_in_an_ellipsis() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdefgh", 5), "abcd…");
+        // a double width character that does not fit is dropped whole
+        assert_eq!(truncate("ab日本", 4), "ab…");
+        assert!(display_width(&truncate("日本語のテキスト", 7)) <= 7);
+        assert_eq!(truncate("abc", 0), "");
+    }
+
+    #[test]
+    fn grid_sizes_columns_and_right_aligns_numbers() {
+        let rs: RecordSet = record_set(
+            &["id", "name"],
+            vec![
+                vec![sqlite::Value::Integer(1), sqlite::Value::String(String::from("Alice"))],
+                vec![sqlite::Value::Integer(20), sqlite::Value::Null],
+            ],
+        );
+        let options: TableOptions = TableOptions { layout: Layout::Grid, ..Default::default() };
+        let expected: &str = "\
+┌────┬───────┐
+│ id │ name  │
+├────┼───────┤
+│  1 │ Alice │
+│ 20 │ NULL  │
+└────┴───────┘
+(2 rows)
+";
+        assert_eq!(render(&rs, &options), expected);
+    }
+
+    #[test]
+    fn long_cells_are_cut_at_max_cell_width() {
+        let rs: RecordSet = record_set(&["note"], vec![vec![sqlite::Value::String(String::from("line one\nline two"))]]);
+        let options: TableOptions = TableOptions { layout: Layout::Grid, max_cell_width: 6, max_width: None };
+        let out: String = render(&rs, &options);
+        assert!(out.contains("│ line … │"), "{}", out);
+        assert!(out.ends_with("(1 row)\n"));
+    }
+
+    #[test]
+    fn auto_switches_to_expanded_when_too_wide() {
+        let rs: RecordSet = record_set(
+            &["id", "description"],
+            vec![vec![sqlite::Value::Integer(7), sqlite::Value::String("x".repeat(30))]],
+        );
+        let narrow: TableOptions = TableOptions { layout: Layout::Auto, max_cell_width: 40, max_width: Some(20) };
+        let out: String = render(&rs, &narrow);
+        assert!(out.starts_with("─[ RECORD 1 ]"), "{}", out);
+        assert!(out.contains("id          │ 7\n"), "{}", out);
+        let wide: TableOptions = TableOptions { max_width: Some(200), ..narrow };
+        assert!(render(&rs, &wide).starts_with('┌'));
+    }
+
+    #[test]
+    fn statements_without_columns_print_nothing() {
+        assert_eq!(render(&RecordSet::default(), &TableOptions::default()), "");
+    }
+}
+
+/* <-- Tests */
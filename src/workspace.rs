@@ -0,0 +1,202 @@
+/* --> Summary of Contents
+
+	Workspace snapshot restored on the next launch
+
+	WorkspaceState / TabState
+		Window geometry, the visible tab and for each tab its connection,
+		editor text, cursor and splitter position. Connections are kept by
+		profile name or as a connection string stripped of its login, the
+		login is asked for again when the tab reconnects.
+
+	pub fn load() -> Option<(WorkspaceState, bool)>
+		The autosave when the last run did not exit cleanly (flagged by
+		the bool), otherwise the snapshot written on exit
+
+	pub fn save(&WorkspaceState) -> Result<(), String>
+	pub fn write_autosave(&WorkspaceState) -> Result<(), String>
+	pub fn clear_autosave()
+		The exit snapshot, and the periodic copy that survives a crash
+
+Summary of Contents <-- */
+/* --> Imports */
+
+use std::{fs, io::ErrorKind, path::Path};
+
+use crate::app_paths;
+use crate::credentials;
+use crate::ini_file::{IniDocument, IniSection};
+use crate::sql_aux_funcs::{Connection, ConnectionBase};
+
+/* <-- Imports */
+/* --> Const */
+
+const WORKSPACE_FILE: &str = "workspace.ini";
+const AUTOSAVE_FILE: &str = "workspace.autosave.ini";
+const WINDOW_SECTION: &str = "window";
+const TAB_PREFIX: &str = "tab:";
+pub const AUTOSAVE_SECONDS: f64 = 30.0;
+
+/* <-- Const */
+/* --> Structs */
+
+#[derive(Clone, Default, PartialEq)]
+pub struct WorkspaceState {
+    pub window: Option<(i32, i32, i32, i32)>, // x, y, w, h
+    pub active_tab: usize,
+    pub tabs: Vec<TabState>,
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct TabState {
+    pub profile_name: Option<String>,
+    pub connection_type: Option<String>, // "odbc" / "sqlite" for quick connects
+    pub target: String,                  // sqlite file, or the ODBC connection string without UID / PWD
+    pub user: Option<String>,            // quick connect login, the password is never written
+    pub editor_text: String,
+    pub cursor: i32,
+    pub grid_height: i32, // result grid part of the grid / editor splitter
+}
+
+impl TabState {
+    pub fn new(conn: &Connection, editor_text: String, cursor: i32, grid_height: i32) -> Self {
+        let mut state: TabState = TabState {
+            editor_text,
+            cursor,
+            grid_height,
+            ..Default::default()
+        };
+        if let Some(profile) = &conn.profile_name {
+            // the profile knows its own target and login mode
+            state.profile_name = Some(profile.clone());
+            return state;
+        }
+        if let (Some(base), Some(target)) = (&conn.connection_type, &conn.connection) {
+            let (stripped, login) = credentials::split_credentials(target);
+            state.connection_type = Some(String::from(match base {
+                ConnectionBase::Odbc => "odbc",
+                ConnectionBase::Sqlite => "sqlite",
+            }));
+            state.target = stripped;
+            state.user = login.map(|l| l.user);
+        }
+        state
+    }
+
+    pub fn connection_base(&self) -> Option<ConnectionBase> {
+        match self.connection_type.as_deref() {
+            Some("odbc") => Some(ConnectionBase::Odbc),
+            Some("sqlite") => Some(ConnectionBase::Sqlite),
+            _ => None,
+        }
+    }
+
+    fn from_section(section: &IniSection) -> Self {
+        let number = |key: &str| section.get(key).and_then(|v| v.parse::<i32>().ok()).unwrap_or(0);
+        TabState {
+            profile_name: section.get("profile").map(String::from),
+            connection_type: section.get("kind").map(String::from),
+            target: section.get("target").unwrap_or("").to_string(),
+            user: section.get("user").map(String::from),
+            editor_text: section.get("text").unwrap_or("").to_string(),
+            cursor: number("cursor"),
+            grid_height: number("grid_height"),
+        }
+    }
+
+    fn write_section(&self, section: &mut IniSection) {
+        if let Some(profile) = &self.profile_name {
+            section.set("profile", profile);
+        }
+        if let Some(kind) = &self.connection_type {
+            section.set("kind", kind);
+            section.set("target", &self.target);
+        }
+        if let Some(user) = &self.user {
+            section.set("user", user);
+        }
+        section.set("cursor", &self.cursor.to_string());
+        section.set("grid_height", &self.grid_height.to_string());
+        section.set("text", &self.editor_text);
+    }
+}
+
+impl WorkspaceState {
+    fn from_document(doc: &IniDocument) -> Self {
+        let mut state: WorkspaceState = WorkspaceState {
+            tabs: doc
+                .sections_with_prefix(TAB_PREFIX)
+                .map(|(_, section)| TabState::from_section(section))
+                .collect(),
+            ..Default::default()
+        };
+        if let Some(section) = doc.section(WINDOW_SECTION) {
+            let number = |key: &str| section.get(key).and_then(|v| v.parse::<i32>().ok());
+            if let (Some(x), Some(y), Some(w), Some(h)) = (number("x"), number("y"), number("w"), number("h")) {
+                state.window = Some((x, y, w, h));
+            }
+            state.active_tab = number("active_tab").and_then(|t| usize::try_from(t).ok()).unwrap_or(0);
+        }
+        state
+    }
+
+    fn to_document(&self) -> IniDocument {
+        let mut doc: IniDocument = IniDocument::default();
+        let window: &mut IniSection = doc.push_section(WINDOW_SECTION);
+        if let Some((x, y, w, h)) = self.window {
+            window.set("x", &x.to_string()).set("y", &y.to_string()).set("w", &w.to_string()).set("h", &h.to_string());
+        }
+        window.set("active_tab", &self.active_tab.to_string());
+        for (index, tab) in self.tabs.iter().enumerate() {
+            tab.write_section(doc.push_section(&format!("{}{}", TAB_PREFIX, index + 1)));
+        }
+        doc
+    }
+}
+
+/* <-- Structs */
+/* --> Functions */
+
+fn read(path: &Path) -> Option<WorkspaceState> {
+    if !path.exists() {
+        return None;
+    }
+    match IniDocument::load(path) {
+        Ok(doc) => Some(WorkspaceState::from_document(&doc)),
+        Err(e) => {
+            println!("Unable to read {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn write(path: &Path, state: &WorkspaceState) -> Result<(), String> {
+    state
+        .to_document()
+        .save(path)
+        .map_err(|e| format!("Unable to save {}: {}", path.display(), e))
+}
+
+// the autosave only outlives a run that crashed, so finding one means it is the newer copy
+pub fn load() -> Option<(WorkspaceState, bool)> {
+    match read(&app_paths::config_file(AUTOSAVE_FILE)) {
+        Some(state) => Some((state, true)),
+        None => read(&app_paths::config_file(WORKSPACE_FILE)).map(|state| (state, false)),
+    }
+}
+
+pub fn save(state: &WorkspaceState) -> Result<(), String> {
+    write(&app_paths::config_file(WORKSPACE_FILE), state)
+}
+
+pub fn write_autosave(state: &WorkspaceState) -> Result<(), String> {
+    write(&app_paths::config_file(AUTOSAVE_FILE), state)
+}
+
+pub fn clear_autosave() {
+    match fs::remove_file(app_paths::config_file(AUTOSAVE_FILE)) {
+        Err(e) if e.kind() != ErrorKind::NotFound => println!("Unable to remove the autosave file: {}", e),
+        _ => {}
+    }
+}
+
+/* <-- Functions */